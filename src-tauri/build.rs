@@ -1,3 +1,14 @@
 fn main() {
+    // Git commit for diagnostics, left unset when building outside a checkout
+    if let Ok(output) = std::process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+    {
+        if output.status.success() {
+            let commit = String::from_utf8_lossy(&output.stdout);
+            println!("cargo:rustc-env=OVIM_GIT_COMMIT={}", commit.trim());
+        }
+    }
+    println!("cargo:rerun-if-changed=../.git/HEAD");
     tauri_build::build();
 }
@@ -0,0 +1,23 @@
+//! Tauri commands for cleaning up editor processes left behind by failed edit sessions
+
+use tauri::State;
+
+use crate::nvim_edit::edit_dir;
+use crate::nvim_edit::terminals::process_utils::{self, EditProcess};
+use crate::AppState;
+
+/// List editor processes holding edit files that no active session owns
+#[tauri::command]
+pub fn list_edit_processes(state: State<AppState>) -> Result<Vec<EditProcess>, String> {
+    let active_files = state.edit_session_manager.active_files();
+    let processes = process_utils::list_edit_processes(&edit_dir()?)?;
+    Ok(process_utils::without_active_sessions(processes, &active_files))
+}
+
+/// Kill a stray editor process; refuses processes without an edit file open, or editing
+/// in an active session
+#[tauri::command]
+pub fn kill_edit_process(state: State<AppState>, pid: u32) -> Result<(), String> {
+    let active_files = state.edit_session_manager.active_files();
+    process_utils::kill_edit_process(pid, &edit_dir()?, &active_files)
+}
@@ -2,7 +2,9 @@
 
 use tauri::State;
 
+use crate::keyboard::benchmark::{self, InjectionBenchmark};
 use crate::keyboard::KeyCode;
+use crate::nvim_edit::accessibility;
 use crate::AppState;
 
 /// Recorded key info returned to frontend
@@ -44,3 +46,14 @@ pub fn cancel_record_key(state: State<AppState>) {
     let mut record_tx = state.record_key_tx.lock().unwrap();
     *record_tx = None;
 }
+
+/// Inject right arrows, 100 unless `count` is given, and time them
+/// Only runs in a text field of ovim's own window, so no other app gets the keys
+#[tauri::command]
+pub fn benchmark_injection(count: Option<u32>) -> Result<InjectionBenchmark, String> {
+    let context = accessibility::capture_focus_context().ok_or("No focused app")?;
+    if context.app_pid != std::process::id() as i32 || !accessibility::is_focused_text_field() {
+        return Err("Focus a text field in ovim's settings window to run the benchmark".to_string());
+    }
+    benchmark::benchmark_right_arrows(count.unwrap_or(benchmark::DEFAULT_BENCHMARK_EVENTS))
+}
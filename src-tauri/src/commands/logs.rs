@@ -0,0 +1,104 @@
+//! Log file Tauri commands
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::process::Command;
+
+use crate::LOG_FILE_PATH;
+
+/// Chunk size used when reading the log file backwards
+const TAIL_CHUNK_SIZE: u64 = 8 * 1024;
+
+/// Reveal the current log file in Finder
+#[tauri::command]
+pub fn open_log_file() -> Result<(), String> {
+    let path = Path::new(LOG_FILE_PATH);
+    if !path.exists() {
+        return Err(format!("Log file not found at {}", LOG_FILE_PATH));
+    }
+
+    Command::new("open")
+        .arg("-R")
+        .arg(path)
+        .spawn()
+        .map_err(|e| format!("Failed to open log file: {}", e))?;
+    Ok(())
+}
+
+/// Get the last `lines` lines of the log file
+#[tauri::command]
+pub fn get_log_tail(lines: usize) -> Result<Vec<String>, String> {
+    read_tail(Path::new(LOG_FILE_PATH), lines)
+        .map_err(|e| format!("Failed to read log file: {}", e))
+}
+
+/// Read the last `lines` lines of a file, reading backwards from the end in chunks
+/// so large log files don't need to be loaded fully
+fn read_tail(path: &Path, lines: usize) -> std::io::Result<Vec<String>> {
+    if lines == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut file = File::open(path)?;
+    let mut pos = file.metadata()?.len();
+    let mut buf: Vec<u8> = Vec::new();
+
+    // One more newline than requested guarantees the first wanted line is complete
+    while pos > 0 && buf.iter().filter(|&&b| b == b'\n').count() <= lines {
+        let chunk_size = TAIL_CHUNK_SIZE.min(pos);
+        pos -= chunk_size;
+
+        let mut chunk = vec![0u8; chunk_size as usize];
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut chunk)?;
+
+        chunk.extend_from_slice(&buf);
+        buf = chunk;
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    let all_lines: Vec<&str> = text.lines().collect();
+    let start = all_lines.len().saturating_sub(lines);
+    Ok(all_lines[start..].iter().map(|s| s.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_fixture(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("ovim-test-{}-{}.log", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_read_tail_last_lines() {
+        let contents: String = (1..=5000).map(|i| format!("[00:00:00.000] INFO - line {}\n", i)).collect();
+        let path = write_fixture("tail", &contents);
+
+        let tail = read_tail(&path, 3).unwrap();
+        assert_eq!(
+            tail,
+            vec![
+                "[00:00:00.000] INFO - line 4998",
+                "[00:00:00.000] INFO - line 4999",
+                "[00:00:00.000] INFO - line 5000",
+            ]
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_read_tail_short_file() {
+        let path = write_fixture("short", "first\nsecond");
+
+        assert_eq!(read_tail(&path, 10).unwrap(), vec!["first", "second"]);
+        assert_eq!(read_tail(&path, 1).unwrap(), vec!["second"]);
+        assert!(read_tail(&path, 0).unwrap().is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,15 +1,19 @@
 //! Tauri command handlers
 
+mod edit_processes;
 mod indicator;
 mod keys;
+mod logs;
 mod permissions;
 mod settings;
 mod updater;
 mod vim_mode;
 mod widgets;
 
+pub use edit_processes::*;
 pub use indicator::*;
 pub use keys::*;
+pub use logs::*;
 pub use permissions::*;
 pub use settings::*;
 pub use updater::*;
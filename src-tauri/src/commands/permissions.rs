@@ -2,7 +2,10 @@
 
 use tauri::State;
 
-use crate::keyboard::{check_accessibility_permission, request_accessibility_permission};
+use crate::keyboard::{
+    check_accessibility_permission, request_accessibility_permission, KeyboardBackend,
+};
+use crate::nvim_edit::accessibility::{self, AttributeSupport};
 use crate::AppState;
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -29,6 +32,13 @@ pub fn get_permission_status(state: State<AppState>) -> PermissionStatus {
     }
 }
 
+/// Check which text attributes the focused element exposes, to tell whether reading text
+/// works in the current app or the clipboard fallback will be used
+#[tauri::command]
+pub fn ping_accessibility() -> AttributeSupport {
+    accessibility::probe_focused_element()
+}
+
 #[tauri::command]
 pub fn open_accessibility_settings() {
     use std::process::Command;
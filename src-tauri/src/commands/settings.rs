@@ -6,8 +6,11 @@ use std::process::Command;
 use serde::Serialize;
 use tauri::{AppHandle, Emitter, Manager, State};
 
-use crate::config::Settings;
+use crate::config::{EditorType, NvimEditSettings, Settings};
+use crate::vim::keymap::KeymapPreset;
+use crate::vim::VimOptions;
 use crate::nvim_edit::terminals::process_utils::{resolve_command_path, resolve_terminal_path};
+use crate::window;
 use crate::AppState;
 
 /// Result of validating terminal and editor paths
@@ -27,6 +30,42 @@ pub struct PathValidation {
     pub editor_error: Option<String>,
 }
 
+/// Terminals in order of preference when picking one automatically
+/// Terminal.app is always installed, so it comes last as the fallback
+const TERMINAL_PREFERENCE: &[&str] = &["alacritty", "ghostty", "kitty", "wezterm", "iterm", "default"];
+
+/// An installed terminal
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DetectedTerminal {
+    /// Terminal type, as in `nvim_edit.terminal`
+    pub terminal: String,
+    pub resolved_path: String,
+}
+
+/// Editors to look for, with their executables
+/// GUI editors other than VS Code and Cursor are run as custom editors
+const KNOWN_EDITORS: &[(EditorType, &str)] = &[
+    (EditorType::Neovim, "nvim"),
+    (EditorType::Vim, "vim"),
+    (EditorType::Helix, "hx"),
+    (EditorType::VSCode, "code"),
+    (EditorType::VSCode, "cursor"),
+    (EditorType::Custom, "zed"),
+    (EditorType::Custom, "subl"),
+    (EditorType::Custom, "mvim"),
+];
+
+/// Whether an editor is installed, and where
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DetectedEditor {
+    pub editor: EditorType,
+    /// Executable name, or the configured path for a custom editor
+    pub executable: String,
+    pub available: bool,
+    /// The resolved path (or empty if not found)
+    pub resolved_path: String,
+}
+
 #[tauri::command]
 pub fn get_settings(state: State<AppState>) -> Settings {
     let settings = state.settings.lock().unwrap();
@@ -39,14 +78,42 @@ pub fn set_settings(
     state: State<AppState>,
     new_settings: Settings,
 ) -> Result<(), String> {
-    let mut settings = state.settings.lock().unwrap();
-    *settings = new_settings.clone();
-    settings.save()?;
+    let options = VimOptions::from_settings(&new_settings);
+    let mode = {
+        let mut vim_state = state.vim_state.lock().unwrap();
+        vim_state.set_options(options);
+        vim_state.mode()
+    };
+    *state.settings.lock().unwrap() = new_settings.clone();
+    state.key_settings.update(&new_settings);
+    // Written without the lock, so commands reading the settings don't wait on the disk
+    new_settings.save()?;
+    window::update_indicator_visibility(&app, &new_settings, mode);
 
     let _ = app.emit("settings-changed", new_settings);
     Ok(())
 }
 
+/// Load a built-in keymap preset, save it and return the updated settings
+#[tauri::command]
+pub fn apply_keymap_preset(
+    app: AppHandle,
+    state: State<AppState>,
+    preset: KeymapPreset,
+) -> Result<Settings, String> {
+    let settings = {
+        let mut settings = state.settings.lock().unwrap();
+        settings.apply_keymap_preset(preset);
+        settings.clone()
+    };
+    state.vim_state.lock().unwrap().set_options(VimOptions::from_settings(&settings));
+    state.key_settings.update(&settings);
+    settings.save()?;
+
+    let _ = app.emit("settings-changed", settings.clone());
+    Ok(settings)
+}
+
 #[tauri::command]
 pub fn open_settings_window(app: AppHandle) -> Result<(), String> {
     if let Some(window) = app.get_webview_window("settings") {
@@ -192,6 +259,7 @@ fn validate_editor(editor_type: &str, editor_path: &str) -> (bool, String, Optio
             "neovim" => "nvim".to_string(),
             "vim" => "vim".to_string(),
             "helix" => "hx".to_string(),
+            "vscode" => "code".to_string(),
             "custom" => {
                 return (
                     false,
@@ -227,6 +295,7 @@ fn validate_editor(editor_type: &str, editor_path: &str) -> (bool, String, Optio
         "neovim" => "Neovim (nvim)",
         "vim" => "Vim",
         "helix" => "Helix (hx)",
+        "vscode" => "VS Code (code)",
         "custom" => "Editor",
         _ => editor_type,
     };
@@ -265,3 +334,178 @@ pub fn validate_nvim_edit_paths(
         editor_error,
     }
 }
+
+/// Resolved path of a terminal type if it's installed, using the same checks as validation
+fn probe_terminal(terminal: &str) -> Option<String> {
+    let (valid, resolved, _) = validate_terminal(terminal, "");
+    valid.then_some(resolved)
+}
+
+/// Terminals found by `probe`, most preferred first
+fn rank_terminals(probe: impl Fn(&str) -> Option<String>) -> Vec<DetectedTerminal> {
+    TERMINAL_PREFERENCE
+        .iter()
+        .filter_map(|&terminal| {
+            probe(terminal).map(|resolved_path| DetectedTerminal {
+                terminal: terminal.to_string(),
+                resolved_path,
+            })
+        })
+        .collect()
+}
+
+/// The preferred installed terminal if `configured` isn't installed, or None to keep it
+fn pick_terminal(configured: &str, probe: impl Fn(&str) -> Option<String>) -> Option<DetectedTerminal> {
+    if probe(configured).is_some() {
+        return None;
+    }
+    rank_terminals(probe).into_iter().next()
+}
+
+/// Switch to the preferred installed terminal if the configured one is missing
+/// A custom terminal path is left alone. Returns whether the terminal changed
+pub fn auto_select_terminal(nvim_edit: &mut NvimEditSettings) -> bool {
+    if !nvim_edit.terminal_path.is_empty() {
+        return false;
+    }
+    match pick_terminal(&nvim_edit.terminal, probe_terminal) {
+        Some(detected) => {
+            log::info!(
+                "Terminal '{}' not found, using {}",
+                nvim_edit.terminal,
+                detected.terminal
+            );
+            nvim_edit.terminal = detected.terminal;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Installed terminals, most preferred first
+#[tauri::command]
+pub fn detect_terminal() -> Vec<DetectedTerminal> {
+    rank_terminals(probe_terminal)
+}
+
+/// Resolved path of an editor executable if it's installed, using the same checks as validation
+fn probe_editor(executable: &str) -> Option<String> {
+    let (valid, resolved, _) = validate_editor("custom", executable);
+    valid.then_some(resolved)
+}
+
+/// Probe the known editors, plus the configured custom editor path if any
+fn collect_editors(custom_path: &str, probe: impl Fn(&str) -> Option<String>) -> Vec<DetectedEditor> {
+    let custom = (!custom_path.is_empty()
+        && !KNOWN_EDITORS.iter().any(|&(_, executable)| executable == custom_path))
+    .then_some((EditorType::Custom, custom_path));
+
+    KNOWN_EDITORS
+        .iter()
+        .cloned()
+        .chain(custom)
+        .map(|(editor, executable)| {
+            let resolved = probe(executable);
+            DetectedEditor {
+                editor,
+                executable: executable.to_string(),
+                available: resolved.is_some(),
+                resolved_path: resolved.unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+/// Known editors and the configured custom editor, with whether each is installed
+#[tauri::command]
+pub fn detect_editors(state: State<AppState>) -> Vec<DetectedEditor> {
+    let custom_path = {
+        let settings = state.settings.lock().unwrap();
+        match settings.nvim_edit.editor {
+            EditorType::Custom => settings.nvim_edit.nvim_path.clone(),
+            _ => String::new(),
+        }
+    };
+    collect_editors(&custom_path, probe_editor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Probe that finds only the given terminals
+    fn installed(terminals: &'static [&'static str]) -> impl Fn(&str) -> Option<String> {
+        move |terminal| {
+            terminals
+                .contains(&terminal)
+                .then(|| format!("/Applications/{}.app", terminal))
+        }
+    }
+
+    fn names(detected: &[DetectedTerminal]) -> Vec<&str> {
+        detected.iter().map(|d| d.terminal.as_str()).collect()
+    }
+
+    #[test]
+    fn test_rank_terminals_by_preference() {
+        let detected = rank_terminals(installed(&["default", "kitty", "iterm", "alacritty"]));
+        assert_eq!(names(&detected), vec!["alacritty", "kitty", "iterm", "default"]);
+        assert_eq!(detected[1].resolved_path, "/Applications/kitty.app");
+        assert!(rank_terminals(installed(&[])).is_empty());
+    }
+
+    #[test]
+    fn test_pick_terminal_only_when_missing() {
+        let probe = installed(&["wezterm", "default"]);
+        assert_eq!(pick_terminal("wezterm", &probe), None);
+        assert_eq!(
+            pick_terminal("alacritty", &probe).map(|d| d.terminal),
+            Some("wezterm".to_string())
+        );
+        assert_eq!(pick_terminal("alacritty", installed(&[])), None);
+    }
+
+    #[test]
+    fn test_collect_editors() {
+        let probe = |executable: &str| match executable {
+            "nvim" => Some("/opt/homebrew/bin/nvim".to_string()),
+            "zed" => Some("/usr/local/bin/zed".to_string()),
+            "/opt/bin/kak" => Some("/opt/bin/kak".to_string()),
+            _ => None,
+        };
+
+        let editors = collect_editors("", probe);
+        assert_eq!(editors.len(), KNOWN_EDITORS.len());
+        assert_eq!(
+            editors[0],
+            DetectedEditor {
+                editor: EditorType::Neovim,
+                executable: "nvim".to_string(),
+                available: true,
+                resolved_path: "/opt/homebrew/bin/nvim".to_string(),
+            }
+        );
+        assert!(!editors[1].available);
+        assert!(editors[1].resolved_path.is_empty());
+        let available: Vec<&str> = editors
+            .iter()
+            .filter(|e| e.available)
+            .map(|e| e.executable.as_str())
+            .collect();
+        assert_eq!(available, vec!["nvim", "zed"]);
+        // VS Code and Cursor run without a terminal
+        let gui: Vec<&str> = editors
+            .iter()
+            .filter(|e| e.editor == EditorType::VSCode)
+            .map(|e| e.executable.as_str())
+            .collect();
+        assert_eq!(gui, vec!["code", "cursor"]);
+
+        // A custom path is probed too, unless it's one of the known editors
+        let editors = collect_editors("/opt/bin/kak", probe);
+        let custom = editors.last().unwrap();
+        assert_eq!(custom.editor, EditorType::Custom);
+        assert!(custom.available);
+        assert_eq!(collect_editors("zed", probe).len(), KNOWN_EDITORS.len());
+    }
+}
@@ -1,9 +1,79 @@
 //! Update-related commands
 
-use tauri::{AppHandle, Manager};
+use std::fmt;
 
+use tauri::{AppHandle, Manager, State};
+
+use super::permissions::PermissionStatus;
+use crate::keyboard::{check_accessibility_permission, KeyboardBackend};
 use crate::updater;
 use crate::window;
+use crate::AppState;
+
+/// Version and environment details for bug reports
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct VersionInfo {
+    pub version: String,
+    /// Commit the app was built from, if built from a git checkout
+    pub git_commit: Option<String>,
+    pub macos_version: Option<String>,
+    pub permissions: PermissionStatus,
+}
+
+impl VersionInfo {
+    pub fn new(
+        git_commit: Option<&str>,
+        macos_version: Option<String>,
+        permissions: PermissionStatus,
+    ) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: git_commit.filter(|c| !c.is_empty()).map(str::to_string),
+            macos_version: macos_version.filter(|v| !v.is_empty()),
+            permissions,
+        }
+    }
+
+    /// Info for the running app
+    pub fn current(capture_running: bool) -> Self {
+        Self::new(
+            option_env!("OVIM_GIT_COMMIT"),
+            macos_version(),
+            PermissionStatus {
+                accessibility: check_accessibility_permission(),
+                capture_running,
+            },
+        )
+    }
+}
+
+impl fmt::Display for VersionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ovim {}", self.version)?;
+        if let Some(commit) = &self.git_commit {
+            write!(f, " ({})", commit)?;
+        }
+        write!(
+            f,
+            " on macOS {}, accessibility {}, capture {}",
+            self.macos_version.as_deref().unwrap_or("unknown"),
+            if self.permissions.accessibility { "granted" } else { "not granted" },
+            if self.permissions.capture_running { "running" } else { "stopped" },
+        )
+    }
+}
+
+/// macOS version from `sw_vers`, e.g. "14.5"
+fn macos_version() -> Option<String> {
+    let output = std::process::Command::new("sw_vers")
+        .arg("-productVersion")
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
 
 /// Get the current application version
 #[tauri::command]
@@ -11,6 +81,12 @@ pub fn get_version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
 }
 
+/// Get the version, build and permission details to include in bug reports
+#[tauri::command]
+pub fn get_version_info(state: State<AppState>) -> VersionInfo {
+    VersionInfo::current(state.keyboard_capture.is_running())
+}
+
 /// Check for updates and install if available
 /// Returns the new version if an update was installed
 #[tauri::command]
@@ -18,6 +94,16 @@ pub async fn check_for_update(app: AppHandle) -> Result<Option<String>, String>
     updater::check_and_install_update(&app).await
 }
 
+/// Check the configured release feed for a newer version, without installing it
+#[tauri::command]
+pub async fn check_for_updates(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<updater::UpdateCheck, String> {
+    let feed_url = state.settings.lock().unwrap().update_feed_url.clone();
+    updater::check_for_updates(&app, &feed_url).await
+}
+
 /// Restart the application to apply the installed update
 #[tauri::command]
 pub fn restart_app(app: AppHandle) {
@@ -33,3 +119,42 @@ pub fn set_indicator_clickable(app: AppHandle, clickable: bool) -> Result<(), St
         Err("Indicator window not found".to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permissions(accessibility: bool) -> PermissionStatus {
+        PermissionStatus {
+            accessibility,
+            capture_running: accessibility,
+        }
+    }
+
+    #[test]
+    fn test_version_info() {
+        let info = VersionInfo::new(Some("abc1234"), Some("14.5".to_string()), permissions(true));
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(info.git_commit.as_deref(), Some("abc1234"));
+        assert_eq!(
+            info.to_string(),
+            format!(
+                "ovim {} (abc1234) on macOS 14.5, accessibility granted, capture running",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn test_version_info_without_build_details() {
+        let info = VersionInfo::new(Some(""), None, permissions(false));
+        assert_eq!(info.git_commit, None);
+        assert_eq!(
+            info.to_string(),
+            format!(
+                "ovim {} on macOS unknown, accessibility not granted, capture stopped",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+}
@@ -1,17 +1,131 @@
 //! Vim mode Tauri commands
 
-use tauri::State;
+use serde::Serialize;
+use tauri::{AppHandle, State};
 
+use crate::vim::mode_history::{self, ModeDurations, ModeTransition};
+use crate::vim::motion::{self, Motion};
+use crate::vim::text_object::TextObjectSpec;
+use crate::config::VimKeyModifiers;
+use crate::keyboard::KeyCode;
+use crate::vim::commands::VimCommand;
+use crate::vim::custom_command::{self, NamedCommand};
+use crate::vim::key_bindings::{self, ModeBinding};
+use crate::vim::{ModeChange, MotionOptions, VimOptions};
 use crate::AppState;
 
+/// Recent mode transitions and the time spent in each mode over them
+#[derive(Debug, Clone, Serialize)]
+pub struct ModeHistoryReport {
+    pub transitions: Vec<ModeTransition>,
+    pub durations: ModeDurations,
+}
+
 #[tauri::command]
 pub fn get_vim_mode(state: State<AppState>) -> String {
     let vim_state = state.vim_state.lock().unwrap();
     vim_state.mode().as_str().to_string()
 }
 
+/// Set the mode by name, as the IPC `SetMode` command does, and emit `mode-change`
+#[tauri::command]
+pub fn set_vim_mode(app: AppHandle, state: State<AppState>, mode: String) -> Result<(), String> {
+    let change = {
+        let mut vim_state = state.vim_state.lock().unwrap();
+        let mode = vim_state.set_mode_named(&mode)?;
+        ModeChange { mode, pending: vim_state.get_pending_keys() }
+    };
+    crate::emit_mode_change(&app, change);
+    Ok(())
+}
+
 #[tauri::command]
 pub fn get_pending_keys(state: State<AppState>) -> String {
     let vim_state = state.vim_state.lock().unwrap();
     vim_state.get_pending_keys()
 }
+
+#[tauri::command]
+pub fn get_mode_history(state: State<AppState>) -> ModeHistoryReport {
+    let history = state.mode_history.lock().unwrap();
+    ModeHistoryReport {
+        transitions: history.transitions(),
+        durations: history.durations(mode_history::now_ms()),
+    }
+}
+
+/// Run a vim command by name, e.g. "insert_literal_tab", in the focused app
+/// Names of custom commands from settings are accepted too
+/// "repeat_motion" re-runs the last motion typed, with `count` or else its own count
+#[tauri::command]
+pub fn run_vim_command(
+    state: State<AppState>,
+    command: NamedCommand,
+    count: Option<u32>,
+) -> Result<(), String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let options = MotionOptions::from_settings(&settings);
+    match command {
+        NamedCommand::Builtin(VimCommand::RepeatMotion) => {
+            let (motion, last_count) = state
+                .vim_state
+                .lock()
+                .unwrap()
+                .last_motion()
+                .ok_or("No motion to repeat")?;
+            motion.execute_with_mode(count.unwrap_or(last_count), false, options)
+        }
+        NamedCommand::Builtin(command) => command.execute_with_mode(count.unwrap_or(1), false, options),
+        NamedCommand::Custom(name) => {
+            let steps = custom_command::find(&settings.custom_commands, &name)
+                .ok_or_else(|| format!("Unknown command: {}", name))?;
+            custom_command::execute(steps, count.unwrap_or(1), options)
+        }
+    }
+}
+
+/// What a key with modifiers does in each mode, with the current keymap
+/// Modes where the key passes through to the app are left out
+#[tauri::command]
+pub fn get_active_bindings_for_key(
+    state: State<AppState>,
+    key: String,
+    modifiers: Option<VimKeyModifiers>,
+) -> Result<Vec<ModeBinding>, String> {
+    let keycode = KeyCode::from_name(&key).ok_or_else(|| format!("Unknown key: {}", key))?;
+    let options = VimOptions::from_settings(&state.settings.lock().unwrap());
+    Ok(key_bindings::bindings_for_key(keycode, &modifiers.unwrap_or_default(), &options))
+}
+
+/// Where a motion would leave the cursor in `text`, without touching the focused app
+/// Offsets are char indices. Errors if the motion can't be computed from text alone
+#[tauri::command]
+pub fn preview_motion(
+    text: String,
+    offset: usize,
+    motion: Motion,
+    count: Option<u32>,
+) -> Result<usize, String> {
+    let chars: Vec<char> = text.chars().collect();
+    motion::motion_target(motion, &chars, offset, count.unwrap_or(1))
+        .ok_or_else(|| format!("No target for {:?} from offset {}", motion, offset))
+}
+
+/// Chars a text object selects in `text`, as `start..end`
+#[derive(Debug, Clone, Serialize)]
+pub struct TextObjectPreview {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// What a text object, e.g. `iw` or `i(`, selects with the cursor at `offset` in `text`
+/// Offsets are char indices. Errors if the object isn't known or there's none at the cursor
+#[tauri::command]
+pub fn get_text_object_preview(text: String, offset: usize, text_object: String) -> Result<TextObjectPreview, String> {
+    let spec = TextObjectSpec::parse(&text_object)?;
+    let chars: Vec<char> = text.chars().collect();
+    let range = spec
+        .range(&chars, offset)
+        .ok_or_else(|| format!("No {} at offset {}", text_object, offset))?;
+    Ok(TextObjectPreview { start: range.start, end: range.end })
+}
@@ -1,3 +1,6 @@
 mod settings;
 
-pub use settings::{NvimEditSettings, Settings};
+pub use settings::{
+    ControlComboModifier, CursorPosition, CursorStyle, EditorType, KeyRepeat, KeyboardLayout, ModifierSide,
+    NvimEditSettings, Settings, TabInserts, TextCapture, UnknownMotionKey, VimKeyModifiers, WordMotionMode,
+};
@@ -1,5 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::keyboard::chord;
+use crate::keyboard::{KeyCode, Modifiers};
+use crate::vim::custom_command::CustomCommand;
+use crate::vim::keymap::{KeyBinding, KeymapPreset};
+use crate::vim::VimMode;
 
 /// Modifier keys for vim key activation
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
@@ -8,6 +16,36 @@ pub struct VimKeyModifiers {
     pub control: bool,
     pub option: bool,
     pub command: bool,
+    /// Side of the keyboard the modifiers must be held on
+    #[serde(default)]
+    pub side: ModifierSide,
+}
+
+/// Side of the keyboard for modifier matching
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModifierSide {
+    #[default]
+    Any,
+    Left,
+    Right,
+}
+
+impl VimKeyModifiers {
+    /// Check whether the held modifiers match these, on the configured side
+    /// When `key` is itself a modifier key, its own flag is ignored
+    pub fn matches(&self, key: KeyCode, held: &Modifiers) -> bool {
+        let held = held.without_key(key);
+        let same = held.shift == self.shift
+            && held.control == self.control
+            && held.option == self.option
+            && held.command == self.command;
+        same && match self.side {
+            ModifierSide::Any => true,
+            ModifierSide::Left => !held.any_right(),
+            ModifierSide::Right => !held.any_left(),
+        }
+    }
 }
 
 /// Supported editor types for Edit Popup
@@ -18,6 +56,8 @@ pub enum EditorType {
     Neovim,
     Vim,
     Helix,
+    /// VS Code or Cursor, opened in its own window rather than a terminal
+    VSCode,
     Custom,
 }
 
@@ -27,6 +67,7 @@ impl EditorType {
             "neovim" | "nvim" => EditorType::Neovim,
             "vim" => EditorType::Vim,
             "helix" | "hx" => EditorType::Helix,
+            "vscode" | "code" | "cursor" => EditorType::VSCode,
             _ => EditorType::Custom,
         }
     }
@@ -37,6 +78,7 @@ impl EditorType {
             EditorType::Neovim => "nvim",
             EditorType::Vim => "vim",
             EditorType::Helix => "hx",
+            EditorType::VSCode => "code",
             EditorType::Custom => "",
         }
     }
@@ -47,18 +89,165 @@ impl EditorType {
             EditorType::Neovim => "nvim",
             EditorType::Vim => "vim",
             EditorType::Helix => "hx",
+            EditorType::VSCode => "code",
             EditorType::Custom => "",
         }
     }
 
+    /// Whether the editor is a GUI app with its own window, so no terminal is spawned
+    pub fn is_gui(&self) -> bool {
+        matches!(self, EditorType::VSCode)
+    }
+
     /// Get the arguments to position cursor at end of file
     pub fn cursor_end_args(&self) -> Vec<&'static str> {
         match self {
             EditorType::Neovim | EditorType::Vim => vec!["+normal G$"],
             EditorType::Helix => vec![], // Helix doesn't have equivalent startup command
-            EditorType::Custom => vec![],
+            EditorType::VSCode | EditorType::Custom => vec![],
         }
     }
+
+    /// Get the arguments to open with the cursor at a line and column
+    /// None if the editor can't be told where to start
+    pub fn cursor_position_args(&self, position: CursorPosition) -> Option<Vec<String>> {
+        match self {
+            EditorType::Neovim | EditorType::Vim => Some(vec![
+                format!("+{}", position.line),
+                format!("+normal {}|", position.column),
+            ]),
+            // VS Code's `--goto` takes the file too, see `gui_editor::wait_args`
+            EditorType::Helix | EditorType::VSCode | EditorType::Custom => None,
+        }
+    }
+
+    /// Get the arguments to start with `init` as the config instead of the user's
+    pub fn config_args(&self, init: &std::path::Path) -> Vec<String> {
+        let init = init.to_string_lossy().to_string();
+        match self {
+            EditorType::Neovim | EditorType::Vim => vec!["-u".to_string(), init],
+            EditorType::Helix => vec!["--config".to_string(), init],
+            EditorType::VSCode | EditorType::Custom => vec![],
+        }
+    }
+}
+
+/// A 1-based line and column in the edited text, where the editor opens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CursorPosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl CursorPosition {
+    /// Position of the char `offset` in `text`, or None if it's past the end
+    pub fn from_offset(text: &str, offset: usize) -> Option<Self> {
+        let mut line = 1;
+        let mut column = 1;
+        let mut chars = text.chars();
+        for _ in 0..offset {
+            if chars.next()? == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+        Some(Self { line, column })
+    }
+}
+
+/// How word motions (w, e, b, ge) move the cursor
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WordMotionMode {
+    /// Use macOS Option+Arrow word navigation
+    #[default]
+    Native,
+    /// Compute vim's word boundaries from the field's text (needs accessibility support)
+    VimAccurate,
+}
+
+/// Keyboard layout used to translate physical keys to vim keys
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyboardLayout {
+    #[default]
+    Qwerty,
+    Dvorak,
+    Colemak,
+    /// Map of physical QWERTY key names to logical key names (e.g. "j" -> "h")
+    Custom(HashMap<String, String>),
+}
+
+/// How held keys repeat in normal mode
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyRepeat {
+    /// Let every OS key repeat through
+    #[default]
+    OsDefault,
+    /// Ignore key repeat: one motion per press
+    Disabled,
+    /// Let repeats through at most once per this many milliseconds
+    Custom(u64),
+}
+
+/// What `>>` inserts at the start of the line
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TabInserts {
+    /// Press Tab and let the app indent
+    #[default]
+    Tab,
+    /// Type this many spaces
+    Spaces(u8),
+}
+
+/// Caret shape requested from the focused app
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorStyle {
+    #[default]
+    Bar,
+    Block,
+    Underline,
+}
+
+/// Modifier that triggers vim's Ctrl-combos (Ctrl+F, Ctrl+D, Ctrl+R, ...) in normal mode
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlComboModifier {
+    #[default]
+    Control,
+    Command,
+    Option,
+}
+
+/// What a key that isn't a motion does after an operator, e.g. `q` in `dq`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownMotionKey {
+    /// Cancel the operator and run the key as a normal mode command
+    #[default]
+    Reprocess,
+    /// Cancel the operator and send the key to the app
+    PassThrough,
+    /// Swallow the key and keep waiting for a motion
+    Ignore,
+}
+
+/// Where the edit popup reads the text of the focused field from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TextCapture {
+    /// Accessibility, or a clipboard copy when that text is empty or looks truncated
+    #[default]
+    Auto,
+    /// Accessibility, a clipboard copy only when that text is empty
+    Accessibility,
+    /// A clipboard copy (select all, copy), accessibility if the copy fails
+    Clipboard,
 }
 
 /// Settings for Edit Popup feature
@@ -92,6 +281,42 @@ pub struct NvimEditSettings {
     /// Enable live sync (BETA) - sync text field as you type in editor
     #[serde(default)]
     pub live_sync_enabled: bool,
+    /// Delay in milliseconds between clipboard steps (select all, copy, paste)
+    /// Increase on slow machines if edited text is not pasted back reliably
+    pub clipboard_delay_ms: u64,
+    /// Address of a running Neovim server (socket path or host:port)
+    /// When set, files are opened with `nvim --server <addr> --remote` instead of a new terminal
+    pub nvim_server_addr: Option<String>,
+    /// Show the changes and ask before replacing the field with the edited text
+    /// Cancelling keeps the temp file. Disables live sync
+    pub confirm_before_apply: bool,
+    /// Keystrokes sent after the edited text is restored, by app bundle id ("*" for all apps)
+    /// Uses vim notation, e.g. "<CR>" to send a chat message or "<Tab>" to move to the next field
+    pub post_edit_keystrokes: HashMap<String, String>,
+    /// Where the field's text is read from. Some apps (Slack, Electron) only expose
+    /// the visible part of long text via accessibility
+    pub text_capture: TextCapture,
+    /// Open the editor at the caret's line and column instead of the end of the text
+    /// Falls back to the end when the caret can't be read
+    pub open_at_cursor_line: bool,
+    /// Shell command the edited text is piped through before it's written back, e.g. "fmt"
+    /// The raw text is used if the command fails or times out. Disables live sync
+    pub shell_command: Option<String>,
+    /// Environment variables the editor is started with, e.g. `NVIM_APPNAME` to use a
+    /// separate config for popup editing
+    pub editor_env: HashMap<String, String>,
+    /// Start the editor with a minimal config instead of the user's, which opens faster
+    pub use_minimal_config: bool,
+    /// Config file used by `use_minimal_config` (empty = the bundled one, Neovim and Vim only)
+    pub minimal_config_path: String,
+    /// Modifier key (key name, e.g. "right_command") that opens the Edit Popup when tapped
+    /// twice, besides the shortcut. Empty to disable
+    pub trigger_on_double_tap: String,
+    /// Open the editor when the field is empty. Off to do nothing on empty fields
+    pub keep_window_open_on_empty: bool,
+    /// Modifier ("shift", "control", "option" or "command") that, added to the shortcut,
+    /// edits all fields of the window at once. Empty to disable batch editing
+    pub batch_modifier: String,
 }
 
 impl Default for NvimEditSettings {
@@ -104,6 +329,7 @@ impl Default for NvimEditSettings {
                 control: false,
                 option: false,
                 command: true, // Cmd+Shift+E
+                side: ModifierSide::Any,
             },
             terminal: "alacritty".to_string(),
             terminal_path: "".to_string(), // Empty means auto-detect
@@ -113,6 +339,19 @@ impl Default for NvimEditSettings {
             popup_width: 0, // 0 = match text field width
             popup_height: 300,
             live_sync_enabled: true, // BETA feature, enabled by default
+            clipboard_delay_ms: 100,
+            nvim_server_addr: None,
+            confirm_before_apply: false,
+            post_edit_keystrokes: HashMap::new(),
+            text_capture: TextCapture::default(),
+            open_at_cursor_line: false,
+            shell_command: None,
+            editor_env: HashMap::new(),
+            use_minimal_config: false,
+            minimal_config_path: String::new(),
+            trigger_on_double_tap: String::new(),
+            keep_window_open_on_empty: true,
+            batch_modifier: "option".to_string(),
         }
     }
 }
@@ -177,8 +416,51 @@ impl NvimEditSettings {
     }
 
     /// Get the editor arguments for cursor positioning
-    pub fn editor_args(&self) -> Vec<&str> {
-        self.editor.cursor_end_args()
+    /// `cursor` is the caret in the edited text, used with `open_at_cursor_line`
+    pub fn editor_args(&self, cursor: Option<CursorPosition>) -> Vec<String> {
+        cursor
+            .filter(|_| self.open_at_cursor_line)
+            .and_then(|position| self.editor.cursor_position_args(position))
+            .unwrap_or_else(|| self.editor.cursor_end_args().into_iter().map(String::from).collect())
+    }
+
+    /// Modifier key whose double tap opens the Edit Popup
+    /// None if it's off or names a key that isn't a modifier
+    pub fn double_tap_key(&self) -> Option<KeyCode> {
+        if !self.enabled {
+            return None;
+        }
+        KeyCode::from_name(&self.trigger_on_double_tap).filter(|&key| chord::is_chord_key(key))
+    }
+
+    /// Modifiers of the batch edit shortcut: the shortcut's plus `batch_modifier`
+    /// None if batch editing is off, or the modifier is already part of the shortcut
+    pub fn batch_shortcut_modifiers(&self) -> Option<VimKeyModifiers> {
+        let mut mods = self.shortcut_modifiers.clone();
+        let flag = match self.batch_modifier.as_str() {
+            "shift" => &mut mods.shift,
+            "control" => &mut mods.control,
+            "option" => &mut mods.option,
+            "command" => &mut mods.command,
+            _ => return None,
+        };
+        if *flag {
+            return None;
+        }
+        *flag = true;
+        Some(mods)
+    }
+
+    /// `editor_env` sorted by name, leaving out variables without a name
+    pub fn editor_env_vars(&self) -> Vec<(&str, &str)> {
+        let mut vars: Vec<(&str, &str)> = self
+            .editor_env
+            .iter()
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        vars.sort_unstable();
+        vars
     }
 
     /// Get the process name to search for when waiting for editor to exit
@@ -231,6 +513,95 @@ impl Default for ModeColors {
     }
 }
 
+/// Features turned on or off for one app, on top of the global switches
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct AppOverride {
+    /// Vim mode, as if the app were in `ignored_apps` when off
+    pub vim_enabled: bool,
+    /// The nvim edit shortcut, passed to the app when off
+    pub nvim_edit_enabled: bool,
+    /// Pause between injected key events in this app, in place of `injection_pacing_ms`
+    pub injection_pacing_ms: Option<u64>,
+}
+
+impl Default for AppOverride {
+    fn default() -> Self {
+        Self {
+            vim_enabled: true,
+            nvim_edit_enabled: true,
+            injection_pacing_ms: None,
+        }
+    }
+}
+
+/// Mode-specific indicator labels
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ModeLabels {
+    pub insert: String,
+    pub normal: String,
+    pub visual: String,
+}
+
+impl Default for ModeLabels {
+    fn default() -> Self {
+        Self {
+            insert: "i".to_string(),
+            normal: "n".to_string(),
+            visual: "v".to_string(),
+        }
+    }
+}
+
+impl ModeLabels {
+    /// Get the label shown for a mode
+    pub fn label(&self, mode: VimMode) -> &str {
+        match mode {
+            VimMode::Insert => &self.insert,
+            VimMode::Normal => &self.normal,
+            VimMode::Visual => &self.visual,
+        }
+    }
+}
+
+/// Modes in which the indicator window is shown
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct IndicatorVisibility {
+    pub insert: bool,
+    pub normal: bool,
+    pub visual: bool,
+}
+
+impl Default for IndicatorVisibility {
+    fn default() -> Self {
+        Self {
+            insert: true,
+            normal: true,
+            visual: true,
+        }
+    }
+}
+
+impl IndicatorVisibility {
+    /// Hidden in every mode
+    pub const HIDDEN: Self = Self {
+        insert: false,
+        normal: false,
+        visual: false,
+    };
+
+    /// Whether the indicator is shown in a mode
+    pub fn shows(&self, mode: VimMode) -> bool {
+        match mode {
+            VimMode::Insert => self.insert,
+            VimMode::Normal => self.normal,
+            VimMode::Visual => self.visual,
+        }
+    }
+}
+
 /// Application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -243,6 +614,9 @@ pub struct Settings {
     /// Modifier keys required for vim key activation
     #[serde(default)]
     pub vim_key_modifiers: VimKeyModifiers,
+    /// Modifier keys (key names) that also toggle vim mode when pressed together on their
+    /// own, e.g. [control, right_control]. Empty to disable
+    pub vim_key_chord: Vec<String>,
     /// Indicator window position (0-5 for 2x3 grid)
     pub indicator_position: u8,
     /// Indicator opacity (0.0 - 1.0)
@@ -255,20 +629,32 @@ pub struct Settings {
     /// Indicator Y offset in pixels
     #[serde(default)]
     pub indicator_offset_y: i32,
-    /// Whether the indicator window is visible
-    #[serde(default = "default_true")]
-    pub indicator_visible: bool,
+    /// Modes in which the indicator window is visible
+    pub indicator_per_mode_visibility: IndicatorVisibility,
+    /// Legacy on/off switch for the indicator, read once to migrate it (see `load`)
+    #[serde(skip_serializing)]
+    indicator_visible: Option<bool>,
     /// Show mode indicator in menu bar icon
     #[serde(default)]
     pub show_mode_in_menu_bar: bool,
     /// Mode-specific background colors
     #[serde(default)]
     pub mode_colors: ModeColors,
+    /// Mode-specific indicator labels (e.g. "NORMAL", "N" or an emoji)
+    pub indicator_text_template: ModeLabels,
     /// Font family for indicator
     #[serde(default = "default_font_family")]
     pub indicator_font: String,
+    /// Show a count being typed, like the `50` of `50j`, in place of the mode label
+    #[serde(default)]
+    pub indicator_show_count: bool,
     /// Bundle identifiers of apps where vim mode is disabled
     pub ignored_apps: Vec<String>,
+    /// Per-app features by bundle identifier, e.g. motions without the nvim edit shortcut
+    pub app_overrides: HashMap<String, AppOverride>,
+    /// Mode an app starts in when it's first focused in a session, by bundle identifier.
+    /// Once any is set, other apps start in `default_mode` on their first focus
+    pub startup_mode_per_app: HashMap<String, VimMode>,
     /// Launch at login
     pub launch_at_login: bool,
     /// Show in menu bar
@@ -284,6 +670,89 @@ pub struct Settings {
     /// Enable automatic update checking
     #[serde(default = "default_true")]
     pub auto_update_enabled: bool,
+    /// Check the release feed once a day at startup and show a notice, without downloading
+    pub auto_check_updates: bool,
+    /// Release feed for update checks (empty = the app's built-in feed)
+    pub update_feed_url: String,
+    /// How word motions move the cursor
+    pub word_motion_mode: WordMotionMode,
+    /// Place the caret directly through accessibility for line and document motions
+    /// (`0`, `$`, `gg`, `G`, `{count}G`, `{count}|`), falling back to arrow keys
+    pub prefer_accessibility_motions: bool,
+    /// Apply `x`, `X`, `dd` and `D` by setting the field's text through accessibility,
+    /// falling back to injected keys, for apps that beep at or drop fast ForwardDelete presses
+    pub prefer_accessibility_edits: bool,
+    /// Skip `x`, `X`, `s`, `h` and `l` where the field's text shows they can't do anything,
+    /// e.g. `x` at the end of the field, instead of injecting a key that makes macOS beep
+    pub suppress_invalid_ops: bool,
+    /// Keyboard layout for vim keys
+    pub keyboard_layout: KeyboardLayout,
+    /// Caret style to request in normal and visual mode, where the app supports it
+    pub visual_mode_cursor_style: CursorStyle,
+    /// Treat Ctrl+C like Escape: enter normal mode, or cancel pending keys in normal mode
+    pub ctrl_c_as_escape: bool,
+    /// Stay in visual mode with the selection kept after yanking with `y`
+    pub keep_selection_after_yank: bool,
+    /// `d`, `y` and `c` on a visual selection over several lines take the whole lines, like `V`
+    pub visual_mode_linewise_operators: bool,
+    /// Yanks and deletes go to the system clipboard. When off they're kept in ovim's unnamed
+    /// register, which `p` pastes from, and the clipboard is left as it was
+    pub yank_to_system_clipboard: bool,
+    /// Modifier used for vim's Ctrl-combos in normal mode
+    pub control_combo_modifier: ControlComboModifier,
+    /// Mode to start in
+    pub default_mode: VimMode,
+    /// Mode Escape switches to: "insert" (default) or "normal" for vim's usual model,
+    /// where Escape leaves insert mode and only cancels pending keys in normal mode
+    pub escape_target_mode: VimMode,
+    /// When Escape switches to insert mode, a first Escape only cancels pending keys such as
+    /// the `d` of `dw`, the way vim does
+    pub escape_cancels_pending_first: bool,
+    /// What a key that isn't a motion does after an operator such as `d`
+    pub unknown_motion_key: UnknownMotionKey,
+    /// Cancel a pending operator when no key follows it for this long, in milliseconds.
+    /// 0 waits for the motion indefinitely
+    pub operator_timeout_ms: u64,
+    /// Delay before starting keyboard capture at launch, in milliseconds
+    pub startup_capture_delay_ms: u64,
+    /// When Caps Lock is the vim key, turn Caps Lock back off after each press
+    /// so the LED doesn't flip and typing isn't capitalized
+    pub preserve_caps_lock_state: bool,
+    /// Readline/emacs-style editing keys in insert mode (Ctrl+W/U/A/E/F/B/D, Ctrl+I for a
+    /// literal Tab), only in text fields
+    pub insert_readline_bindings: bool,
+    /// Keys that always pass through in insert mode when pressed with Ctrl, so editors'
+    /// completion menus keep working. Names as in the keymap, e.g. "n" for Ctrl+N
+    pub insert_completion_keys: Vec<String>,
+    /// Key repeat for held keys in normal mode, e.g. holding `j`
+    pub normal_mode_key_repeat: KeyRepeat,
+    /// `gt`/`gT` switch to the next/previous tab and `{count}gt` to tab {count},
+    /// using the Cmd+Shift+]/[ and Cmd+1-9 shortcuts most tabbed apps support
+    pub enable_tab_motions: bool,
+    /// `0` goes to the first non-blank char, and a second `0` to the line start
+    pub smart_home: bool,
+    /// Clicking with the mouse switches to insert mode, since a click usually means typing next
+    pub mouse_disables_normal_mode: bool,
+    /// Normal mode key bindings that take precedence over vim's keys
+    pub keymap: Vec<KeyBinding>,
+    /// Preset the keymap was last loaded from
+    pub keymap_preset: KeymapPreset,
+    /// Commands composed of editing primitives, which the keymap and `run_vim_command`
+    /// take by name
+    pub custom_commands: Vec<CustomCommand>,
+    /// What `>>` inserts: a Tab press or a number of spaces. `<<` always sends Shift+Tab
+    pub tab_inserts: TabInserts,
+    /// Column `gq` and `gw` wrap lines at
+    pub text_width: usize,
+    /// Pause between the key events of a command, for apps that drop events sent back to
+    /// back, e.g. Electron apps applying half of `dd`. 0 sends them without a pause
+    pub injection_pacing_ms: u64,
+    /// Track modes and update the indicator without injecting any keys,
+    /// for apps and remote desktops that reject synthetic input
+    pub tracking_only_mode: bool,
+    /// Emit a `key-decision` event for each key press, showing whether it was suppressed,
+    /// passed through or ran an action. Off by default, as it's only for debugging
+    pub debug_events: bool,
 }
 
 fn default_font_family() -> String {
@@ -304,16 +773,22 @@ impl Default for Settings {
             enabled: true,
             vim_key: "caps_lock".to_string(),
             vim_key_modifiers: VimKeyModifiers::default(),
+            vim_key_chord: Vec::new(),
             indicator_position: 1, // Top center
             indicator_opacity: 0.9,
             indicator_size: 1.0,
             indicator_offset_x: 0,
             indicator_offset_y: 0,
-            indicator_visible: true,
+            indicator_per_mode_visibility: IndicatorVisibility::default(),
+            indicator_visible: None,
             show_mode_in_menu_bar: false,
             mode_colors: ModeColors::default(),
+            indicator_text_template: ModeLabels::default(),
             indicator_font: default_font_family(),
+            indicator_show_count: false,
             ignored_apps: vec![],
+            app_overrides: HashMap::new(),
+            startup_mode_per_app: HashMap::new(),
             launch_at_login: false,
             show_in_menu_bar: true,
             top_widget: "None".to_string(),
@@ -321,6 +796,40 @@ impl Default for Settings {
             electron_apps: vec![],
             nvim_edit: NvimEditSettings::default(),
             auto_update_enabled: true,
+            auto_check_updates: false,
+            update_feed_url: String::new(),
+            word_motion_mode: WordMotionMode::default(),
+            prefer_accessibility_motions: false,
+            prefer_accessibility_edits: false,
+            suppress_invalid_ops: false,
+            keyboard_layout: KeyboardLayout::default(),
+            visual_mode_cursor_style: CursorStyle::default(),
+            ctrl_c_as_escape: false,
+            keep_selection_after_yank: false,
+            visual_mode_linewise_operators: false,
+            yank_to_system_clipboard: true,
+            control_combo_modifier: ControlComboModifier::default(),
+            default_mode: VimMode::Insert,
+            escape_target_mode: VimMode::Insert,
+            escape_cancels_pending_first: true,
+            unknown_motion_key: UnknownMotionKey::default(),
+            operator_timeout_ms: 0,
+            startup_capture_delay_ms: 0,
+            preserve_caps_lock_state: true,
+            insert_readline_bindings: false,
+            insert_completion_keys: vec!["n".to_string(), "p".to_string()],
+            normal_mode_key_repeat: KeyRepeat::default(),
+            enable_tab_motions: false,
+            smart_home: false,
+            mouse_disables_normal_mode: false,
+            keymap: vec![],
+            keymap_preset: KeymapPreset::default(),
+            custom_commands: vec![],
+            tab_inserts: TabInserts::default(),
+            text_width: 80,
+            injection_pacing_ms: 0,
+            tracking_only_mode: false,
+            debug_events: false,
         }
     }
 }
@@ -339,11 +848,25 @@ impl Settings {
     /// Load settings from disk (YAML format, with JSON migration)
     pub fn load() -> Self {
         let mut settings = Self::load_raw();
+        settings.migrate();
         // Sanitize settings to fix any invalid state
         settings.nvim_edit.sanitize();
         settings
     }
 
+    /// Carry settings from older versions over to the ones that replaced them
+    fn migrate(&mut self) {
+        // A hidden indicator stays hidden in every mode
+        if self.indicator_visible.take() == Some(false) {
+            self.indicator_per_mode_visibility = IndicatorVisibility::HIDDEN;
+        }
+    }
+
+    /// Whether the indicator window is shown in a mode
+    pub fn indicator_shown(&self, mode: VimMode) -> bool {
+        self.enabled && self.indicator_per_mode_visibility.shows(mode)
+    }
+
     /// Load raw settings without sanitization
     fn load_raw() -> Self {
         // First, try to load from YAML
@@ -387,4 +910,352 @@ impl Settings {
 
         std::fs::write(&path, contents).map_err(|e| format!("Failed to write settings: {}", e))
     }
+
+    /// Bundle identifiers of apps where vim mode is off: ignored apps and overrides turning it off
+    pub fn vim_disabled_apps(&self) -> Vec<String> {
+        let overridden = self
+            .app_overrides
+            .iter()
+            .filter(|(_, o)| !o.vim_enabled)
+            .map(|(id, _)| id);
+        self.ignored_apps.iter().chain(overridden).cloned().collect()
+    }
+
+    /// Whether the nvim edit shortcut is on in the app with this bundle identifier
+    pub fn nvim_edit_enabled_in_app(&self, bundle_id: &str) -> bool {
+        self.nvim_edit.enabled
+            && self
+                .app_overrides
+                .get(bundle_id)
+                .is_none_or(|o| o.nvim_edit_enabled)
+    }
+
+    /// Pause between injected key events in apps that override `injection_pacing_ms`,
+    /// by bundle identifier
+    pub fn app_injection_pacing(&self) -> HashMap<String, Duration> {
+        self.app_overrides
+            .iter()
+            .filter_map(|(id, o)| Some((id.clone(), Duration::from_millis(o.injection_pacing_ms?))))
+            .collect()
+    }
+
+    /// Mode the app with this bundle identifier starts in on its first focus
+    pub fn startup_mode_for_app(&self, bundle_id: &str) -> VimMode {
+        startup_mode_for_app(&self.startup_mode_per_app, self.default_mode, bundle_id)
+    }
+
+    /// Replace the keymap with a preset's bindings
+    pub fn apply_keymap_preset(&mut self, preset: KeymapPreset) {
+        self.keymap = preset.bindings();
+        self.keymap_preset = preset;
+    }
+
+    /// Keys of the modifier chord that toggles vim mode
+    /// None if there's no chord or it names a key that can't be part of one
+    pub fn vim_key_chord_keys(&self) -> Option<Vec<KeyCode>> {
+        if self.vim_key_chord.is_empty() {
+            return None;
+        }
+        self.vim_key_chord
+            .iter()
+            .map(|name| KeyCode::from_name(name).filter(|&key| chord::is_chord_key(key)))
+            .collect()
+    }
+
+    /// Whether a Caps Lock press that toggled the vim mode should reset the Caps Lock state
+    /// Only applies when Caps Lock itself is the vim key, with no modifiers
+    pub fn resets_caps_lock(&self) -> bool {
+        let mods = &self.vim_key_modifiers;
+        self.preserve_caps_lock_state
+            && KeyCode::from_name(&self.vim_key) == Some(KeyCode::CapsLock)
+            && !(mods.shift || mods.control || mods.option || mods.command)
+    }
+}
+
+/// Mode the app with this bundle identifier starts in on its first focus: its entry in
+/// `startup_mode_per_app`, or `default_mode` when it has none
+fn startup_mode_for_app(
+    startup_mode_per_app: &HashMap<String, VimMode>,
+    default_mode: VimMode,
+    bundle_id: &str,
+) -> VimMode {
+    startup_mode_per_app.get(bundle_id).copied().unwrap_or(default_mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::keycode::SideModifiers;
+
+    #[test]
+    fn test_mode_label_per_mode() {
+        let labels = ModeLabels {
+            insert: "INSERT".to_string(),
+            normal: "NORMAL".to_string(),
+            visual: "👀".to_string(),
+        };
+        assert_eq!(labels.label(VimMode::Insert), "INSERT");
+        assert_eq!(labels.label(VimMode::Normal), "NORMAL");
+        assert_eq!(labels.label(VimMode::Visual), "👀");
+
+        assert_eq!(ModeLabels::default().label(VimMode::Normal), "n");
+    }
+
+    #[test]
+    fn test_missing_mode_labels_use_defaults() {
+        let labels: ModeLabels = serde_yml::from_str("normal: NORMAL").unwrap();
+        assert_eq!(labels.label(VimMode::Normal), "NORMAL");
+        assert_eq!(labels.label(VimMode::Insert), "i");
+    }
+
+    #[test]
+    fn test_app_override_resolution() {
+        let settings: Settings = serde_yml::from_str(
+            "ignored_apps: [com.example.ignored]\n\
+             app_overrides:\n  \
+               com.example.motions: { nvim_edit_enabled: false }\n  \
+               com.example.edit: { vim_enabled: false }\n",
+        )
+        .unwrap();
+
+        let mut disabled = settings.vim_disabled_apps();
+        disabled.sort();
+        assert_eq!(disabled, vec!["com.example.edit", "com.example.ignored"]);
+
+        assert!(!settings.nvim_edit_enabled_in_app("com.example.motions"));
+        assert!(settings.nvim_edit_enabled_in_app("com.example.edit"));
+        assert!(settings.nvim_edit_enabled_in_app("com.example.other"));
+
+        // The global switch wins over an override
+        let mut settings = settings;
+        settings.nvim_edit.enabled = false;
+        assert!(!settings.nvim_edit_enabled_in_app("com.example.edit"));
+    }
+
+    #[test]
+    fn test_app_injection_pacing() {
+        let settings: Settings = serde_yml::from_str(
+            "injection_pacing_ms: 5\n\
+             app_overrides:\n  \
+               com.example.electron: { injection_pacing_ms: 20 }\n  \
+               com.example.edit: { vim_enabled: false }\n",
+        )
+        .unwrap();
+        assert_eq!(settings.injection_pacing_ms, 5);
+        let pacing = settings.app_injection_pacing();
+        assert_eq!(pacing.len(), 1);
+        assert_eq!(pacing["com.example.electron"], Duration::from_millis(20));
+        assert_eq!(Settings::default().injection_pacing_ms, 0);
+    }
+
+    #[test]
+    fn test_startup_mode_for_app() {
+        let settings: Settings = serde_yml::from_str(
+            "default_mode: normal\n\
+             startup_mode_per_app:\n  \
+               com.apple.Terminal: insert\n  \
+               com.apple.Notes: normal\n",
+        )
+        .unwrap();
+        assert_eq!(settings.startup_mode_for_app("com.apple.Terminal"), VimMode::Insert);
+        assert_eq!(settings.startup_mode_for_app("com.apple.Notes"), VimMode::Normal);
+        assert_eq!(settings.startup_mode_for_app("com.example.other"), VimMode::Normal);
+        assert!(Settings::default().startup_mode_per_app.is_empty());
+
+        let modes = &settings.startup_mode_per_app;
+        let mode = |bundle_id| startup_mode_for_app(modes, VimMode::Visual, bundle_id);
+        assert_eq!(mode("com.apple.Terminal"), VimMode::Insert);
+        assert_eq!(mode("com.example.other"), VimMode::Visual);
+    }
+
+    #[test]
+    fn test_key_repeat_from_yaml() {
+        let settings: Settings = serde_yml::from_str("normal_mode_key_repeat: disabled").unwrap();
+        assert_eq!(settings.normal_mode_key_repeat, KeyRepeat::Disabled);
+        let settings: Settings = serde_yml::from_str("normal_mode_key_repeat: !custom 80").unwrap();
+        assert_eq!(settings.normal_mode_key_repeat, KeyRepeat::Custom(80));
+        assert_eq!(Settings::default().normal_mode_key_repeat, KeyRepeat::OsDefault);
+    }
+
+    #[test]
+    fn test_resets_caps_lock_only_for_caps_lock_vim_key() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.vim_key, "caps_lock");
+        assert!(settings.resets_caps_lock());
+
+        settings.preserve_caps_lock_state = false;
+        assert!(!settings.resets_caps_lock());
+
+        settings.preserve_caps_lock_state = true;
+        settings.vim_key_modifiers.shift = true;
+        assert!(!settings.resets_caps_lock());
+
+        settings.vim_key_modifiers.shift = false;
+        settings.vim_key = "escape".to_string();
+        assert!(!settings.resets_caps_lock());
+    }
+
+    #[test]
+    fn test_indicator_per_mode_visibility() {
+        let settings: Settings =
+            serde_yml::from_str("indicator_per_mode_visibility: { insert: false }").unwrap();
+        assert!(!settings.indicator_shown(VimMode::Insert));
+        assert!(settings.indicator_shown(VimMode::Normal));
+        assert!(settings.indicator_shown(VimMode::Visual));
+
+        // Disabling vim mode hides it everywhere
+        let settings = Settings { enabled: false, ..settings };
+        assert!(!settings.indicator_shown(VimMode::Normal));
+    }
+
+    #[test]
+    fn test_indicator_visible_migration() {
+        let mut settings: Settings = serde_yml::from_str("indicator_visible: false").unwrap();
+        settings.migrate();
+        assert_eq!(settings.indicator_per_mode_visibility, IndicatorVisibility::HIDDEN);
+        assert!(!serde_yml::to_string(&settings).unwrap().contains("indicator_visible:"));
+
+        let mut settings: Settings = serde_yml::from_str("indicator_visible: true").unwrap();
+        settings.migrate();
+        assert_eq!(settings.indicator_per_mode_visibility, IndicatorVisibility::default());
+    }
+
+    #[test]
+    fn test_vim_key_chord_keys() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.vim_key_chord_keys(), None);
+
+        settings.vim_key_chord = vec!["control".to_string(), "right_control".to_string()];
+        assert_eq!(
+            settings.vim_key_chord_keys(),
+            Some(vec![KeyCode::Control, KeyCode::RightControl])
+        );
+        // Only modifiers make a chord
+        settings.vim_key_chord = vec!["control".to_string(), "j".to_string()];
+        assert_eq!(settings.vim_key_chord_keys(), None);
+        settings.vim_key_chord = vec!["caps_lock".to_string()];
+        assert_eq!(settings.vim_key_chord_keys(), None);
+    }
+
+    #[test]
+    fn test_batch_shortcut_modifiers() {
+        let mut settings = NvimEditSettings::default();
+        let batch = settings.batch_shortcut_modifiers().unwrap();
+        assert!(batch.command && batch.shift && batch.option && !batch.control);
+
+        settings.batch_modifier = "control".to_string();
+        let batch = settings.batch_shortcut_modifiers().unwrap();
+        assert!(batch.control && !batch.option);
+        // Already held for the shortcut, so it can't tell batch editing apart
+        settings.batch_modifier = "shift".to_string();
+        assert_eq!(settings.batch_shortcut_modifiers(), None);
+        settings.batch_modifier = String::new();
+        assert_eq!(settings.batch_shortcut_modifiers(), None);
+    }
+
+    #[test]
+    fn test_double_tap_key() {
+        let mut settings = NvimEditSettings::default();
+        assert_eq!(settings.double_tap_key(), None);
+
+        settings.trigger_on_double_tap = "right_command".to_string();
+        assert_eq!(settings.double_tap_key(), Some(KeyCode::RightCommand));
+        // Only modifiers can be double tapped, and not with the Edit Popup off
+        settings.trigger_on_double_tap = "e".to_string();
+        assert_eq!(settings.double_tap_key(), None);
+        settings.trigger_on_double_tap = "option".to_string();
+        settings.enabled = false;
+        assert_eq!(settings.double_tap_key(), None);
+    }
+
+    #[test]
+    fn test_vim_key_modifier_side() {
+        let right_command = Modifiers {
+            command: true,
+            right: SideModifiers { command: true, ..Default::default() },
+            ..Default::default()
+        };
+        let left_command = Modifiers {
+            command: true,
+            left: SideModifiers { command: true, ..Default::default() },
+            ..Default::default()
+        };
+        let mut mods = VimKeyModifiers { command: true, ..Default::default() };
+        assert!(mods.matches(KeyCode::J, &right_command));
+        assert!(mods.matches(KeyCode::J, &left_command));
+
+        mods.side = ModifierSide::Right;
+        assert!(mods.matches(KeyCode::J, &right_command));
+        assert!(!mods.matches(KeyCode::J, &left_command));
+    }
+
+    #[test]
+    fn test_modifier_vim_key_ignores_its_own_flag() {
+        let pressed = Modifiers {
+            command: true,
+            right: SideModifiers { command: true, ..Default::default() },
+            ..Default::default()
+        };
+        let mods = VimKeyModifiers::default();
+        assert!(mods.matches(KeyCode::RightCommand, &pressed));
+        // Right Command held while pressing another key is not a bare key press
+        assert!(!mods.matches(KeyCode::J, &pressed));
+    }
+    #[test]
+    fn test_cursor_position_from_offset() {
+        let text = "first\nsecond line\n";
+        assert_eq!(CursorPosition::from_offset(text, 0), Some(CursorPosition { line: 1, column: 1 }));
+        assert_eq!(CursorPosition::from_offset(text, 5), Some(CursorPosition { line: 1, column: 6 }));
+        assert_eq!(CursorPosition::from_offset(text, 6), Some(CursorPosition { line: 2, column: 1 }));
+        assert_eq!(CursorPosition::from_offset(text, 13), Some(CursorPosition { line: 2, column: 8 }));
+        assert_eq!(CursorPosition::from_offset(text, 18), Some(CursorPosition { line: 3, column: 1 }));
+        assert_eq!(CursorPosition::from_offset(text, 19), None);
+        // Offsets are in chars
+        assert_eq!(CursorPosition::from_offset("héllo", 2), Some(CursorPosition { line: 1, column: 3 }));
+    }
+
+    #[test]
+    fn test_config_args() {
+        let init = std::path::Path::new("/tmp/ovim/minimal_init.vim");
+        assert_eq!(EditorType::Neovim.config_args(init), vec!["-u", "/tmp/ovim/minimal_init.vim"]);
+        assert_eq!(EditorType::Vim.config_args(init), vec!["-u", "/tmp/ovim/minimal_init.vim"]);
+        assert_eq!(EditorType::Helix.config_args(init), vec!["--config", "/tmp/ovim/minimal_init.vim"]);
+        // A custom editor's flags aren't known
+        assert!(EditorType::Custom.config_args(init).is_empty());
+    }
+
+    #[test]
+    fn test_vscode_editor_type() {
+        assert_eq!(EditorType::from_string("code"), EditorType::VSCode);
+        assert_eq!(EditorType::from_string("Cursor"), EditorType::VSCode);
+        assert_eq!(serde_json::to_string(&EditorType::VSCode).unwrap(), "\"vscode\"");
+        assert!(EditorType::VSCode.is_gui());
+        assert!(!EditorType::Neovim.is_gui());
+        // Where it opens is passed with the file, so there are no editor args
+        let settings = NvimEditSettings {
+            editor: EditorType::VSCode,
+            open_at_cursor_line: true,
+            ..Default::default()
+        };
+        assert!(settings.editor_args(Some(CursorPosition { line: 2, column: 1 })).is_empty());
+    }
+
+    #[test]
+    fn test_editor_args_open_at_cursor() {
+        let position = Some(CursorPosition { line: 12, column: 4 });
+        let mut settings = NvimEditSettings {
+            open_at_cursor_line: true,
+            ..Default::default()
+        };
+        assert_eq!(settings.editor_args(position), vec!["+12", "+normal 4|"]);
+        // Without the caret, the editor opens at the end as before
+        assert_eq!(settings.editor_args(None), vec!["+normal G$"]);
+
+        settings.editor = EditorType::Helix;
+        assert!(settings.editor_args(position).is_empty());
+
+        settings.editor = EditorType::Vim;
+        settings.open_at_cursor_line = false;
+        assert_eq!(settings.editor_args(position), vec!["+normal G$"]);
+    }
 }
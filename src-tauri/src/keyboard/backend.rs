@@ -0,0 +1,32 @@
+//! Platform keyboard layer
+//!
+//! Capture and injection are behind `KeyboardBackend`, with a CGEventTap implementation on
+//! macOS, a low-level hook/`SendInput` one on Windows and an evdev/uinput one on Linux. Key
+//! codes and shortcuts are expressed in macOS terms throughout; other platforms translate them.
+
+use super::inject::InjectError;
+use super::keycode::{KeyCode, KeyEvent, Modifiers};
+
+pub type KeyEventCallback = Box<dyn Fn(KeyEvent) -> Option<KeyEvent> + Send + 'static>;
+
+/// Captures key events and injects synthetic ones
+pub trait KeyboardBackend {
+    /// Set the callback for key events
+    /// Return Some(event) to pass through (possibly modified)
+    /// Return None to suppress the event
+    fn set_callback<F>(&self, callback: F)
+    where
+        F: Fn(KeyEvent) -> Option<KeyEvent> + Send + 'static;
+
+    /// Start capturing keyboard events
+    fn start(&self) -> Result<(), String>;
+
+    /// Stop capturing keyboard events
+    fn stop(&self);
+
+    /// Check if currently capturing
+    fn is_running(&self) -> bool;
+
+    /// Post a synthetic key event, marked so capture skips it
+    fn inject_key(keycode: KeyCode, key_down: bool, modifiers: Modifiers) -> Result<(), InjectError>;
+}
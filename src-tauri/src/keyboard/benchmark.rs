@@ -0,0 +1,76 @@
+//! Timing injected key events, to check how fast injection is on this machine
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use super::inject::{inject_arrow, ArrowDirection};
+use super::keycode::Modifiers;
+
+/// Right arrows a benchmark injects when no count is given
+pub const DEFAULT_BENCHMARK_EVENTS: u32 = 100;
+/// Most right arrows a benchmark injects
+pub const MAX_BENCHMARK_EVENTS: u32 = 1000;
+
+/// Latency of a run of injected key presses, in microseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct InjectionBenchmark {
+    pub events: u32,
+    pub average_us: f64,
+    pub min_us: u64,
+    pub max_us: u64,
+    pub total_us: u64,
+}
+
+impl InjectionBenchmark {
+    /// Summary of the time each key press took
+    pub fn from_timings(timings: &[Duration]) -> Self {
+        let micros: Vec<u64> = timings.iter().map(|t| t.as_micros() as u64).collect();
+        let total_us: u64 = micros.iter().sum();
+        let events = micros.len() as u32;
+        Self {
+            events,
+            average_us: if events == 0 { 0.0 } else { total_us as f64 / events as f64 },
+            min_us: micros.iter().copied().min().unwrap_or(0),
+            max_us: micros.iter().copied().max().unwrap_or(0),
+            total_us,
+        }
+    }
+}
+
+/// Inject `count` right arrows into the focused field, timing each press
+pub fn benchmark_right_arrows(count: u32) -> Result<InjectionBenchmark, String> {
+    let mut timings = Vec::with_capacity(count as usize);
+    for _ in 0..count.min(MAX_BENCHMARK_EVENTS) {
+        let start = Instant::now();
+        inject_arrow(ArrowDirection::Right, Modifiers::default())?;
+        timings.push(start.elapsed());
+    }
+    Ok(InjectionBenchmark::from_timings(&timings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_timings() {
+        let timings = [
+            Duration::from_micros(100),
+            Duration::from_micros(300),
+            Duration::from_micros(200),
+            Duration::from_micros(400),
+        ];
+        let benchmark = InjectionBenchmark::from_timings(&timings);
+        assert_eq!(benchmark.events, 4);
+        assert_eq!(benchmark.average_us, 250.0);
+        assert_eq!(benchmark.min_us, 100);
+        assert_eq!(benchmark.max_us, 400);
+        assert_eq!(benchmark.total_us, 1000);
+    }
+
+    #[test]
+    fn test_no_timings() {
+        assert_eq!(InjectionBenchmark::from_timings(&[]), InjectionBenchmark::default());
+    }
+}
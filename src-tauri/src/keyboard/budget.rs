@@ -0,0 +1,86 @@
+//! Keeps slow checks from stalling the event tap
+//!
+//! The tap callback runs on the thread that delivers every key press system-wide, so a
+//! check that can hang, e.g. an accessibility read of a busy app, runs on a worker thread
+//! and the tap only waits for it up to a budget.
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// A long-lived thread that runs one check on request, so the tap doesn't start a thread per key
+/// The thread exits once the worker is dropped
+pub struct Worker<T> {
+    requests: mpsc::Sender<mpsc::Sender<T>>,
+}
+
+impl<T: Clone + Send + 'static> Worker<T> {
+    pub fn spawn<F>(check: F) -> Self
+    where
+        F: Fn() -> T + Send + 'static,
+    {
+        let (requests, rx) = mpsc::channel::<mpsc::Sender<T>>();
+        thread::spawn(move || {
+            while let Ok(reply) = rx.recv() {
+                // Requests that queued up while a check hung share the next answer
+                let mut replies = vec![reply];
+                replies.extend(rx.try_iter());
+                let answer = check();
+                for reply in replies {
+                    let _ = reply.send(answer.clone());
+                }
+            }
+        });
+        Self { requests }
+    }
+
+    /// Run the check, waiting at most `budget` for its answer
+    /// None when it took longer; the answer is dropped once it comes
+    pub fn run_within(&self, budget: Duration) -> Option<T> {
+        let (tx, rx) = mpsc::channel();
+        self.requests.send(tx).ok()?;
+        rx.recv_timeout(budget).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    #[test]
+    fn test_quick_check_answers() {
+        let worker = Worker::spawn(|| 42);
+        assert_eq!(worker.run_within(Duration::from_secs(1)), Some(42));
+        assert_eq!(worker.run_within(Duration::from_secs(1)), Some(42));
+    }
+
+    #[test]
+    fn test_slow_check_gives_up_at_budget() {
+        let worker = Worker::spawn(|| {
+            thread::sleep(Duration::from_millis(300));
+            true
+        });
+        let start = Instant::now();
+        assert_eq!(worker.run_within(Duration::from_millis(20)), None);
+        assert!(start.elapsed() < Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_queued_requests_share_one_check() {
+        let checks = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&checks);
+        let worker = Worker::spawn(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(100));
+        });
+        // The first check is running while the others queue up
+        for _ in 0..5 {
+            worker.run_within(Duration::from_millis(1));
+        }
+        assert_eq!(worker.run_within(Duration::from_secs(5)), Some(()));
+        assert!(checks.load(Ordering::SeqCst) <= 3, "ran {} checks", checks.load(Ordering::SeqCst));
+    }
+}
@@ -5,23 +5,27 @@ use std::time::Duration;
 
 use core_foundation::runloop::{kCFRunLoopDefaultMode, CFRunLoop};
 use core_graphics::event::{
-    CGEventTap, CGEventTapLocation, CGEventTapOptions, CGEventTapPlacement,
-    CGEventTapProxy, CGEventType, EventField, CallbackResult,
+    CGEvent, CGEventFlags, CGEventTap, CGEventTapLocation, CGEventTapOptions,
+    CGEventTapPlacement, CGEventTapProxy, CGEventType, EventField, CallbackResult, ScrollEventUnit,
 };
+use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+use core_graphics::geometry::CGPoint;
 
-use super::inject::INJECTED_EVENT_MARKER;
-use super::keycode::{KeyEvent, Modifiers};
-
-pub type KeyEventCallback = Box<dyn Fn(KeyEvent) -> Option<KeyEvent> + Send + 'static>;
+use super::backend::{KeyEventCallback, KeyboardBackend};
+use super::inject::{InjectError, INJECTED_EVENT_MARKER};
+use super::keycode::{KeyCode, KeyEvent, Modifiers};
 
 /// Helper to compare CGEventType (which doesn't implement PartialEq)
 fn is_event_type(event_type: CGEventType, expected: CGEventType) -> bool {
     (event_type as u32) == (expected as u32)
 }
 
+type MouseDownCallback = Box<dyn Fn() + Send + 'static>;
+
 /// Keyboard capture using CGEventTap
 pub struct KeyboardCapture {
     callback: Arc<Mutex<Option<KeyEventCallback>>>,
+    mouse_down_callback: Arc<Mutex<Option<MouseDownCallback>>>,
     running: Arc<Mutex<bool>>,
 }
 
@@ -29,14 +33,22 @@ impl KeyboardCapture {
     pub fn new() -> Self {
         Self {
             callback: Arc::new(Mutex::new(None)),
+            mouse_down_callback: Arc::new(Mutex::new(None)),
             running: Arc::new(Mutex::new(false)),
         }
     }
 
-    /// Set the callback for key events
-    /// Return Some(event) to pass through (possibly modified)
-    /// Return None to suppress the event
-    pub fn set_callback<F>(&self, callback: F)
+    /// Set the callback for left mouse button presses, which always pass through
+    pub fn set_mouse_down_callback<F>(&self, callback: F)
+    where
+        F: Fn() + Send + 'static,
+    {
+        *self.mouse_down_callback.lock().unwrap() = Some(Box::new(callback));
+    }
+}
+
+impl KeyboardBackend for KeyboardCapture {
+    fn set_callback<F>(&self, callback: F)
     where
         F: Fn(KeyEvent) -> Option<KeyEvent> + Send + 'static,
     {
@@ -46,7 +58,7 @@ impl KeyboardCapture {
 
     /// Start capturing keyboard events
     /// This spawns a new thread with its own run loop
-    pub fn start(&self) -> Result<(), String> {
+    fn start(&self) -> Result<(), String> {
         let mut running = self.running.lock().unwrap();
         if *running {
             return Ok(());
@@ -55,6 +67,7 @@ impl KeyboardCapture {
         drop(running);
 
         let callback = Arc::clone(&self.callback);
+        let mouse_down_callback = Arc::clone(&self.mouse_down_callback);
         let running_flag = Arc::clone(&self.running);
 
         // Flag to signal that tap needs re-enabling
@@ -71,6 +84,8 @@ impl KeyboardCapture {
                     CGEventType::KeyDown,
                     CGEventType::KeyUp,
                     CGEventType::FlagsChanged,
+                    // Only presses of the primary button, to keep mouse traffic out of the tap
+                    CGEventType::LeftMouseDown,
                 ],
                 move |_proxy: CGEventTapProxy, event_type: CGEventType, event| -> CallbackResult {
                     // Handle tap disabled by timeout - signal re-enable
@@ -94,8 +109,14 @@ impl KeyboardCapture {
                         return CallbackResult::Keep;
                     }
 
-                    // Skip FlagsChanged events (modifier key changes) - pass through
-                    if is_event_type(event_type, CGEventType::FlagsChanged) {
+                    if is_event_type(event_type, CGEventType::LeftMouseDown) {
+                        // Callbacks are only locked here and when they're replaced, so a
+                        // busy lock is a replacement underway; the click isn't waited on
+                        if let Ok(cb_lock) = mouse_down_callback.try_lock() {
+                            if let Some(ref cb) = *cb_lock {
+                                cb();
+                            }
+                        }
                         return CallbackResult::Keep;
                     }
 
@@ -103,16 +124,31 @@ impl KeyboardCapture {
                     let keycode = event.get_integer_value_field(EventField::KEYBOARD_EVENT_KEYCODE) as u16;
                     log::trace!("Key event: keycode={}, type={:?}", keycode, event_type);
                     let flags = event.get_flags();
-                    let is_key_down = is_event_type(event_type, CGEventType::KeyDown);
 
-                    let key_event = KeyEvent {
-                        code: keycode,
-                        modifiers: Modifiers::from_cg_flags(flags.bits()),
-                        is_key_down,
+                    let key_event = if is_event_type(event_type, CGEventType::FlagsChanged) {
+                        // Modifier key presses are reported as key downs, so a modifier can be
+                        // the vim key, and releases as key ups, to detect modifier chords
+                        match KeyEvent::modifier_change(keycode, flags.bits()) {
+                            Some(key_event) => key_event,
+                            None => return CallbackResult::Keep,
+                        }
+                    } else {
+                        KeyEvent {
+                            code: keycode,
+                            modifiers: Modifiers::from_cg_flags(flags.bits()),
+                            is_key_down: is_event_type(event_type, CGEventType::KeyDown),
+                        }
                     };
 
-                    // Call user callback
-                    let cb_lock = callback.lock().unwrap();
+                    // Call user callback. The tap holds up every key press system-wide, so
+                    // it never waits for the lock; the key passes through instead
+                    let cb_lock = match callback.try_lock() {
+                        Ok(cb_lock) => cb_lock,
+                        Err(_) => {
+                            log::debug!("capture: callback busy, passing through keycode={}", keycode);
+                            return CallbackResult::Keep;
+                        }
+                    };
                     if let Some(ref cb) = *cb_lock {
                         match cb(key_event) {
                             Some(_modified_event) => {
@@ -181,16 +217,45 @@ impl KeyboardCapture {
         Ok(())
     }
 
-    /// Stop capturing keyboard events
-    pub fn stop(&self) {
+    fn stop(&self) {
         let mut running = self.running.lock().unwrap();
         *running = false;
     }
 
-    /// Check if currently capturing
-    pub fn is_running(&self) -> bool {
+    fn is_running(&self) -> bool {
         *self.running.lock().unwrap()
     }
+
+    fn inject_key(keycode: KeyCode, key_down: bool, modifiers: Modifiers) -> Result<(), InjectError> {
+        let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
+            .map_err(|_| InjectError::EventSource)?;
+
+        let event = CGEvent::new_keyboard_event(source, keycode.as_raw(), key_down)
+            .map_err(|_| InjectError::Event)?;
+
+        let flags = CGEventFlags::from_bits_truncate(modifiers.to_cg_flags());
+        event.set_flags(flags);
+
+        // Mark the event as injected by us so we don't capture it again
+        event.set_integer_value_field(EventField::EVENT_SOURCE_USER_DATA, INJECTED_EVENT_MARKER);
+
+        event.post(CGEventTapLocation::HID);
+
+        Ok(())
+    }
+}
+
+/// Scroll the view under (`x`, `y`) by `pixels`, as a trackpad would
+/// Positive values scroll the content down, showing what's above
+pub fn post_scroll_wheel(x: f64, y: f64, pixels: i32) -> Result<(), String> {
+    let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
+        .map_err(|_| "Failed to create event source")?;
+    let event = CGEvent::new_scroll_event(source, ScrollEventUnit::PIXEL, 1, pixels, 0, 0)
+        .map_err(|_| "Failed to create scroll event")?;
+    // Scroll events go to the view under their location
+    event.set_location(CGPoint::new(x, y));
+    event.post(CGEventTapLocation::HID);
+    Ok(())
 }
 
 impl Default for KeyboardCapture {
@@ -0,0 +1,173 @@
+//! Detection of a chord of modifier keys used as a toggle, e.g. both Control keys
+//!
+//! The chord counts once all its keys were held together and then released, with no
+//! other key pressed in between, so shortcuts that start with the same modifiers
+//! don't trigger it.
+
+use super::keycode::{KeyCode, KeyEvent, Modifiers};
+
+/// Modifier keys a chord can be made of
+const CHORD_KEYS: [KeyCode; 9] = [
+    KeyCode::Shift,
+    KeyCode::RightShift,
+    KeyCode::Control,
+    KeyCode::RightControl,
+    KeyCode::Option,
+    KeyCode::RightOption,
+    KeyCode::Command,
+    KeyCode::RightCommand,
+    KeyCode::Function,
+];
+
+/// Whether `key` can be part of a chord
+pub fn is_chord_key(key: KeyCode) -> bool {
+    CHORD_KEYS.contains(&key)
+}
+
+/// Chord keys held in `modifiers`
+pub(super) fn held_keys(modifiers: &Modifiers) -> Vec<KeyCode> {
+    CHORD_KEYS
+        .into_iter()
+        .filter(|&key| modifiers.is_key_held(key) == Some(true))
+        .collect()
+}
+
+/// Follows modifier presses and releases to find when a chord was pressed on its own
+#[derive(Debug, Default)]
+pub struct ChordDetector {
+    /// All of the chord's keys have been held together since modifiers were last all up
+    complete: bool,
+    /// Another key was pressed with the modifiers, so they were used for a shortcut
+    spoiled: bool,
+}
+
+impl ChordDetector {
+    /// Feed a key event; returns true on the release that ends a clean press of `chord`
+    /// Modifier events must include releases
+    pub fn update(&mut self, chord: &[KeyCode], event: &KeyEvent) -> bool {
+        let held = held_keys(&event.modifiers);
+        if !event.keycode().is_some_and(KeyCode::is_modifier) {
+            if event.is_key_down {
+                self.complete = false;
+                self.spoiled = !held.is_empty();
+            }
+            return false;
+        }
+
+        if held.is_empty() {
+            let triggered = self.complete && !self.spoiled;
+            *self = Self::default();
+            return triggered;
+        }
+        if held.iter().any(|key| !chord.contains(key)) {
+            self.spoiled = true;
+        } else if chord.iter().all(|key| held.contains(key)) {
+            self.complete = true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::keycode::SideModifiers;
+
+    const BOTH_CONTROLS: [KeyCode; 2] = [KeyCode::Control, KeyCode::RightControl];
+
+    fn modifier(keycode: KeyCode, left: bool, right: bool) -> KeyEvent {
+        KeyEvent {
+            code: keycode.as_raw(),
+            modifiers: Modifiers {
+                control: left || right,
+                left: SideModifiers { control: left, ..Default::default() },
+                right: SideModifiers { control: right, ..Default::default() },
+                ..Default::default()
+            },
+            is_key_down: true,
+        }
+    }
+
+    fn key(keycode: KeyCode, modifiers: Modifiers) -> KeyEvent {
+        KeyEvent { code: keycode.as_raw(), modifiers, is_key_down: true }
+    }
+
+    #[test]
+    fn test_chord_triggers_on_release() {
+        let mut detector = ChordDetector::default();
+        assert!(!detector.update(&BOTH_CONTROLS, &modifier(KeyCode::Control, true, false)));
+        assert!(!detector.update(&BOTH_CONTROLS, &modifier(KeyCode::RightControl, true, true)));
+        assert!(!detector.update(&BOTH_CONTROLS, &modifier(KeyCode::Control, false, true)));
+        assert!(detector.update(&BOTH_CONTROLS, &modifier(KeyCode::RightControl, false, false)));
+    }
+
+    #[test]
+    fn test_part_of_the_chord_does_nothing() {
+        let mut detector = ChordDetector::default();
+        detector.update(&BOTH_CONTROLS, &modifier(KeyCode::Control, true, false));
+        assert!(!detector.update(&BOTH_CONTROLS, &modifier(KeyCode::Control, false, false)));
+    }
+
+    #[test]
+    fn test_shortcut_with_the_chord_held_does_nothing() {
+        let mut detector = ChordDetector::default();
+        detector.update(&BOTH_CONTROLS, &modifier(KeyCode::Control, true, false));
+        let both = modifier(KeyCode::RightControl, true, true);
+        detector.update(&BOTH_CONTROLS, &both);
+        detector.update(&BOTH_CONTROLS, &key(KeyCode::A, both.modifiers));
+        detector.update(&BOTH_CONTROLS, &modifier(KeyCode::Control, false, true));
+        assert!(!detector.update(&BOTH_CONTROLS, &modifier(KeyCode::RightControl, false, false)));
+
+        // The next clean press counts again
+        detector.update(&BOTH_CONTROLS, &modifier(KeyCode::Control, true, false));
+        detector.update(&BOTH_CONTROLS, &modifier(KeyCode::RightControl, true, true));
+        assert!(detector.update(&BOTH_CONTROLS, &modifier(KeyCode::RightControl, false, false)));
+    }
+
+    #[test]
+    fn test_extra_modifier_does_nothing() {
+        let chord = [KeyCode::Control, KeyCode::Command];
+        let mut detector = ChordDetector::default();
+        let control = Modifiers {
+            control: true,
+            left: SideModifiers { control: true, ..Default::default() },
+            ..Default::default()
+        };
+        detector.update(&chord, &key(KeyCode::Control, control));
+        let control_command = Modifiers {
+            command: true,
+            left: SideModifiers { command: true, ..control.left },
+            ..control
+        };
+        detector.update(&chord, &key(KeyCode::Command, control_command));
+        let all = Modifiers {
+            shift: true,
+            left: SideModifiers { shift: true, ..control_command.left },
+            ..control_command
+        };
+        detector.update(&chord, &key(KeyCode::Shift, all));
+        assert!(!detector.update(&chord, &key(KeyCode::Shift, Modifiers::default())));
+    }
+
+    #[test]
+    fn test_typing_before_the_chord() {
+        let mut detector = ChordDetector::default();
+        detector.update(&BOTH_CONTROLS, &key(KeyCode::A, Modifiers::default()));
+        detector.update(&BOTH_CONTROLS, &modifier(KeyCode::Control, true, false));
+        detector.update(&BOTH_CONTROLS, &modifier(KeyCode::RightControl, true, true));
+        assert!(detector.update(&BOTH_CONTROLS, &modifier(KeyCode::RightControl, false, false)));
+    }
+
+    #[test]
+    fn test_function_key_chord() {
+        let chord = [KeyCode::Function];
+        let mut detector = ChordDetector::default();
+        let function = Modifiers { function: true, ..Default::default() };
+        detector.update(&chord, &key(KeyCode::Function, function));
+        assert!(detector.update(&chord, &key(KeyCode::Function, Modifiers::default())));
+        // Fn held for an arrow key is a shortcut
+        detector.update(&chord, &key(KeyCode::Function, function));
+        detector.update(&chord, &key(KeyCode::Left, function));
+        assert!(!detector.update(&chord, &key(KeyCode::Function, Modifiers::default())));
+    }
+}
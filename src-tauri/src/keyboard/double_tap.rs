@@ -0,0 +1,137 @@
+//! Detection of a modifier key tapped twice in a row, e.g. Command Command
+//!
+//! A tap is the key pressed and released on its own, with no other key in between, so
+//! shortcuts that start with the modifier don't count. Like a chord (see `chord`), it's
+//! read from modifier events only and their releases pass through.
+
+use std::time::{Duration, Instant};
+
+use super::chord::held_keys;
+use super::keycode::{KeyCode, KeyEvent};
+
+/// Longest time between the two releases of a double tap
+pub const DOUBLE_TAP_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Follows modifier presses and releases to find a key tapped twice
+#[derive(Debug, Default)]
+pub struct DoubleTapDetector {
+    /// The key is held on its own, with nothing else pressed since
+    pressed: bool,
+    /// Release of the last clean tap, waiting for the second one
+    last_tap: Option<Instant>,
+}
+
+impl DoubleTapDetector {
+    /// Feed a key event at `now`; returns true on the release that ends a second tap of
+    /// `key` within `DOUBLE_TAP_INTERVAL` of the first. Modifier events must include releases
+    pub fn update(&mut self, key: KeyCode, event: &KeyEvent, now: Instant) -> bool {
+        if !event.keycode().is_some_and(KeyCode::is_modifier) {
+            if event.is_key_down {
+                *self = Self::default();
+            }
+            return false;
+        }
+
+        let held = held_keys(&event.modifiers);
+        if held.is_empty() {
+            if !std::mem::take(&mut self.pressed) {
+                return false;
+            }
+            let second = self
+                .last_tap
+                .is_some_and(|first| now.saturating_duration_since(first) <= DOUBLE_TAP_INTERVAL);
+            self.last_tap = if second { None } else { Some(now) };
+            return second;
+        }
+        if held == [key] {
+            self.pressed = true;
+        } else {
+            *self = Self::default();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::keycode::{Modifiers, SideModifiers};
+
+    fn command(held: bool) -> KeyEvent {
+        KeyEvent {
+            code: KeyCode::Command.as_raw(),
+            modifiers: Modifiers {
+                command: held,
+                left: SideModifiers { command: held, ..Default::default() },
+                ..Default::default()
+            },
+            is_key_down: true,
+        }
+    }
+
+    /// Press and release Command at `at`, returning whether the release triggered
+    fn tap(detector: &mut DoubleTapDetector, at: Instant) -> bool {
+        detector.update(KeyCode::Command, &command(true), at);
+        detector.update(KeyCode::Command, &command(false), at)
+    }
+
+    #[test]
+    fn test_double_tap_triggers_on_second_release() {
+        let mut detector = DoubleTapDetector::default();
+        let start = Instant::now();
+        assert!(!tap(&mut detector, start));
+        assert!(tap(&mut detector, start + Duration::from_millis(150)));
+        // A third tap starts over
+        assert!(!tap(&mut detector, start + Duration::from_millis(300)));
+    }
+
+    #[test]
+    fn test_slow_taps_do_nothing() {
+        let mut detector = DoubleTapDetector::default();
+        let start = Instant::now();
+        assert!(!tap(&mut detector, start));
+        assert!(!tap(&mut detector, start + DOUBLE_TAP_INTERVAL + Duration::from_millis(1)));
+        // The slow second tap can be the first of a new double tap
+        assert!(tap(&mut detector, start + DOUBLE_TAP_INTERVAL + Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_shortcut_between_taps_does_nothing() {
+        let mut detector = DoubleTapDetector::default();
+        let start = Instant::now();
+        assert!(!tap(&mut detector, start));
+        // Cmd+C
+        detector.update(KeyCode::Command, &command(true), start);
+        let copy = KeyEvent { code: KeyCode::C.as_raw(), ..command(true) };
+        detector.update(KeyCode::Command, &copy, start);
+        assert!(!detector.update(KeyCode::Command, &command(false), start));
+        assert!(!tap(&mut detector, start + Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_typing_between_taps_does_nothing() {
+        let mut detector = DoubleTapDetector::default();
+        let start = Instant::now();
+        assert!(!tap(&mut detector, start));
+        let typed = KeyEvent { code: KeyCode::A.as_raw(), ..command(false) };
+        detector.update(KeyCode::Command, &typed, start);
+        assert!(!tap(&mut detector, start + Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_other_modifier_does_nothing() {
+        let mut detector = DoubleTapDetector::default();
+        let start = Instant::now();
+        let shift = Modifiers {
+            shift: true,
+            left: SideModifiers { shift: true, ..Default::default() },
+            ..Default::default()
+        };
+        let pressed = KeyEvent { code: KeyCode::Shift.as_raw(), modifiers: shift, is_key_down: true };
+        let released = KeyEvent { modifiers: Modifiers::default(), ..pressed };
+        detector.update(KeyCode::Command, &pressed, start);
+        detector.update(KeyCode::Command, &released, start);
+        detector.update(KeyCode::Command, &pressed, start);
+        assert!(!detector.update(KeyCode::Command, &released, start));
+    }
+}
@@ -1,33 +1,187 @@
-use core_graphics::event::{CGEvent, CGEventFlags, CGEventTapLocation, EventField};
-use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
-
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::backend::KeyboardBackend;
 use super::keycode::{KeyCode, Modifiers};
+use super::KeyboardCapture;
 
 /// Custom user data field to mark our injected events
 /// We use a high value that's unlikely to conflict with real keycodes
 pub const INJECTED_EVENT_MARKER: i64 = 0x54495649; // "TIVI" in hex
 
-/// Inject a single key event
-pub fn inject_key(keycode: KeyCode, key_down: bool, modifiers: Modifiers) -> Result<(), String> {
-    let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
-        .map_err(|_| "Failed to create event source")?;
+/// Attempts at posting a key event before giving up on it
+const INJECT_ATTEMPTS: u32 = 3;
+/// Pause before posting a key event again
+const INJECT_RETRY_DELAY: Duration = Duration::from_millis(2);
+/// How far back failed injections are counted
+pub const INJECT_FAILURE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Why a key event couldn't be posted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InjectError {
+    /// The event source couldn't be created, e.g. while the system is busy
+    EventSource,
+    /// The event itself couldn't be created
+    Event,
+    /// The platform has no key for the key code
+    Unmapped(KeyCode),
+    /// The platform refused the event, e.g. a missing permission or device
+    Rejected(String),
+}
+
+impl InjectError {
+    /// Whether posting the event again may work, with a new event source
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::EventSource | Self::Event)
+    }
+}
 
-    let event = CGEvent::new_keyboard_event(source, keycode.as_raw(), key_down)
-        .map_err(|_| "Failed to create keyboard event")?;
+impl fmt::Display for InjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EventSource => write!(f, "Failed to create event source"),
+            Self::Event => write!(f, "Failed to create keyboard event"),
+            Self::Unmapped(keycode) => write!(f, "No platform key for {:?}", keycode),
+            Self::Rejected(message) => write!(f, "{}", message),
+        }
+    }
+}
 
-    let flags = CGEventFlags::from_bits_truncate(modifiers.to_cg_flags());
-    event.set_flags(flags);
+impl std::error::Error for InjectError {}
 
-    // Mark the event as injected by us so we don't capture it again
-    event.set_integer_value_field(EventField::EVENT_SOURCE_USER_DATA, INJECTED_EVENT_MARKER);
+impl From<String> for InjectError {
+    fn from(message: String) -> Self {
+        Self::Rejected(message)
+    }
+}
 
-    event.post(CGEventTapLocation::HID);
+impl From<InjectError> for String {
+    fn from(err: InjectError) -> Self {
+        err.to_string()
+    }
+}
 
-    Ok(())
+/// Run `post` up to `attempts` times while it fails with a transient error
+fn with_retry<F>(attempts: u32, delay: Duration, mut post: F) -> Result<(), InjectError>
+where
+    F: FnMut() -> Result<(), InjectError>,
+{
+    let mut attempt = 1;
+    loop {
+        match post() {
+            Err(e) if e.is_transient() && attempt < attempts => {
+                log::debug!("Key injection failed ({}), retrying", e);
+                attempt += 1;
+                thread::sleep(delay);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Times of recent failed injections, oldest first
+static FAILURES: Mutex<VecDeque<Instant>> = Mutex::new(VecDeque::new());
+
+/// Note a failed injection at `now`, forgetting those older than the window
+fn record_failure(failures: &mut VecDeque<Instant>, now: Instant) {
+    failures.push_back(now);
+    forget_old_failures(failures, now);
+}
+
+fn forget_old_failures(failures: &mut VecDeque<Instant>, now: Instant) {
+    while failures
+        .front()
+        .is_some_and(|&at| now.duration_since(at) > INJECT_FAILURE_WINDOW)
+    {
+        failures.pop_front();
+    }
+}
+
+/// Key events that failed to post within `INJECT_FAILURE_WINDOW`, after retries
+pub fn recent_inject_failures() -> usize {
+    let mut failures = FAILURES.lock().unwrap();
+    forget_old_failures(&mut failures, Instant::now());
+    failures.len()
+}
+
+/// Where pacing reads the time and waits, so tests can stand in for it
+trait Clock {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+/// Spaces out the key events of one command
+#[derive(Debug, Default)]
+struct Pacer {
+    pacing: Duration,
+    last_post: Option<Instant>,
+}
+
+impl Pacer {
+    /// Wait until `pacing` has passed since the last event, before posting the next
+    fn wait(&mut self, clock: &impl Clock) {
+        if let Some(last) = self.last_post {
+            let since = clock.now().saturating_duration_since(last);
+            if since < self.pacing {
+                clock.sleep(self.pacing - since);
+            }
+        }
+        self.last_post = Some(clock.now());
+    }
+}
+
+thread_local! {
+    /// Pacing of the command being run on this thread
+    static PACER: RefCell<Pacer> = RefCell::new(Pacer::default());
+}
+
+/// Run `f` with `pacing` between the key events it injects on this thread
+/// Commands made of several shortcuts, e.g. select, copy and delete for a cut, are sent
+/// slowly enough for apps that drop events posted back to back
+pub fn with_injection_pacing<T>(pacing: Duration, f: impl FnOnce() -> T) -> T {
+    let previous = PACER.with(|pacer| pacer.replace(Pacer { pacing, last_post: None }));
+    let result = f();
+    PACER.with(|pacer| pacer.replace(previous));
+    result
+}
+
+/// Inject a single key event, posting it again if the event source fails
+/// Shortcuts are given in macOS terms, e.g. Cmd+Left, and translated on other platforms
+pub fn inject_key(keycode: KeyCode, key_down: bool, modifiers: Modifiers) -> Result<(), InjectError> {
+    PACER.with(|pacer| {
+        let mut pacer = pacer.borrow_mut();
+        if !pacer.pacing.is_zero() {
+            pacer.wait(&SystemClock);
+        }
+    });
+    let result = with_retry(INJECT_ATTEMPTS, INJECT_RETRY_DELAY, || {
+        KeyboardCapture::inject_key(keycode, key_down, modifiers)
+    });
+    if let Err(e) = &result {
+        log::warn!("Failed to inject {:?}: {}", keycode, e);
+        record_failure(&mut FAILURES.lock().unwrap(), Instant::now());
+    }
+    result
 }
 
 /// Inject a key press (down + up)
-pub fn inject_key_press(keycode: KeyCode, modifiers: Modifiers) -> Result<(), String> {
+pub fn inject_key_press(keycode: KeyCode, modifiers: Modifiers) -> Result<(), InjectError> {
     inject_key(keycode, true, modifiers)?;
     inject_key(keycode, false, modifiers)?;
     Ok(())
@@ -41,7 +195,7 @@ pub fn inject_arrow(direction: ArrowDirection, modifiers: Modifiers) -> Result<(
         ArrowDirection::Up => KeyCode::Up,
         ArrowDirection::Down => KeyCode::Down,
     };
-    inject_key_press(keycode, modifiers)
+    inject_key_press(keycode, modifiers).map_err(String::from)
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -122,6 +276,30 @@ pub fn word_backward(count: u32, select: bool) -> Result<(), String> {
     Ok(())
 }
 
+/// Move to end of previous word (ge)
+/// Option+Left past the current word, Option+Right to the end of the previous one,
+/// then Left to land on its last char
+pub fn word_end_backward(count: u32, select: bool) -> Result<(), String> {
+    let mods = Modifiers {
+        option: true,
+        shift: select,
+        ..Default::default()
+    };
+
+    for _ in 0..=count {
+        inject_arrow(ArrowDirection::Left, mods)?;
+    }
+    inject_arrow(ArrowDirection::Right, mods)?;
+    cursor_left(1, select)
+}
+
+/// Move to end of previous WORD (gE)
+/// macOS word motions have no WORD variant, so this moves like `ge`; vim-accurate
+/// word motions tell the two apart
+pub fn word_end_backward_big(count: u32, select: bool) -> Result<(), String> {
+    word_end_backward(count, select)
+}
+
 /// Move to end of word (e) / next word (w) - Option+Right on macOS
 pub fn word_forward(count: u32, select: bool) -> Result<(), String> {
     let mods = Modifiers {
@@ -182,7 +360,7 @@ pub fn page_up(select: bool) -> Result<(), String> {
         shift: select,
         ..Default::default()
     };
-    inject_key_press(KeyCode::PageUp, mods)
+    inject_key_press(KeyCode::PageUp, mods).map_err(String::from)
 }
 
 /// Page down (Ctrl+f or Ctrl+d)
@@ -191,17 +369,17 @@ pub fn page_down(select: bool) -> Result<(), String> {
         shift: select,
         ..Default::default()
     };
-    inject_key_press(KeyCode::PageDown, mods)
+    inject_key_press(KeyCode::PageDown, mods).map_err(String::from)
 }
 
 /// Delete character (x)
 pub fn delete_char() -> Result<(), String> {
-    inject_key_press(KeyCode::ForwardDelete, Modifiers::default())
+    inject_key_press(KeyCode::ForwardDelete, Modifiers::default()).map_err(String::from)
 }
 
 /// Delete character before cursor (X)
 pub fn backspace() -> Result<(), String> {
-    inject_key_press(KeyCode::Delete, Modifiers::default())
+    inject_key_press(KeyCode::Delete, Modifiers::default()).map_err(String::from)
 }
 
 /// Cut selection (Cmd+X)
@@ -213,6 +391,7 @@ pub fn cut() -> Result<(), String> {
             ..Default::default()
         },
     )
+    .map_err(String::from)
 }
 
 /// Copy selection (Cmd+C)
@@ -224,6 +403,7 @@ pub fn copy() -> Result<(), String> {
             ..Default::default()
         },
     )
+    .map_err(String::from)
 }
 
 /// Paste (Cmd+V)
@@ -235,6 +415,7 @@ pub fn paste() -> Result<(), String> {
             ..Default::default()
         },
     )
+    .map_err(String::from)
 }
 
 /// Undo (Cmd+Z)
@@ -246,6 +427,7 @@ pub fn undo() -> Result<(), String> {
             ..Default::default()
         },
     )
+    .map_err(String::from)
 }
 
 /// Redo (Cmd+Shift+Z)
@@ -258,12 +440,13 @@ pub fn redo() -> Result<(), String> {
             ..Default::default()
         },
     )
+    .map_err(String::from)
 }
 
 /// New line below (o) - Cmd+Right, Return
 pub fn new_line_below() -> Result<(), String> {
     line_end(false)?;
-    inject_key_press(KeyCode::Return, Modifiers::default())
+    inject_key_press(KeyCode::Return, Modifiers::default()).map_err(String::from)
 }
 
 /// New line above (O) - Cmd+Left, Return, Up
@@ -305,7 +488,7 @@ pub fn paragraph_down(count: u32, select: bool) -> Result<(), String> {
 pub fn join_lines() -> Result<(), String> {
     line_end(false)?;
     delete_char()?;
-    inject_key_press(KeyCode::Space, Modifiers::default())
+    inject_key_press(KeyCode::Space, Modifiers::default()).map_err(String::from)
 }
 
 /// Select inner word (iw) - Option+Left to word start, Option+Shift+Right to select word
@@ -324,7 +507,16 @@ pub fn select_around_word() -> Result<(), String> {
 /// Indent line (>>) - Tab key
 pub fn indent_line() -> Result<(), String> {
     line_start(false)?;
-    inject_key_press(KeyCode::Tab, Modifiers::default())
+    inject_key_press(KeyCode::Tab, Modifiers::default()).map_err(String::from)
+}
+
+/// Indent line (>>) with spaces instead of Tab
+pub fn indent_line_with_spaces(width: u8) -> Result<(), String> {
+    line_start(false)?;
+    for _ in 0..width {
+        inject_key_press(KeyCode::Space, Modifiers::default())?;
+    }
+    Ok(())
 }
 
 /// Outdent line (<<) - Shift+Tab
@@ -337,6 +529,57 @@ pub fn outdent_line() -> Result<(), String> {
             ..Default::default()
         },
     )
+    .map_err(String::from)
+}
+
+/// Next tab (gt) - Cmd+Shift+]
+pub fn next_tab() -> Result<(), String> {
+    inject_key_press(
+        KeyCode::RightBracket,
+        Modifiers {
+            command: true,
+            shift: true,
+            ..Default::default()
+        },
+    )
+    .map_err(String::from)
+}
+
+/// Previous tab (gT) - Cmd+Shift+[
+pub fn prev_tab() -> Result<(), String> {
+    inject_key_press(
+        KeyCode::LeftBracket,
+        Modifiers {
+            command: true,
+            shift: true,
+            ..Default::default()
+        },
+    )
+    .map_err(String::from)
+}
+
+/// Go to tab number `tab` ({count}gt) - Cmd+1 to Cmd+9
+pub fn go_to_tab(tab: u32) -> Result<(), String> {
+    let keycode = match tab {
+        1 => KeyCode::Num1,
+        2 => KeyCode::Num2,
+        3 => KeyCode::Num3,
+        4 => KeyCode::Num4,
+        5 => KeyCode::Num5,
+        6 => KeyCode::Num6,
+        7 => KeyCode::Num7,
+        8 => KeyCode::Num8,
+        9 => KeyCode::Num9,
+        _ => return Err(format!("No shortcut for tab {}", tab)),
+    };
+    inject_key_press(
+        keycode,
+        Modifiers {
+            command: true,
+            ..Default::default()
+        },
+    )
+    .map_err(String::from)
 }
 
 /// Type a character
@@ -349,5 +592,158 @@ pub fn type_char(keycode: KeyCode, shift: bool) -> Result<(), String> {
     } else {
         Modifiers::default()
     };
-    inject_key_press(keycode, mods)
+    inject_key_press(keycode, mods).map_err(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    /// An event source that fails `failures` times before posting, and its call count
+    fn failing_source(
+        failures: u32,
+        error: InjectError,
+    ) -> (impl FnMut() -> Result<(), InjectError>, Rc<Cell<u32>>) {
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let post = move || {
+            counter.set(counter.get() + 1);
+            if counter.get() <= failures {
+                Err(error.clone())
+            } else {
+                Ok(())
+            }
+        };
+        (post, calls)
+    }
+
+    #[test]
+    fn test_retry_recovers_from_transient_failure() {
+        let (post, calls) = failing_source(2, InjectError::EventSource);
+        assert_eq!(with_retry(3, Duration::ZERO, post), Ok(()));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn test_retry_gives_up_after_attempts() {
+        let (post, calls) = failing_source(5, InjectError::EventSource);
+        assert_eq!(with_retry(3, Duration::ZERO, post), Err(InjectError::EventSource));
+        assert_eq!(calls.get(), 3);
+    }
+
+    #[test]
+    fn test_no_retry_for_lasting_failure() {
+        let (post, calls) = failing_source(5, InjectError::Unmapped(KeyCode::A));
+        assert_eq!(with_retry(3, Duration::ZERO, post), Err(InjectError::Unmapped(KeyCode::A)));
+        assert_eq!(calls.get(), 1);
+    }
+
+    /// A clock that only moves when slept or advanced, noting each post and wait
+    struct MockClock {
+        start: Instant,
+        elapsed: Cell<Duration>,
+        log: RefCell<Vec<String>>,
+    }
+
+    impl MockClock {
+        fn new() -> Self {
+            Self {
+                start: Instant::now(),
+                elapsed: Cell::new(Duration::ZERO),
+                log: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn post(&self, pacer: &mut Pacer, event: &str) {
+            pacer.wait(self);
+            self.log.borrow_mut().push(event.to_string());
+        }
+
+        fn advance(&self, duration: Duration) {
+            self.elapsed.set(self.elapsed.get() + duration);
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            self.start + self.elapsed.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.log.borrow_mut().push(format!("sleep {}ms", duration.as_millis()));
+            self.advance(duration);
+        }
+    }
+
+    /// Select to the line end, then cut, as `D` does
+    const CUT_TO_LINE_END: [&str; 4] = [
+        "down Shift+Cmd+Right",
+        "up Shift+Cmd+Right",
+        "down Cmd+X",
+        "up Cmd+X",
+    ];
+
+    #[test]
+    fn test_pacing_waits_between_events() {
+        let clock = MockClock::new();
+        let mut pacer = Pacer { pacing: Duration::from_millis(10), last_post: None };
+        for event in CUT_TO_LINE_END {
+            clock.post(&mut pacer, event);
+        }
+        assert_eq!(
+            *clock.log.borrow(),
+            vec![
+                "down Shift+Cmd+Right",
+                "sleep 10ms",
+                "up Shift+Cmd+Right",
+                "sleep 10ms",
+                "down Cmd+X",
+                "sleep 10ms",
+                "up Cmd+X",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pacing_counts_time_already_passed() {
+        let clock = MockClock::new();
+        let mut pacer = Pacer { pacing: Duration::from_millis(10), last_post: None };
+        clock.post(&mut pacer, "down A");
+        clock.advance(Duration::from_millis(4));
+        clock.post(&mut pacer, "up A");
+        clock.advance(Duration::from_millis(30));
+        clock.post(&mut pacer, "down B");
+        assert_eq!(*clock.log.borrow(), vec!["down A", "sleep 6ms", "up A", "down B"]);
+    }
+
+    #[test]
+    fn test_no_pacing_keeps_events_back_to_back() {
+        let clock = MockClock::new();
+        let mut pacer = Pacer::default();
+        for event in CUT_TO_LINE_END {
+            clock.post(&mut pacer, event);
+        }
+        assert_eq!(*clock.log.borrow(), CUT_TO_LINE_END.to_vec());
+    }
+
+    #[test]
+    fn test_pacing_is_restored_after_command() {
+        let pacing = Duration::from_millis(10);
+        let inner = with_injection_pacing(pacing, || PACER.with(|pacer| pacer.borrow().pacing));
+        assert_eq!(inner, pacing);
+        assert_eq!(PACER.with(|pacer| pacer.borrow().pacing), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_failures_outside_window_are_forgotten() {
+        let mut failures = VecDeque::new();
+        let start = Instant::now();
+        record_failure(&mut failures, start);
+        record_failure(&mut failures, start + Duration::from_secs(1));
+        assert_eq!(failures.len(), 2);
+        record_failure(&mut failures, start + INJECT_FAILURE_WINDOW + Duration::from_secs(2));
+        assert_eq!(failures.len(), 1);
+    }
 }
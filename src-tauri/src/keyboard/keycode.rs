@@ -116,6 +116,7 @@ define_keycodes! {
     Space = (0x31, "space", "Space", char: ' '),
     Delete = (0x33, "delete", "Delete"),
     Escape = (0x35, "escape", "Escape"),
+    RightCommand = (0x36, "right_command", "Right Command"),
     Command = (0x37, "command", "Command"),
     Shift = (0x38, "shift", "Shift"),
     CapsLock = (0x39, "caps_lock", "Caps Lock"),
@@ -167,6 +168,40 @@ define_keycodes! {
     Grave = (0x32, "grave", "`"),
 }
 
+impl KeyCode {
+    /// Check if this is a modifier key, which only sends FlagsChanged events
+    pub fn is_modifier(self) -> bool {
+        matches!(
+            self,
+            Self::Command
+                | Self::RightCommand
+                | Self::Shift
+                | Self::RightShift
+                | Self::Option
+                | Self::RightOption
+                | Self::Control
+                | Self::RightControl
+                | Self::CapsLock
+                | Self::Function
+        )
+    }
+}
+
+/// Shift/control/option/command held on one side of the keyboard
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SideModifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub option: bool,
+    pub command: bool,
+}
+
+impl SideModifiers {
+    fn any(&self) -> bool {
+        self.shift || self.control || self.option || self.command
+    }
+}
+
 /// Modifier flags matching CGEventFlags
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Modifiers {
@@ -175,6 +210,12 @@ pub struct Modifiers {
     pub option: bool,
     pub command: bool,
     pub caps_lock: bool,
+    /// Fn held (also set for arrow and navigation keys)
+    pub function: bool,
+    /// Modifiers held on the left side, from the device-dependent flags
+    pub left: SideModifiers,
+    /// Modifiers held on the right side, from the device-dependent flags
+    pub right: SideModifiers,
 }
 
 impl Modifiers {
@@ -183,14 +224,55 @@ impl Modifiers {
     const OPTION_MASK: u64 = 0x00080000;
     const COMMAND_MASK: u64 = 0x00100000;
     const CAPS_LOCK_MASK: u64 = 0x00010000;
+    const FUNCTION_MASK: u64 = 0x00800000;
+
+    // Device-dependent masks (NX_DEVICE*KEYMASK)
+    const LEFT_CONTROL_MASK: u64 = 0x00000001;
+    const LEFT_SHIFT_MASK: u64 = 0x00000002;
+    const RIGHT_SHIFT_MASK: u64 = 0x00000004;
+    const LEFT_COMMAND_MASK: u64 = 0x00000008;
+    const RIGHT_COMMAND_MASK: u64 = 0x00000010;
+    const LEFT_OPTION_MASK: u64 = 0x00000020;
+    const RIGHT_OPTION_MASK: u64 = 0x00000040;
+    const RIGHT_CONTROL_MASK: u64 = 0x00002000;
 
     pub fn from_cg_flags(flags: u64) -> Self {
+        let has = |mask: u64| flags & mask != 0;
+        // Synthetic events often set only the generic flag; count those as left-hand
+        let side = |generic: u64, left: u64, right: u64| {
+            (
+                has(generic) && (has(left) || !has(right)),
+                has(generic) && has(right),
+            )
+        };
+        let (left_shift, right_shift) =
+            side(Self::SHIFT_MASK, Self::LEFT_SHIFT_MASK, Self::RIGHT_SHIFT_MASK);
+        let (left_control, right_control) =
+            side(Self::CONTROL_MASK, Self::LEFT_CONTROL_MASK, Self::RIGHT_CONTROL_MASK);
+        let (left_option, right_option) =
+            side(Self::OPTION_MASK, Self::LEFT_OPTION_MASK, Self::RIGHT_OPTION_MASK);
+        let (left_command, right_command) =
+            side(Self::COMMAND_MASK, Self::LEFT_COMMAND_MASK, Self::RIGHT_COMMAND_MASK);
+
         Self {
-            shift: flags & Self::SHIFT_MASK != 0,
-            control: flags & Self::CONTROL_MASK != 0,
-            option: flags & Self::OPTION_MASK != 0,
-            command: flags & Self::COMMAND_MASK != 0,
-            caps_lock: flags & Self::CAPS_LOCK_MASK != 0,
+            shift: has(Self::SHIFT_MASK),
+            control: has(Self::CONTROL_MASK),
+            option: has(Self::OPTION_MASK),
+            command: has(Self::COMMAND_MASK),
+            caps_lock: has(Self::CAPS_LOCK_MASK),
+            function: has(Self::FUNCTION_MASK),
+            left: SideModifiers {
+                shift: left_shift,
+                control: left_control,
+                option: left_option,
+                command: left_command,
+            },
+            right: SideModifiers {
+                shift: right_shift,
+                control: right_control,
+                option: right_option,
+                command: right_command,
+            },
         }
     }
 
@@ -211,8 +293,67 @@ impl Modifiers {
         if self.caps_lock {
             flags |= Self::CAPS_LOCK_MASK;
         }
+        if self.right.shift {
+            flags |= Self::RIGHT_SHIFT_MASK;
+        }
+        if self.right.control {
+            flags |= Self::RIGHT_CONTROL_MASK;
+        }
+        if self.right.option {
+            flags |= Self::RIGHT_OPTION_MASK;
+        }
+        if self.right.command {
+            flags |= Self::RIGHT_COMMAND_MASK;
+        }
         flags
     }
+
+    /// Check whether a modifier key is held, or None for other keys
+    pub fn is_key_held(&self, key: KeyCode) -> Option<bool> {
+        match key {
+            KeyCode::Shift => Some(self.left.shift),
+            KeyCode::RightShift => Some(self.right.shift),
+            KeyCode::Control => Some(self.left.control),
+            KeyCode::RightControl => Some(self.right.control),
+            KeyCode::Option => Some(self.left.option),
+            KeyCode::RightOption => Some(self.right.option),
+            KeyCode::Command => Some(self.left.command),
+            KeyCode::RightCommand => Some(self.right.command),
+            KeyCode::Function => Some(self.function),
+            _ => None,
+        }
+    }
+
+    /// These modifiers without the flag set by pressing `key` itself
+    pub fn without_key(mut self, key: KeyCode) -> Self {
+        match key {
+            KeyCode::Shift => self.left.shift = false,
+            KeyCode::RightShift => self.right.shift = false,
+            KeyCode::Control => self.left.control = false,
+            KeyCode::RightControl => self.right.control = false,
+            KeyCode::Option => self.left.option = false,
+            KeyCode::RightOption => self.right.option = false,
+            KeyCode::Command => self.left.command = false,
+            KeyCode::RightCommand => self.right.command = false,
+            KeyCode::Function => self.function = false,
+            _ => return self,
+        }
+        self.shift = self.left.shift || self.right.shift;
+        self.control = self.left.control || self.right.control;
+        self.option = self.left.option || self.right.option;
+        self.command = self.left.command || self.right.command;
+        self
+    }
+
+    /// Whether any left-hand modifier is held
+    pub fn any_left(&self) -> bool {
+        self.left.any()
+    }
+
+    /// Whether any right-hand modifier is held
+    pub fn any_right(&self) -> bool {
+        self.right.any()
+    }
 }
 
 /// A key event with code and modifiers
@@ -227,4 +368,77 @@ impl KeyEvent {
     pub fn keycode(&self) -> Option<KeyCode> {
         KeyCode::from_raw(self.code)
     }
+
+    /// Key down or up event for a FlagsChanged event, from whether the modifier is now held
+    /// Caps Lock sends one FlagsChanged per press, whichever way it toggles
+    pub fn modifier_change(code: u16, flags: u64) -> Option<Self> {
+        let modifiers = Modifiers::from_cg_flags(flags);
+        let pressed = match KeyCode::from_raw(code)? {
+            KeyCode::CapsLock => true,
+            key => modifiers.is_key_held(key)?,
+        };
+        Some(Self {
+            code,
+            modifiers,
+            is_key_down: pressed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_right_modifier_flags() {
+        // Right Command + Left Shift, as reported by the HID system
+        let flags = Modifiers::COMMAND_MASK
+            | Modifiers::RIGHT_COMMAND_MASK
+            | Modifiers::SHIFT_MASK
+            | Modifiers::LEFT_SHIFT_MASK;
+        let mods = Modifiers::from_cg_flags(flags);
+        assert!(mods.command && mods.shift);
+        assert!(mods.right.command && !mods.left.command);
+        assert!(mods.left.shift && !mods.right.shift);
+        assert_eq!(mods.is_key_held(KeyCode::RightCommand), Some(true));
+        assert_eq!(mods.is_key_held(KeyCode::Command), Some(false));
+        assert_eq!(mods.is_key_held(KeyCode::A), None);
+        assert_eq!(Modifiers::from_cg_flags(mods.to_cg_flags()).right, mods.right);
+    }
+
+    #[test]
+    fn test_both_sides_and_generic_only_flags() {
+        let flags = Modifiers::OPTION_MASK | Modifiers::LEFT_OPTION_MASK | Modifiers::RIGHT_OPTION_MASK;
+        let mods = Modifiers::from_cg_flags(flags);
+        assert!(mods.left.option && mods.right.option);
+
+        // Injected events carry only the generic flag
+        let mods = Modifiers::from_cg_flags(Modifiers::CONTROL_MASK);
+        assert!(mods.left.control && !mods.any_right());
+    }
+
+    #[test]
+    fn test_without_key() {
+        let flags = Modifiers::COMMAND_MASK | Modifiers::RIGHT_COMMAND_MASK;
+        let mods = Modifiers::from_cg_flags(flags).without_key(KeyCode::RightCommand);
+        assert!(!mods.command && !mods.any_right());
+    }
+
+    #[test]
+    fn test_modifier_change() {
+        let flags = Modifiers::COMMAND_MASK | Modifiers::RIGHT_COMMAND_MASK;
+        let event = KeyEvent::modifier_change(KeyCode::RightCommand.as_raw(), flags).unwrap();
+        assert_eq!(event.keycode(), Some(KeyCode::RightCommand));
+        assert!(event.is_key_down);
+
+        // Releasing Right Command
+        let event = KeyEvent::modifier_change(KeyCode::RightCommand.as_raw(), 0).unwrap();
+        assert!(!event.is_key_down);
+        // Caps Lock presses are always key downs
+        assert!(KeyEvent::modifier_change(KeyCode::CapsLock.as_raw(), 0).unwrap().is_key_down);
+        assert!(KeyEvent::modifier_change(KeyCode::Function.as_raw(), Modifiers::FUNCTION_MASK)
+            .unwrap()
+            .is_key_down);
+        assert!(KeyEvent::modifier_change(KeyCode::A.as_raw(), 0).is_none());
+    }
 }
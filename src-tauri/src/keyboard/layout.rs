@@ -0,0 +1,177 @@
+//! Keyboard layout translation
+//!
+//! macOS virtual keycodes identify physical key positions, named after QWERTY.
+//! On other layouts the key labelled `j` sends a different code, so events are
+//! translated to the logical key before the vim state machine sees them.
+
+use super::keycode::{KeyCode, KeyEvent};
+use crate::config::KeyboardLayout;
+
+/// Dvorak: (physical QWERTY position, logical key)
+const DVORAK: &[(KeyCode, KeyCode)] = &[
+    (KeyCode::Minus, KeyCode::LeftBracket),
+    (KeyCode::Equal, KeyCode::RightBracket),
+    (KeyCode::Q, KeyCode::Quote),
+    (KeyCode::W, KeyCode::Comma),
+    (KeyCode::E, KeyCode::Period),
+    (KeyCode::R, KeyCode::P),
+    (KeyCode::T, KeyCode::Y),
+    (KeyCode::Y, KeyCode::F),
+    (KeyCode::U, KeyCode::G),
+    (KeyCode::I, KeyCode::C),
+    (KeyCode::O, KeyCode::R),
+    (KeyCode::P, KeyCode::L),
+    (KeyCode::LeftBracket, KeyCode::Slash),
+    (KeyCode::RightBracket, KeyCode::Equal),
+    (KeyCode::S, KeyCode::O),
+    (KeyCode::D, KeyCode::E),
+    (KeyCode::F, KeyCode::U),
+    (KeyCode::G, KeyCode::I),
+    (KeyCode::H, KeyCode::D),
+    (KeyCode::J, KeyCode::H),
+    (KeyCode::K, KeyCode::T),
+    (KeyCode::L, KeyCode::N),
+    (KeyCode::Semicolon, KeyCode::S),
+    (KeyCode::Quote, KeyCode::Minus),
+    (KeyCode::Z, KeyCode::Semicolon),
+    (KeyCode::X, KeyCode::Q),
+    (KeyCode::C, KeyCode::J),
+    (KeyCode::V, KeyCode::K),
+    (KeyCode::B, KeyCode::X),
+    (KeyCode::N, KeyCode::B),
+    (KeyCode::Comma, KeyCode::W),
+    (KeyCode::Period, KeyCode::V),
+    (KeyCode::Slash, KeyCode::Z),
+];
+
+/// Colemak: (physical QWERTY position, logical key)
+const COLEMAK: &[(KeyCode, KeyCode)] = &[
+    (KeyCode::E, KeyCode::F),
+    (KeyCode::R, KeyCode::P),
+    (KeyCode::T, KeyCode::G),
+    (KeyCode::Y, KeyCode::J),
+    (KeyCode::U, KeyCode::L),
+    (KeyCode::I, KeyCode::U),
+    (KeyCode::O, KeyCode::Y),
+    (KeyCode::P, KeyCode::Semicolon),
+    (KeyCode::S, KeyCode::R),
+    (KeyCode::D, KeyCode::S),
+    (KeyCode::F, KeyCode::T),
+    (KeyCode::G, KeyCode::D),
+    (KeyCode::J, KeyCode::N),
+    (KeyCode::K, KeyCode::E),
+    (KeyCode::L, KeyCode::I),
+    (KeyCode::Semicolon, KeyCode::O),
+    (KeyCode::N, KeyCode::K),
+];
+
+/// Get the logical key produced by a physical key on this layout
+pub fn to_logical(layout: &KeyboardLayout, physical: KeyCode) -> KeyCode {
+    match layout {
+        KeyboardLayout::Qwerty => physical,
+        KeyboardLayout::Dvorak => lookup(DVORAK, physical, false),
+        KeyboardLayout::Colemak => lookup(COLEMAK, physical, false),
+        KeyboardLayout::Custom(map) => map
+            .get(physical.to_name())
+            .and_then(|name| KeyCode::from_name(name))
+            .unwrap_or(physical),
+    }
+}
+
+/// Get the physical key that produces a logical key on this layout
+/// Used when typing a key back, e.g. the replacement char of `r`
+pub fn to_physical(layout: &KeyboardLayout, logical: KeyCode) -> KeyCode {
+    match layout {
+        KeyboardLayout::Qwerty => logical,
+        KeyboardLayout::Dvorak => lookup(DVORAK, logical, true),
+        KeyboardLayout::Colemak => lookup(COLEMAK, logical, true),
+        KeyboardLayout::Custom(map) => map
+            .iter()
+            .find(|(_, name)| KeyCode::from_name(name) == Some(logical))
+            .and_then(|(physical, _)| KeyCode::from_name(physical))
+            .unwrap_or(logical),
+    }
+}
+
+/// Translate an event's keycode to the logical key, keeping modifiers and state
+pub fn translate_event(layout: &KeyboardLayout, event: KeyEvent) -> KeyEvent {
+    match event.keycode() {
+        Some(physical) => KeyEvent {
+            code: to_logical(layout, physical).as_raw(),
+            ..event
+        },
+        None => event,
+    }
+}
+
+fn lookup(table: &[(KeyCode, KeyCode)], key: KeyCode, reverse: bool) -> KeyCode {
+    table
+        .iter()
+        .find_map(|&(physical, logical)| {
+            if reverse {
+                (logical == key).then_some(physical)
+            } else {
+                (physical == key).then_some(logical)
+            }
+        })
+        .unwrap_or(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_dvorak_translations() {
+        let layout = KeyboardLayout::Dvorak;
+        // The QWERTY `j` position is `h` on Dvorak
+        assert_eq!(to_logical(&layout, KeyCode::J), KeyCode::H);
+        assert_eq!(to_logical(&layout, KeyCode::C), KeyCode::J);
+        assert_eq!(to_logical(&layout, KeyCode::V), KeyCode::K);
+        assert_eq!(to_logical(&layout, KeyCode::P), KeyCode::L);
+        // Unchanged keys pass through
+        assert_eq!(to_logical(&layout, KeyCode::A), KeyCode::A);
+        assert_eq!(to_logical(&layout, KeyCode::Escape), KeyCode::Escape);
+    }
+
+    #[test]
+    fn test_colemak_translations() {
+        let layout = KeyboardLayout::Colemak;
+        assert_eq!(to_logical(&layout, KeyCode::J), KeyCode::N);
+        assert_eq!(to_logical(&layout, KeyCode::K), KeyCode::E);
+        assert_eq!(to_logical(&layout, KeyCode::H), KeyCode::H);
+    }
+
+    #[test]
+    fn test_to_physical_reverses_translation() {
+        for layout in [KeyboardLayout::Dvorak, KeyboardLayout::Colemak] {
+            for &(physical, _) in DVORAK.iter().chain(COLEMAK) {
+                let logical = to_logical(&layout, physical);
+                assert_eq!(to_physical(&layout, logical), physical);
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_layout() {
+        let map = HashMap::from([("j".to_string(), "h".to_string())]);
+        let layout = KeyboardLayout::Custom(map);
+        assert_eq!(to_logical(&layout, KeyCode::J), KeyCode::H);
+        assert_eq!(to_physical(&layout, KeyCode::H), KeyCode::J);
+        assert_eq!(to_logical(&layout, KeyCode::K), KeyCode::K);
+    }
+
+    #[test]
+    fn test_translate_event_keeps_modifiers() {
+        let event = KeyEvent {
+            code: KeyCode::J.as_raw(),
+            modifiers: crate::keyboard::Modifiers { shift: true, ..Default::default() },
+            is_key_down: true,
+        };
+        let translated = translate_event(&KeyboardLayout::Dvorak, event);
+        assert_eq!(translated.keycode(), Some(KeyCode::H));
+        assert!(translated.modifiers.shift);
+        assert!(translated.is_key_down);
+    }
+}
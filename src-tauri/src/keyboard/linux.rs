@@ -0,0 +1,326 @@
+//! Linux keyboard backend: evdev for capture and uinput for injection
+//!
+//! Keyboards are grabbed exclusively, and every key the vim layer passes through is re-emitted
+//! from a virtual uinput keyboard, along with injected keys. This works under both X11 and
+//! Wayland, and needs read access to /dev/input and write access to /dev/uinput.
+
+use std::ffi::CString;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+use libc::{c_int, c_ulong};
+
+use super::backend::{KeyEventCallback, KeyboardBackend};
+use super::inject::InjectError;
+use super::keycode::{KeyCode, KeyEvent, Modifiers};
+use super::linux_keys::{evdev_key, from_evdev_key, update_modifiers};
+use super::shortcuts::pc_shortcut;
+
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const SYN_REPORT: u16 = 0;
+const BUS_VIRTUAL: u16 = 0x06;
+/// Highest key code the virtual keyboard can emit (KEY_MAX)
+const KEY_MAX: c_int = 0x2ff;
+
+// ioctl requests from linux/input.h and linux/uinput.h
+const EVIOCGRAB: c_ulong = 0x4004_4590;
+const UI_SET_EVBIT: c_ulong = 0x4004_5564;
+const UI_SET_KEYBIT: c_ulong = 0x4004_5565;
+const UI_DEV_SETUP: c_ulong = 0x405c_5503;
+const UI_DEV_CREATE: c_ulong = 0x5501;
+
+/// Directory with stable symlinks to input devices, keyboards ending in `-event-kbd`
+const INPUT_BY_PATH: &str = "/dev/input/by-path";
+/// Device the virtual keyboard is created through
+const UINPUT: &str = "/dev/uinput";
+
+fn ioctl(file: &File, request: c_ulong, arg: c_ulong) -> Result<(), String> {
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), request, arg) };
+    if result < 0 {
+        return Err(format!("ioctl {:#x} failed: {}", request, std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+/// Virtual keyboard that passed-through and injected keys are sent from
+struct VirtualKeyboard {
+    device: File,
+}
+
+impl VirtualKeyboard {
+    fn create() -> Result<Self, String> {
+        let device = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(UINPUT)
+            .map_err(|e| format!("Failed to open {}: {}", UINPUT, e))?;
+
+        ioctl(&device, UI_SET_EVBIT, EV_KEY as c_ulong)?;
+        for code in 1..=KEY_MAX {
+            ioctl(&device, UI_SET_KEYBIT, code as c_ulong)?;
+        }
+
+        let mut setup: libc::uinput_setup = unsafe { std::mem::zeroed() };
+        setup.id.bustype = BUS_VIRTUAL;
+        for (dst, src) in setup.name.iter_mut().zip(b"ovim virtual keyboard") {
+            *dst = *src as libc::c_char;
+        }
+        ioctl(&device, UI_DEV_SETUP, &setup as *const _ as c_ulong)?;
+        ioctl(&device, UI_DEV_CREATE, 0)?;
+
+        log::info!("Created uinput virtual keyboard");
+        Ok(Self { device })
+    }
+
+    fn emit(&mut self, type_: u16, code: u16, value: i32) -> Result<(), String> {
+        let mut event: libc::input_event = unsafe { std::mem::zeroed() };
+        event.type_ = type_;
+        event.code = code;
+        event.value = value;
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                &event as *const libc::input_event as *const u8,
+                std::mem::size_of::<libc::input_event>(),
+            )
+        };
+        self.device
+            .write_all(bytes)
+            .map_err(|e| format!("Failed to write to uinput: {}", e))
+    }
+
+    /// Emit a key event (1 = down, 0 = up, 2 = repeat) followed by a sync
+    fn key(&mut self, code: u16, value: i32) -> Result<(), String> {
+        self.emit(EV_KEY, code, value)?;
+        self.emit(EV_SYN, SYN_REPORT, 0)
+    }
+}
+
+/// The shared virtual keyboard, created on first use
+fn virtual_keyboard() -> Result<&'static Mutex<VirtualKeyboard>, String> {
+    static KEYBOARD: OnceLock<Result<Mutex<VirtualKeyboard>, String>> = OnceLock::new();
+    KEYBOARD
+        .get_or_init(|| VirtualKeyboard::create().map(Mutex::new))
+        .as_ref()
+        .map_err(Clone::clone)
+}
+
+/// Keyboard devices, found through their `-event-kbd` symlinks
+fn keyboard_devices() -> Vec<PathBuf> {
+    let entries = match std::fs::read_dir(INPUT_BY_PATH) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::error!("Failed to list {}: {}", INPUT_BY_PATH, e);
+            return Vec::new();
+        }
+    };
+    let mut devices: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().ends_with("-event-kbd"))
+        .filter_map(|entry| std::fs::canonicalize(entry.path()).ok())
+        .collect();
+    devices.sort();
+    devices.dedup();
+    devices
+}
+
+/// Whether the keyboards can be read and the virtual keyboard created, which usually takes
+/// membership of the `input` group and a udev rule for /dev/uinput
+pub fn has_device_access() -> bool {
+    can_use_devices(&keyboard_devices(), Path::new(UINPUT))
+}
+
+/// Whether one of `keyboards` is readable and `uinput` writable
+fn can_use_devices(keyboards: &[PathBuf], uinput: &Path) -> bool {
+    keyboards.iter().any(|path| accessible(path, libc::R_OK)) && accessible(uinput, libc::W_OK)
+}
+
+fn accessible(path: &Path, mode: c_int) -> bool {
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    unsafe { libc::access(path.as_ptr(), mode) == 0 }
+}
+
+/// Read key events from a grabbed keyboard until `running` is cleared
+fn capture_device(
+    path: &Path,
+    callback: &Mutex<Option<KeyEventCallback>>,
+    running: &Mutex<bool>,
+) -> Result<(), String> {
+    let mut device = OpenOptions::new()
+        .read(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let keyboard = virtual_keyboard()?;
+    ioctl(&device, EVIOCGRAB, 1)?;
+    log::info!("Capturing {}", path.display());
+
+    let mut modifiers = Modifiers::default();
+    let mut buf = [0u8; std::mem::size_of::<libc::input_event>()];
+    while *running.lock().unwrap() {
+        // Wake up regularly to notice when capture is stopped
+        let mut poll_fd = libc::pollfd {
+            fd: device.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        if unsafe { libc::poll(&mut poll_fd, 1, 100) } <= 0 {
+            continue;
+        }
+        device
+            .read_exact(&mut buf)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let event: libc::input_event = unsafe { std::ptr::read_unaligned(buf.as_ptr() as *const _) };
+
+        // Scan codes and syncs are dropped; each re-emitted key gets its own sync
+        if event.type_ != EV_KEY {
+            continue;
+        }
+        let pass_through = match from_evdev_key(event.code) {
+            Some(keycode) => {
+                let is_key_down = event.value != 0;
+                update_modifiers(&mut modifiers, keycode, is_key_down);
+                let key_event = KeyEvent {
+                    code: keycode.as_raw(),
+                    modifiers,
+                    is_key_down,
+                };
+                match callback.lock().unwrap().as_ref() {
+                    Some(cb) => cb(key_event).is_some(),
+                    None => true,
+                }
+            }
+            None => true,
+        };
+        if pass_through {
+            keyboard.lock().unwrap().key(event.code, event.value)?;
+        } else {
+            log::trace!("capture: SUPPRESSING code={}", event.code);
+        }
+    }
+
+    ioctl(&device, EVIOCGRAB, 0)?;
+    Ok(())
+}
+
+/// Keyboard capture using evdev
+pub struct LinuxKeyboard {
+    callback: Arc<Mutex<Option<KeyEventCallback>>>,
+    running: Arc<Mutex<bool>>,
+}
+
+impl LinuxKeyboard {
+    pub fn new() -> Self {
+        Self {
+            callback: Arc::new(Mutex::new(None)),
+            running: Arc::new(Mutex::new(false)),
+        }
+    }
+}
+
+impl Default for LinuxKeyboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeyboardBackend for LinuxKeyboard {
+    fn set_callback<F>(&self, callback: F)
+    where
+        F: Fn(KeyEvent) -> Option<KeyEvent> + Send + 'static,
+    {
+        *self.callback.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    /// Start capturing keyboard events
+    /// This spawns a thread per keyboard device
+    fn start(&self) -> Result<(), String> {
+        let mut running = self.running.lock().unwrap();
+        if *running {
+            return Ok(());
+        }
+
+        let devices = keyboard_devices();
+        if devices.is_empty() {
+            return Err(format!("No keyboards found in {}", INPUT_BY_PATH));
+        }
+        // Create the virtual keyboard before grabbing anything, so keys aren't lost
+        virtual_keyboard()?;
+        *running = true;
+        drop(running);
+
+        for path in devices {
+            let callback = Arc::clone(&self.callback);
+            let running = Arc::clone(&self.running);
+            thread::spawn(move || {
+                if let Err(e) = capture_device(&path, &callback, &running) {
+                    log::error!("Keyboard capture stopped for {}: {}", path.display(), e);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn stop(&self) {
+        *self.running.lock().unwrap() = false;
+    }
+
+    fn is_running(&self) -> bool {
+        *self.running.lock().unwrap()
+    }
+
+    fn inject_key(keycode: KeyCode, key_down: bool, modifiers: Modifiers) -> Result<(), InjectError> {
+        let (keycode, modifiers) = pc_shortcut(keycode, modifiers);
+        let code = evdev_key(keycode).ok_or(InjectError::Unmapped(keycode))?;
+        let held: Vec<u16> = [
+            (modifiers.control, KeyCode::Control),
+            (modifiers.shift, KeyCode::Shift),
+            (modifiers.option, KeyCode::Option),
+            (modifiers.command, KeyCode::Command),
+        ]
+        .into_iter()
+        .filter(|&(held, _)| held)
+        .filter_map(|(_, key)| evdev_key(key))
+        .collect();
+
+        let mut keyboard = virtual_keyboard()?.lock().unwrap();
+        // Modifiers go down before the key and come up after it
+        if key_down {
+            for &m in &held {
+                keyboard.key(m, 1)?;
+            }
+            Ok(keyboard.key(code, 1)?)
+        } else {
+            keyboard.key(code, 0)?;
+            for &m in held.iter().rev() {
+                keyboard.key(m, 0)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_access() {
+        let file = std::env::temp_dir().join(format!("ovim-device-{}", std::process::id()));
+        std::fs::write(&file, "").unwrap();
+        let missing = Path::new("/nonexistent/uinput");
+
+        assert!(can_use_devices(&[PathBuf::from(missing), file.clone()], &file));
+        // Both a keyboard and uinput are needed
+        assert!(!can_use_devices(&[], &file));
+        assert!(!can_use_devices(std::slice::from_ref(&file), missing));
+        let _ = std::fs::remove_file(&file);
+    }
+}
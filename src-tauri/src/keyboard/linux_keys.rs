@@ -0,0 +1,155 @@
+//! Translation between macOS key codes and Linux evdev key codes
+
+use super::keycode::{KeyCode, Modifiers};
+
+/// evdev key code (`KEY_*` in linux/input-event-codes.h) for each key code
+const EVDEV_KEYS: &[(KeyCode, u16)] = &[
+    (KeyCode::Escape, 1),
+    (KeyCode::Num1, 2),
+    (KeyCode::Num2, 3),
+    (KeyCode::Num3, 4),
+    (KeyCode::Num4, 5),
+    (KeyCode::Num5, 6),
+    (KeyCode::Num6, 7),
+    (KeyCode::Num7, 8),
+    (KeyCode::Num8, 9),
+    (KeyCode::Num9, 10),
+    (KeyCode::Num0, 11),
+    (KeyCode::Minus, 12),
+    (KeyCode::Equal, 13),
+    (KeyCode::Delete, 14), // Backspace
+    (KeyCode::Tab, 15),
+    (KeyCode::Q, 16),
+    (KeyCode::W, 17),
+    (KeyCode::E, 18),
+    (KeyCode::R, 19),
+    (KeyCode::T, 20),
+    (KeyCode::Y, 21),
+    (KeyCode::U, 22),
+    (KeyCode::I, 23),
+    (KeyCode::O, 24),
+    (KeyCode::P, 25),
+    (KeyCode::LeftBracket, 26),
+    (KeyCode::RightBracket, 27),
+    (KeyCode::Return, 28),
+    (KeyCode::Control, 29),
+    (KeyCode::A, 30),
+    (KeyCode::S, 31),
+    (KeyCode::D, 32),
+    (KeyCode::F, 33),
+    (KeyCode::G, 34),
+    (KeyCode::H, 35),
+    (KeyCode::J, 36),
+    (KeyCode::K, 37),
+    (KeyCode::L, 38),
+    (KeyCode::Semicolon, 39),
+    (KeyCode::Quote, 40),
+    (KeyCode::Grave, 41),
+    (KeyCode::Shift, 42),
+    (KeyCode::Backslash, 43),
+    (KeyCode::Z, 44),
+    (KeyCode::X, 45),
+    (KeyCode::C, 46),
+    (KeyCode::V, 47),
+    (KeyCode::B, 48),
+    (KeyCode::N, 49),
+    (KeyCode::M, 50),
+    (KeyCode::Comma, 51),
+    (KeyCode::Period, 52),
+    (KeyCode::Slash, 53),
+    (KeyCode::RightShift, 54),
+    (KeyCode::Option, 56), // Left Alt
+    (KeyCode::Space, 57),
+    (KeyCode::CapsLock, 58),
+    (KeyCode::F1, 59),
+    (KeyCode::F2, 60),
+    (KeyCode::F3, 61),
+    (KeyCode::F4, 62),
+    (KeyCode::F5, 63),
+    (KeyCode::F6, 64),
+    (KeyCode::F7, 65),
+    (KeyCode::F8, 66),
+    (KeyCode::F9, 67),
+    (KeyCode::F10, 68),
+    (KeyCode::F11, 87),
+    (KeyCode::F12, 88),
+    (KeyCode::RightControl, 97),
+    (KeyCode::RightOption, 100),
+    (KeyCode::Home, 102),
+    (KeyCode::Up, 103),
+    (KeyCode::PageUp, 104),
+    (KeyCode::Left, 105),
+    (KeyCode::Right, 106),
+    (KeyCode::End, 107),
+    (KeyCode::Down, 108),
+    (KeyCode::PageDown, 109),
+    (KeyCode::ForwardDelete, 111),
+    (KeyCode::Command, 125), // Left Super
+    (KeyCode::RightCommand, 126),
+];
+
+/// evdev key code for a key code, if it has one (Fn doesn't)
+pub fn evdev_key(keycode: KeyCode) -> Option<u16> {
+    EVDEV_KEYS
+        .iter()
+        .find(|&&(k, _)| k == keycode)
+        .map(|&(_, code)| code)
+}
+
+/// Key code for an evdev key code
+pub fn from_evdev_key(code: u16) -> Option<KeyCode> {
+    EVDEV_KEYS
+        .iter()
+        .find(|&&(_, c)| c == code)
+        .map(|&(k, _)| k)
+}
+
+/// Track held modifiers from key events, since evdev reports no modifier flags
+/// Super is Cmd and Alt is Option, as the vim layer expects
+pub fn update_modifiers(modifiers: &mut Modifiers, keycode: KeyCode, down: bool) {
+    match keycode {
+        KeyCode::Shift | KeyCode::RightShift => modifiers.shift = down,
+        KeyCode::Control | KeyCode::RightControl => modifiers.control = down,
+        KeyCode::Option | KeyCode::RightOption => modifiers.option = down,
+        KeyCode::Command | KeyCode::RightCommand => modifiers.command = down,
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evdev_keys_round_trip() {
+        for &(keycode, code) in EVDEV_KEYS {
+            assert_eq!(evdev_key(keycode), Some(code));
+            assert_eq!(from_evdev_key(code), Some(keycode));
+        }
+        assert_eq!(evdev_key(KeyCode::Function), None);
+        assert_eq!(from_evdev_key(0), None);
+    }
+
+    #[test]
+    fn test_motion_keys() {
+        // KEY_H/J/K/L and the keys Cmd/Option motions translate to
+        assert_eq!(evdev_key(KeyCode::H), Some(35));
+        assert_eq!(evdev_key(KeyCode::L), Some(38));
+        assert_eq!(evdev_key(KeyCode::Home), Some(102));
+        assert_eq!(evdev_key(KeyCode::End), Some(107));
+        assert_eq!(evdev_key(KeyCode::Left), Some(105));
+        assert_eq!(evdev_key(KeyCode::Control), Some(29));
+    }
+
+    #[test]
+    fn test_update_modifiers() {
+        let mut modifiers = Modifiers::default();
+        update_modifiers(&mut modifiers, KeyCode::Command, true);
+        update_modifiers(&mut modifiers, KeyCode::RightShift, true);
+        update_modifiers(&mut modifiers, KeyCode::A, true);
+        assert!(modifiers.command && modifiers.shift && !modifiers.control && !modifiers.option);
+
+        update_modifiers(&mut modifiers, KeyCode::RightShift, false);
+        assert!(modifiers.command && !modifiers.shift);
+    }
+}
@@ -1,9 +1,35 @@
+mod backend;
+pub mod benchmark;
+pub mod budget;
+pub mod chord;
+#[cfg(target_os = "macos")]
 mod capture;
+pub mod double_tap;
 mod inject;
 pub mod keycode;
+pub mod layout;
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(any(target_os = "linux", test))]
+mod linux_keys;
 mod permission;
+pub mod repeat;
+#[cfg(any(target_os = "windows", target_os = "linux", test))]
+mod shortcuts;
+#[cfg(target_os = "windows")]
+mod windows;
+#[cfg(any(target_os = "windows", test))]
+mod windows_keys;
 
-pub use capture::KeyboardCapture;
+pub use backend::KeyboardBackend;
+#[cfg(target_os = "macos")]
+pub use capture::{post_scroll_wheel, KeyboardCapture};
+#[cfg(target_os = "linux")]
+pub use linux::LinuxKeyboard as KeyboardCapture;
+#[cfg(target_os = "windows")]
+pub use windows::WindowsKeyboard as KeyboardCapture;
 pub use inject::*;
 pub use keycode::{KeyCode, KeyEvent, Modifiers};
-pub use permission::{check_accessibility_permission, request_accessibility_permission};
+pub use permission::{
+    check_accessibility_permission, request_accessibility_permission, wait_for_permission,
+};
@@ -1,22 +1,51 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(target_os = "macos")]
 use core_foundation::base::TCFType;
+#[cfg(target_os = "macos")]
 use core_foundation::boolean::CFBoolean;
+#[cfg(target_os = "macos")]
 use core_foundation::dictionary::CFDictionary;
+#[cfg(target_os = "macos")]
 use core_foundation::string::CFString;
 
 // ApplicationServices framework binding for accessibility
+#[cfg(target_os = "macos")]
 #[link(name = "ApplicationServices", kind = "framework")]
 extern "C" {
     fn AXIsProcessTrustedWithOptions(options: core_foundation::dictionary::CFDictionaryRef) -> bool;
 }
 
+#[cfg(target_os = "macos")]
 const K_AX_TRUSTED_CHECK_OPTION_PROMPT: &str = "AXTrustedCheckOptionPrompt";
 
 /// Check if the app has accessibility/input monitoring permission
+#[cfg(target_os = "macos")]
 pub fn check_accessibility_permission() -> bool {
     unsafe { AXIsProcessTrustedWithOptions(std::ptr::null()) }
 }
 
+/// On Linux, whether the keyboards and /dev/uinput can be opened (see `linux`)
+#[cfg(target_os = "linux")]
+pub fn check_accessibility_permission() -> bool {
+    super::linux::has_device_access()
+}
+
+/// Windows has no accessibility permission to check
+#[cfg(target_os = "windows")]
+pub fn check_accessibility_permission() -> bool {
+    true
+}
+
+/// Other platforms can't prompt for access, so this only checks it
+#[cfg(not(target_os = "macos"))]
+pub fn request_accessibility_permission() -> bool {
+    check_accessibility_permission()
+}
+
 /// Request accessibility permission (shows system prompt)
+#[cfg(target_os = "macos")]
 pub fn request_accessibility_permission() -> bool {
     unsafe {
         let key = CFString::new(K_AX_TRUSTED_CHECK_OPTION_PROMPT);
@@ -27,3 +56,68 @@ pub fn request_accessibility_permission() -> bool {
         AXIsProcessTrustedWithOptions(options.as_concrete_TypeRef())
     }
 }
+
+/// Poll `check` every `interval` until it returns true or `timeout` has passed
+/// `on_wait` gets the attempt number before each wait, for progress reporting
+pub fn wait_for_permission(
+    check: impl Fn() -> bool,
+    interval: Duration,
+    timeout: Duration,
+    mut on_wait: impl FnMut(u32),
+) -> bool {
+    let start = Instant::now();
+    let mut attempt = 0;
+    loop {
+        if check() {
+            return true;
+        }
+        if start.elapsed() >= timeout {
+            return false;
+        }
+        attempt += 1;
+        on_wait(attempt);
+        thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_wait_until_permission_granted() {
+        let checks = Cell::new(0);
+        let mut waits = Vec::new();
+        let granted = wait_for_permission(
+            || {
+                checks.set(checks.get() + 1);
+                checks.get() >= 3
+            },
+            Duration::ZERO,
+            Duration::from_secs(60),
+            |attempt| waits.push(attempt),
+        );
+        assert!(granted);
+        assert_eq!(checks.get(), 3);
+        assert_eq!(waits, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_wait_gives_up_after_timeout() {
+        let granted = wait_for_permission(
+            || false,
+            Duration::from_millis(1),
+            Duration::from_millis(20),
+            |_| {},
+        );
+        assert!(!granted);
+    }
+
+    #[test]
+    fn test_no_wait_when_already_granted() {
+        let mut waits = 0;
+        assert!(wait_for_permission(|| true, Duration::ZERO, Duration::ZERO, |_| waits += 1));
+        assert_eq!(waits, 0);
+    }
+}
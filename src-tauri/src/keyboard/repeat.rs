@@ -0,0 +1,97 @@
+//! Filtering of OS key repeat
+//!
+//! A key down for a key that is already held is a repeat. Held keys are tracked
+//! from the event stream, so the filter works without the OS autorepeat flag.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use super::keycode::KeyEvent;
+use crate::config::KeyRepeat;
+
+/// Tracks held keys and decides which repeated key downs to let through
+#[derive(Debug, Default)]
+pub struct RepeatFilter {
+    /// Held keys, with when a down event for each was last let through
+    held: HashMap<u16, Instant>,
+}
+
+impl RepeatFilter {
+    /// Whether the event should be processed under `policy`
+    /// Key ups and first presses always are; repeats depend on the policy
+    pub fn accept(&mut self, event: &KeyEvent, policy: KeyRepeat, now: Instant) -> bool {
+        if !event.is_key_down {
+            self.held.remove(&event.code);
+            return true;
+        }
+
+        let last = match self.held.get_mut(&event.code) {
+            Some(last) => last,
+            None => {
+                self.held.insert(event.code, now);
+                return true;
+            }
+        };
+
+        let accepted = match policy {
+            KeyRepeat::OsDefault => true,
+            KeyRepeat::Disabled => false,
+            KeyRepeat::Custom(ms) => now.duration_since(*last) >= Duration::from_millis(ms),
+        };
+        if accepted {
+            *last = now;
+        }
+        accepted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::{KeyCode, Modifiers};
+
+    fn event(keycode: KeyCode, is_key_down: bool) -> KeyEvent {
+        KeyEvent {
+            code: keycode.as_raw(),
+            modifiers: Modifiers::default(),
+            is_key_down,
+        }
+    }
+
+    /// Feed `j` down at each offset (ms), returning which were accepted
+    fn held_j(policy: KeyRepeat, offsets: &[u64]) -> Vec<bool> {
+        let mut filter = RepeatFilter::default();
+        let start = Instant::now();
+        offsets
+            .iter()
+            .map(|&ms| filter.accept(&event(KeyCode::J, true), policy, start + Duration::from_millis(ms)))
+            .collect()
+    }
+
+    #[test]
+    fn test_os_default_accepts_repeats() {
+        assert_eq!(held_j(KeyRepeat::OsDefault, &[0, 30, 60]), vec![true, true, true]);
+    }
+
+    #[test]
+    fn test_disabled_drops_repeats_until_release() {
+        let mut filter = RepeatFilter::default();
+        let now = Instant::now();
+        assert!(filter.accept(&event(KeyCode::J, true), KeyRepeat::Disabled, now));
+        assert!(!filter.accept(&event(KeyCode::J, true), KeyRepeat::Disabled, now));
+        // Other keys are tracked separately
+        assert!(filter.accept(&event(KeyCode::K, true), KeyRepeat::Disabled, now));
+        // Releasing makes the next press a fresh one
+        assert!(filter.accept(&event(KeyCode::J, false), KeyRepeat::Disabled, now));
+        assert!(filter.accept(&event(KeyCode::J, true), KeyRepeat::Disabled, now));
+    }
+
+    #[test]
+    fn test_custom_cadence() {
+        // OS repeats every 30ms, let through at most every 100ms
+        assert_eq!(
+            held_j(KeyRepeat::Custom(100), &[0, 30, 60, 90, 120, 150, 210, 240]),
+            vec![true, false, false, false, true, false, false, true]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! macOS shortcuts translated for PC platforms
+//!
+//! The vim layer speaks in macOS shortcuts (Option+Left for a word, Cmd+Left for the line
+//! start). On Windows and Linux these become Ctrl+Left, Home and so on.
+
+use super::keycode::{KeyCode, Modifiers};
+
+/// Translate a macOS shortcut into the PC one with the same effect
+/// Cmd+arrows become Home/End (Ctrl+Home/End for the document), Option+arrows and
+/// Option+Delete use Ctrl, and other Cmd shortcuts use Ctrl
+pub fn pc_shortcut(keycode: KeyCode, modifiers: Modifiers) -> (KeyCode, Modifiers) {
+    let with = |control: bool| Modifiers {
+        shift: modifiers.shift,
+        control,
+        ..Default::default()
+    };
+
+    if modifiers.command {
+        return match keycode {
+            KeyCode::Left => (KeyCode::Home, with(false)),
+            KeyCode::Right => (KeyCode::End, with(false)),
+            KeyCode::Up => (KeyCode::Home, with(true)),
+            KeyCode::Down => (KeyCode::End, with(true)),
+            _ => (
+                keycode,
+                Modifiers {
+                    command: false,
+                    control: true,
+                    ..modifiers
+                },
+            ),
+        };
+    }
+
+    if modifiers.option
+        && matches!(
+            keycode,
+            KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down | KeyCode::Delete
+        )
+    {
+        return (keycode, with(true));
+    }
+
+    (keycode, modifiers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mods(shift: bool, control: bool, option: bool, command: bool) -> Modifiers {
+        Modifiers {
+            shift,
+            control,
+            option,
+            command,
+            ..Default::default()
+        }
+    }
+
+    fn translate(keycode: KeyCode, modifiers: Modifiers) -> (KeyCode, bool, bool, bool, bool) {
+        let (keycode, m) = pc_shortcut(keycode, modifiers);
+        (keycode, m.shift, m.control, m.option, m.command)
+    }
+
+    #[test]
+    fn test_line_and_document_motions() {
+        let cmd = mods(false, false, false, true);
+        let cmd_shift = mods(true, false, false, true);
+        assert_eq!(translate(KeyCode::Left, cmd), (KeyCode::Home, false, false, false, false));
+        assert_eq!(translate(KeyCode::Right, cmd_shift), (KeyCode::End, true, false, false, false));
+        assert_eq!(translate(KeyCode::Up, cmd), (KeyCode::Home, false, true, false, false));
+        assert_eq!(translate(KeyCode::Down, cmd_shift), (KeyCode::End, true, true, false, false));
+    }
+
+    #[test]
+    fn test_word_motions_use_control() {
+        let option = mods(false, false, true, false);
+        let option_shift = mods(true, false, true, false);
+        assert_eq!(translate(KeyCode::Left, option), (KeyCode::Left, false, true, false, false));
+        assert_eq!(
+            translate(KeyCode::Right, option_shift),
+            (KeyCode::Right, true, true, false, false)
+        );
+        assert_eq!(translate(KeyCode::Delete, option), (KeyCode::Delete, false, true, false, false));
+    }
+
+    #[test]
+    fn test_command_shortcuts_use_control() {
+        let cmd = mods(false, false, false, true);
+        assert_eq!(translate(KeyCode::C, cmd), (KeyCode::C, false, true, false, false));
+        assert_eq!(
+            translate(KeyCode::Z, mods(true, false, false, true)),
+            (KeyCode::Z, true, true, false, false)
+        );
+        // Plain and shifted keys are unchanged
+        assert_eq!(translate(KeyCode::Left, Modifiers::default()), (KeyCode::Left, false, false, false, false));
+        assert_eq!(
+            translate(KeyCode::Down, mods(true, false, false, false)),
+            (KeyCode::Down, true, false, false, false)
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Windows keyboard backend: a low-level keyboard hook for capture and `SendInput` for injection
+//!
+//! Key events are converted to macOS key codes so the vim layer is unchanged, and injected
+//! shortcuts are translated with `windows_keys::windows_shortcut`.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use windows_sys::Win32::Foundation::{LPARAM, LRESULT, WPARAM};
+use windows_sys::Win32::System::Threading::GetCurrentThreadId;
+use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_EXTENDEDKEY,
+    KEYEVENTF_KEYUP,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+    CallNextHookEx, GetMessageW, PostThreadMessageW, SetWindowsHookExW, UnhookWindowsHookEx,
+    KBDLLHOOKSTRUCT, MSG, WH_KEYBOARD_LL, WM_KEYDOWN, WM_QUIT, WM_SYSKEYDOWN,
+};
+
+use super::backend::{KeyEventCallback, KeyboardBackend};
+use super::inject::{InjectError, INJECTED_EVENT_MARKER};
+use super::keycode::{KeyCode, KeyEvent, Modifiers};
+use super::windows_keys::{
+    from_virtual_key, is_extended_key, modifiers_from_windows, virtual_key, windows_shortcut,
+};
+
+/// Callback for the hook procedure, which can't carry user data
+static HOOK_CALLBACK: Mutex<Option<KeyEventCallback>> = Mutex::new(None);
+
+/// Keyboard capture using a low-level keyboard hook
+pub struct WindowsKeyboard {
+    running: Arc<Mutex<bool>>,
+    /// Thread running the hook's message loop, to post WM_QUIT to
+    hook_thread: Arc<AtomicU32>,
+}
+
+impl WindowsKeyboard {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(Mutex::new(false)),
+            hook_thread: Arc::new(AtomicU32::new(0)),
+        }
+    }
+}
+
+impl Default for WindowsKeyboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn key_held(vk: i32) -> bool {
+    unsafe { GetAsyncKeyState(vk) as u16 & 0x8000 != 0 }
+}
+
+unsafe extern "system" fn hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code < 0 {
+        return CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam);
+    }
+
+    let info = &*(lparam as *const KBDLLHOOKSTRUCT);
+    // Skip events we injected ourselves
+    if info.dwExtraInfo == INJECTED_EVENT_MARKER as usize {
+        return CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam);
+    }
+    let Some(keycode) = from_virtual_key(info.vkCode as u16) else {
+        return CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam);
+    };
+
+    let message = wparam as u32;
+    let key_event = KeyEvent {
+        code: keycode.as_raw(),
+        modifiers: modifiers_from_windows(
+            key_held(0x10),
+            key_held(0x11),
+            key_held(0x12),
+            key_held(0x5B) || key_held(0x5C),
+        ),
+        is_key_down: message == WM_KEYDOWN || message == WM_SYSKEYDOWN,
+    };
+
+    let suppress = match HOOK_CALLBACK.lock().unwrap().as_ref() {
+        Some(cb) => cb(key_event).is_none(),
+        None => false,
+    };
+    if suppress {
+        log::trace!("capture: SUPPRESSING vk={}", info.vkCode);
+        return 1;
+    }
+    CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+}
+
+fn key_input(vk: u16, key_down: bool) -> INPUT {
+    let mut flags = if key_down { 0 } else { KEYEVENTF_KEYUP };
+    if is_extended_key(vk) {
+        flags |= KEYEVENTF_EXTENDEDKEY;
+    }
+    INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: INJECTED_EVENT_MARKER as usize,
+            },
+        },
+    }
+}
+
+/// Virtual keys for the modifiers to hold with a key
+fn modifier_keys(modifiers: Modifiers) -> Vec<u16> {
+    [
+        (modifiers.control, KeyCode::Control),
+        (modifiers.shift, KeyCode::Shift),
+        (modifiers.option, KeyCode::Option),
+        (modifiers.command, KeyCode::Command),
+    ]
+    .into_iter()
+    .filter(|&(held, _)| held)
+    .filter_map(|(_, key)| virtual_key(key))
+    .collect()
+}
+
+impl KeyboardBackend for WindowsKeyboard {
+    fn set_callback<F>(&self, callback: F)
+    where
+        F: Fn(KeyEvent) -> Option<KeyEvent> + Send + 'static,
+    {
+        *HOOK_CALLBACK.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    /// Start capturing keyboard events
+    /// This spawns a new thread that installs the hook and runs a message loop
+    fn start(&self) -> Result<(), String> {
+        let mut running = self.running.lock().unwrap();
+        if *running {
+            return Ok(());
+        }
+        *running = true;
+        drop(running);
+
+        let running_flag = Arc::clone(&self.running);
+        let hook_thread = Arc::clone(&self.hook_thread);
+
+        thread::spawn(move || unsafe {
+            hook_thread.store(GetCurrentThreadId(), Ordering::SeqCst);
+            let hook = SetWindowsHookExW(WH_KEYBOARD_LL, Some(hook_proc), std::ptr::null_mut(), 0);
+            if hook.is_null() {
+                log::error!("Failed to install the low-level keyboard hook");
+                *running_flag.lock().unwrap() = false;
+                return;
+            }
+            log::info!("Keyboard hook started successfully");
+
+            // The hook is called on this thread while it pumps messages
+            let mut msg: MSG = std::mem::zeroed();
+            while GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) > 0 {}
+
+            UnhookWindowsHookEx(hook);
+            *running_flag.lock().unwrap() = false;
+            log::info!("Keyboard hook stopped");
+        });
+
+        Ok(())
+    }
+
+    fn stop(&self) {
+        let thread_id = self.hook_thread.load(Ordering::SeqCst);
+        if thread_id != 0 {
+            unsafe {
+                PostThreadMessageW(thread_id, WM_QUIT, 0, 0);
+            }
+        }
+        *self.running.lock().unwrap() = false;
+    }
+
+    fn is_running(&self) -> bool {
+        *self.running.lock().unwrap()
+    }
+
+    fn inject_key(keycode: KeyCode, key_down: bool, modifiers: Modifiers) -> Result<(), InjectError> {
+        let (keycode, modifiers) = windows_shortcut(keycode, modifiers);
+        let vk = virtual_key(keycode).ok_or(InjectError::Unmapped(keycode))?;
+        let held = modifier_keys(modifiers);
+
+        // Modifiers go down before the key and come up after it
+        let inputs: Vec<INPUT> = if key_down {
+            held.iter()
+                .map(|&m| key_input(m, true))
+                .chain(std::iter::once(key_input(vk, true)))
+                .collect()
+        } else {
+            std::iter::once(key_input(vk, false))
+                .chain(held.iter().rev().map(|&m| key_input(m, false)))
+                .collect()
+        };
+
+        let sent = unsafe {
+            SendInput(
+                inputs.len() as u32,
+                inputs.as_ptr(),
+                std::mem::size_of::<INPUT>() as i32,
+            )
+        };
+        if sent as usize != inputs.len() {
+            return Err(InjectError::Rejected("SendInput was blocked".to_string()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+//! Translation between macOS key codes/shortcuts and Windows virtual keys
+
+use super::keycode::{KeyCode, Modifiers};
+use super::shortcuts::pc_shortcut;
+
+/// Windows virtual key for each key code
+const VIRTUAL_KEYS: &[(KeyCode, u16)] = &[
+    (KeyCode::A, 0x41),
+    (KeyCode::B, 0x42),
+    (KeyCode::C, 0x43),
+    (KeyCode::D, 0x44),
+    (KeyCode::E, 0x45),
+    (KeyCode::F, 0x46),
+    (KeyCode::G, 0x47),
+    (KeyCode::H, 0x48),
+    (KeyCode::I, 0x49),
+    (KeyCode::J, 0x4A),
+    (KeyCode::K, 0x4B),
+    (KeyCode::L, 0x4C),
+    (KeyCode::M, 0x4D),
+    (KeyCode::N, 0x4E),
+    (KeyCode::O, 0x4F),
+    (KeyCode::P, 0x50),
+    (KeyCode::Q, 0x51),
+    (KeyCode::R, 0x52),
+    (KeyCode::S, 0x53),
+    (KeyCode::T, 0x54),
+    (KeyCode::U, 0x55),
+    (KeyCode::V, 0x56),
+    (KeyCode::W, 0x57),
+    (KeyCode::X, 0x58),
+    (KeyCode::Y, 0x59),
+    (KeyCode::Z, 0x5A),
+    (KeyCode::Num0, 0x30),
+    (KeyCode::Num1, 0x31),
+    (KeyCode::Num2, 0x32),
+    (KeyCode::Num3, 0x33),
+    (KeyCode::Num4, 0x34),
+    (KeyCode::Num5, 0x35),
+    (KeyCode::Num6, 0x36),
+    (KeyCode::Num7, 0x37),
+    (KeyCode::Num8, 0x38),
+    (KeyCode::Num9, 0x39),
+    (KeyCode::Return, 0x0D),
+    (KeyCode::Tab, 0x09),
+    (KeyCode::Space, 0x20),
+    (KeyCode::Delete, 0x08), // Backspace
+    (KeyCode::Escape, 0x1B),
+    (KeyCode::Command, 0x5B), // Left Windows key
+    (KeyCode::RightCommand, 0x5C),
+    (KeyCode::Shift, 0xA0),
+    (KeyCode::RightShift, 0xA1),
+    (KeyCode::Control, 0xA2),
+    (KeyCode::RightControl, 0xA3),
+    (KeyCode::Option, 0xA4), // Left Alt
+    (KeyCode::RightOption, 0xA5),
+    (KeyCode::CapsLock, 0x14),
+    (KeyCode::Left, 0x25),
+    (KeyCode::Up, 0x26),
+    (KeyCode::Right, 0x27),
+    (KeyCode::Down, 0x28),
+    (KeyCode::F1, 0x70),
+    (KeyCode::F2, 0x71),
+    (KeyCode::F3, 0x72),
+    (KeyCode::F4, 0x73),
+    (KeyCode::F5, 0x74),
+    (KeyCode::F6, 0x75),
+    (KeyCode::F7, 0x76),
+    (KeyCode::F8, 0x77),
+    (KeyCode::F9, 0x78),
+    (KeyCode::F10, 0x79),
+    (KeyCode::F11, 0x7A),
+    (KeyCode::F12, 0x7B),
+    (KeyCode::Home, 0x24),
+    (KeyCode::End, 0x23),
+    (KeyCode::PageUp, 0x21),
+    (KeyCode::PageDown, 0x22),
+    (KeyCode::ForwardDelete, 0x2E),
+    (KeyCode::Equal, 0xBB),
+    (KeyCode::Minus, 0xBD),
+    (KeyCode::LeftBracket, 0xDB),
+    (KeyCode::RightBracket, 0xDD),
+    (KeyCode::Quote, 0xDE),
+    (KeyCode::Semicolon, 0xBA),
+    (KeyCode::Backslash, 0xDC),
+    (KeyCode::Comma, 0xBC),
+    (KeyCode::Slash, 0xBF),
+    (KeyCode::Period, 0xBE),
+    (KeyCode::Grave, 0xC0),
+];
+
+/// Windows virtual key for a key code, if it has one (Fn doesn't)
+pub fn virtual_key(keycode: KeyCode) -> Option<u16> {
+    VIRTUAL_KEYS
+        .iter()
+        .find(|&&(k, _)| k == keycode)
+        .map(|&(_, vk)| vk)
+}
+
+/// Key code for a Windows virtual key
+/// The generic Shift/Ctrl/Alt keys map to the left-hand ones
+pub fn from_virtual_key(vk: u16) -> Option<KeyCode> {
+    let vk = match vk {
+        0x10 => 0xA0,
+        0x11 => 0xA2,
+        0x12 => 0xA4,
+        vk => vk,
+    };
+    VIRTUAL_KEYS
+        .iter()
+        .find(|&&(_, v)| v == vk)
+        .map(|&(k, _)| k)
+}
+
+/// Whether a virtual key needs the extended-key flag when injected
+pub fn is_extended_key(vk: u16) -> bool {
+    matches!(vk, 0x21..=0x28 | 0x2E | 0x5B | 0x5C | 0xA3 | 0xA5)
+}
+
+/// Translate a macOS shortcut into the Windows one with the same effect
+/// As on other PCs (see `pc_shortcut`), except that redo is Ctrl+Y
+pub fn windows_shortcut(keycode: KeyCode, modifiers: Modifiers) -> (KeyCode, Modifiers) {
+    if keycode == KeyCode::Z && modifiers.command && modifiers.shift {
+        return (
+            KeyCode::Y,
+            Modifiers {
+                control: true,
+                ..Default::default()
+            },
+        );
+    }
+    pc_shortcut(keycode, modifiers)
+}
+
+/// Modifiers as the vim layer sees them: the Windows key is Cmd and Alt is Option
+pub fn modifiers_from_windows(shift: bool, control: bool, alt: bool, win: bool) -> Modifiers {
+    Modifiers {
+        shift,
+        control,
+        option: alt,
+        command: win,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mods(shift: bool, control: bool, option: bool, command: bool) -> Modifiers {
+        Modifiers {
+            shift,
+            control,
+            option,
+            command,
+            ..Default::default()
+        }
+    }
+
+    fn translate(keycode: KeyCode, modifiers: Modifiers) -> (KeyCode, bool, bool, bool, bool) {
+        let (keycode, m) = windows_shortcut(keycode, modifiers);
+        (keycode, m.shift, m.control, m.option, m.command)
+    }
+
+    #[test]
+    fn test_virtual_keys_round_trip() {
+        for &(keycode, vk) in VIRTUAL_KEYS {
+            assert_eq!(virtual_key(keycode), Some(vk));
+            assert_eq!(from_virtual_key(vk), Some(keycode));
+        }
+        assert_eq!(virtual_key(KeyCode::Function), None);
+        assert_eq!(from_virtual_key(0x11), Some(KeyCode::Control));
+        assert!(is_extended_key(virtual_key(KeyCode::Left).unwrap()));
+        assert!(!is_extended_key(virtual_key(KeyCode::A).unwrap()));
+    }
+
+    #[test]
+    fn test_redo_is_control_y() {
+        assert_eq!(
+            translate(KeyCode::Z, mods(true, false, false, true)),
+            (KeyCode::Y, false, true, false, false)
+        );
+        assert_eq!(
+            translate(KeyCode::Z, mods(false, false, false, true)),
+            (KeyCode::Z, false, true, false, false)
+        );
+        assert_eq!(
+            translate(KeyCode::Left, mods(false, false, false, true)),
+            (KeyCode::Home, false, false, false, false)
+        );
+    }
+
+    #[test]
+    fn test_modifiers_from_windows() {
+        let m = modifiers_from_windows(true, false, true, true);
+        assert_eq!((m.shift, m.control, m.option, m.command), (true, false, true, true));
+    }
+}
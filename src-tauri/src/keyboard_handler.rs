@@ -1,31 +1,174 @@
 //! Keyboard event handler for vim mode processing
 
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
+use std::time::Instant;
+
+use serde::Serialize;
+use tokio::sync::broadcast;
 
 use crate::commands::{RecordedKey, RecordedModifiers};
-use crate::config::Settings;
-use crate::keyboard::{KeyCode, KeyEvent};
-use crate::nvim_edit::{self, EditSessionManager};
-use crate::vim::{ProcessResult, VimAction, VimMode, VimState};
+use crate::config::{KeyRepeat, KeyboardLayout, NvimEditSettings, Settings, VimKeyModifiers};
+use crate::keyboard::budget;
+use crate::keyboard::chord::ChordDetector;
+use crate::keyboard::double_tap::DoubleTapDetector;
+use crate::keyboard::repeat::RepeatFilter;
+use crate::keyboard::{self, layout, KeyCode, KeyEvent, Modifiers, INJECT_FAILURE_WINDOW};
+use crate::nvim_edit::{self, accessibility, clipboard, EditSessionManager};
+use crate::vim::registers::{self, YankTarget};
+use crate::vim::seen_apps::SeenApps;
+use crate::vim::{MacroStep, MotionOptions, ProcessResult, VimAction, VimMode, VimState};
+use crate::widgets::capslock;
 
 #[cfg(target_os = "macos")]
 use objc::{class, msg_send, sel, sel_impl};
 
+/// How long to wait for a cut to reach the clipboard before reading it for the delete ring
+const CUT_CLIPBOARD_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Longest the event tap waits to learn whether a text field is focused
+/// An app that doesn't answer in time gets the key, as outside text fields
+const FOCUS_CHECK_BUDGET: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Longest the event tap waits for NSWorkspace to name the frontmost app
+/// Without an answer the key is handled as in an app without its own settings
+const FRONTMOST_APP_BUDGET: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Failed key injections within `INJECT_FAILURE_WINDOW` that point at a lasting problem
+const FREQUENT_INJECT_FAILURES: usize = 5;
+
+/// A key press and what the state machine did with it, for the live key debugger
+/// Sent for each key press when `debug_events` is on
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyDecision {
+    pub keycode: u16,
+    /// Key name, if the keycode is known
+    pub key: Option<String>,
+    pub modifiers: RecordedModifiers,
+    /// Mode the key was pressed in
+    pub mode: VimMode,
+    pub result: DecisionResult,
+}
+
+/// What happened to a key press
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DecisionResult {
+    Suppress,
+    PassThrough,
+    ModeChanged { mode: VimMode, action: Option<String> },
+    Action { action: String },
+}
+
+impl KeyDecision {
+    fn new(event: &KeyEvent, mode: VimMode, result: &ProcessResult) -> Self {
+        let result = match result {
+            ProcessResult::Suppress => DecisionResult::Suppress,
+            ProcessResult::PassThrough => DecisionResult::PassThrough,
+            ProcessResult::ModeChanged(mode, action)
+            | ProcessResult::ModeChangedWithPending(mode, action, _) => DecisionResult::ModeChanged {
+                mode: *mode,
+                action: action.as_ref().map(|action| format!("{:?}", action)),
+            },
+            ProcessResult::SuppressWithAction(action) => DecisionResult::Action {
+                action: format!("{:?}", action),
+            },
+        };
+        Self {
+            keycode: event.code,
+            key: event.keycode().map(|keycode| keycode.to_name().to_string()),
+            modifiers: RecordedModifiers {
+                shift: event.modifiers.shift,
+                control: event.modifiers.control,
+                option: event.modifiers.option,
+                command: event.modifiers.command,
+            },
+            mode,
+            result,
+        }
+    }
+}
+
 /// Execute a VimAction on a separate thread with a small delay
-fn execute_action_async(action: VimAction) {
+/// Text cut by the action is added to the delete ring (`"1`-`"9`), and yanked or cut text
+/// is moved off the clipboard into the unnamed register when `yank_to_system_clipboard` is off.
+/// Its key events are paced as the frontmost app's override says, if it has one
+fn execute_action_async(action: VimAction, key_settings: Arc<KeySettings>) {
     thread::spawn(move || {
         thread::sleep(std::time::Duration::from_micros(500));
-        if let Err(e) = action.execute() {
+        let motion_options = key_settings.motion_options;
+        let app_pacing = &key_settings.app_pacing;
+        let pacing = injection_pacing_in_frontmost_app(motion_options.injection_pacing, app_pacing);
+        // Read-only fields skip the cut, so there's nothing to record
+        let records_cut = action.cuts_text() && accessibility::is_editable();
+        let keeps_clipboard = registers::yank_target(motion_options.yank_to_system_clipboard)
+            == YankTarget::Internal
+            && (records_cut || action.yanks_text())
+            && !matches!(action, VimAction::ToClipboard(_));
+        let before =
+            (records_cut || keeps_clipboard).then(|| clipboard::read_clipboard().unwrap_or_default());
+        let executed = keyboard::with_injection_pacing(pacing, || action.execute(motion_options));
+        if let Err(e) = executed {
             log::error!("Failed to execute vim action: {}", e);
+            warn_if_injection_failing();
+            return;
+        }
+        if let Some(previous) = before {
+            // Deleting the text that's already on the clipboard leaves it unchanged
+            let written = clipboard::wait_for_clipboard_change(&previous, CUT_CLIPBOARD_TIMEOUT)
+                .or_else(clipboard::read_clipboard);
+            if let Some(text) = written {
+                if records_cut {
+                    registers::record_delete(text.clone());
+                }
+                if keeps_clipboard {
+                    registers::set_unnamed(text);
+                }
+            }
+            if keeps_clipboard {
+                if let Err(e) = clipboard::write_clipboard(&previous) {
+                    log::warn!("Failed to restore the clipboard: {}", e);
+                }
+            }
         }
     });
 }
 
+/// Pause between injected key events in the frontmost app: its own, or `default`
+fn injection_pacing_in_frontmost_app(
+    default: std::time::Duration,
+    app_pacing: &HashMap<String, std::time::Duration>,
+) -> std::time::Duration {
+    if app_pacing.is_empty() {
+        return default;
+    }
+    #[cfg(target_os = "macos")]
+    {
+        if let Some(pacing) = get_frontmost_app_bundle_id().and_then(|id| app_pacing.get(&id)) {
+            return *pacing;
+        }
+    }
+    default
+}
+
+/// Log once key injection fails often, which a transient error doesn't explain
+fn warn_if_injection_failing() {
+    let failures = keyboard::recent_inject_failures();
+    if failures >= FREQUENT_INJECT_FAILURES {
+        log::error!(
+            "{} key events failed to post in the last {}s, check the Accessibility permission",
+            failures,
+            INJECT_FAILURE_WINDOW.as_secs()
+        );
+    }
+}
+
 /// Get the bundle identifier of the frontmost (currently focused) application
 #[cfg(target_os = "macos")]
 fn get_frontmost_app_bundle_id() -> Option<String> {
-    unsafe {
+    // Called off the main thread, where no run loop drains what NSWorkspace autoreleases
+    objc::rc::autoreleasepool(|| unsafe {
         let workspace: *mut objc::runtime::Object =
             msg_send![class!(NSWorkspace), sharedWorkspace];
         if workspace.is_null() {
@@ -48,35 +191,237 @@ fn get_frontmost_app_bundle_id() -> Option<String> {
                 .to_string_lossy()
                 .into_owned(),
         )
+    })
+}
+
+/// Looks up the frontmost app's bundle identifier off the tap thread
+type FrontmostApp = budget::Worker<Option<String>>;
+
+/// Bundle identifier of the frontmost app, for the event tap's worker to look up
+fn frontmost_app_bundle_id() -> Option<String> {
+    #[cfg(target_os = "macos")]
+    return get_frontmost_app_bundle_id();
+    #[cfg(not(target_os = "macos"))]
+    None
+}
+
+/// Bundle identifier of the frontmost app, None if NSWorkspace doesn't answer within the budget
+fn frontmost_app_within_budget(frontmost_app: &FrontmostApp) -> Option<String> {
+    let bundle_id = frontmost_app.run_within(FRONTMOST_APP_BUDGET);
+    if bundle_id.is_none() {
+        log::debug!("Frontmost app didn't answer within {:?}", FRONTMOST_APP_BUDGET);
     }
+    bundle_id.flatten()
 }
 
 /// Check if the frontmost app is in the ignored apps list.
-fn is_frontmost_app_ignored(ignored_apps: &[String]) -> bool {
+fn is_frontmost_app_ignored(ignored_apps: &[String], frontmost_app: &FrontmostApp) -> bool {
     if ignored_apps.is_empty() {
         return false;
     }
-    #[cfg(target_os = "macos")]
-    {
-        if let Some(bundle_id) = get_frontmost_app_bundle_id() {
-            return ignored_apps.iter().any(|id| id == &bundle_id);
+    frontmost_app_within_budget(frontmost_app).is_some_and(|id| ignored_apps.contains(&id))
+}
+
+/// Check if the nvim edit shortcut is turned off for the frontmost app
+fn is_nvim_edit_disabled_in_frontmost_app(
+    settings: &Settings,
+    frontmost_app: &FrontmostApp,
+) -> bool {
+    if settings.app_overrides.is_empty() {
+        return false;
+    }
+    frontmost_app_within_budget(frontmost_app)
+        .is_some_and(|bundle_id| !settings.nvim_edit_enabled_in_app(&bundle_id))
+}
+
+/// Whether a text field is focused, false if accessibility doesn't answer within the budget
+fn focused_text_field_within_budget(focus_check: &budget::Worker<bool>) -> bool {
+    let focused = focus_check.run_within(FOCUS_CHECK_BUDGET);
+    if focused.is_none() {
+        log::debug!("Focused element didn't answer within {:?}", FOCUS_CHECK_BUDGET);
+    }
+    focused.unwrap_or(false)
+}
+
+/// Mode to switch to when the frontmost app is focused for the first time this session,
+/// once `startup_mode_per_app` has any app in it
+fn first_focus_startup_mode(
+    settings: &Settings,
+    frontmost_app: &FrontmostApp,
+    seen_apps: &Mutex<SeenApps>,
+) -> Option<VimMode> {
+    if settings.startup_mode_per_app.is_empty() {
+        return None;
+    }
+    let bundle_id = frontmost_app_within_budget(frontmost_app)?;
+    seen_apps
+        .lock()
+        .unwrap()
+        .focus(&bundle_id)
+        .then(|| settings.startup_mode_for_app(&bundle_id))
+}
+
+/// Toggle the mode for a vim key press or modifier chord, running the action it produces
+/// Returns false without toggling in insert mode in an ignored app, where the key passes through
+fn toggle_vim_mode(
+    vim_state: &Mutex<VimState>,
+    key_settings: &Arc<KeySettings>,
+    frontmost_app: &FrontmostApp,
+    event: &KeyEvent,
+    decision_tx: &broadcast::Sender<KeyDecision>,
+) -> bool {
+    let current_mode = vim_state.lock().unwrap().mode();
+    let ignored_apps = &key_settings.ignored_apps;
+    if current_mode == VimMode::Insert && is_frontmost_app_ignored(ignored_apps, frontmost_app) {
+        log::debug!("Vim key: ignored app, passing through");
+        return false;
+    }
+
+    let result = vim_state.lock().unwrap().handle_vim_key();
+    if key_settings.settings.debug_events {
+        let _ = decision_tx.send(KeyDecision::new(event, current_mode, &result));
+    }
+
+    if let ProcessResult::ModeChanged(_mode, action) = result {
+        log::debug!("Vim key: ModeChanged");
+        if let Some(action) = action.filter(|_| !key_settings.settings.tracking_only_mode) {
+            execute_action_async(action, Arc::clone(key_settings));
         }
     }
-    false
+    true
+}
+
+/// Whether exactly the shortcut's modifiers are held, on either side
+fn shortcut_modifiers_held(mods: &VimKeyModifiers, held: &Modifiers) -> bool {
+    held.shift == mods.shift
+        && held.control == mods.control
+        && held.option == mods.option
+        && held.command == mods.command
+}
+
+/// What the event tap reads from the settings, worked out once each time they change
+/// The tap takes the current snapshot with an `Arc` clone, so it never waits on the settings
+/// lock and copies nothing per key
+struct KeySettings {
+    /// For the Edit Popup and the per-app lookups, which read it as it is
+    settings: Settings,
+    /// Key of the nvim edit shortcut, None while nvim edit is off
+    edit_key: Option<KeyCode>,
+    batch_modifiers: Option<VimKeyModifiers>,
+    double_tap_key: Option<KeyCode>,
+    /// None while vim mode is off
+    chord: Option<Vec<KeyCode>>,
+    vim_key: Option<KeyCode>,
+    resets_caps_lock: bool,
+    motion_options: MotionOptions,
+    ignored_apps: Vec<String>,
+    app_pacing: HashMap<String, std::time::Duration>,
+}
+
+impl KeySettings {
+    fn new(settings: &Settings) -> Self {
+        let nvim_settings = &settings.nvim_edit;
+        Self {
+            settings: settings.clone(),
+            edit_key: KeyCode::from_name(&nvim_settings.shortcut_key)
+                .filter(|_| nvim_settings.enabled),
+            batch_modifiers: nvim_settings.batch_shortcut_modifiers(),
+            double_tap_key: nvim_settings.double_tap_key(),
+            chord: settings.vim_key_chord_keys().filter(|_| settings.enabled),
+            vim_key: KeyCode::from_name(&settings.vim_key),
+            resets_caps_lock: settings.resets_caps_lock(),
+            motion_options: MotionOptions::from_settings(settings),
+            ignored_apps: settings.vim_disabled_apps(),
+            app_pacing: settings.app_injection_pacing(),
+        }
+    }
+
+    /// Whether the key is the nvim edit shortcut: Some(true) with `batch_modifier` added
+    fn edit_shortcut(&self, event: &KeyEvent) -> Option<bool> {
+        self.edit_key
+            .filter(|&key| event.is_key_down && event.keycode() == Some(key))
+            .and_then(|_| {
+                // Adding `batch_modifier` to the shortcut edits all fields in the window at once
+                let batch = self
+                    .batch_modifiers
+                    .as_ref()
+                    .is_some_and(|batch| shortcut_modifiers_held(batch, &event.modifiers));
+                let modifiers = &self.settings.nvim_edit.shortcut_modifiers;
+                (batch || shortcut_modifiers_held(modifiers, &event.modifiers)).then_some(batch)
+            })
+    }
+
+    /// Whether the key is the vim key with its modifiers
+    fn is_vim_key(&self, event: &KeyEvent) -> bool {
+        let held = &event.modifiers;
+        self.vim_key.is_some_and(|key| {
+            event.keycode() == Some(key) && self.settings.vim_key_modifiers.matches(key, held)
+        })
+    }
+}
+
+/// The latest `KeySettings`, replaced whole when the settings change
+#[derive(Clone)]
+pub struct SharedKeySettings(Arc<RwLock<Arc<KeySettings>>>);
+
+impl SharedKeySettings {
+    pub fn new(settings: &Settings) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(KeySettings::new(settings)))))
+    }
+
+    /// Publish a snapshot of changed settings
+    /// It's built before the write lock is taken, so the tap only ever waits for the swap
+    pub fn update(&self, settings: &Settings) {
+        let key_settings = Arc::new(KeySettings::new(settings));
+        *self.0.write().unwrap() = key_settings;
+    }
+
+    fn current(&self) -> Arc<KeySettings> {
+        Arc::clone(&self.0.read().unwrap())
+    }
+}
+
+/// Open the Edit Popup for the focused field, or all fields of the window with `batch`,
+/// on a separate thread
+fn trigger_nvim_edit_async(manager: Arc<EditSessionManager>, nvim_settings: NvimEditSettings, batch: bool) {
+    thread::spawn(move || {
+        let result = if batch {
+            nvim_edit::trigger_batch_edit(manager, nvim_settings)
+        } else {
+            nvim_edit::trigger_nvim_edit(manager, nvim_settings)
+        };
+        if let Err(e) = result {
+            log::error!("Failed to trigger nvim edit: {}", e);
+            if let Some(guidance) = e.guidance() {
+                log::info!("{}", guidance);
+            }
+        }
+    });
 }
 
 /// Create the keyboard callback that processes key events
 pub fn create_keyboard_callback(
     vim_state: Arc<Mutex<VimState>>,
-    settings: Arc<Mutex<Settings>>,
+    settings: SharedKeySettings,
     record_key_tx: Arc<Mutex<Option<tokio::sync::oneshot::Sender<RecordedKey>>>>,
     edit_session_manager: Arc<EditSessionManager>,
+    decision_tx: broadcast::Sender<KeyDecision>,
 ) -> impl Fn(KeyEvent) -> Option<KeyEvent> + Send + 'static {
+    let repeat_filter = Mutex::new(RepeatFilter::default());
+    let chord_detector = Mutex::new(ChordDetector::default());
+    let double_tap_detector = Mutex::new(DoubleTapDetector::default());
+    let seen_apps = Mutex::new(SeenApps::default());
+    let focus_check = budget::Worker::spawn(accessibility::is_focused_text_field);
+    let frontmost_app = budget::Worker::spawn(frontmost_app_bundle_id);
     move |event| {
         // Check if we're recording a key (only on key down)
         if event.is_key_down {
             let mut record_tx = record_key_tx.lock().unwrap();
-            if let Some(tx) = record_tx.take() {
+            // Modifier presses start a combo, so they aren't recorded on their own (except Caps Lock)
+            let is_combo_modifier = event
+                .keycode()
+                .is_some_and(|k| k.is_modifier() && k != KeyCode::CapsLock);
+            if let Some(tx) = record_tx.take_if(|_| !is_combo_modifier) {
                 if let Some(keycode) = event.keycode() {
                     let recorded = RecordedKey {
                         name: keycode.to_name().to_string(),
@@ -94,126 +439,423 @@ pub fn create_keyboard_callback(
             }
         }
 
-        // Check if this is the configured nvim edit shortcut
-        if event.is_key_down {
-            let settings_guard = settings.lock().unwrap();
-            let nvim_settings = &settings_guard.nvim_edit;
-
-            if nvim_settings.enabled {
-                let nvim_key = KeyCode::from_name(&nvim_settings.shortcut_key);
-                let mods = &nvim_settings.shortcut_modifiers;
-
-                let modifiers_match = event.modifiers.shift == mods.shift
-                    && event.modifiers.control == mods.control
-                    && event.modifiers.option == mods.option
-                    && event.modifiers.command == mods.command;
-
-                if let Some(configured_key) = nvim_key {
-                    if event.keycode() == Some(configured_key) && modifiers_match {
-                        let nvim_settings_clone = nvim_settings.clone();
-                        drop(settings_guard);
-
-                        let manager = Arc::clone(&edit_session_manager);
-                        thread::spawn(move || {
-                            if let Err(e) =
-                                nvim_edit::trigger_nvim_edit(manager, nvim_settings_clone)
-                            {
-                                log::error!("Failed to trigger nvim edit: {}", e);
-                            }
-                        });
-
-                        return None;
-                    }
-                }
+        let key_settings = settings.current();
+        let settings = &key_settings.settings;
+        // Double-tapping the configured modifier opens the Edit Popup like the shortcut
+        let double_tapped = key_settings
+            .double_tap_key
+            .is_some_and(|key| double_tap_detector.lock().unwrap().update(key, &event, Instant::now()));
+
+        if let Some(batch) = key_settings.edit_shortcut(&event) {
+            if !is_nvim_edit_disabled_in_frontmost_app(settings, &frontmost_app) {
+                let manager = Arc::clone(&edit_session_manager);
+                trigger_nvim_edit_async(manager, settings.nvim_edit.clone(), batch);
+                return None;
             }
         }
 
-        // Check if this is the configured vim key with matching modifiers
-        if event.is_key_down {
-            let settings_guard = settings.lock().unwrap();
+        // The double tap's events still pass through, as the chord's do
+        if double_tapped {
+            if !is_nvim_edit_disabled_in_frontmost_app(settings, &frontmost_app) {
+                let manager = Arc::clone(&edit_session_manager);
+                trigger_nvim_edit_async(manager, settings.nvim_edit.clone(), false);
+            }
+            return Some(event);
+        }
 
-            if !settings_guard.enabled {
+        // A modifier chord toggles like the vim key once it's released. The release
+        // still passes through, so apps don't see the modifiers as stuck
+        if let Some(chord) = &key_settings.chord {
+            if chord_detector.lock().unwrap().update(chord, &event) {
+                toggle_vim_mode(&vim_state, &key_settings, &frontmost_app, &event, &decision_tx);
                 return Some(event);
             }
+        }
 
-            let vim_key = KeyCode::from_name(&settings_guard.vim_key);
-            let mods = &settings_guard.vim_key_modifiers;
-
-            let modifiers_match = event.modifiers.shift == mods.shift
-                && event.modifiers.control == mods.control
-                && event.modifiers.option == mods.option
-                && event.modifiers.command == mods.command;
+        if !settings.enabled {
+            return Some(event);
+        }
 
-            if let Some(configured_key) = vim_key {
-                if event.keycode() == Some(configured_key) && modifiers_match {
-                    let ignored_apps = settings_guard.ignored_apps.clone();
-                    drop(settings_guard);
+        // Check if this is the configured vim key with matching modifiers
+        if event.is_key_down && key_settings.is_vim_key(&event) {
+            if !toggle_vim_mode(&vim_state, &key_settings, &frontmost_app, &event, &decision_tx) {
+                return Some(event);
+            }
 
-                    let current_mode = vim_state.lock().unwrap().mode();
-                    if current_mode == VimMode::Insert {
-                        if is_frontmost_app_ignored(&ignored_apps) {
-                            log::debug!("Vim key: ignored app, passing through");
-                            return Some(event);
-                        }
-                    }
+            // The press already toggled Caps Lock in the HID system; suppressing
+            // the event doesn't undo that, so turn it back off
+            if key_settings.resets_caps_lock {
+                if let Err(e) = capslock::set_caps_lock_state(false) {
+                    log::warn!("Failed to reset Caps Lock: {}", e);
+                }
+            }
+            return None;
+        }
 
-                    let result = {
-                        let mut state = vim_state.lock().unwrap();
-                        state.handle_vim_key()
-                    };
+        // Modifier keys are only reported so they can be the vim key or a chord
+        if event.keycode().is_some_and(KeyCode::is_modifier) {
+            return Some(event);
+        }
 
-                    return match result {
-                        ProcessResult::ModeChanged(_mode, action) => {
-                            log::debug!("Vim key: ModeChanged");
-                            if let Some(action) = action {
-                                execute_action_async(action);
-                            }
-                            None
-                        }
-                        _ => None,
-                    };
-                }
+        // An app typed in for the first time this session starts in its own mode
+        if event.is_key_down {
+            if let Some(mode) = first_focus_startup_mode(settings, &frontmost_app, &seen_apps) {
+                log::debug!("First focus of the frontmost app, starting in {:?}", mode);
+                vim_state.lock().unwrap().set_mode_external(mode);
             }
         }
 
-        // Check if vim mode is disabled for non-key-down events
-        {
-            let settings_guard = settings.lock().unwrap();
-            if !settings_guard.enabled {
+        // The state machine works with logical keys; passed-through events stay physical
+        let vim_event = layout::translate_event(&settings.keyboard_layout, event);
+        let (result, mode) = {
+            let mut state = vim_state.lock().unwrap();
+            // Held keys repeat as configured in normal mode; other modes keep the OS repeat
+            let policy = if state.mode() == VimMode::Normal {
+                settings.normal_mode_key_repeat
+            } else {
+                KeyRepeat::OsDefault
+            };
+            if !repeat_filter.lock().unwrap().accept(&event, policy, Instant::now()) {
+                log::debug!("Dropping key repeat: keycode={}", event.code);
+                return None;
+            }
+            // Like the vim key, keys that leave insert mode are left to ignored apps
+            if state.would_leave_insert(&vim_event)
+                && is_frontmost_app_ignored(&key_settings.ignored_apps, &frontmost_app)
+            {
+                log::debug!("Ignored app, passing through keycode={}", event.code);
                 return Some(event);
             }
+            let mode = state.mode();
+            (state.process_key(vim_event), mode)
+        };
+        let was_insert = mode == VimMode::Insert;
+
+        // Insert-mode bindings only apply in text fields, so apps keep shortcuts like Ctrl+W (close tab).
+        // Without injection they would do nothing, so the app gets them too. The accessibility
+        // read runs off the tap thread, so an app that hangs can't stall typing
+        let result = match result {
+            ProcessResult::SuppressWithAction(_)
+                if was_insert
+                    && (settings.tracking_only_mode || !focused_text_field_within_budget(&focus_check)) =>
+            {
+                ProcessResult::PassThrough
+            }
+            result => result,
+        };
+
+        // The replacement char of `r` and text replayed by `.` or macros are typed back,
+        // which needs the physical keys
+        let result = match result {
+            ProcessResult::SuppressWithAction(action) => {
+                ProcessResult::SuppressWithAction(to_physical_keys(&settings.keyboard_layout, action))
+            }
+            result => result,
+        };
+
+        if settings.debug_events && event.is_key_down {
+            let _ = decision_tx.send(KeyDecision::new(&event, mode, &result));
         }
 
-        let result = {
-            let mut state = vim_state.lock().unwrap();
-            state.process_key(event)
+        let (passed, action) = resolve_result(event, result, settings.tracking_only_mode);
+        if let Some(action) = action {
+            execute_action_async(action, Arc::clone(&key_settings));
+        }
+        passed
+    }
+}
+
+/// Create the callback for mouse clicks, which switch to insert mode when
+/// `mouse_disables_normal_mode` is set
+#[cfg(any(target_os = "macos", test))]
+pub fn create_mouse_down_callback(
+    vim_state: Arc<Mutex<VimState>>,
+    settings: SharedKeySettings,
+) -> impl Fn() + Send + 'static {
+    move || {
+        let key_settings = settings.current();
+        if !key_settings.settings.enabled || !key_settings.settings.mouse_disables_normal_mode {
+            return;
+        }
+        let mut state = vim_state.lock().unwrap();
+        if state.mode() != VimMode::Insert {
+            log::debug!("Mouse click, switching to insert mode");
+            state.set_mode_external(VimMode::Insert);
+        }
+    }
+}
+
+/// Map the keys an action types back from logical to physical keys
+fn to_physical_keys(layout: &KeyboardLayout, action: VimAction) -> VimAction {
+    match action {
+        VimAction::ReplaceChar { keycode, shift, count } => VimAction::ReplaceChar {
+            keycode: layout::to_physical(layout, keycode),
+            shift,
+            count,
+        },
+        VimAction::RepeatLastInsertedText { change, keys, count } => VimAction::RepeatLastInsertedText {
+            change,
+            keys: keys
+                .into_iter()
+                .map(|(keycode, shift)| (layout::to_physical(layout, keycode), shift))
+                .collect(),
+            count,
+        },
+        VimAction::PlayMacro { steps } => VimAction::PlayMacro {
+            steps: steps
+                .into_iter()
+                .map(|step| match step {
+                    MacroStep::Action(action) => MacroStep::Action(to_physical_keys(layout, action)),
+                    MacroStep::Key { keycode, modifiers } => MacroStep::Key {
+                        keycode: layout::to_physical(layout, keycode),
+                        modifiers,
+                    },
+                })
+                .collect(),
+        },
+        action => action,
+    }
+}
+
+/// Split a processed key into the event to pass on and the action to run
+/// In tracking-only mode actions are dropped, so nothing is injected while modes still update
+fn resolve_result(
+    event: KeyEvent,
+    result: ProcessResult,
+    tracking_only: bool,
+) -> (Option<KeyEvent>, Option<VimAction>) {
+    let (passed, action) = match result {
+        ProcessResult::Suppress => {
+            log::debug!("Suppress: keycode={}", event.code);
+            (None, None)
+        }
+        ProcessResult::SuppressWithAction(action) => {
+            log::debug!(
+                "SuppressWithAction: keycode={}, action={:?}",
+                event.code,
+                action
+            );
+            (None, Some(action))
+        }
+        ProcessResult::PassThrough => {
+            log::debug!("PassThrough: keycode={}", event.code);
+            (Some(event), None)
+        }
+        ProcessResult::ModeChanged(_mode, action) => {
+            log::debug!("ModeChanged: keycode={}", event.code);
+            (None, action)
+        }
+        ProcessResult::ModeChangedWithPending(_mode, action, pending) => {
+            log::debug!("ModeChangedWithPending: keycode={}, pending={:?}", event.code, pending);
+            (None, action)
+        }
+    };
+
+    if tracking_only && action.is_some() {
+        log::debug!("Tracking only, skipping action {:?}", action);
+        return (passed, None);
+    }
+    (passed, action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::Modifiers;
+
+    fn press(state: &mut VimState, keycode: KeyCode) -> (KeyEvent, ProcessResult) {
+        let event = KeyEvent {
+            code: keycode.as_raw(),
+            modifiers: Modifiers::default(),
+            is_key_down: true,
         };
+        (event, state.process_key(event))
+    }
+
+    #[test]
+    fn test_tracking_only_skips_actions_but_keeps_mode() {
+        let (mut state, _rx) = VimState::with_mode(VimMode::Normal);
+
+        let (event, result) = press(&mut state, KeyCode::J);
+        let (passed, action) = resolve_result(event, result, true);
+        assert!(passed.is_none());
+        assert!(action.is_none());
+
+        // `cw` enters insert mode without running the change
+        press(&mut state, KeyCode::C);
+        let (event, result) = press(&mut state, KeyCode::W);
+        let (passed, action) = resolve_result(event, result, true);
+        assert!(passed.is_none());
+        assert!(action.is_none());
+        assert_eq!(state.mode(), VimMode::Insert);
+    }
+
+    #[test]
+    fn test_actions_run_without_tracking_only() {
+        let (mut state, _rx) = VimState::with_mode(VimMode::Normal);
+        let (event, result) = press(&mut state, KeyCode::J);
+        let (passed, action) = resolve_result(event, result, false);
+        assert!(passed.is_none());
+        assert!(matches!(action, Some(VimAction::Command { .. })));
+
+        // Typing in insert mode still passes through
+        let (mut state, _rx) = VimState::with_mode(VimMode::Insert);
+        let (event, result) = press(&mut state, KeyCode::J);
+        let (passed, _) = resolve_result(event, result, true);
+        assert_eq!(passed.map(|e| e.code), Some(KeyCode::J.as_raw()));
+    }
+
+    #[test]
+    fn test_mouse_down_switches_to_insert() {
+        let (state, _rx) = VimState::with_mode(VimMode::Normal);
+        let vim_state = Arc::new(Mutex::new(state));
+        let settings = SharedKeySettings::new(&Settings::default());
+        let click = create_mouse_down_callback(Arc::clone(&vim_state), settings.clone());
 
-        match result {
-            ProcessResult::Suppress => {
-                log::debug!("Suppress: keycode={}", event.code);
-                None
-            }
-            ProcessResult::SuppressWithAction(ref action) => {
-                log::debug!(
-                    "SuppressWithAction: keycode={}, action={:?}",
-                    event.code,
-                    action
-                );
-                execute_action_async(action.clone());
-                None
-            }
-            ProcessResult::PassThrough => {
-                log::debug!("PassThrough: keycode={}", event.code);
-                Some(event)
-            }
-            ProcessResult::ModeChanged(_mode, action) => {
-                log::debug!("ModeChanged: keycode={}", event.code);
-                if let Some(action) = action {
-                    execute_action_async(action);
+        // Off by default
+        click();
+        assert_eq!(vim_state.lock().unwrap().mode(), VimMode::Normal);
+
+        let mut changed = Settings::default();
+        changed.mouse_disables_normal_mode = true;
+        settings.update(&changed);
+        press(&mut vim_state.lock().unwrap(), KeyCode::D);
+        click();
+        assert_eq!(vim_state.lock().unwrap().mode(), VimMode::Insert);
+        // The pending `d` was dropped with the mode change
+        assert_eq!(vim_state.lock().unwrap().get_pending_keys(), "");
+
+        vim_state.lock().unwrap().set_mode_external(VimMode::Visual);
+        click();
+        assert_eq!(vim_state.lock().unwrap().mode(), VimMode::Insert);
+    }
+
+    #[test]
+    fn test_ipc_during_keystrokes_does_not_deadlock() {
+        use crate::ipc::IpcCommand;
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let (state, _rx) = VimState::with_mode(VimMode::Normal);
+        let vim_state = Arc::new(Mutex::new(state));
+        // Nothing is injected in tracking-only mode
+        let settings = Arc::new(Mutex::new(Settings::default()));
+        settings.lock().unwrap().tracking_only_mode = true;
+        let (decision_tx, _decision_rx) = broadcast::channel(16);
+        let callback = create_keyboard_callback(
+            Arc::clone(&vim_state),
+            SharedKeySettings::new(&settings.lock().unwrap()),
+            Arc::new(Mutex::new(None)),
+            Arc::new(EditSessionManager::new()),
+            decision_tx,
+        );
+
+        let (done_tx, done_rx) = mpsc::channel();
+        let ipc_threads: Vec<_> = (0..4)
+            .map(|i| {
+                let vim_state = Arc::clone(&vim_state);
+                let settings = Arc::clone(&settings);
+                thread::spawn(move || {
+                    for n in 0..500 {
+                        let cmd = match (i + n) % 4 {
+                            0 => IpcCommand::Toggle,
+                            1 => IpcCommand::GetMode,
+                            2 => IpcCommand::Normal,
+                            _ => IpcCommand::SetMode("visual".to_string()),
+                        };
+                        let (_, change) = crate::apply_ipc_command(&vim_state, cmd);
+                        // As emitting `mode-change` reads the mode's label
+                        if let Some(change) = change {
+                            let _ = settings.lock().unwrap().indicator_text_template.label(change.mode).len();
+                        }
+                    }
+                })
+            })
+            .collect();
+        let keys = thread::spawn(move || {
+            let keys = [KeyCode::J, KeyCode::D, KeyCode::I, KeyCode::Escape, KeyCode::K, KeyCode::V];
+            for n in 0..2000 {
+                let code = keys[n % keys.len()].as_raw();
+                for is_key_down in [true, false] {
+                    callback(KeyEvent { code, modifiers: Modifiers::default(), is_key_down });
                 }
-                None
             }
+            let _ = done_tx.send(());
+        });
+
+        assert!(done_rx.recv_timeout(Duration::from_secs(30)).is_ok(), "keystrokes stalled");
+        keys.join().unwrap();
+        for thread in ipc_threads {
+            thread.join().unwrap();
         }
     }
+
+    #[test]
+    fn test_held_settings_lock_does_not_leak_keys() {
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let (state, _rx) = VimState::with_mode(VimMode::Normal);
+        // Nothing is injected in tracking-only mode
+        let settings = Arc::new(Mutex::new(Settings::default()));
+        settings.lock().unwrap().tracking_only_mode = true;
+        let key_settings = SharedKeySettings::new(&settings.lock().unwrap());
+        let (decision_tx, _decision_rx) = broadcast::channel(16);
+        let callback = create_keyboard_callback(
+            Arc::new(Mutex::new(state)),
+            key_settings.clone(),
+            Arc::new(Mutex::new(None)),
+            Arc::new(EditSessionManager::new()),
+            decision_tx,
+        );
+
+        let (event_tx, event_rx) = mpsc::channel::<KeyEvent>();
+        let (passed_tx, passed_rx) = mpsc::channel();
+        thread::spawn(move || {
+            for event in event_rx {
+                let _ = passed_tx.send(callback(event).map(|e| e.code));
+            }
+        });
+        let j = KeyEvent { code: KeyCode::J.as_raw(), modifiers: Modifiers::default(), is_key_down: true };
+
+        // `set_settings` holds the lock while it replaces the settings
+        let mut guard = settings.lock().unwrap();
+        event_tx.send(j).unwrap();
+        // J still moves down in normal mode instead of being typed into the app
+        assert_eq!(passed_rx.recv_timeout(Duration::from_secs(5)), Ok(None));
+
+        // Keys after the new settings are published see them
+        guard.enabled = false;
+        key_settings.update(&guard);
+        drop(guard);
+        event_tx.send(j).unwrap();
+        assert_eq!(passed_rx.recv_timeout(Duration::from_secs(5)), Ok(Some(KeyCode::J.as_raw())));
+    }
+
+    #[test]
+    fn test_key_decision_payload() {
+        let (mut state, _rx) = VimState::with_mode(VimMode::Normal);
+
+        let (event, result) = press(&mut state, KeyCode::D);
+        let decision = KeyDecision::new(&event, VimMode::Normal, &result);
+        assert_eq!(decision.result, DecisionResult::Suppress);
+
+        let (mut state, _rx) = VimState::with_mode(VimMode::Normal);
+        let (event, result) = press(&mut state, KeyCode::I);
+        let decision = KeyDecision::new(&event, VimMode::Normal, &result);
+        assert_eq!(decision.key.as_deref(), Some("i"));
+        let payload = serde_json::to_value(&decision).unwrap();
+        assert_eq!(payload["keycode"], KeyCode::I.as_raw());
+        assert_eq!(payload["mode"], "normal");
+        assert_eq!(payload["modifiers"]["shift"], false);
+        assert_eq!(payload["result"]["kind"], "mode_changed");
+        assert_eq!(payload["result"]["mode"], "insert");
+        assert_eq!(payload["result"]["action"], serde_json::Value::Null);
+
+        state.set_mode_external(VimMode::Normal);
+        let (event, result) = press(&mut state, KeyCode::J);
+        let payload = serde_json::to_value(KeyDecision::new(&event, VimMode::Normal, &result)).unwrap();
+        assert_eq!(payload["result"]["kind"], "action");
+        assert!(payload["result"]["action"].as_str().unwrap().contains("MoveDown"));
+    }
 }
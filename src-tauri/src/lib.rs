@@ -13,7 +13,9 @@ mod widgets;
 mod window;
 
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use serde::{Deserialize, Serialize};
 use tauri::{
     image::Image,
     menu::{Menu, MenuItem},
@@ -22,12 +24,20 @@ use tauri::{
 };
 
 use commands::RecordedKey;
-use config::Settings;
+use config::{CursorStyle, Settings};
 use ipc::{IpcCommand, IpcResponse};
-use keyboard::{check_accessibility_permission, request_accessibility_permission, KeyboardCapture};
-use keyboard_handler::create_keyboard_callback;
+use keyboard::{
+    check_accessibility_permission, request_accessibility_permission, wait_for_permission,
+    KeyboardBackend, KeyboardCapture,
+};
+use keyboard_handler::{create_keyboard_callback, KeyDecision, SharedKeySettings};
+#[cfg(target_os = "macos")]
+use keyboard_handler::create_mouse_down_callback;
 use nvim_edit::EditSessionManager;
-use vim::{VimMode, VimState};
+use tokio::sync::broadcast;
+use vim::mode_history::{self, ModeHistory, MAX_MODE_TRANSITIONS};
+use vim::pending_help::Continuation;
+use vim::{ModeChange, VimMode, VimOptions, VimState};
 use window::setup_indicator_window;
 
 use std::fs::OpenOptions;
@@ -36,12 +46,15 @@ use std::sync::OnceLock;
 
 static LOG_FILE: OnceLock<Mutex<std::fs::File>> = OnceLock::new();
 
+/// Path of the application log file
+pub(crate) const LOG_FILE_PATH: &str = "/tmp/ovim-rust.log";
+
 fn init_file_logger() {
     let file = OpenOptions::new()
         .create(true)
         .write(true)
         .truncate(true)
-        .open("/tmp/ovim-rust.log")
+        .open(LOG_FILE_PATH)
         .expect("Failed to create log file");
 
     LOG_FILE.set(Mutex::new(file)).ok();
@@ -69,65 +82,231 @@ fn init_file_logger() {
 }
 
 /// Application state shared across commands
+///
+/// Lock order: `vim_state` before `settings` when both are held, though it's best to copy what's
+/// needed out of one and release it before taking the other. Neither is held across disk writes,
+/// event emits or key injection: the keyboard callback locks `vim_state` on every key, and the
+/// event tap stalls all input while it waits. The tap never takes `settings`; it reads the
+/// `key_settings` snapshot, which is republished whenever `settings` changes.
 pub struct AppState {
     pub settings: Arc<Mutex<Settings>>,
+    pub key_settings: SharedKeySettings,
     pub vim_state: Arc<Mutex<VimState>>,
     pub keyboard_capture: KeyboardCapture,
     pub record_key_tx: Arc<Mutex<Option<tokio::sync::oneshot::Sender<RecordedKey>>>>,
-    #[allow(dead_code)]
+    pub mode_history: Arc<Mutex<ModeHistory>>,
     edit_session_manager: Arc<EditSessionManager>,
 }
 
-fn handle_ipc_command(
-    state: &mut VimState,
-    app_handle: &AppHandle,
-    cmd: IpcCommand,
-) -> IpcResponse {
-    match cmd {
-        IpcCommand::GetMode => IpcResponse::Mode(state.mode().as_str().to_string()),
-        IpcCommand::Toggle => {
-            let new_mode = state.toggle_mode();
-            let _ = app_handle.emit("mode-change", new_mode.as_str());
-            IpcResponse::Mode(new_mode.as_str().to_string())
+/// Payload of the `mode-change` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ModeChangeEvent {
+    mode: VimMode,
+    /// Indicator label for the mode, from `indicator_text_template`
+    label: String,
+    /// Pending keys left after the change, so the indicator updates them with the mode
+    #[serde(default)]
+    pending: String,
+}
+
+/// Emit `mode-change` with the mode, its configured label and the pending keys
+pub(crate) fn emit_mode_change(app_handle: &AppHandle, change: ModeChange) {
+    let ModeChange { mode, pending } = change;
+    let label = app_handle
+        .state::<AppState>()
+        .settings
+        .lock()
+        .map(|s| s.indicator_text_template.label(mode).to_string())
+        .unwrap_or_default();
+    let _ = app_handle.emit("mode-change", ModeChangeEvent { mode, label, pending });
+}
+
+/// How long pending keys must sit unchanged before `pending-help` lists what can follow
+const PENDING_HELP_DELAY: Duration = Duration::from_millis(600);
+
+/// Payload of the `pending-help` event
+/// An empty prefix means the sequence finished or was cancelled, so the help should hide
+#[derive(Debug, Clone, Serialize)]
+struct PendingHelpEvent {
+    prefix: String,
+    keys: Vec<Continuation>,
+}
+
+/// Emit `pending-help` once pending keys have been left for `PENDING_HELP_DELAY`
+/// While the help is shown it follows each further key, and hides when the sequence ends
+async fn forward_pending_help(app_handle: AppHandle, mut pending_rx: broadcast::Receiver<String>) {
+    let emit = |prefix: &str| {
+        let keys = vim::pending_help::continuations(prefix);
+        let shown = !keys.is_empty();
+        let prefix = if shown { prefix.to_string() } else { String::new() };
+        let _ = app_handle.emit("pending-help", PendingHelpEvent { prefix, keys });
+        shown
+    };
+
+    let mut prefix = String::new();
+    let mut shown = false;
+    // Whether the current prefix has already been left for the delay
+    let mut waited = false;
+    loop {
+        let next = if prefix.is_empty() || waited {
+            pending_rx.recv().await
+        } else {
+            match tokio::time::timeout(PENDING_HELP_DELAY, pending_rx.recv()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    waited = true;
+                    shown = !vim::pending_help::continuations(&prefix).is_empty() && emit(&prefix);
+                    continue;
+                }
+            }
+        };
+
+        match next {
+            Ok(next) => prefix = next,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
         }
-        IpcCommand::Insert => {
-            state.set_mode_external(VimMode::Insert);
-            let _ = app_handle.emit("mode-change", "insert");
-            IpcResponse::Ok
+        waited = false;
+        if shown {
+            shown = emit(&prefix);
+            waited = shown;
         }
-        IpcCommand::Normal => {
-            state.set_mode_external(VimMode::Normal);
-            let _ = app_handle.emit("mode-change", "normal");
-            IpcResponse::Ok
+    }
+}
+
+/// Payload of the `pending-count` event
+/// `None` means the count was used or cancelled, so the indicator shows the mode again
+#[derive(Debug, Clone, Serialize)]
+struct PendingCountEvent {
+    count: Option<u32>,
+}
+
+/// Emit `pending-count` as a count is typed, when `indicator_show_count` is on
+async fn forward_pending_count(app_handle: AppHandle, mut count_rx: broadcast::Receiver<Option<u32>>) {
+    loop {
+        let count = match count_rx.recv().await {
+            Ok(count) => count,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        let shows_count = app_handle
+            .state::<AppState>()
+            .settings
+            .lock()
+            .is_ok_and(|s| s.indicator_show_count);
+        if shows_count {
+            let _ = app_handle.emit("pending-count", PendingCountEvent { count });
         }
-        IpcCommand::Visual => {
-            state.set_mode_external(VimMode::Visual);
-            let _ = app_handle.emit("mode-change", "visual");
-            IpcResponse::Ok
+    }
+}
+
+/// Emit `key-decision` for each key press sent by the keyboard callback (see `debug_events`)
+async fn forward_key_decisions(app_handle: AppHandle, mut decision_rx: broadcast::Receiver<KeyDecision>) {
+    loop {
+        match decision_rx.recv().await {
+            Ok(decision) => {
+                let _ = app_handle.emit("key-decision", decision);
+            }
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                log::debug!("Key debugger fell behind, skipped {} decisions", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
         }
-        IpcCommand::SetMode(mode_str) => handle_set_mode(state, app_handle, &mode_str),
     }
 }
 
-fn handle_set_mode(state: &mut VimState, app_handle: &AppHandle, mode_str: &str) -> IpcResponse {
-    match mode_str.to_lowercase().as_str() {
-        "insert" | "i" => {
+/// Payload of the `capture-startup` event, reporting progress of starting capture at launch
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum CaptureStartupEvent {
+    WaitingForPermission { attempt: u32 },
+    Started,
+    Failed { message: String },
+}
+
+/// How often to re-check permissions while waiting at startup
+const PERMISSION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How long to wait for permissions before giving up on starting capture
+const PERMISSION_WAIT_TIMEOUT: Duration = Duration::from_secs(600);
+/// Attempts at creating the event tap once permissions are granted
+const CAPTURE_START_ATTEMPTS: u32 = 3;
+
+/// Start keyboard capture after `delay`, once permissions are ready
+/// Right after login the permission state can lag, so the tap would fail silently
+fn start_capture_when_ready(app_handle: AppHandle, delay: Duration) {
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let emit = |event: CaptureStartupEvent| {
+            let _ = app_handle.emit("capture-startup", event);
+        };
+
+        if !check_accessibility_permission() {
+            log::warn!("Accessibility permission not granted, requesting...");
+            request_accessibility_permission();
+        }
+        let granted = wait_for_permission(
+            check_accessibility_permission,
+            PERMISSION_POLL_INTERVAL,
+            PERMISSION_WAIT_TIMEOUT,
+            |attempt| emit(CaptureStartupEvent::WaitingForPermission { attempt }),
+        );
+        if !granted {
+            log::error!("Accessibility permission not granted, keyboard capture not started");
+            emit(CaptureStartupEvent::Failed {
+                message: "Accessibility permission not granted".to_string(),
+            });
+            return;
+        }
+
+        let state: State<AppState> = app_handle.state();
+        for attempt in 1..=CAPTURE_START_ATTEMPTS {
+            if let Err(e) = state.keyboard_capture.start() {
+                log::error!("Failed to start keyboard capture: {}", e);
+            }
+            // The tap is created on its own thread, which clears the running flag on failure
+            std::thread::sleep(PERMISSION_POLL_INTERVAL);
+            if state.keyboard_capture.is_running() {
+                log::info!("Keyboard capture started automatically");
+                emit(CaptureStartupEvent::Started);
+                return;
+            }
+            log::warn!("Keyboard capture failed to start (attempt {})", attempt);
+        }
+        emit(CaptureStartupEvent::Failed {
+            message: "Failed to create the keyboard event tap".to_string(),
+        });
+    });
+}
+
+/// Apply an IPC command to the vim state, returning the response and the mode change to announce
+/// The state is only locked while the command applies, so `mode-change` listeners that
+/// read settings run without it (see `AppState`)
+pub(crate) fn apply_ipc_command(vim_state: &Mutex<VimState>, cmd: IpcCommand) -> (IpcResponse, Option<ModeChange>) {
+    let mut state = vim_state.lock().unwrap();
+    let (response, mode) = match cmd {
+        IpcCommand::GetMode => (IpcResponse::Mode(state.mode().as_str().to_string()), None),
+        IpcCommand::Toggle => {
+            let new_mode = state.toggle_mode();
+            (IpcResponse::Mode(new_mode.as_str().to_string()), Some(new_mode))
+        }
+        IpcCommand::Insert => {
             state.set_mode_external(VimMode::Insert);
-            let _ = app_handle.emit("mode-change", "insert");
-            IpcResponse::Ok
+            (IpcResponse::Ok, Some(VimMode::Insert))
         }
-        "normal" | "n" => {
+        IpcCommand::Normal => {
             state.set_mode_external(VimMode::Normal);
-            let _ = app_handle.emit("mode-change", "normal");
-            IpcResponse::Ok
+            (IpcResponse::Ok, Some(VimMode::Normal))
         }
-        "visual" | "v" => {
+        IpcCommand::Visual => {
             state.set_mode_external(VimMode::Visual);
-            let _ = app_handle.emit("mode-change", "visual");
-            IpcResponse::Ok
+            (IpcResponse::Ok, Some(VimMode::Visual))
         }
-        _ => IpcResponse::Error(format!("Unknown mode: {}", mode_str)),
-    }
+        IpcCommand::SetMode(mode_str) => match state.set_mode_named(&mode_str) {
+            Ok(mode) => (IpcResponse::Ok, Some(mode)),
+            Err(e) => (IpcResponse::Error(e), None),
+        },
+    };
+    (response, mode.map(|mode| ModeChange { mode, pending: state.get_pending_keys() }))
 }
 
 fn update_tray_icon(tray: &TrayIcon, mode: &str, show_mode: bool) {
@@ -161,28 +340,49 @@ fn update_tray_icon(tray: &TrayIcon, mode: &str, show_mode: bool) {
 pub fn run() {
     init_file_logger();
     log::info!("ovim-rust started");
+    log::info!("{}", commands::VersionInfo::current(false));
 
-    let (vim_state, mode_rx) = VimState::new();
+    let first_run = Settings::file_path().is_some_and(|path| !path.exists());
+    let mut settings = Settings::load();
+    if first_run && commands::auto_select_terminal(&mut settings.nvim_edit) {
+        if let Err(e) = settings.save() {
+            log::error!("Failed to save detected terminal: {}", e);
+        }
+    }
+    let (mut vim_state, mode_rx) = VimState::with_mode(settings.default_mode);
+    vim_state.set_options(VimOptions::from_settings(&settings));
+    let mut mode_history = ModeHistory::new(MAX_MODE_TRANSITIONS);
+    mode_history.record(settings.default_mode, mode_history::now_ms());
     let vim_state = Arc::new(Mutex::new(vim_state));
 
-    let settings = Arc::new(Mutex::new(Settings::load()));
+    let key_settings = SharedKeySettings::new(&settings);
+    let settings = Arc::new(Mutex::new(settings));
     let record_key_tx: Arc<Mutex<Option<tokio::sync::oneshot::Sender<RecordedKey>>>> =
         Arc::new(Mutex::new(None));
     let edit_session_manager = Arc::new(EditSessionManager::new());
+    let (decision_tx, decision_rx) = broadcast::channel(64);
 
     let keyboard_capture = KeyboardCapture::new();
     keyboard_capture.set_callback(create_keyboard_callback(
         Arc::clone(&vim_state),
-        Arc::clone(&settings),
+        key_settings.clone(),
         Arc::clone(&record_key_tx),
         Arc::clone(&edit_session_manager),
+        decision_tx,
+    ));
+    #[cfg(target_os = "macos")]
+    keyboard_capture.set_mouse_down_callback(create_mouse_down_callback(
+        Arc::clone(&vim_state),
+        key_settings.clone(),
     ));
 
     let app_state = AppState {
         settings,
+        key_settings,
         vim_state: Arc::clone(&vim_state),
         keyboard_capture,
         record_key_tx,
+        mode_history: Arc::new(Mutex::new(mode_history)),
         edit_session_manager,
     };
 
@@ -197,11 +397,14 @@ pub fn run() {
             commands::check_permission,
             commands::request_permission,
             commands::get_permission_status,
+            commands::ping_accessibility,
             commands::open_accessibility_settings,
             commands::open_input_monitoring_settings,
             commands::get_vim_mode,
+            commands::set_vim_mode,
             commands::get_settings,
             commands::set_settings,
+            commands::apply_keymap_preset,
             commands::start_capture,
             commands::stop_capture,
             commands::is_capture_running,
@@ -211,23 +414,41 @@ pub fn run() {
             commands::get_battery_info,
             commands::get_caps_lock_state,
             commands::get_pending_keys,
+            commands::get_mode_history,
+            commands::run_vim_command,
+            commands::preview_motion,
+            commands::get_text_object_preview,
             commands::get_key_display_name,
+            commands::benchmark_injection,
+            commands::get_active_bindings_for_key,
             commands::record_key,
             commands::cancel_record_key,
             commands::webview_log,
             commands::validate_nvim_edit_paths,
+            commands::detect_terminal,
+            commands::detect_editors,
             commands::set_indicator_ignores_mouse,
             commands::is_command_key_pressed,
             commands::is_mouse_over_indicator,
             commands::get_version,
+            commands::get_version_info,
             commands::check_for_update,
+            commands::check_for_updates,
             commands::restart_app,
             commands::set_indicator_clickable,
+            commands::open_log_file,
+            commands::get_log_tail,
+            commands::list_edit_processes,
+            commands::kill_edit_process,
         ])
         .setup(move |app| {
             #[cfg(target_os = "macos")]
             app.set_activation_policy(tauri::ActivationPolicy::Accessory);
 
+            let edit_session_manager = &app.state::<AppState>().edit_session_manager;
+            edit_session_manager.set_app_handle(app.handle().clone());
+            nvim_edit::offer_recovery(app.handle(), edit_session_manager.recover_orphaned());
+
             let settings_item =
                 MenuItem::with_id(app, "settings", "Settings...", true, None::<&str>)?;
             let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
@@ -268,10 +489,13 @@ pub fn run() {
                 let tray_for_mode = tray.clone();
                 let app_handle_for_tray = app.handle().clone();
                 app.listen("mode-change", move |event| {
-                    let mode = event.payload().trim_matches('"');
+                    let mode = match serde_json::from_str::<ModeChangeEvent>(event.payload()) {
+                        Ok(event) => event.mode,
+                        Err(_) => return,
+                    };
                     let state: State<AppState> = app_handle_for_tray.state();
                     let show_mode = state.settings.lock().map(|s| s.show_mode_in_menu_bar).unwrap_or(false);
-                    update_tray_icon(&tray_for_mode, mode, show_mode);
+                    update_tray_icon(&tray_for_mode, mode.as_str(), show_mode);
                 });
             }
 
@@ -279,6 +503,10 @@ pub fn run() {
                 if let Err(e) = setup_indicator_window(&indicator_window) {
                     log::error!("Failed to setup indicator window: {}", e);
                 }
+                let state: State<AppState> = app.state();
+                let mode = state.vim_state.lock().unwrap().mode();
+                let settings = state.settings.lock().unwrap().clone();
+                window::update_indicator_visibility(app.handle(), &settings, mode);
             }
 
             if let Some(settings_window) = app.get_webview_window("settings") {
@@ -295,30 +523,48 @@ pub fn run() {
             let mut rx = mode_rx.lock().unwrap().resubscribe();
 
             tauri::async_runtime::spawn(async move {
-                while let Ok(mode) = rx.recv().await {
+                let mut cursor_style = CursorStyle::default();
+                while let Ok(change) = rx.recv().await {
+                    let mode = change.mode;
                     log::info!("Mode changed to: {:?}", mode);
-                    let _ = app_handle.emit("mode-change", mode.as_str());
+                    app_handle
+                        .state::<AppState>()
+                        .mode_history
+                        .lock()
+                        .unwrap()
+                        .record(mode, mode_history::now_ms());
+                    emit_mode_change(&app_handle, change);
+
+                    let settings = app_handle.state::<AppState>().settings.lock().unwrap().clone();
+                    window::update_indicator_visibility(&app_handle, &settings, mode);
+                    let configured = settings.visual_mode_cursor_style;
+                    let style = window::cursor_style_for_mode(mode, configured);
+                    if style != cursor_style {
+                        window::set_cursor_style(&app_handle, style);
+                        cursor_style = style;
+                    }
                 }
             });
 
-            if check_accessibility_permission() {
-                let state: State<AppState> = app.state();
-                if let Err(e) = state.keyboard_capture.start() {
-                    log::error!("Failed to start keyboard capture: {}", e);
-                } else {
-                    log::info!("Keyboard capture started automatically");
-                }
-            } else {
-                log::warn!("Accessibility permission not granted, requesting...");
-                request_accessibility_permission();
-            }
+            let pending_rx = vim_state.lock().unwrap().subscribe_pending();
+            tauri::async_runtime::spawn(forward_pending_help(app.handle().clone(), pending_rx));
+            let count_rx = vim_state.lock().unwrap().subscribe_count();
+            tauri::async_runtime::spawn(forward_pending_count(app.handle().clone(), count_rx));
+            tauri::async_runtime::spawn(forward_key_decisions(app.handle().clone(), decision_rx));
+
+            let state: State<AppState> = app.state();
+            let delay_ms = state.settings.lock().unwrap().startup_capture_delay_ms;
+            start_capture_when_ready(app.handle().clone(), Duration::from_millis(delay_ms));
 
             let vim_state_for_ipc = Arc::clone(&vim_state);
             let app_handle_for_ipc = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 let handler = move |cmd: IpcCommand| -> IpcResponse {
-                    let mut state = vim_state_for_ipc.lock().unwrap();
-                    handle_ipc_command(&mut state, &app_handle_for_ipc, cmd)
+                    let (response, change) = apply_ipc_command(&vim_state_for_ipc, cmd);
+                    if let Some(change) = change {
+                        emit_mode_change(&app_handle_for_ipc, change);
+                    }
+                    response
                 };
 
                 if let Err(e) = ipc::start_ipc_server(handler).await {
@@ -329,6 +575,7 @@ pub fn run() {
             // Start periodic update checker
             let state: State<AppState> = app.state();
             updater::start_update_checker(app.handle().clone(), Arc::clone(&state.settings));
+            updater::start_update_notifier(app.handle().clone(), Arc::clone(&state.settings));
 
             Ok(())
         })
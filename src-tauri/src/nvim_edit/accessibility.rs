@@ -1,12 +1,27 @@
 //! Accessibility APIs for getting text from focused UI elements
 
-use core_foundation::base::{CFRelease, CFTypeRef, TCFType};
+use core_foundation::array::{CFArray, CFArrayRef};
+use core_foundation::base::{CFGetTypeID, CFRange, CFRelease, CFType, CFTypeRef, TCFType};
+use core_foundation::number::CFNumber;
 use core_foundation::string::CFString;
+use serde::Serialize;
+
+use crate::keyboard;
 
 #[allow(non_upper_case_globals)]
 const kAXValueCGPointType: i32 = 1;
 #[allow(non_upper_case_globals)]
 const kAXValueCGSizeType: i32 = 2;
+#[allow(non_upper_case_globals)]
+const kAXValueCGRectType: i32 = 3;
+#[allow(non_upper_case_globals)]
+const kAXValueCFRangeType: i32 = 4;
+
+/// Roles of elements that hold editable text
+const TEXT_FIELD_ROLES: &[&str] = &["AXTextField", "AXTextArea"];
+/// Limits for walking a window's element tree when collecting fields
+const MAX_FIELD_SEARCH_DEPTH: usize = 25;
+const MAX_BATCH_FIELDS: usize = 50;
 
 #[link(name = "ApplicationServices", kind = "framework")]
 extern "C" {
@@ -17,16 +32,28 @@ extern "C" {
         attribute: CFTypeRef,
         value: *mut CFTypeRef,
     ) -> i32;
+    fn AXUIElementCopyParameterizedAttributeValue(
+        element: CFTypeRef,
+        parameterized_attribute: CFTypeRef,
+        parameter: CFTypeRef,
+        value: *mut CFTypeRef,
+    ) -> i32;
     fn AXUIElementSetAttributeValue(
         element: CFTypeRef,
         attribute: CFTypeRef,
         value: CFTypeRef,
     ) -> i32;
+    fn AXUIElementIsAttributeSettable(
+        element: CFTypeRef,
+        attribute: CFTypeRef,
+        settable: *mut bool,
+    ) -> i32;
     fn AXValueGetValue(
         value: CFTypeRef,
         the_type: i32,
         value_ptr: *mut std::ffi::c_void,
     ) -> bool;
+    fn AXValueCreate(the_type: i32, value_ptr: *const std::ffi::c_void) -> CFTypeRef;
     fn CFRetain(cf: CFTypeRef) -> CFTypeRef;
 }
 
@@ -109,6 +136,58 @@ impl CFHandle {
         }
     }
 
+    /// Get a parameterized attribute value, e.g. the bounds of a text range
+    fn get_parameterized_attribute(&self, attr_name: &str, parameter: &CFHandle) -> Option<CFHandle> {
+        let attr = CFString::new(attr_name);
+        let mut value: CFTypeRef = std::ptr::null();
+        let result = unsafe {
+            AXUIElementCopyParameterizedAttributeValue(self.0, attr.as_CFTypeRef(), parameter.0, &mut value)
+        };
+        if result != 0 || value.is_null() {
+            None
+        } else {
+            Some(CFHandle(value))
+        }
+    }
+
+    /// Get a string attribute value from this element
+    /// Returns None if the value isn't a CFString
+    fn get_string_attribute(&self, attr_name: &str) -> Option<String> {
+        let value = self.get_attribute(attr_name)?;
+        if unsafe { CFGetTypeID(value.0) } != CFString::type_id() {
+            return None;
+        }
+        value.into_string()
+    }
+
+    /// Check whether an attribute of this element can be written
+    fn is_attribute_settable(&self, attr_name: &str) -> bool {
+        let attr = CFString::new(attr_name);
+        let mut settable = false;
+        let result =
+            unsafe { AXUIElementIsAttributeSettable(self.0, attr.as_CFTypeRef(), &mut settable) };
+        result == 0 && settable
+    }
+
+    /// Get the child elements of this element
+    fn children(&self) -> Vec<CFHandle> {
+        let children = match self.get_attribute("AXChildren") {
+            Some(children) => children,
+            None => return Vec::new(),
+        };
+        // The array is retained by wrap_under_get_rule, `children` releases its own reference
+        let array: CFArray<CFType> =
+            unsafe { CFArray::wrap_under_get_rule(children.0 as CFArrayRef) };
+        array
+            .iter()
+            .filter_map(|child| {
+                let ptr = child.as_CFTypeRef();
+                unsafe { CFRetain(ptr) };
+                CFHandle::new(ptr)
+            })
+            .collect()
+    }
+
     /// Extract a CGPoint from an AXValue
     fn extract_point(&self) -> Option<core_graphics::geometry::CGPoint> {
         let mut point = core_graphics::geometry::CGPoint::new(0.0, 0.0);
@@ -143,6 +222,55 @@ impl CFHandle {
         }
     }
 
+    /// Extract a CGRect from an AXValue
+    fn extract_rect(&self) -> Option<core_graphics::geometry::CGRect> {
+        let mut rect = core_graphics::geometry::CGRect::new(
+            &core_graphics::geometry::CGPoint::new(0.0, 0.0),
+            &core_graphics::geometry::CGSize::new(0.0, 0.0),
+        );
+        let extracted = unsafe {
+            AXValueGetValue(
+                self.0,
+                kAXValueCGRectType,
+                &mut rect as *mut _ as *mut std::ffi::c_void,
+            )
+        };
+        if extracted {
+            Some(rect)
+        } else {
+            None
+        }
+    }
+
+    /// Get the screen frame of this element from its position and size
+    fn frame(&self) -> Option<ElementFrame> {
+        let point = self.get_attribute("AXPosition")?.extract_point()?;
+        let size = self.get_attribute("AXSize")?.extract_size()?;
+        Some(ElementFrame {
+            x: point.x,
+            y: point.y,
+            width: size.width,
+            height: size.height,
+        })
+    }
+
+    /// Extract a CFRange from an AXValue
+    fn extract_range(&self) -> Option<CFRange> {
+        let mut range = CFRange::init(0, 0);
+        let extracted = unsafe {
+            AXValueGetValue(
+                self.0,
+                kAXValueCFRangeType,
+                &mut range as *mut _ as *mut std::ffi::c_void,
+            )
+        };
+        if extracted {
+            Some(range)
+        } else {
+            None
+        }
+    }
+
     /// Convert to CFString and get as Rust String.
     /// Note: This consumes the handle to avoid double-free.
     fn into_string(self) -> Option<String> {
@@ -154,6 +282,16 @@ impl CFHandle {
         std::mem::forget(self);
         Some(result)
     }
+
+    /// Convert to an integer, if this is a CFNumber
+    fn into_i64(self) -> Option<i64> {
+        if unsafe { CFGetTypeID(self.0) } != CFNumber::type_id() {
+            return None;
+        }
+        let number: CFNumber = unsafe { CFNumber::wrap_under_create_rule(self.0 as _) };
+        std::mem::forget(self);
+        number.to_i64()
+    }
 }
 
 impl Drop for CFHandle {
@@ -168,7 +306,6 @@ impl Drop for CFHandle {
 #[derive(Debug, Clone)]
 pub struct FocusContext {
     pub app_pid: i32,
-    #[allow(dead_code)]
     pub app_bundle_id: String,
     /// The focused UI element (if captured) for live text updates
     pub focused_element: Option<AXElementHandle>,
@@ -191,20 +328,7 @@ pub fn capture_focus_context() -> Option<FocusContext> {
         }
 
         let pid: i32 = msg_send![app, processIdentifier];
-
-        let bundle_id: *mut objc::runtime::Object = msg_send![app, bundleIdentifier];
-        if bundle_id.is_null() {
-            return None;
-        }
-
-        let utf8: *const std::os::raw::c_char = msg_send![bundle_id, UTF8String];
-        if utf8.is_null() {
-            return None;
-        }
-
-        let bundle_id_str = std::ffi::CStr::from_ptr(utf8)
-            .to_string_lossy()
-            .into_owned();
+        let bundle_id_str = running_app_bundle_id(app)?;
 
         // Also try to capture the focused UI element for live text updates
         let focused_element = capture_focused_element();
@@ -218,7 +342,7 @@ pub fn capture_focus_context() -> Option<FocusContext> {
 }
 
 /// Capture a handle to the currently focused UI element
-fn capture_focused_element() -> Option<AXElementHandle> {
+pub fn capture_focused_element() -> Option<AXElementHandle> {
     unsafe {
         let system_wide = AXUIElementCreateSystemWide();
         if system_wide.is_null() {
@@ -265,6 +389,39 @@ fn capture_focused_element() -> Option<AXElementHandle> {
     }
 }
 
+/// Bundle id of an `NSRunningApplication`
+unsafe fn running_app_bundle_id(app: *mut objc::runtime::Object) -> Option<String> {
+    use objc::{msg_send, sel, sel_impl};
+
+    let bundle_id: *mut objc::runtime::Object = msg_send![app, bundleIdentifier];
+    if bundle_id.is_null() {
+        return None;
+    }
+
+    let utf8: *const std::os::raw::c_char = msg_send![bundle_id, UTF8String];
+    if utf8.is_null() {
+        return None;
+    }
+
+    Some(std::ffi::CStr::from_ptr(utf8).to_string_lossy().into_owned())
+}
+
+/// Bundle id of the running application with the given PID
+pub fn bundle_id_for_pid(pid: i32) -> Option<String> {
+    unsafe {
+        use objc::{class, msg_send, sel, sel_impl};
+
+        let app: *mut objc::runtime::Object = msg_send![
+            class!(NSRunningApplication),
+            runningApplicationWithProcessIdentifier: pid
+        ];
+        if app.is_null() {
+            return None;
+        }
+        running_app_bundle_id(app)
+    }
+}
+
 /// Restore focus to a previously captured application
 pub fn restore_focus(context: &FocusContext) -> Result<(), String> {
     log::info!("Attempting to restore focus to PID {}", context.app_pid);
@@ -386,6 +543,38 @@ pub fn get_focused_element_frame() -> Option<ElementFrame> {
     })
 }
 
+/// Get the screen frame of the caret (the selected range) in the focused element
+pub fn get_caret_frame() -> Option<ElementFrame> {
+    let system_wide = CFHandle::new(unsafe { AXUIElementCreateSystemWide() })?;
+    let focused_app = system_wide.get_attribute("AXFocusedApplication")?;
+    let focused_element = focused_app.get_attribute("AXFocusedUIElement")?;
+    let range = focused_element.get_attribute("AXSelectedTextRange")?;
+    let rect = focused_element
+        .get_parameterized_attribute("AXBoundsForRange", &range)?
+        .extract_rect()?;
+    Some(ElementFrame {
+        x: rect.origin.x,
+        y: rect.origin.y,
+        width: rect.size.width,
+        height: rect.size.height,
+    })
+}
+
+/// Get the frame of the scroll view the focused element sits in
+/// Returns None if no ancestor is a scroll area
+pub fn get_scroll_view_frame() -> Option<ElementFrame> {
+    let system_wide = CFHandle::new(unsafe { AXUIElementCreateSystemWide() })?;
+    let focused_app = system_wide.get_attribute("AXFocusedApplication")?;
+    let mut element = focused_app.get_attribute("AXFocusedUIElement")?;
+    for _ in 0..MAX_FIELD_SEARCH_DEPTH {
+        element = element.get_attribute("AXParent")?;
+        if element.get_string_attribute("AXRole").as_deref() == Some("AXScrollArea") {
+            return element.frame();
+        }
+    }
+    None
+}
+
 /// Get the full text value from the currently focused UI element
 pub fn get_focused_element_text() -> Option<String> {
     let system_wide = CFHandle::new(unsafe { AXUIElementCreateSystemWide() })?;
@@ -395,6 +584,287 @@ pub fn get_focused_element_text() -> Option<String> {
     value.into_string()
 }
 
+/// Get the length of the focused element's whole text (AXNumberOfCharacters), in UTF-16 units
+/// Unlike AXValue, this counts text an app doesn't expose
+pub fn get_focused_character_count() -> Option<usize> {
+    let system_wide = CFHandle::new(unsafe { AXUIElementCreateSystemWide() })?;
+    let focused_app = system_wide.get_attribute("AXFocusedApplication")?;
+    let focused_element = focused_app.get_attribute("AXFocusedUIElement")?;
+    let count = focused_element.get_attribute("AXNumberOfCharacters")?.into_i64()?;
+    usize::try_from(count).ok()
+}
+
+/// Check whether the focused element is a text input
+/// Combo boxes count too, since they take typed text like a text field
+pub fn is_focused_text_field() -> bool {
+    let role = CFHandle::new(unsafe { AXUIElementCreateSystemWide() })
+        .and_then(|system_wide| system_wide.get_attribute("AXFocusedApplication"))
+        .and_then(|app| app.get_attribute("AXFocusedUIElement"))
+        .and_then(|element| element.get_string_attribute("AXRole"));
+    matches!(role, Some(role) if TEXT_FIELD_ROLES.contains(&role.as_str()) || role == "AXComboBox")
+}
+
+/// Whether an element takes edits, judged by its role and whether its value can be written
+/// Only text roles are judged, as other elements (e.g. web content) may edit text their own way
+fn is_editable_element(role: Option<&str>, value_settable: bool) -> bool {
+    match role {
+        Some(role) if TEXT_FIELD_ROLES.contains(&role) || role == "AXComboBox" => value_settable,
+        _ => true,
+    }
+}
+
+/// Check whether the focused element accepts edits
+/// Elements that can't be inspected count as editable, so edits aren't skipped wrongly
+pub fn is_editable() -> bool {
+    let element = match CFHandle::new(unsafe { AXUIElementCreateSystemWide() })
+        .and_then(|system_wide| system_wide.get_attribute("AXFocusedApplication"))
+        .and_then(|app| app.get_attribute("AXFocusedUIElement"))
+    {
+        Some(element) => element,
+        None => return true,
+    };
+    let role = element.get_string_attribute("AXRole");
+    is_editable_element(role.as_deref(), element.is_attribute_settable("AXValue"))
+}
+
+/// Which attributes the focused element exposes, from `probe_focused_element`
+/// Without the value and selected range, the edit popup falls back to the clipboard
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AttributeSupport {
+    /// Something has keyboard focus
+    pub focused_element: bool,
+    pub role: bool,
+    /// The text (AXValue)
+    pub value: bool,
+    /// The text can be written back
+    pub value_settable: bool,
+    /// The caret and selection (AXSelectedTextRange)
+    pub selected_text_range: bool,
+    /// Position and size, for placing the edit popup
+    pub frame: bool,
+}
+
+impl AttributeSupport {
+    /// Support of a focused element, given which attributes it has
+    fn from_attributes(has: impl Fn(&str) -> bool, value_settable: bool) -> Self {
+        Self {
+            focused_element: true,
+            role: has("AXRole"),
+            value: has("AXValue"),
+            value_settable,
+            selected_text_range: has("AXSelectedTextRange"),
+            frame: has("AXPosition") && has("AXSize"),
+        }
+    }
+}
+
+/// Check which text attributes the focused element exposes
+pub fn probe_focused_element() -> AttributeSupport {
+    let element = match CFHandle::new(unsafe { AXUIElementCreateSystemWide() })
+        .and_then(|system_wide| system_wide.get_attribute("AXFocusedApplication"))
+        .and_then(|app| app.get_attribute("AXFocusedUIElement"))
+    {
+        Some(element) => element,
+        None => return AttributeSupport::default(),
+    };
+    let has = |attr: &str| match attr {
+        // Text must be a string to be read
+        "AXRole" | "AXValue" => element.get_string_attribute(attr).is_some(),
+        _ => element.get_attribute(attr).is_some(),
+    };
+    AttributeSupport::from_attributes(has, element.is_attribute_settable("AXValue"))
+}
+
+/// An editable text field found in a window
+#[derive(Debug, Clone)]
+pub struct EditableField {
+    pub element: AXElementHandle,
+    /// Title, description or placeholder identifying the field
+    pub label: Option<String>,
+    pub text: String,
+}
+
+/// Get all editable text fields in the focused window of an application, in tree order
+pub fn get_editable_fields_for_pid(pid: i32) -> Vec<EditableField> {
+    let app_element = match CFHandle::new(unsafe { AXUIElementCreateApplication(pid) }) {
+        Some(app_element) => app_element,
+        None => return Vec::new(),
+    };
+    let window = match app_element.get_attribute("AXFocusedWindow") {
+        Some(window) => window,
+        None => {
+            log::warn!("get_editable_fields_for_pid: Failed to get AXFocusedWindow for pid {}", pid);
+            return Vec::new();
+        }
+    };
+
+    let mut fields = Vec::new();
+    collect_editable_fields(&window, 0, &mut fields);
+    fields
+}
+
+fn collect_editable_fields(element: &CFHandle, depth: usize, fields: &mut Vec<EditableField>) {
+    if depth > MAX_FIELD_SEARCH_DEPTH || fields.len() >= MAX_BATCH_FIELDS {
+        return;
+    }
+
+    let role = element.get_string_attribute("AXRole").unwrap_or_default();
+    if TEXT_FIELD_ROLES.contains(&role.as_str()) {
+        // Read-only text areas (e.g. labels) have the role but can't be written back
+        if element.is_attribute_settable("AXValue") {
+            if let Some(handle) = unsafe { AXElementHandle::new(element.0) } {
+                let label = ["AXTitle", "AXDescription", "AXPlaceholderValue"]
+                    .iter()
+                    .filter_map(|attr| element.get_string_attribute(attr))
+                    .find(|label| !label.trim().is_empty());
+                fields.push(EditableField {
+                    element: handle,
+                    label,
+                    text: element.get_string_attribute("AXValue").unwrap_or_default(),
+                });
+            }
+        }
+        return;
+    }
+
+    for child in element.children() {
+        collect_editable_fields(&child, depth + 1, fields);
+    }
+}
+
+/// Get the text of the focused element and the caret position as a char offset
+/// The caret is the start of the selection. Returns None if the element doesn't
+/// expose its selection range
+pub fn get_focused_text_and_caret() -> Option<(String, usize)> {
+    let selection = get_focused_text_selection()?;
+    Some((selection.text, selection.start))
+}
+
+/// Text of a field and its selected range, in chars
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextSelection {
+    pub text: String,
+    pub start: usize,
+    pub length: usize,
+}
+
+/// Get the text and selected range of the focused element from its
+/// `AXValue` and `AXSelectedTextRange`
+/// Returns None if the element lacks either attribute
+pub fn get_focused_text_selection() -> Option<TextSelection> {
+    let system_wide = CFHandle::new(unsafe { AXUIElementCreateSystemWide() })?;
+    let focused_app = system_wide.get_attribute("AXFocusedApplication")?;
+    let focused_element = focused_app.get_attribute("AXFocusedUIElement")?;
+    let range = focused_element
+        .get_attribute("AXSelectedTextRange")?
+        .extract_range()?;
+    let text = focused_element.get_string_attribute("AXValue")?;
+
+    let location = range.location.max(0) as usize;
+    let start = utf16_offset_to_char_index(&text, location);
+    let end = utf16_offset_to_char_index(&text, location + range.length.max(0) as usize);
+    Some(TextSelection {
+        text,
+        start,
+        length: end - start,
+    })
+}
+
+/// Set the selection of an element to `length` chars at char offset `start`
+/// An empty range places the caret at `start`
+pub fn set_selected_range(element: &AXElementHandle, start: usize, length: usize) -> Result<(), String> {
+    let element = CFHandle::new(unsafe { CFRetain(element.as_ptr()) })
+        .ok_or("No element to select in")?;
+    if !element.is_attribute_settable("AXSelectedTextRange") {
+        return Err("AXSelectedTextRange is not settable".to_string());
+    }
+    let text = element.get_string_attribute("AXValue").unwrap_or_default();
+    let (location, length) = char_range_to_utf16(&text, start, length);
+    let range = CFRange::init(location as isize, length as isize);
+
+    let value = CFHandle::new(unsafe {
+        AXValueCreate(kAXValueCFRangeType, &range as *const _ as *const std::ffi::c_void)
+    })
+    .ok_or("Failed to create AXValue for range")?;
+    let attr = CFString::new("AXSelectedTextRange");
+    let result =
+        unsafe { AXUIElementSetAttributeValue(element.0, attr.as_CFTypeRef(), value.0) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(format!("Setting AXSelectedTextRange failed with error code: {}", result))
+    }
+}
+
+/// Whether an element's selected text can be replaced, for `replace_range`
+pub fn is_selected_text_settable(element: &AXElementHandle) -> bool {
+    CFHandle::new(unsafe { CFRetain(element.as_ptr()) })
+        .is_some_and(|element| element.is_attribute_settable("AXSelectedText"))
+}
+
+/// Replace `length` chars at `start` with `text` by selecting them and setting AXSelectedText
+/// The app edits its current value, where writing the whole AXValue would undo edits made
+/// since it was read. The caret ends after the inserted text
+pub fn replace_range(element: &AXElementHandle, start: usize, length: usize, text: &str) -> Result<(), String> {
+    set_selected_range(element, start, length)?;
+    let attr = CFString::new("AXSelectedText");
+    let cf_text = CFString::new(text);
+    let result = unsafe {
+        AXUIElementSetAttributeValue(element.as_ptr(), attr.as_CFTypeRef(), cf_text.as_CFTypeRef())
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(format!("Setting AXSelectedText failed with error code: {}", result))
+    }
+}
+
+/// Select `length` chars at `start`, moving from `caret` with arrow keys when the
+/// element can't have its range set. The arrow fallback assumes nothing is selected
+pub fn select_range_or_arrows(
+    element: Option<&AXElementHandle>,
+    caret: usize,
+    start: usize,
+    length: usize,
+) -> Result<(), String> {
+    if let Some(element) = element {
+        match set_selected_range(element, start, length) {
+            Ok(()) => return Ok(()),
+            Err(e) => log::debug!("select_range_or_arrows: falling back to arrow keys: {}", e),
+        }
+    }
+
+    if start < caret {
+        keyboard::cursor_left((caret - start) as u32, false)?;
+    } else if start > caret {
+        keyboard::cursor_right((start - caret) as u32, false)?;
+    }
+    if length > 0 {
+        keyboard::cursor_right(length as u32, true)?;
+    }
+    Ok(())
+}
+
+/// Convert a char range to the UTF-16 (location, length) used by accessibility ranges
+fn char_range_to_utf16(text: &str, start: usize, length: usize) -> (usize, usize) {
+    let mut chars = text.chars();
+    let location = chars.by_ref().take(start).map(char::len_utf16).sum();
+    let length = chars.take(length).map(char::len_utf16).sum();
+    (location, length)
+}
+
+/// Convert a UTF-16 offset, as used by accessibility ranges, to a char index
+fn utf16_offset_to_char_index(text: &str, offset: usize) -> usize {
+    let mut utf16_pos = 0;
+    for (index, c) in text.chars().enumerate() {
+        if utf16_pos >= offset {
+            return index;
+        }
+        utf16_pos += c.len_utf16();
+    }
+    text.chars().count()
+}
+
 /// Get the bounds of the screen containing a given point
 /// Returns the screen frame (x, y, width, height) in screen coordinates
 pub fn get_screen_bounds_for_point(x: f64, y: f64) -> Option<ElementFrame> {
@@ -506,3 +976,66 @@ pub fn set_element_text(element: &AXElementHandle, text: &str) -> Result<(), Str
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf16_offset_to_char_index() {
+        assert_eq!(utf16_offset_to_char_index("hello", 3), 3);
+        // The emoji takes two UTF-16 units but is one char
+        assert_eq!(utf16_offset_to_char_index("a😀b", 3), 2);
+        assert_eq!(utf16_offset_to_char_index("a😀b", 10), 3);
+    }
+
+    #[test]
+    fn test_is_editable_element() {
+        assert!(is_editable_element(Some("AXTextArea"), true));
+        assert!(is_editable_element(Some("AXComboBox"), true));
+        // Read-only text, e.g. a label or a locked document
+        assert!(!is_editable_element(Some("AXTextField"), false));
+        assert!(!is_editable_element(Some("AXTextArea"), false));
+        // Other roles and unknown elements aren't judged
+        assert!(is_editable_element(Some("AXWebArea"), false));
+        assert!(is_editable_element(None, false));
+    }
+
+    #[test]
+    fn test_attribute_support() {
+        let all = AttributeSupport::from_attributes(|_| true, true);
+        assert!(all.focused_element && all.value && all.selected_text_range && all.frame);
+
+        // e.g. a canvas-based editor that only exposes its role and frame
+        let support = AttributeSupport::from_attributes(
+            |attr| ["AXRole", "AXPosition", "AXSize"].contains(&attr),
+            false,
+        );
+        assert_eq!(
+            support,
+            AttributeSupport {
+                focused_element: true,
+                role: true,
+                value: false,
+                value_settable: false,
+                selected_text_range: false,
+                frame: true,
+            }
+        );
+
+        // A frame needs both the position and size
+        let support = AttributeSupport::from_attributes(|attr| attr == "AXPosition", false);
+        assert!(!support.frame);
+        assert!(!AttributeSupport::default().focused_element);
+    }
+
+    #[test]
+    fn test_char_range_to_utf16() {
+        assert_eq!(char_range_to_utf16("hello", 1, 3), (1, 3));
+        // The emoji is one char but two UTF-16 units
+        assert_eq!(char_range_to_utf16("a😀bc", 1, 2), (1, 3));
+        assert_eq!(char_range_to_utf16("a😀bc", 2, 1), (3, 1));
+        // Ranges past the end are clamped to the text
+        assert_eq!(char_range_to_utf16("ab", 5, 2), (2, 0));
+    }
+}
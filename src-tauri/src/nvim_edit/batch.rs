@@ -0,0 +1,138 @@
+//! Batch editing - several text fields edited as sections of one temp file
+//!
+//! Each field is preceded by a marker line such as `=== ovim field 2: Email ===`.
+//! Markers are numbered so that text resembling a marker inside a field is left alone.
+
+const MARKER_PREFIX: &str = "=== ovim field ";
+const MARKER_SUFFIX: &str = " ===";
+
+/// Build the marker line for the field at `index` (1-based)
+fn section_marker(index: usize, label: Option<&str>) -> String {
+    match label.map(str::trim).filter(|l| !l.is_empty()) {
+        // Keep labels on one line so the marker stays a single line
+        Some(label) => format!(
+            "{}{}: {}{}",
+            MARKER_PREFIX,
+            index,
+            label.replace('\n', " "),
+            MARKER_SUFFIX
+        ),
+        None => format!("{}{}{}", MARKER_PREFIX, index, MARKER_SUFFIX),
+    }
+}
+
+/// Check whether `line` is the marker for the field at `index`
+fn is_section_marker(line: &str, index: usize) -> bool {
+    let inner = match line
+        .strip_prefix(MARKER_PREFIX)
+        .and_then(|rest| rest.strip_suffix(MARKER_SUFFIX))
+    {
+        Some(inner) => inner,
+        None => return false,
+    };
+    let number = inner.split(':').next().unwrap_or(inner);
+    number.parse::<usize>() == Ok(index)
+}
+
+/// Serialize field texts into one document, each preceded by its marker line
+pub fn serialize_sections(fields: &[(Option<String>, String)]) -> String {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, (label, text))| format!("{}\n{}", section_marker(i + 1, label.as_deref()), text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parse an edited document back into `expected` field texts
+/// Fails if any marker is missing or out of order, so no field is written with the wrong text
+pub fn parse_sections(document: &str, expected: usize) -> Result<Vec<String>, String> {
+    // Editors add a final newline on save (fixeol); it isn't part of the last field
+    let document = document.strip_suffix('\n').unwrap_or(document);
+
+    let mut sections: Vec<Vec<&str>> = Vec::with_capacity(expected);
+    for line in document.split('\n') {
+        if sections.len() < expected && is_section_marker(line, sections.len() + 1) {
+            sections.push(Vec::new());
+        } else if let Some(current) = sections.last_mut() {
+            current.push(line);
+        } else if !line.trim().is_empty() {
+            return Err("Text found before the first field marker".to_string());
+        }
+    }
+
+    if sections.len() != expected {
+        return Err(format!(
+            "Expected {} field markers, found {}",
+            expected,
+            sections.len()
+        ));
+    }
+
+    Ok(sections.into_iter().map(|lines| lines.join("\n")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(texts: &[&str]) -> Vec<(Option<String>, String)> {
+        texts.iter().map(|t| (None, t.to_string())).collect()
+    }
+
+    #[test]
+    fn test_serialize_sections() {
+        let document = serialize_sections(&[
+            (Some("Name".to_string()), "Ada".to_string()),
+            (None, "line 1\nline 2".to_string()),
+        ]);
+        assert_eq!(
+            document,
+            "=== ovim field 1: Name ===\nAda\n=== ovim field 2 ===\nline 1\nline 2"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_preserves_text() {
+        let texts = ["Ada", "", "multi\nline", "trailing newline\n", "  indented"];
+        let document = serialize_sections(&fields(&texts));
+
+        assert_eq!(parse_sections(&document, texts.len()).unwrap(), texts);
+        // Saving in the editor adds a final newline
+        assert_eq!(parse_sections(&format!("{}\n", document), texts.len()).unwrap(), texts);
+    }
+
+    #[test]
+    fn test_parse_edited_sections() {
+        let document = "=== ovim field 1: Name ===\nGrace\n=== ovim field 2 ===\nnew\ntext\n";
+        assert_eq!(parse_sections(document, 2).unwrap(), vec!["Grace", "new\ntext"]);
+    }
+
+    #[test]
+    fn test_marker_like_text_inside_field_is_kept() {
+        let document = "=== ovim field 1 ===\n=== ovim field 1 ===\n=== ovim field 2 ===\nb";
+        assert_eq!(
+            parse_sections(document, 2).unwrap(),
+            vec!["=== ovim field 1 ===", "b"]
+        );
+    }
+
+    #[test]
+    fn test_parse_fails_when_marker_removed() {
+        let document = "=== ovim field 1 ===\na\nb";
+        assert!(parse_sections(document, 2).is_err());
+    }
+
+    #[test]
+    fn test_parse_fails_on_text_before_first_marker() {
+        let document = "stray\n=== ovim field 1 ===\na";
+        assert!(parse_sections(document, 1).is_err());
+    }
+
+    #[test]
+    fn test_label_newlines_are_flattened() {
+        let document = serialize_sections(&[(Some("First\nName".to_string()), "x".to_string())]);
+        assert!(document.starts_with("=== ovim field 1: First Name ===\n"));
+        assert_eq!(parse_sections(&document, 1).unwrap(), vec!["x"]);
+    }
+}
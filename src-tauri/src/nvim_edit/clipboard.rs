@@ -0,0 +1,150 @@
+//! Clipboard helpers for the Edit Popup flow (pbcopy/pbpaste)
+
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Interval between clipboard polls
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Longest we wait for a paste to land before restoring the clipboard anyway
+const MAX_PASTE_CONFIRM_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Read the current clipboard contents
+pub fn read_clipboard() -> Option<String> {
+    Command::new("pbpaste")
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+}
+
+/// Replace the clipboard contents
+pub fn write_clipboard(text: &str) -> Result<(), String> {
+    let mut pbcopy = Command::new("pbcopy")
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to spawn pbcopy: {}", e))?;
+
+    if let Some(mut stdin) = pbcopy.stdin.take() {
+        use std::io::Write;
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|e| format!("Failed to write to pbcopy: {}", e))?;
+    }
+    pbcopy.wait().map_err(|e| format!("pbcopy failed: {}", e))?;
+    Ok(())
+}
+
+/// Wait until the clipboard holds something other than `previous`
+/// Returns the new contents, or None if nothing changed within `timeout`
+pub fn wait_for_clipboard_change(previous: &str, timeout: Duration) -> Option<String> {
+    poll_until(timeout, POLL_INTERVAL, || {
+        read_clipboard().filter(|current| current != previous)
+    })
+}
+
+/// How long to wait for a paste to land before restoring the clipboard anyway
+/// Larger pastes take the target app longer to process, so the timeout grows with the text
+pub fn paste_confirm_timeout(text_len: usize, delay: Duration) -> Duration {
+    (delay * 5 + Duration::from_millis(text_len as u64 / 100)).min(MAX_PASTE_CONFIRM_TIMEOUT)
+}
+
+/// Run `restore` once `pasted` confirms the paste landed, or after `timeout` as a fallback
+/// Returns whether the paste was confirmed
+pub fn restore_when_pasted(
+    timeout: Duration,
+    mut pasted: impl FnMut() -> bool,
+    restore: impl FnOnce(),
+) -> bool {
+    let confirmed = poll_until(timeout, POLL_INTERVAL, || pasted().then_some(())).is_some();
+    restore();
+    confirmed
+}
+
+/// Repeatedly run `check` until it returns Some or `timeout` elapses
+/// `check` always runs at least once, even with a zero timeout
+pub fn poll_until<T>(
+    timeout: Duration,
+    interval: Duration,
+    mut check: impl FnMut() -> Option<T>,
+) -> Option<T> {
+    let start = Instant::now();
+    loop {
+        if let Some(value) = check() {
+            return Some(value);
+        }
+        if start.elapsed() >= timeout {
+            return None;
+        }
+        thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poll_until_returns_once_changed() {
+        let mut calls = 0;
+        let result = poll_until(Duration::from_secs(1), Duration::from_millis(1), || {
+            calls += 1;
+            (calls == 3).then_some("changed")
+        });
+        assert_eq!(result, Some("changed"));
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_poll_until_times_out() {
+        let start = Instant::now();
+        let result: Option<()> =
+            poll_until(Duration::from_millis(30), Duration::from_millis(5), || None);
+        assert!(result.is_none());
+        assert!(start.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_restore_when_pasted_waits_for_confirmation() {
+        let events = std::cell::RefCell::new(Vec::new());
+        let mut checks = 0;
+        let confirmed = restore_when_pasted(
+            Duration::from_secs(1),
+            || {
+                checks += 1;
+                events.borrow_mut().push("check");
+                checks == 2
+            },
+            || events.borrow_mut().push("restore"),
+        );
+        assert!(confirmed);
+        assert_eq!(*events.borrow(), vec!["check", "check", "restore"]);
+    }
+
+    #[test]
+    fn test_restore_when_pasted_restores_after_timeout() {
+        let mut restored = false;
+        let confirmed = restore_when_pasted(Duration::from_millis(20), || false, || restored = true);
+        assert!(!confirmed);
+        assert!(restored);
+    }
+
+    #[test]
+    fn test_paste_confirm_timeout_scales_and_caps() {
+        let delay = Duration::from_millis(100);
+        assert_eq!(paste_confirm_timeout(0, delay), Duration::from_millis(500));
+        assert_eq!(paste_confirm_timeout(100_000, delay), Duration::from_millis(1500));
+        assert_eq!(paste_confirm_timeout(10_000_000, delay), MAX_PASTE_CONFIRM_TIMEOUT);
+    }
+
+    #[test]
+    fn test_poll_until_checks_once_with_zero_timeout() {
+        let mut calls = 0;
+        let result: Option<()> = poll_until(Duration::ZERO, Duration::from_millis(1), || {
+            calls += 1;
+            None
+        });
+        assert!(result.is_none());
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,139 @@
+//! Optional confirmation step before writing edited text back to the original field
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+/// Maximum number of changed lines shown in the confirm dialog
+const MAX_DIFF_LINES: usize = 20;
+
+/// Build a short line diff between the original and edited text
+/// Lines shared at the start and end are skipped; the changed middle is shown as -/+ lines
+pub fn diff_summary(original: &str, edited: &str) -> String {
+    let old: Vec<&str> = original.lines().collect();
+    let new: Vec<&str> = edited.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let removed = &old[prefix..old.len() - suffix];
+    let added = &new[prefix..new.len() - suffix];
+
+    let mut lines: Vec<String> = removed
+        .iter()
+        .map(|line| format!("- {}", line))
+        .chain(added.iter().map(|line| format!("+ {}", line)))
+        .collect();
+
+    if lines.is_empty() {
+        return "No line changes".to_string();
+    }
+
+    if lines.len() > MAX_DIFF_LINES {
+        let more = lines.len() - MAX_DIFF_LINES;
+        lines.truncate(MAX_DIFF_LINES);
+        lines.push(format!("... {} more changed lines", more));
+    }
+
+    lines.join("\n")
+}
+
+/// Write the edited text back via `apply`, asking `confirm` first when `confirm_before_apply` is set
+/// Returns whether the text was applied
+pub fn apply_edited_text(
+    original: &str,
+    edited: &str,
+    confirm_before_apply: bool,
+    confirm: impl FnOnce(&str, &str) -> bool,
+    apply: impl FnOnce(&str) -> Result<(), String>,
+) -> Result<bool, String> {
+    if confirm_before_apply && !confirm(original, edited) {
+        log::info!("Applying edited text cancelled by user");
+        return Ok(false);
+    }
+
+    apply(edited)?;
+    Ok(true)
+}
+
+/// Ask the user whether to apply the edited text, showing what changed
+pub fn confirm_with_dialog(app: Option<&AppHandle>, original: &str, edited: &str) -> bool {
+    let app = match app {
+        Some(app) => app,
+        None => {
+            log::warn!("No app handle for confirm dialog, applying without confirmation");
+            return true;
+        }
+    };
+
+    let message = format!(
+        "Replace the text field contents with the edited text?\n\n{}",
+        diff_summary(original, edited)
+    );
+
+    app.dialog()
+        .message(message)
+        .title("Apply edited text")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Apply".to_string(),
+            "Discard".to_string(),
+        ))
+        .blocking_show()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_skips_apply() {
+        let mut applied = false;
+        let result = apply_edited_text("old", "new", true, |_, _| false, |_| {
+            applied = true;
+            Ok(())
+        });
+        assert_eq!(result, Ok(false));
+        assert!(!applied);
+    }
+
+    #[test]
+    fn test_confirm_applies() {
+        let mut applied_text = None;
+        let result = apply_edited_text("old", "new", true, |_, _| true, |text| {
+            applied_text = Some(text.to_string());
+            Ok(())
+        });
+        assert_eq!(result, Ok(true));
+        assert_eq!(applied_text.as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn test_no_confirm_when_disabled() {
+        let mut asked = false;
+        let result = apply_edited_text(
+            "old",
+            "new",
+            false,
+            |_, _| {
+                asked = true;
+                false
+            },
+            |_| Ok(()),
+        );
+        assert_eq!(result, Ok(true));
+        assert!(!asked);
+    }
+
+    #[test]
+    fn test_diff_summary_shows_changed_lines_only() {
+        let original = "keep\nold line\nkeep end";
+        let edited = "keep\nnew line\nextra\nkeep end";
+        assert_eq!(diff_summary(original, edited), "- old line\n+ new line\n+ extra");
+        assert_eq!(diff_summary("same", "same"), "No line changes");
+    }
+}
@@ -0,0 +1,110 @@
+//! Errors of the "Edit with Neovim" flow, distinct enough for the UI to suggest a fix
+
+use std::fmt;
+use std::io;
+
+/// Why an edit couldn't be started
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    /// No application has keyboard focus
+    NoFocusedApp,
+    /// The focused window has no text fields exposed via accessibility
+    NoEditableFields,
+    /// The configured terminal isn't installed or not on the path
+    TerminalNotFound(String),
+    /// The terminal was found but couldn't be started
+    TerminalSpawn { terminal: String, message: String },
+    /// The temp file or its directory couldn't be written
+    TempFile(String),
+    /// The session ended before it could be used
+    SessionNotFound,
+    Other(String),
+}
+
+impl EditError {
+    /// Error for a terminal that failed to start, telling a missing binary apart
+    pub fn spawn_failed(terminal: &str, err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::NotFound {
+            Self::TerminalNotFound(terminal.to_string())
+        } else {
+            Self::TerminalSpawn {
+                terminal: terminal.to_string(),
+                message: err.to_string(),
+            }
+        }
+    }
+
+    /// What the user can do about the error, if anything
+    pub fn guidance(&self) -> Option<&'static str> {
+        match self {
+            Self::NoFocusedApp => Some("Click into the text field you want to edit and try again"),
+            Self::NoEditableFields => {
+                Some("This app doesn't expose its fields via accessibility, edit one field at a time")
+            }
+            Self::TerminalNotFound(_) => {
+                Some("Install the terminal or pick another one in the Edit Popup settings")
+            }
+            Self::TerminalSpawn { .. } => Some("Check the terminal and editor paths in the Edit Popup settings"),
+            Self::TempFile(_) => Some("Check that the cache directory is writable"),
+            Self::SessionNotFound | Self::Other(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoFocusedApp => write!(f, "No focused application found"),
+            Self::NoEditableFields => write!(f, "No editable fields found in the focused window"),
+            Self::TerminalNotFound(terminal) => write!(f, "Terminal not found: {}", terminal),
+            Self::TerminalSpawn { terminal, message } => {
+                write!(f, "Failed to spawn {}: {}", terminal, message)
+            }
+            Self::TempFile(message) => write!(f, "Temp file error: {}", message),
+            Self::SessionNotFound => write!(f, "Session not found"),
+            Self::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+impl From<String> for EditError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<EditError> for String {
+    fn from(err: EditError) -> Self {
+        err.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_terminal_binary() {
+        let err = EditError::spawn_failed("kitty", io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(err, EditError::TerminalNotFound("kitty".to_string()));
+        assert_eq!(err.to_string(), "Terminal not found: kitty");
+        assert!(err.guidance().is_some());
+    }
+
+    #[test]
+    fn test_terminal_failing_to_start() {
+        let err = EditError::spawn_failed("wezterm", io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(matches!(err, EditError::TerminalSpawn { ref terminal, .. } if terminal == "wezterm"));
+        assert!(err.to_string().starts_with("Failed to spawn wezterm: "));
+    }
+
+    #[test]
+    fn test_string_errors_convert_both_ways() {
+        let err: EditError = "Failed to write field".to_string().into();
+        assert_eq!(err, EditError::Other("Failed to write field".to_string()));
+        assert_eq!(err.guidance(), None);
+        assert_eq!(String::from(EditError::NoFocusedApp), "No focused application found");
+    }
+}
@@ -0,0 +1,105 @@
+//! Opening the edit file in a GUI editor (VS Code, Cursor) instead of a terminal
+//!
+//! The editor's CLI is started with `--wait`, which returns once the file's tab is closed,
+//! so the session waits on the CLI's process as it would on a terminal editor.
+
+use std::path::Path;
+use std::process::Command;
+
+use super::error::EditError;
+use super::terminals::process_utils::resolve_command_path;
+use super::terminals::{apply_editor_env, SpawnInfo, TerminalType};
+use crate::config::{CursorPosition, NvimEditSettings};
+
+/// Arguments that open `file_path` and block until its tab is closed
+/// With `open_at_cursor_line` and a known caret, the file opens there via `--goto`
+pub fn wait_args(settings: &NvimEditSettings, file_path: &str, cursor: Option<CursorPosition>) -> Vec<String> {
+    let mut args = vec!["--wait".to_string()];
+    match cursor.filter(|_| settings.open_at_cursor_line) {
+        Some(position) => {
+            args.push("--goto".to_string());
+            args.push(format!("{}:{}:{}", file_path, position.line, position.column));
+        }
+        None => args.push(file_path.to_string()),
+    }
+    args
+}
+
+/// Start the GUI editor on the temp file, returning its CLI's process to wait on
+pub fn spawn(
+    settings: &NvimEditSettings,
+    temp_file: &Path,
+    cursor: Option<CursorPosition>,
+) -> Result<SpawnInfo, EditError> {
+    let editor = resolve_command_path(&settings.editor_path());
+    let args = wait_args(settings, &temp_file.to_string_lossy(), cursor);
+    log::info!("Opening {} {:?}", editor, args);
+
+    let mut cmd = Command::new(&editor);
+    cmd.args(&args);
+    apply_editor_env(&mut cmd, settings);
+    let child = cmd.spawn().map_err(|e| EditError::spawn_failed(&editor, e))?;
+
+    Ok(SpawnInfo {
+        terminal_type: TerminalType::GuiEditor,
+        process_id: Some(child.id()),
+        child: Some(child),
+        window_title: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::EditorType;
+    use crate::nvim_edit::terminals::{spawn_terminal, wait_for_process};
+
+    #[test]
+    fn test_wait_args() {
+        let mut settings = NvimEditSettings {
+            editor: EditorType::VSCode,
+            ..Default::default()
+        };
+        let position = Some(CursorPosition { line: 3, column: 7 });
+        assert_eq!(wait_args(&settings, "/tmp/edit.txt", position), vec!["--wait", "/tmp/edit.txt"]);
+
+        settings.open_at_cursor_line = true;
+        assert_eq!(
+            wait_args(&settings, "/tmp/edit.txt", position),
+            vec!["--wait", "--goto", "/tmp/edit.txt:3:7"]
+        );
+        // Without the caret, the file opens where the editor left it
+        assert_eq!(wait_args(&settings, "/tmp/edit.txt", None), vec!["--wait", "/tmp/edit.txt"]);
+    }
+
+    #[test]
+    fn test_session_waits_for_editor_process() {
+        // `true` exits at once, like an editor whose tab was closed
+        let settings = NvimEditSettings {
+            editor: EditorType::VSCode,
+            nvim_path: "true".to_string(),
+            ..Default::default()
+        };
+        let info = spawn_terminal(&settings, Path::new("/tmp/edit.txt"), None, None, None).unwrap();
+        assert_eq!(info.terminal_type, TerminalType::GuiEditor);
+        assert_eq!(info.process_id, info.child.as_ref().map(|child| child.id()));
+        assert!(info.window_title.is_none());
+        assert_eq!(wait_for_process(&info.terminal_type, info.process_id), Ok(()));
+
+        // Without a process there's nothing to wait on
+        assert!(wait_for_process(&TerminalType::GuiEditor, None).is_err());
+    }
+
+    #[test]
+    fn test_missing_editor() {
+        let settings = NvimEditSettings {
+            editor: EditorType::VSCode,
+            nvim_path: "/nonexistent/code".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            spawn(&settings, Path::new("/tmp/edit.txt"), None).err(),
+            Some(EditError::TerminalNotFound("/nonexistent/code".to_string()))
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Keystrokes sent to the original app after edited text is restored
+//!
+//! Sequences use vim-style notation: plain characters are typed as-is, and
+//! special keys go in angle brackets with optional modifiers, e.g. `<CR>`,
+//! `<Tab>`, `<C-s>` or `<D-Enter>` (Cmd+Return).
+
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use crate::keyboard::{KeyCode, Modifiers};
+
+/// Key in the sequence map that applies to every app
+const ALL_APPS: &str = "*";
+
+/// A single key press in a post-edit sequence
+#[derive(Debug, Clone, Copy)]
+pub struct Keystroke {
+    pub keycode: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+/// Get the sequence configured for an app, falling back to the `*` entry
+pub fn sequence_for_app<'a>(
+    sequences: &'a HashMap<String, String>,
+    bundle_id: &str,
+) -> Option<&'a str> {
+    sequences
+        .get(bundle_id)
+        .or_else(|| sequences.get(ALL_APPS))
+        .map(String::as_str)
+        .filter(|seq| !seq.is_empty())
+}
+
+/// Parse a keystroke sequence such as `<C-a>hello<CR>`
+pub fn parse_keystrokes(sequence: &str) -> Result<Vec<Keystroke>, String> {
+    let mut keystrokes = Vec::new();
+    let mut chars = sequence.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '<' {
+            let mut name = String::new();
+            loop {
+                match chars.next() {
+                    Some('>') => break,
+                    Some(c) => name.push(c),
+                    None => return Err(format!("Unclosed '<' in keystrokes \"{}\"", sequence)),
+                }
+            }
+            keystrokes.push(parse_special(&name)?);
+        } else {
+            keystrokes.push(char_keystroke(c)?);
+        }
+    }
+
+    Ok(keystrokes)
+}
+
+/// Parse the inside of `<...>`: modifier prefixes followed by a key name
+fn parse_special(name: &str) -> Result<Keystroke, String> {
+    let mut modifiers = Modifiers::default();
+    let mut rest = name;
+
+    while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+        match rest.as_bytes()[0].to_ascii_uppercase() {
+            b'C' => modifiers.control = true,
+            b'S' => modifiers.shift = true,
+            b'M' | b'A' => modifiers.option = true,
+            b'D' => modifiers.command = true,
+            _ => return Err(format!("Unknown modifier in <{}>", name)),
+        }
+        rest = &rest[2..];
+    }
+
+    let keycode = match rest.to_lowercase().as_str() {
+        "cr" | "enter" | "return" => KeyCode::Return,
+        "tab" => KeyCode::Tab,
+        "esc" | "escape" => KeyCode::Escape,
+        "space" => KeyCode::Space,
+        "bs" | "backspace" => KeyCode::Delete,
+        "del" => KeyCode::ForwardDelete,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        other => {
+            let mut single = other.chars();
+            match (single.next(), single.next()) {
+                (Some(c), None) => {
+                    let keystroke = char_keystroke(c)?;
+                    modifiers.shift |= keystroke.modifiers.shift;
+                    keystroke.keycode
+                }
+                _ => KeyCode::from_name(other).ok_or_else(|| format!("Unknown key <{}>", name))?,
+            }
+        }
+    };
+
+    Ok(Keystroke { keycode, modifiers })
+}
+
+/// Keystroke that types a plain character
+fn char_keystroke(c: char) -> Result<Keystroke, String> {
+    let keycode = match c {
+        ' ' => Some(KeyCode::Space),
+        '=' => Some(KeyCode::Equal),
+        '-' => Some(KeyCode::Minus),
+        '[' => Some(KeyCode::LeftBracket),
+        ']' => Some(KeyCode::RightBracket),
+        '\'' => Some(KeyCode::Quote),
+        ';' => Some(KeyCode::Semicolon),
+        '\\' => Some(KeyCode::Backslash),
+        ',' => Some(KeyCode::Comma),
+        '/' => Some(KeyCode::Slash),
+        '.' => Some(KeyCode::Period),
+        '`' => Some(KeyCode::Grave),
+        c if c.is_ascii_alphanumeric() => KeyCode::from_name(&c.to_ascii_lowercase().to_string()),
+        _ => None,
+    }
+    .ok_or_else(|| format!("Unsupported character '{}' in keystrokes", c))?;
+
+    Ok(Keystroke {
+        keycode,
+        modifiers: Modifiers {
+            shift: c.is_ascii_uppercase(),
+            ..Default::default()
+        },
+    })
+}
+
+/// Send the sequence configured for `bundle_id` via `send`, but only if the text was restored
+/// Returns the number of keystrokes sent
+pub fn send_after_restore(
+    restored: bool,
+    sequences: &HashMap<String, String>,
+    bundle_id: &str,
+    delay: Duration,
+    mut send: impl FnMut(KeyCode, Modifiers) -> Result<(), String>,
+) -> Result<usize, String> {
+    if !restored {
+        return Ok(0);
+    }
+    let sequence = match sequence_for_app(sequences, bundle_id) {
+        Some(sequence) => sequence,
+        None => return Ok(0),
+    };
+
+    let keystrokes = parse_keystrokes(sequence)?;
+    log::info!(
+        "Sending {} post-edit keystrokes to {}",
+        keystrokes.len(),
+        bundle_id
+    );
+    for keystroke in &keystrokes {
+        // Let the paste (or previous key) settle first
+        thread::sleep(delay);
+        send(keystroke.keycode, keystroke.modifiers)?;
+    }
+    Ok(keystrokes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(sequence: &str) -> Vec<(KeyCode, bool, bool, bool, bool)> {
+        parse_keystrokes(sequence)
+            .unwrap()
+            .into_iter()
+            .map(|k| {
+                let m = k.modifiers;
+                (k.keycode, m.shift, m.control, m.option, m.command)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_plain_characters() {
+        assert_eq!(
+            keys("Hi 1."),
+            vec![
+                (KeyCode::H, true, false, false, false),
+                (KeyCode::I, false, false, false, false),
+                (KeyCode::Space, false, false, false, false),
+                (KeyCode::Num1, false, false, false, false),
+                (KeyCode::Period, false, false, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_special_keys_and_modifiers() {
+        assert_eq!(
+            keys("<CR><tab><C-s><D-Enter><S-Tab><M-BS>"),
+            vec![
+                (KeyCode::Return, false, false, false, false),
+                (KeyCode::Tab, false, false, false, false),
+                (KeyCode::S, false, true, false, false),
+                (KeyCode::Return, false, false, false, true),
+                (KeyCode::Tab, true, false, false, false),
+                (KeyCode::Delete, false, false, true, false),
+            ]
+        );
+        assert_eq!(
+            keys("<up><f5>"),
+            vec![
+                (KeyCode::Up, false, false, false, false),
+                (KeyCode::F5, false, false, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_keystrokes("<CR").is_err());
+        assert!(parse_keystrokes("<X-a>").is_err());
+        assert!(parse_keystrokes("<nope>").is_err());
+        assert!(parse_keystrokes("!").is_err());
+        assert!(parse_keystrokes("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sequence_for_app() {
+        let sequences = HashMap::from([
+            ("com.tinyspeck.slackmacgap".to_string(), "<CR>".to_string()),
+            ("*".to_string(), "<Tab>".to_string()),
+        ]);
+        assert_eq!(
+            sequence_for_app(&sequences, "com.tinyspeck.slackmacgap"),
+            Some("<CR>")
+        );
+        assert_eq!(
+            sequence_for_app(&sequences, "com.apple.TextEdit"),
+            Some("<Tab>")
+        );
+        assert_eq!(
+            sequence_for_app(&HashMap::new(), "com.apple.TextEdit"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_send_only_after_restore() {
+        let sequences = HashMap::from([("*".to_string(), "<CR>".to_string())]);
+        let mut sent = Vec::new();
+
+        let count = send_after_restore(false, &sequences, "app", Duration::ZERO, |k, _| {
+            sent.push(k);
+            Ok(())
+        });
+        assert_eq!(count, Ok(0));
+        assert!(sent.is_empty());
+
+        let count = send_after_restore(true, &sequences, "app", Duration::ZERO, |k, _| {
+            sent.push(k);
+            Ok(())
+        });
+        assert_eq!(count, Ok(1));
+        assert_eq!(sent, vec![KeyCode::Return]);
+    }
+}
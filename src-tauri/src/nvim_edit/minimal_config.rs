@@ -0,0 +1,87 @@
+//! A minimal editor config for the popup, which starts faster than a full plugin config
+//!
+//! The bundled init is written to the edit directory when it's first used, and a
+//! configured `minimal_config_path` takes its place.
+
+use std::path::PathBuf;
+
+use crate::config::{EditorType, NvimEditSettings};
+
+use super::edit_dir;
+
+/// The bundled init, in vimscript for Neovim and Vim alike
+const BUNDLED_INIT: &str = include_str!("minimal_init.vim");
+
+/// File name of the bundled init in the edit directory
+const BUNDLED_INIT_NAME: &str = "minimal_init.vim";
+
+/// Write the bundled init to the edit directory, if it isn't there as shipped
+fn bundled_init_path() -> Result<PathBuf, String> {
+    let dir = edit_dir()?;
+    let path = dir.join(BUNDLED_INIT_NAME);
+    if std::fs::read_to_string(&path).ok().as_deref() != Some(BUNDLED_INIT) {
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        std::fs::write(&path, BUNDLED_INIT).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(path)
+}
+
+/// Init file the editor starts with: `minimal_config_path`, or the bundled one
+/// Only Neovim and Vim have a bundled init
+pub fn init_file(settings: &NvimEditSettings) -> Result<PathBuf, String> {
+    if !settings.minimal_config_path.is_empty() {
+        return Ok(PathBuf::from(&settings.minimal_config_path));
+    }
+    match settings.editor {
+        EditorType::Neovim | EditorType::Vim => bundled_init_path(),
+        EditorType::Helix | EditorType::VSCode | EditorType::Custom => {
+            Err(format!("No bundled minimal config for {:?}, set its path", settings.editor))
+        }
+    }
+}
+
+/// Arguments that start the editor with the minimal config, when `use_minimal_config` is on
+pub fn config_args(settings: &NvimEditSettings) -> Vec<String> {
+    if !settings.use_minimal_config {
+        return Vec::new();
+    }
+    match init_file(settings) {
+        Ok(init) => settings.editor.config_args(&init),
+        Err(e) => {
+            log::warn!("Starting the editor with its own config: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_path_wins() {
+        let mut settings = NvimEditSettings {
+            use_minimal_config: true,
+            minimal_config_path: "/Users/me/popup.lua".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(config_args(&settings), vec!["-u", "/Users/me/popup.lua"]);
+
+        settings.editor = EditorType::Helix;
+        assert_eq!(config_args(&settings), vec!["--config", "/Users/me/popup.lua"]);
+
+        settings.use_minimal_config = false;
+        assert!(config_args(&settings).is_empty());
+    }
+
+    #[test]
+    fn test_no_bundled_config_outside_vim() {
+        let settings = NvimEditSettings {
+            use_minimal_config: true,
+            editor: EditorType::Helix,
+            ..Default::default()
+        };
+        assert!(init_file(&settings).is_err());
+        assert!(config_args(&settings).is_empty());
+    }
+}
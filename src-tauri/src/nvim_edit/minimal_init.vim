@@ -0,0 +1,18 @@
+" Minimal config ovim starts the popup editor with, when use_minimal_config is on
+" Vimscript, so the same file works for Neovim and Vim
+
+set nocompatible
+" Skip plugins for a fast start; the text is short and edited once
+set noloadplugins
+set noswapfile nobackup nowritebackup
+set shortmess+=I
+set laststatus=1
+set wrap linebreak
+set backspace=indent,eol,start
+set clipboard=unnamed
+
+" Save on each change, so the file holds the text as typed even without :w
+augroup ovim_popup
+  autocmd!
+  autocmd TextChanged,TextChangedI * silent! update
+augroup END
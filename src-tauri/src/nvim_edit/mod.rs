@@ -1,16 +1,32 @@
 //! "Edit with Neovim" feature - open any text field in nvim via a keyboard shortcut
 
-mod accessibility;
+pub mod accessibility;
+mod batch;
 mod browser_scripting;
+pub mod clipboard;
+mod confirm;
+mod error;
+mod gui_editor;
+mod keystrokes;
+mod minimal_config;
 mod rpc;
+mod recovery;
+mod server;
 mod session;
+mod shell_command;
+mod target_app;
+mod text_capture;
+pub mod text_source;
 pub mod terminals;
 
-pub use session::EditSessionManager;
+pub use error::EditError;
+pub use recovery::offer_recovery;
+pub use session::{edit_dir, EditSessionManager};
+use session::EditSession;
 
-use crate::config::NvimEditSettings;
+use crate::config::{CursorPosition, NvimEditSettings};
 use crate::keyboard::{inject_key_press, KeyCode, Modifiers};
-use std::process::Command;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
@@ -21,10 +37,10 @@ use terminals::WindowGeometry;
 pub fn trigger_nvim_edit(
     manager: Arc<EditSessionManager>,
     settings: NvimEditSettings,
-) -> Result<(), String> {
+) -> Result<(), EditError> {
     // 1. Capture focus context (which app we're in)
     let focus_context = accessibility::capture_focus_context()
-        .ok_or("No focused application found")?;
+        .ok_or(EditError::NoFocusedApp)?;
     log::info!("Captured focus context: {:?}", focus_context);
 
     // 2. Capture geometry info BEFORE any clipboard operations (which may change focus)
@@ -48,18 +64,23 @@ pub fn trigger_nvim_edit(
         element_frame
     };
 
-    // 3. Get text from the focused element (try accessibility first, then clipboard fallback)
-    let mut text = accessibility::get_focused_element_text().unwrap_or_default();
-    log::info!("Got text from accessibility API: {} chars", text.len());
+    let clipboard_delay = Duration::from_millis(settings.clipboard_delay_ms);
 
-    // If accessibility returned empty, try clipboard-based capture (for web text fields)
-    if text.is_empty() {
-        log::info!("Accessibility returned empty, trying clipboard-based capture");
-        if let Some(captured) = capture_text_via_clipboard() {
-            text = captured;
-            log::info!("Captured {} chars via clipboard", text.len());
-        }
+    // The caret has to be read before a clipboard capture selects all
+    let caret = if settings.open_at_cursor_line {
+        accessibility::get_focused_text_and_caret().map(|(_, caret)| caret)
+    } else {
+        None
+    };
+
+    // 3. Get text from the focused element (accessibility, or clipboard for web and truncated fields)
+    let text = capture_field_text(&settings, clipboard_delay);
+    if !text_capture::opens_editor(settings.keep_window_open_on_empty, &text) {
+        log::info!("Field is empty and keep_window_open_on_empty is off, not opening the editor");
+        return Ok(());
     }
+    let cursor = caret.and_then(|caret| CursorPosition::from_offset(&text, caret));
+    log::info!("Caret offset {:?}, opening at {:?}", caret, cursor);
 
     // 4. Calculate window geometry if popup mode is enabled
     let geometry = if settings.popup_mode {
@@ -140,12 +161,12 @@ pub fn trigger_nvim_edit(
     log::info!("Final geometry: {:?}", geometry);
 
     // 4. Start edit session (writes temp file, spawns terminal)
-    let session_id = manager.start_session(focus_context, text.clone(), settings.clone(), geometry)?;
+    let session_id = manager.start_session(focus_context, text.clone(), settings.clone(), geometry, cursor)?;
     log::info!("Started edit session: {}", session_id);
 
     // 5. Start RPC connection and live sync in background (if enabled)
     let session = manager.get_session(&session_id)
-        .ok_or("Session not found immediately after creation")?;
+        .ok_or(EditError::SessionNotFound)?;
 
     // Flag to track if live sync was active (successful at least once)
     let live_sync_worked = Arc::new(AtomicBool::new(false));
@@ -154,14 +175,22 @@ pub fn trigger_nvim_edit(
     // Clone what we need for the RPC task
     let socket_path = session.socket_path.clone();
     let focus_element = session.focus_context.focused_element.clone();
+    let app_pid = session.focus_context.app_pid;
     let browser_type = browser_scripting::detect_browser_type(&session.focus_context.app_bundle_id);
-    let live_sync_enabled = settings.live_sync_enabled;
+    // The server's socket outlives the edit, so live sync's exit detection doesn't apply.
+    // Live sync also writes to the field as you type, which would defeat confirm_before_apply
+    // and the shell command's output. GUI editors have no RPC socket
+    let live_sync_enabled = settings.live_sync_enabled
+        && session.server_addr.is_none()
+        && !settings.editor.is_gui()
+        && !settings.confirm_before_apply
+        && settings.shell_command.as_deref().is_none_or(|command| command.trim().is_empty());
 
     // Spawn async task for RPC communication
     let rt = tokio::runtime::Builder::new_current_thread()
         .enable_all()
         .build()
-        .map_err(|e| format!("Failed to create tokio runtime: {}", e))?;
+        .map_err(|e| EditError::Other(format!("Failed to create tokio runtime: {}", e)))?;
 
     // Spawn the RPC handler in a separate thread with its own runtime
     let rpc_handle = thread::spawn(move || {
@@ -181,6 +210,7 @@ pub fn trigger_nvim_edit(
 
             let on_lines = Arc::new(move |lines: Vec<String>| {
                 let text = lines.join("\n");
+                text_source::set_live_buffer(app_pid, text.clone());
 
                 // Try to update the text field via accessibility first
                 if let Some(ref element) = element_for_callback {
@@ -228,6 +258,7 @@ pub fn trigger_nvim_edit(
 
                     // Try to detach cleanly
                     let _ = rpc_session.detach().await;
+                    text_source::clear_live_buffer();
                 }
                 Err(e) => {
                     log::warn!("RPC connection failed, falling back to clipboard-only mode: {}", e);
@@ -241,10 +272,7 @@ pub fn trigger_nvim_edit(
     thread::spawn(move || {
         // Wait for the terminal process to exit
         if let Some(session) = manager_clone2.get_session(&session_id) {
-            log::info!("Waiting for process: {:?} (PID: {:?})", session.terminal_type, session.process_id);
-
-            // Wait for process
-            if let Err(e) = terminals::wait_for_process(&session.terminal_type, session.process_id) {
+            if let Err(e) = wait_for_editor_exit(&settings, &session) {
                 log::error!("Error waiting for terminal process: {}", e);
                 manager_clone2.cancel_session(&session_id);
                 return;
@@ -255,6 +283,15 @@ pub fn trigger_nvim_edit(
             // Wait for RPC thread to finish
             let _ = rpc_handle.join();
 
+            // Pasting now would go to whatever app took over focus
+            if !target_app::target_app_running(&session.focus_context) {
+                let command = settings.shell_command.as_deref();
+                copy_edit_to_clipboard(&manager_clone2, &session, command);
+                let _ = std::fs::remove_file(&session.socket_path);
+                manager_clone2.remove_session(&session_id);
+                return;
+            }
+
             // Restore focus to the original app immediately
             log::info!("Restoring focus immediately");
             if let Err(e) = accessibility::restore_focus(&session.focus_context) {
@@ -262,16 +299,19 @@ pub fn trigger_nvim_edit(
             }
 
             // Small delay to ensure file is written and focus is settled
-            thread::sleep(Duration::from_millis(100));
+            thread::sleep(clipboard_delay);
 
             // Check if live sync was working - if so, text is already updated, skip clipboard paste
             let did_live_sync = live_sync_worked.load(Ordering::SeqCst);
             log::info!("Live sync status: {}", if did_live_sync { "worked" } else { "not used" });
 
             // Complete the session - skip clipboard paste if live sync worked
-            if let Err(e) = complete_edit_session_no_focus(&manager_clone2, &session_id, did_live_sync) {
-                log::error!("Error completing edit session: {}", e);
-            }
+            let restored = complete_edit_session_no_focus(&manager_clone2, &session_id, did_live_sync, &settings)
+                .unwrap_or_else(|e| {
+                    log::error!("Error completing edit session: {}", e);
+                    false
+                });
+            send_post_edit_keystrokes(restored, &settings, &session);
 
             // Clean up socket file
             let _ = std::fs::remove_file(&session.socket_path);
@@ -286,14 +326,220 @@ pub fn trigger_nvim_edit(
     Ok(())
 }
 
+/// Trigger the batch flow: edit every text field in the focused window in one session
+/// Only works in apps that expose their fields via accessibility
+pub fn trigger_batch_edit(
+    manager: Arc<EditSessionManager>,
+    settings: NvimEditSettings,
+) -> Result<(), EditError> {
+    let focus_context = accessibility::capture_focus_context()
+        .ok_or(EditError::NoFocusedApp)?;
+    log::info!("Captured focus context for batch edit: {:?}", focus_context);
+
+    let fields = accessibility::get_editable_fields_for_pid(focus_context.app_pid);
+    if fields.is_empty() {
+        return Err(EditError::NoEditableFields);
+    }
+    log::info!("Found {} editable fields for batch edit", fields.len());
+
+    // Center the popup in the focused window, there is no single field to anchor to
+    let geometry = if settings.popup_mode {
+        accessibility::get_window_frame_for_pid(focus_context.app_pid).map(|wf| {
+            let width = if settings.popup_width > 0 { settings.popup_width } else { 500 };
+            let height = settings.popup_height;
+            let x = (wf.x + (wf.width - width as f64) / 2.0) as i32;
+            let y = (wf.y + (wf.height - height as f64) / 2.0) as i32;
+            WindowGeometry { x, y, width, height }
+        })
+    } else {
+        None
+    };
+
+    let session_id = manager.start_batch_session(focus_context, fields, settings.clone(), geometry)?;
+    log::info!("Started batch edit session: {}", session_id);
+
+    thread::spawn(move || {
+        let session = match manager.get_session(&session_id) {
+            Some(session) => session,
+            None => {
+                log::error!("Session not found: {}", session_id);
+                return;
+            }
+        };
+
+        if let Err(e) = wait_for_editor_exit(&settings, &session) {
+            log::error!("Error waiting for terminal process: {}", e);
+            manager.cancel_session(&session_id);
+            return;
+        }
+
+        if !target_app::target_app_running(&session.focus_context) {
+            // Batch edits aren't piped through the shell command
+            copy_edit_to_clipboard(&manager, &session, None);
+            let _ = std::fs::remove_file(&session.socket_path);
+            manager.remove_session(&session_id);
+            return;
+        }
+
+        if let Err(e) = accessibility::restore_focus(&session.focus_context) {
+            log::error!("Error restoring focus: {}", e);
+        }
+
+        let restored = complete_batch_session(&manager, &session_id, &settings).unwrap_or_else(|e| {
+            log::error!("Error completing batch edit session: {}", e);
+            false
+        });
+        send_post_edit_keystrokes(restored, &settings, &session);
+
+        let _ = std::fs::remove_file(&session.socket_path);
+        manager.remove_session(&session_id);
+    });
+
+    Ok(())
+}
+
+/// Block until the editor for a session has exited (or closed the buffer in server mode)
+fn wait_for_editor_exit(settings: &NvimEditSettings, session: &EditSession) -> Result<(), String> {
+    if let Some(ref addr) = session.server_addr {
+        log::info!("Waiting for buffer to close in nvim server {}", addr);
+        server::wait_for_buffer_close(settings, addr, &session.temp_file);
+        Ok(())
+    } else {
+        log::info!("Waiting for process: {:?} (PID: {:?})", session.terminal_type, session.process_id);
+        terminals::wait_for_process(&session.terminal_type, session.process_id)
+    }
+}
+
+/// Read the saved edit, without the trailing newline nvim adds (fixeol option)
+/// Returns the text as saved and as it's written back, after the shell command
+fn read_edited_text(path: &Path, command: Option<&str>) -> Result<(String, String), String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read temp file: {}", e))?;
+    let saved = text.strip_suffix('\n').unwrap_or(&text).to_string();
+    log::info!("Read {} chars from temp file", saved.len());
+    let processed =
+        shell_command::post_process(command, &saved, shell_command::SHELL_COMMAND_TIMEOUT);
+    Ok((saved, processed))
+}
+
+/// Copy the edited text to the clipboard instead of pasting it, once the original app has quit
+/// The temp file is kept if copying fails, so the edit can still be recovered
+fn copy_edit_to_clipboard(
+    manager: &EditSessionManager,
+    session: &EditSession,
+    command: Option<&str>,
+) {
+    let app_bundle_id = &session.focus_context.app_bundle_id;
+    log::warn!("{} is no longer running, not pasting the edited text", app_bundle_id);
+
+    let modified = std::fs::metadata(&session.temp_file)
+        .and_then(|m| m.modified())
+        .is_ok_and(|mtime| mtime != session.file_mtime);
+    if !modified {
+        log::info!("File not modified, nothing to copy");
+        let _ = std::fs::remove_file(&session.temp_file);
+        return;
+    }
+
+    let result = read_edited_text(&session.temp_file, command).and_then(|(_, text)| {
+        target_app::copy_to_clipboard_and_notify(manager.app_handle(), app_bundle_id, &text)
+    });
+    match result {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&session.temp_file);
+        }
+        Err(e) => log::error!("Failed to copy the edited text, keeping {:?}: {}", session.temp_file, e),
+    }
+}
+
+/// Send the configured post-edit keystrokes to the original app if the text was restored
+fn send_post_edit_keystrokes(restored: bool, settings: &NvimEditSettings, session: &EditSession) {
+    if let Err(e) = keystrokes::send_after_restore(
+        restored,
+        &settings.post_edit_keystrokes,
+        &session.focus_context.app_bundle_id,
+        Duration::from_millis(settings.clipboard_delay_ms),
+        |keycode, modifiers| Ok(inject_key_press(keycode, modifiers)?),
+    ) {
+        log::error!("Failed to send post-edit keystrokes: {}", e);
+    }
+}
+
+/// Complete a batch session: write each edited section back to its field
+/// Fields whose text didn't change are left untouched
+/// Returns whether the edited text was written back
+fn complete_batch_session(
+    manager: &EditSessionManager,
+    session_id: &uuid::Uuid,
+    settings: &NvimEditSettings,
+) -> Result<bool, String> {
+    let session = manager.get_session(session_id)
+        .ok_or("Session not found")?;
+
+    let current_mtime = std::fs::metadata(&session.temp_file)
+        .and_then(|m| m.modified())
+        .map_err(|e| format!("Failed to get current file mtime: {}", e))?;
+
+    if current_mtime == session.file_mtime {
+        log::info!("File not modified (nvim quit without saving), skipping restoration");
+        let _ = std::fs::remove_file(&session.temp_file);
+        return Ok(false);
+    }
+
+    let edited_text = std::fs::read_to_string(&session.temp_file)
+        .map_err(|e| format!("Failed to read temp file: {}", e))?;
+
+    let expected = session.batch_targets.len();
+    // On a parse error the temp file is kept so the edits aren't lost
+    let original_sections = batch::parse_sections(&session.original_text, expected)?;
+    let edited_sections = batch::parse_sections(&edited_text, expected)?;
+
+    let applied = confirm::apply_edited_text(
+        &session.original_text,
+        edited_text.strip_suffix('\n').unwrap_or(&edited_text),
+        settings.confirm_before_apply,
+        |original, edited| confirm::confirm_with_dialog(manager.app_handle(), original, edited),
+        |_| {
+            let mut failed = 0;
+            for (i, element) in session.batch_targets.iter().enumerate() {
+                if edited_sections[i] == original_sections[i] {
+                    continue;
+                }
+                if let Err(e) = accessibility::set_element_text(element, &edited_sections[i]) {
+                    log::error!("Failed to write field {}: {}", i + 1, e);
+                    failed += 1;
+                }
+            }
+            if failed > 0 {
+                return Err(format!("Failed to write {} of {} fields", failed, expected));
+            }
+            Ok(())
+        },
+    )?;
+
+    if !applied {
+        log::info!("Edited text not applied, keeping temp file at {:?}", session.temp_file);
+        return Ok(false);
+    }
+
+    let _ = std::fs::remove_file(&session.temp_file);
+    log::info!("Successfully restored {} batch fields", expected);
+    Ok(true)
+}
+
 /// Complete the edit session: clean up temp file and optionally restore text via clipboard
 /// Note: Focus should already be restored before calling this function
 /// If live_sync_worked is true, the text field already has the correct content, so we skip clipboard paste
+/// With confirm_before_apply, the user is asked first; if they cancel, the temp file is kept
+/// Returns whether the field now holds the edited text
 fn complete_edit_session_no_focus(
     manager: &EditSessionManager,
     session_id: &uuid::Uuid,
     live_sync_worked: bool,
-) -> Result<(), String> {
+    settings: &NvimEditSettings,
+) -> Result<bool, String> {
+    let clipboard_delay = Duration::from_millis(settings.clipboard_delay_ms);
+
     // Read the temp file
     let session = manager.get_session(session_id)
         .ok_or("Session not found")?;
@@ -309,66 +555,70 @@ fn complete_edit_session_no_focus(
         log::info!("File not modified (nvim quit without saving), skipping restoration");
         // Clean up temp file
         let _ = std::fs::remove_file(&session.temp_file);
-        return Ok(());
+        return Ok(false);
     }
 
-    let edited_text = std::fs::read_to_string(&session.temp_file)
-        .map_err(|e| format!("Failed to read temp file: {}", e))?;
+    let (saved_text, edited_text) =
+        read_edited_text(&session.temp_file, settings.shell_command.as_deref())?;
 
-    // Strip trailing newline that nvim adds (fixeol option)
-    // This ensures we don't add extra newlines when pasting back
-    let edited_text = edited_text.strip_suffix('\n').unwrap_or(&edited_text).to_string();
+    // If live sync worked, the saved text is already in the field - no need for clipboard paste
+    // unless the shell command changed it
+    if live_sync_worked {
+        if edited_text == saved_text {
+            log::info!("Live sync worked, skipping clipboard paste");
+            let _ = std::fs::remove_file(&session.temp_file);
+            return Ok(true);
+        }
+        log::info!("Shell command changed the live synced text, pasting its output");
+    }
 
-    log::info!("Read {} chars from temp file", edited_text.len());
+    let applied = confirm::apply_edited_text(
+        &session.original_text,
+        &edited_text,
+        settings.confirm_before_apply,
+        |original, edited| confirm::confirm_with_dialog(manager.app_handle(), original, edited),
+        |text| {
+            // The dialog takes focus, so give it back to the original app
+            if settings.confirm_before_apply {
+                accessibility::restore_focus(&session.focus_context)?;
+            }
 
-    // Clean up temp file
-    let _ = std::fs::remove_file(&session.temp_file);
+            // Small delay for focus to settle (focus was restored before this call)
+            thread::sleep(clipboard_delay);
 
-    // If live sync worked, text is already in the field - no need for clipboard paste
-    if live_sync_worked {
-        log::info!("Live sync worked, skipping clipboard paste");
-        return Ok(());
-    }
+            log::info!("Replacing text via clipboard");
 
-    // Small delay for focus to settle (focus was restored before this call)
-    thread::sleep(Duration::from_millis(100));
+            // Replace text via clipboard
+            replace_text_via_clipboard(text, clipboard_delay)
+        },
+    )?;
 
-    log::info!("Replacing text via clipboard (live sync was not available)");
+    if !applied {
+        log::info!("Edited text not applied, keeping temp file at {:?}", session.temp_file);
+        return Ok(false);
+    }
 
-    // Replace text via clipboard
-    replace_text_via_clipboard(&edited_text)?;
+    // Clean up temp file
+    let _ = std::fs::remove_file(&session.temp_file);
 
     log::info!("Successfully restored edited text");
-    Ok(())
+    Ok(true)
 }
 
 /// Replace text in the focused field using clipboard
-fn replace_text_via_clipboard(text: &str) -> Result<(), String> {
+fn replace_text_via_clipboard(text: &str, delay: Duration) -> Result<(), String> {
     log::info!("Saving current clipboard and setting new content ({} chars)", text.len());
 
     // Save current clipboard
-    let original_clipboard = Command::new("pbpaste")
-        .output()
-        .ok()
-        .and_then(|o| String::from_utf8(o.stdout).ok());
+    let original_clipboard = clipboard::read_clipboard();
 
     // Set new clipboard content
-    let mut pbcopy = Command::new("pbcopy")
-        .stdin(std::process::Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to spawn pbcopy: {}", e))?;
-
-    if let Some(mut stdin) = pbcopy.stdin.take() {
-        use std::io::Write;
-        stdin.write_all(text.as_bytes())
-            .map_err(|e| format!("Failed to write to pbcopy: {}", e))?;
-    }
-    pbcopy.wait().map_err(|e| format!("pbcopy failed: {}", e))?;
+    clipboard::write_clipboard(text)?;
 
     log::info!("Clipboard set, now sending Cmd+A");
 
     // Select all and paste
-    thread::sleep(Duration::from_millis(100));
+    thread::sleep(delay);
     inject_key_press(
         KeyCode::A,
         Modifiers { command: true, ..Default::default() },
@@ -376,7 +626,7 @@ fn replace_text_via_clipboard(text: &str) -> Result<(), String> {
 
     log::info!("Sent Cmd+A, now sending Cmd+V");
 
-    thread::sleep(Duration::from_millis(100));
+    thread::sleep(delay);
     inject_key_press(
         KeyCode::V,
         Modifiers { command: true, ..Default::default() },
@@ -384,48 +634,72 @@ fn replace_text_via_clipboard(text: &str) -> Result<(), String> {
 
     log::info!("Sent Cmd+V");
 
-    // Restore original clipboard after a delay
+    // Restore original clipboard only once the field shows the pasted text, so a slow
+    // paste can't read the restored clipboard instead
     if let Some(original) = original_clipboard {
+        let expected = text.to_string();
+        let timeout = clipboard::paste_confirm_timeout(text.len(), delay);
         thread::spawn(move || {
-            thread::sleep(Duration::from_millis(500));
-            let _ = Command::new("pbcopy")
-                .stdin(std::process::Stdio::piped())
-                .spawn()
-                .and_then(|mut p| {
-                    if let Some(mut stdin) = p.stdin.take() {
-                        use std::io::Write;
-                        let _ = stdin.write_all(original.as_bytes());
-                    }
-                    p.wait()
-                });
+            let confirmed = clipboard::restore_when_pasted(
+                timeout,
+                || {
+                    accessibility::get_focused_element_text()
+                        .is_some_and(|current| current.trim_end() == expected.trim_end())
+                },
+                || {
+                    let _ = clipboard::write_clipboard(&original);
+                },
+            );
+            if !confirmed {
+                log::warn!("Could not confirm paste within {:?}, restored clipboard anyway", timeout);
+            }
         });
     }
 
     Ok(())
 }
 
+/// Get the focused field's text from accessibility or a clipboard copy, as `text_capture` prefers
+fn capture_field_text(settings: &NvimEditSettings, delay: Duration) -> String {
+    let text = accessibility::get_focused_element_text().unwrap_or_default();
+    log::info!("Got text from accessibility API: {} chars", text.len());
+
+    let use_clipboard = text_capture::prefers_clipboard(settings.text_capture, &text, || {
+        text_capture::looks_truncated(
+            &text,
+            accessibility::get_focused_character_count(),
+            accessibility::get_scroll_view_frame().is_some(),
+        )
+    });
+    if !use_clipboard {
+        return text;
+    }
+
+    log::info!("Trying clipboard-based capture");
+    match capture_text_via_clipboard(delay) {
+        Some(captured) if !captured.is_empty() => {
+            log::info!("Captured {} chars via clipboard", captured.len());
+            captured
+        }
+        _ => text,
+    }
+}
+
 /// Capture text from focused element via clipboard (fallback for web text fields)
-fn capture_text_via_clipboard() -> Option<String> {
+/// Empty if the field is, None if the keys couldn't be sent
+fn capture_text_via_clipboard(delay: Duration) -> Option<String> {
     // Save current clipboard
-    let original_clipboard = Command::new("pbpaste")
-        .output()
-        .ok()
-        .and_then(|o| String::from_utf8(o.stdout).ok());
+    let original_clipboard = clipboard::read_clipboard();
 
     // Clear clipboard with a unique marker to detect if copy actually worked
-    let marker = "\x00__OVIM_EMPTY_MARKER__\x00";
-    let _ = Command::new("pbcopy")
-        .stdin(std::process::Stdio::piped())
-        .spawn()
-        .and_then(|mut p| {
-            if let Some(mut stdin) = p.stdin.take() {
-                use std::io::Write;
-                let _ = stdin.write_all(marker.as_bytes());
-            }
-            p.wait()
-        });
+    let nonce = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+    let marker = text_capture::empty_marker(nonce);
+    let _ = clipboard::write_clipboard(&marker);
 
-    thread::sleep(Duration::from_millis(50));
+    thread::sleep(delay / 2);
 
     // Select all (Cmd+A)
     if inject_key_press(
@@ -435,7 +709,7 @@ fn capture_text_via_clipboard() -> Option<String> {
         return None;
     }
 
-    thread::sleep(Duration::from_millis(50));
+    thread::sleep(delay / 2);
 
     // Copy (Cmd+C)
     if inject_key_press(
@@ -445,13 +719,9 @@ fn capture_text_via_clipboard() -> Option<String> {
         return None;
     }
 
-    thread::sleep(Duration::from_millis(100));
-
-    // Read clipboard
-    let captured_text = Command::new("pbpaste")
-        .output()
-        .ok()
-        .and_then(|o| String::from_utf8(o.stdout).ok());
+    // Wait for the copy to land; if the clipboard still holds our marker, the field was empty
+    let copied = clipboard::wait_for_clipboard_change(&marker, delay * 5);
+    let captured_text = text_capture::copied_field_text(copied, &marker);
 
     // Deselect by pressing Right arrow (moves cursor to end of selection)
     let _ = inject_key_press(
@@ -462,20 +732,10 @@ fn capture_text_via_clipboard() -> Option<String> {
     // Restore original clipboard
     if let Some(original) = original_clipboard {
         thread::spawn(move || {
-            thread::sleep(Duration::from_millis(100));
-            let _ = Command::new("pbcopy")
-                .stdin(std::process::Stdio::piped())
-                .spawn()
-                .and_then(|mut p| {
-                    if let Some(mut stdin) = p.stdin.take() {
-                        use std::io::Write;
-                        let _ = stdin.write_all(original.as_bytes());
-                    }
-                    p.wait()
-                });
+            thread::sleep(delay);
+            let _ = clipboard::write_clipboard(&original);
         });
     }
 
-    // If clipboard still contains our marker, the field was empty
-    captured_text.filter(|text| text != marker)
+    Some(captured_text)
 }
@@ -0,0 +1,181 @@
+//! Crash-safe recovery of edit sessions
+//!
+//! Active sessions are written to a small state file. If ovim is killed while an
+//! edit is open, the next launch finds the leftover temp files and offers to copy
+//! any unapplied edits to the clipboard.
+
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use super::clipboard;
+
+/// Metadata persisted for each active edit session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersistedSession {
+    pub id: String,
+    pub temp_file: PathBuf,
+    pub app_bundle_id: String,
+    /// Modification time of the temp file when the session started
+    pub file_mtime: SystemTime,
+}
+
+/// Location of the session state file
+pub fn state_file_path() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join("ovim").join("sessions.json"))
+}
+
+/// Write the active sessions to the state file, removing it when there are none
+pub fn save_sessions(path: &Path, sessions: &[PersistedSession]) -> Result<(), String> {
+    if sessions.is_empty() {
+        let _ = std::fs::remove_file(path);
+        return Ok(());
+    }
+
+    let json = serde_json::to_string_pretty(sessions)
+        .map_err(|e| format!("Failed to serialize sessions: {}", e))?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to write session state: {}", e))
+}
+
+/// Read the sessions from the state file
+pub fn load_sessions(path: &Path) -> Vec<PersistedSession> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Find sessions left behind by a previous run that still hold unapplied edits
+/// Temp files that were never saved are deleted, and the state file is cleared
+pub fn recover_orphaned_at(path: &Path) -> Vec<PersistedSession> {
+    let orphaned = load_sessions(path);
+    let _ = std::fs::remove_file(path);
+
+    orphaned
+        .into_iter()
+        .filter(|session| {
+            let modified = std::fs::metadata(&session.temp_file)
+                .and_then(|m| m.modified())
+                .ok();
+            match modified {
+                Some(mtime) if mtime != session.file_mtime => true,
+                Some(_) => {
+                    let _ = std::fs::remove_file(&session.temp_file);
+                    false
+                }
+                None => false,
+            }
+        })
+        .collect()
+}
+
+/// Ask the user, one session at a time, whether to copy recovered edits to the clipboard
+pub fn offer_recovery(app: &AppHandle, sessions: Vec<PersistedSession>) {
+    if sessions.is_empty() {
+        return;
+    }
+
+    let app = app.clone();
+    thread::spawn(move || {
+        for session in sessions {
+            let text = match std::fs::read_to_string(&session.temp_file) {
+                Ok(text) => text,
+                Err(e) => {
+                    log::warn!("Failed to read recovered edit {:?}: {}", session.temp_file, e);
+                    continue;
+                }
+            };
+
+            let copy = app
+                .dialog()
+                .message(format!(
+                    "An edit in {} was interrupted before it was applied ({} chars).\n\nCopy the edited text to the clipboard?",
+                    session.app_bundle_id,
+                    text.len()
+                ))
+                .title("Recover edit")
+                .kind(MessageDialogKind::Info)
+                .buttons(MessageDialogButtons::OkCancelCustom(
+                    "Copy".to_string(),
+                    "Discard".to_string(),
+                ))
+                .blocking_show();
+
+            if copy {
+                let text = text.strip_suffix('\n').unwrap_or(&text);
+                if let Err(e) = clipboard::write_clipboard(text) {
+                    log::error!("Failed to copy recovered edit: {}", e);
+                    continue;
+                }
+                log::info!("Copied recovered edit {} to clipboard", session.id);
+            }
+            let _ = std::fs::remove_file(&session.temp_file);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ovim-recovery-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn persisted(dir: &Path, id: &str, contents: &str) -> PersistedSession {
+        let temp_file = dir.join(format!("edit_{}.txt", id));
+        std::fs::write(&temp_file, contents).unwrap();
+        PersistedSession {
+            id: id.to_string(),
+            temp_file: temp_file.clone(),
+            app_bundle_id: "com.apple.TextEdit".to_string(),
+            file_mtime: std::fs::metadata(&temp_file).unwrap().modified().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_save_and_load_sessions() {
+        let dir = test_dir("persist");
+        let state = dir.join("sessions.json");
+        let sessions = vec![persisted(&dir, "a", "one"), persisted(&dir, "b", "two")];
+
+        save_sessions(&state, &sessions).unwrap();
+        assert_eq!(load_sessions(&state), sessions);
+
+        // Saving no sessions removes the state file
+        save_sessions(&state, &[]).unwrap();
+        assert!(!state.exists());
+        assert!(load_sessions(&state).is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_recover_only_modified_temp_files() {
+        let dir = test_dir("recover");
+        let state = dir.join("sessions.json");
+        let unchanged = persisted(&dir, "unchanged", "original");
+        let mut edited = persisted(&dir, "edited", "original");
+        // Simulate the file having been saved after the session started
+        edited.file_mtime -= Duration::from_secs(60);
+        let mut missing = persisted(&dir, "missing", "original");
+        std::fs::remove_file(&missing.temp_file).unwrap();
+        missing.file_mtime -= Duration::from_secs(60);
+
+        save_sessions(&state, &[unchanged.clone(), edited.clone(), missing]).unwrap();
+
+        assert_eq!(recover_orphaned_at(&state), vec![edited.clone()]);
+        assert!(!state.exists());
+        assert!(!unchanged.temp_file.exists());
+        assert!(edited.temp_file.exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
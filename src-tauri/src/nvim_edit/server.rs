@@ -0,0 +1,142 @@
+//! Neovim server mode - open the temp file in an already running nvim via `--remote`
+//!
+//! This reuses a persistent Neovim instance instead of spawning a terminal, so the
+//! edit starts instantly. The session ends when the buffer is closed in the server
+//! (e.g. `:w | bd`), which we detect by polling `bufloaded()`.
+
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use super::terminals::process_utils::resolve_command_path;
+use crate::config::{EditorType, NvimEditSettings};
+
+/// Interval between checks for the buffer being closed
+const BUFFER_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Get the server address to use, or None to fall back to spawning a terminal
+/// Server mode only applies to Neovim with a non-empty address configured
+pub fn server_addr(settings: &NvimEditSettings) -> Option<&str> {
+    if settings.editor != EditorType::Neovim {
+        return None;
+    }
+    settings
+        .nvim_server_addr
+        .as_deref()
+        .map(str::trim)
+        .filter(|addr| !addr.is_empty())
+}
+
+/// Arguments to open a file in a running server
+fn remote_open_args(addr: &str, file_path: &str) -> Vec<String> {
+    vec![
+        "--server".to_string(),
+        addr.to_string(),
+        "--remote".to_string(),
+        file_path.to_string(),
+    ]
+}
+
+/// Arguments to ask a running server whether a file's buffer is still loaded
+fn buffer_loaded_args(addr: &str, file_path: &str) -> Vec<String> {
+    vec![
+        "--server".to_string(),
+        addr.to_string(),
+        "--remote-expr".to_string(),
+        format!("bufloaded('{}')", file_path.replace('\'', "''")),
+    ]
+}
+
+/// Open the file in the running Neovim server
+pub fn open_in_server(settings: &NvimEditSettings, addr: &str, file_path: &Path) -> Result<(), String> {
+    let editor = resolve_command_path(&settings.editor_path());
+    let args = remote_open_args(addr, &file_path.to_string_lossy());
+    log::info!("Opening in nvim server: {} {:?}", editor, args);
+
+    let output = Command::new(&editor)
+        .args(&args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", editor, e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "nvim --remote failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Block until the file's buffer is no longer loaded in the server
+/// Also returns if the server stops responding (e.g. it was quit with `:wq`)
+pub fn wait_for_buffer_close(settings: &NvimEditSettings, addr: &str, file_path: &Path) {
+    let editor = resolve_command_path(&settings.editor_path());
+    let args = buffer_loaded_args(addr, &file_path.to_string_lossy());
+
+    loop {
+        thread::sleep(BUFFER_POLL_INTERVAL);
+
+        let loaded = Command::new(&editor)
+            .args(&args)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim() == "1");
+
+        match loaded {
+            Some(true) => continue,
+            Some(false) => {
+                log::info!("Buffer closed in nvim server");
+                return;
+            }
+            None => {
+                log::info!("nvim server at {} no longer responding", addr);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings_with_addr(addr: Option<&str>) -> NvimEditSettings {
+        NvimEditSettings {
+            nvim_server_addr: addr.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_remote_open_args() {
+        assert_eq!(
+            remote_open_args("/tmp/nvim.sock", "/tmp/edit.txt"),
+            vec!["--server", "/tmp/nvim.sock", "--remote", "/tmp/edit.txt"]
+        );
+    }
+
+    #[test]
+    fn test_buffer_loaded_args_escapes_quotes() {
+        let args = buffer_loaded_args("127.0.0.1:6666", "/tmp/it's.txt");
+        assert_eq!(args[2], "--remote-expr");
+        assert_eq!(args[3], "bufloaded('/tmp/it''s.txt')");
+    }
+
+    #[test]
+    fn test_server_addr_falls_back_without_address() {
+        assert_eq!(server_addr(&settings_with_addr(None)), None);
+        assert_eq!(server_addr(&settings_with_addr(Some(""))), None);
+        assert_eq!(server_addr(&settings_with_addr(Some("   "))), None);
+    }
+
+    #[test]
+    fn test_server_addr_requires_neovim() {
+        let mut settings = settings_with_addr(Some("/tmp/nvim.sock"));
+        assert_eq!(server_addr(&settings), Some("/tmp/nvim.sock"));
+
+        settings.editor = EditorType::Helix;
+        assert_eq!(server_addr(&settings), None);
+    }
+}
@@ -2,13 +2,18 @@
 
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::SystemTime;
+use tauri::AppHandle;
 use uuid::Uuid;
 
-use super::accessibility::FocusContext;
+use super::accessibility::{AXElementHandle, EditableField, FocusContext};
+use super::batch;
+use super::error::EditError;
+use super::recovery::{self, PersistedSession};
+use super::server;
 use super::terminals::{spawn_terminal, SpawnInfo, TerminalType, WindowGeometry};
-use crate::config::NvimEditSettings;
+use crate::config::{CursorPosition, NvimEditSettings};
 
 /// An active edit session
 pub struct EditSession {
@@ -22,34 +27,50 @@ pub struct EditSession {
     pub window_title: Option<String>,
     /// Socket path for RPC communication with nvim
     pub socket_path: PathBuf,
+    /// Address of the Neovim server the file was opened in (server mode only)
+    pub server_addr: Option<String>,
+    /// Fields edited together as sections of the temp file (batch mode only)
+    pub batch_targets: Vec<AXElementHandle>,
 }
 
 /// Manager for edit sessions
 pub struct EditSessionManager {
     sessions: Arc<Mutex<HashMap<Uuid, EditSession>>>,
+    /// App handle used for dialogs shown during a session
+    app_handle: OnceLock<AppHandle>,
 }
 
 impl EditSessionManager {
     pub fn new() -> Self {
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
+            app_handle: OnceLock::new(),
         }
     }
 
-    /// Start a new edit session
+    /// Set the app handle once the Tauri app is running
+    pub fn set_app_handle(&self, app_handle: AppHandle) {
+        let _ = self.app_handle.set(app_handle);
+    }
+
+    /// Get the app handle, if the app has been set up
+    pub fn app_handle(&self) -> Option<&AppHandle> {
+        self.app_handle.get()
+    }
+
+    /// Start a new edit session, with the editor opening at `cursor` if known
     pub fn start_session(
         &self,
         focus_context: FocusContext,
         text: String,
         settings: NvimEditSettings,
         geometry: Option<WindowGeometry>,
-    ) -> Result<Uuid, String> {
+        cursor: Option<CursorPosition>,
+    ) -> Result<Uuid, EditError> {
         // Create temp directory if needed
-        let cache_dir = dirs::cache_dir()
-            .ok_or("Could not determine cache directory")?
-            .join("ovim");
+        let cache_dir = edit_dir().map_err(EditError::TempFile)?;
         std::fs::create_dir_all(&cache_dir)
-            .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+            .map_err(|e| EditError::TempFile(format!("Failed to create cache directory: {}", e)))?;
 
         // Generate session ID and temp file
         let session_id = Uuid::new_v4();
@@ -63,20 +84,36 @@ impl EditSessionManager {
 
         // Write text to temp file
         std::fs::write(&temp_file, &text)
-            .map_err(|e| format!("Failed to write temp file: {}", e))?;
+            .map_err(|e| EditError::TempFile(format!("Failed to write temp file: {}", e)))?;
 
         // Get file modification time after writing
         let file_mtime = std::fs::metadata(&temp_file)
             .and_then(|m| m.modified())
-            .map_err(|e| format!("Failed to get file mtime: {}", e))?;
+            .map_err(|e| EditError::TempFile(format!("Failed to get file mtime: {}", e)))?;
 
-        // Spawn terminal with RPC socket for live buffer sync
-        let SpawnInfo {
-            terminal_type,
-            process_id,
-            child: _,
-            window_title,
-        } = spawn_terminal(&settings, &temp_file, geometry, Some(&socket_path))?;
+        // Prefer opening in a running Neovim server, falling back to a new terminal
+        let server_addr = server::server_addr(&settings).and_then(|addr| {
+            match server::open_in_server(&settings, addr, &temp_file) {
+                Ok(()) => Some(addr.to_string()),
+                Err(e) => {
+                    log::warn!("Failed to open in nvim server {}, spawning terminal: {}", addr, e);
+                    None
+                }
+            }
+        });
+
+        let (terminal_type, process_id, window_title) = if server_addr.is_some() {
+            (TerminalType::from_string(&settings.terminal), None, None)
+        } else {
+            // Spawn terminal with RPC socket for live buffer sync
+            let SpawnInfo {
+                terminal_type,
+                process_id,
+                child: _,
+                window_title,
+            } = spawn_terminal(&settings, &temp_file, geometry, Some(&socket_path), cursor)?;
+            (terminal_type, process_id, window_title)
+        };
 
         // Create session
         let session = EditSession {
@@ -89,11 +126,39 @@ impl EditSessionManager {
             process_id,
             window_title,
             socket_path,
+            server_addr,
+            batch_targets: Vec::new(),
         };
 
         // Store session
         let mut sessions = self.sessions.lock().unwrap();
         sessions.insert(session_id, session);
+        persist_sessions(&sessions);
+
+        Ok(session_id)
+    }
+
+    /// Start an edit session for several fields at once
+    /// Each field becomes a marked section of the temp file
+    pub fn start_batch_session(
+        &self,
+        focus_context: FocusContext,
+        fields: Vec<EditableField>,
+        settings: NvimEditSettings,
+        geometry: Option<WindowGeometry>,
+    ) -> Result<Uuid, EditError> {
+        let sections: Vec<(Option<String>, String)> = fields
+            .iter()
+            .map(|field| (field.label.clone(), field.text.clone()))
+            .collect();
+        let text = batch::serialize_sections(&sections);
+
+        let session_id = self.start_session(focus_context, text, settings, geometry, None)?;
+
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(session) = sessions.get_mut(&session_id) {
+            session.batch_targets = fields.into_iter().map(|field| field.element).collect();
+        }
 
         Ok(session_id)
     }
@@ -111,6 +176,8 @@ impl EditSessionManager {
             process_id: s.process_id,
             window_title: s.window_title.clone(),
             socket_path: s.socket_path.clone(),
+            server_addr: s.server_addr.clone(),
+            batch_targets: s.batch_targets.clone(),
         })
     }
 
@@ -122,12 +189,37 @@ impl EditSessionManager {
             let _ = std::fs::remove_file(&session.temp_file);
             let _ = std::fs::remove_file(&session.socket_path);
         }
+        persist_sessions(&sessions);
     }
 
     /// Remove a session after completion
     pub fn remove_session(&self, id: &Uuid) {
         let mut sessions = self.sessions.lock().unwrap();
         sessions.remove(id);
+        persist_sessions(&sessions);
+    }
+
+    /// Find edit sessions left open by a previous run that was killed
+    /// Returns the sessions whose temp file still holds unapplied edits
+    pub fn recover_orphaned(&self) -> Vec<PersistedSession> {
+        let path = match recovery::state_file_path() {
+            Some(path) => path,
+            None => return Vec::new(),
+        };
+        let recovered = recovery::recover_orphaned_at(&path);
+        if !recovered.is_empty() {
+            log::info!("Found {} interrupted edit sessions", recovered.len());
+        }
+        recovered
+    }
+
+    /// Temp files and sockets belonging to the active sessions
+    pub fn active_files(&self) -> Vec<PathBuf> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .values()
+            .flat_map(|s| [s.temp_file.clone(), s.socket_path.clone()])
+            .collect()
     }
 
     /// Check if there are any active sessions
@@ -138,6 +230,33 @@ impl EditSessionManager {
     }
 }
 
+/// Directory holding the temp files and sockets of edit sessions
+pub fn edit_dir() -> Result<PathBuf, String> {
+    Ok(dirs::cache_dir()
+        .ok_or("Could not determine cache directory")?
+        .join("ovim"))
+}
+
+/// Write the active sessions to the state file so they can be recovered after a crash
+fn persist_sessions(sessions: &HashMap<Uuid, EditSession>) {
+    let path = match recovery::state_file_path() {
+        Some(path) => path,
+        None => return,
+    };
+    let persisted: Vec<PersistedSession> = sessions
+        .values()
+        .map(|session| PersistedSession {
+            id: session.id.to_string(),
+            temp_file: session.temp_file.clone(),
+            app_bundle_id: session.focus_context.app_bundle_id.clone(),
+            file_mtime: session.file_mtime,
+        })
+        .collect();
+    if let Err(e) = recovery::save_sessions(&path, &persisted) {
+        log::warn!("Failed to persist edit sessions: {}", e);
+    }
+}
+
 impl Default for EditSessionManager {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,141 @@
+//! Piping the edited text through a shell command (e.g. a formatter) before it's written back
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long the command may run before it's killed and the raw text is used
+pub const SHELL_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run `command` with `sh -c`, feeding `text` on stdin, and return its stdout
+/// Fails if the command exits with an error or runs longer than `timeout`
+pub fn pipe_through(command: &str, text: &str, timeout: Duration) -> Result<String, String> {
+    let mut child = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run shell command: {}", e))?;
+
+    // Write and read on threads, so a command that fills its output pipe before
+    // reading all input can't block either side
+    let mut stdin = child.stdin.take().ok_or("No stdin for shell command")?;
+    let input = text.to_string();
+    let writer = thread::spawn(move || {
+        // An error here means the command closed stdin early, which its exit status reports
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let mut stdout = child.stdout.take().ok_or("No stdout for shell command")?;
+    let reader = thread::spawn(move || {
+        let mut output = String::new();
+        stdout.read_to_string(&mut output).map(|_| output)
+    });
+    // Drained too, so a command that logs a lot can't block on a full stderr pipe
+    let mut stderr = child.stderr.take().ok_or("No stderr for shell command")?;
+    let errors = thread::spawn(move || {
+        let mut errors = Vec::new();
+        let _ = stderr.read_to_end(&mut errors);
+        String::from_utf8_lossy(&errors).into_owned()
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|e| format!("Failed to wait for shell command: {}", e))?
+        {
+            break status;
+        }
+        if started.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("Shell command timed out after {:?}", timeout));
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    let _ = writer.join();
+    let output = reader
+        .join()
+        .map_err(|_| "Failed to read shell command output".to_string())?
+        .map_err(|e| format!("Shell command output is not UTF-8: {}", e))?;
+
+    let errors = errors.join().unwrap_or_default();
+
+    if !status.success() {
+        return Err(format!("Shell command failed ({}): {}", status, errors.trim()));
+    }
+
+    // Formatters end their output with a newline; keep the text's own ending instead
+    if !text.ends_with('\n') {
+        if let Some(trimmed) = output.strip_suffix('\n') {
+            return Ok(trimmed.to_string());
+        }
+    }
+    Ok(output)
+}
+
+/// Pipe `text` through the configured command, or return it unchanged if there's
+/// no command or the command fails
+pub fn post_process(command: Option<&str>, text: &str, timeout: Duration) -> String {
+    let command = match command.map(str::trim).filter(|command| !command.is_empty()) {
+        Some(command) => command,
+        None => return text.to_string(),
+    };
+    match pipe_through(command, text, timeout) {
+        Ok(output) => {
+            log::info!("Piped edited text through `{}` ({} -> {} chars)", command, text.len(), output.len());
+            output
+        }
+        Err(e) => {
+            log::warn!("{}, using the edited text as is", e);
+            text.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn test_pipe_through() {
+        assert_eq!(pipe_through("tr a-z A-Z", "hello\nworld", TIMEOUT).unwrap(), "HELLO\nWORLD");
+        // A trailing newline the text already had is kept
+        assert_eq!(pipe_through("cat", "line\n", TIMEOUT).unwrap(), "line\n");
+    }
+
+    #[test]
+    fn test_failing_command() {
+        let err = pipe_through("echo oops >&2; exit 3", "text", TIMEOUT).unwrap_err();
+        assert!(err.contains("oops"), "{}", err);
+        assert_eq!(post_process(Some("exit 1"), "text", TIMEOUT), "text");
+    }
+
+    #[test]
+    fn test_noisy_stderr_does_not_block() {
+        // More than a pipe buffer's worth of stderr before any output
+        let command = "head -c 200000 /dev/zero >&2; cat";
+        assert_eq!(pipe_through(command, "text", TIMEOUT).unwrap(), "text");
+    }
+
+    #[test]
+    fn test_timeout_falls_back() {
+        let started = Instant::now();
+        let err = pipe_through("sleep 5", "text", Duration::from_millis(100)).unwrap_err();
+        assert!(err.contains("timed out"), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(post_process(Some("sleep 5"), "text", Duration::from_millis(100)), "text");
+    }
+
+    #[test]
+    fn test_no_command() {
+        assert_eq!(post_process(None, "text", TIMEOUT), "text");
+        assert_eq!(post_process(Some("  "), "text", TIMEOUT), "text");
+        assert_eq!(post_process(Some("sed s/a/b/"), "a", TIMEOUT), "b");
+    }
+}
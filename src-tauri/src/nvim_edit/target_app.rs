@@ -0,0 +1,91 @@
+//! Guard against writing the edited text into the wrong app
+//!
+//! If the app the edit started from quits while nvim is open, pasting would go to
+//! whatever is focused now. The edited text is copied to the clipboard instead.
+
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use super::accessibility::{self, FocusContext};
+use super::clipboard;
+
+/// Whether a process with this PID exists
+pub fn is_pid_alive(pid: i32) -> bool {
+    if pid <= 0 {
+        return false;
+    }
+    // EPERM means the process exists but belongs to another user
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Whether the app the edit started from still runs under the same PID
+/// A reused PID belongs to another app, so the bundle id has to match too
+pub fn is_target_running(
+    context: &FocusContext,
+    is_alive: impl Fn(i32) -> bool,
+    bundle_id_for_pid: impl Fn(i32) -> Option<String>,
+) -> bool {
+    is_alive(context.app_pid)
+        && bundle_id_for_pid(context.app_pid).as_deref() == Some(context.app_bundle_id.as_str())
+}
+
+/// Whether the app the edit started from is still running
+pub fn target_app_running(context: &FocusContext) -> bool {
+    is_target_running(context, is_pid_alive, accessibility::bundle_id_for_pid)
+}
+
+/// Copy the edited text to the clipboard and tell the user the original app is gone
+pub fn copy_to_clipboard_and_notify(app: Option<&AppHandle>, app_bundle_id: &str, text: &str) -> Result<(), String> {
+    clipboard::write_clipboard(text)?;
+    log::info!("{} quit during the edit, copied {} chars to the clipboard", app_bundle_id, text.len());
+
+    if let Some(app) = app {
+        app.dialog()
+            .message(format!(
+                "{} quit while you were editing, so the edited text couldn't be pasted back.\n\nIt has been copied to the clipboard.",
+                app_bundle_id
+            ))
+            .title("Edited text copied")
+            .kind(MessageDialogKind::Info)
+            .show(|_| {});
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> FocusContext {
+        FocusContext {
+            app_pid: 42,
+            app_bundle_id: "com.apple.TextEdit".to_string(),
+            focused_element: None,
+        }
+    }
+
+    #[test]
+    fn test_running_target() {
+        assert!(is_target_running(&context(), |_| true, |_| Some("com.apple.TextEdit".to_string())));
+    }
+
+    #[test]
+    fn test_quit_target() {
+        assert!(!is_target_running(&context(), |_| false, |_| Some("com.apple.TextEdit".to_string())));
+        assert!(!is_target_running(&context(), |_| false, |_| None));
+    }
+
+    #[test]
+    fn test_reused_pid() {
+        assert!(!is_target_running(&context(), |_| true, |_| Some("com.apple.Safari".to_string())));
+        assert!(!is_target_running(&context(), |_| true, |_| None));
+    }
+
+    #[test]
+    fn test_pid_alive() {
+        assert!(is_pid_alive(std::process::id() as i32));
+        assert!(!is_pid_alive(0));
+        assert!(!is_pid_alive(-1));
+    }
+}
@@ -7,8 +7,9 @@ use super::applescript_utils::{
     find_alacritty_window_by_title, focus_alacritty_window_by_index, set_window_bounds_atomic,
 };
 use super::process_utils::{find_editor_pid_for_file, resolve_command_path, resolve_terminal_path};
-use super::{SpawnInfo, TerminalSpawner, TerminalType, WindowGeometry};
-use crate::config::NvimEditSettings;
+use super::{apply_editor_env, editor_command_with_env, SpawnInfo, TerminalSpawner, TerminalType, WindowGeometry};
+use crate::config::{CursorPosition, NvimEditSettings};
+use crate::nvim_edit::EditError;
 
 pub struct AlacrittySpawner;
 
@@ -23,13 +24,14 @@ impl TerminalSpawner for AlacrittySpawner {
         file_path: &str,
         geometry: Option<WindowGeometry>,
         socket_path: Option<&Path>,
-    ) -> Result<SpawnInfo, String> {
+        cursor: Option<CursorPosition>,
+    ) -> Result<SpawnInfo, EditError> {
         // Generate a unique window title so we can find it
         let unique_title = format!("ovim-edit-{}", std::process::id());
 
         // Get editor path and args from settings
         let editor_path = settings.editor_path();
-        let editor_args = settings.editor_args();
+        let editor_args = super::editor_args(settings, cursor);
         let process_name = settings.editor_process_name();
 
         // Build socket args for nvim RPC if socket_path provided and using nvim
@@ -91,8 +93,9 @@ impl TerminalSpawner for AlacrittySpawner {
             "-o".to_string(),
             format!("window.dimensions.lines={}", init_lines),
             "-e".to_string(),
-            resolved_editor.clone(),
         ];
+        // The window is started by the running Alacritty, so the environment goes on the command line
+        cmd_args.extend(editor_command_with_env(&resolved_editor, settings));
         cmd_args.extend(socket_args.iter().cloned());
         for arg in &editor_args {
             cmd_args.push(arg.to_string());
@@ -139,11 +142,13 @@ impl TerminalSpawner for AlacrittySpawner {
                 }
                 fallback_args.push(file_path.to_string());
 
+                let mut fallback_cmd = Command::new(&resolved_terminal);
+                apply_editor_env(&mut fallback_cmd, settings);
                 Some(
-                    Command::new(&resolved_terminal)
+                    fallback_cmd
                         .args(&fallback_args)
                         .spawn()
-                        .map_err(|e| format!("Failed to spawn alacritty: {}", e))?,
+                        .map_err(|e| EditError::spawn_failed("alacritty", e))?,
                 )
             }
         };
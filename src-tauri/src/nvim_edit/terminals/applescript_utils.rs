@@ -4,6 +4,11 @@ use std::process::Command;
 
 use super::WindowGeometry;
 
+/// Escape text to put inside a double-quoted AppleScript string
+pub fn escape_string(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
 /// Set window size using AppleScript
 pub fn set_window_size(app_name: &str, width: u32, height: u32) {
     let script = format!(
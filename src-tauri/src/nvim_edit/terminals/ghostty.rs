@@ -4,8 +4,9 @@ use std::path::Path;
 use std::process::Command;
 
 use super::process_utils::{find_editor_pid_for_file, resolve_command_path};
-use super::{SpawnInfo, TerminalSpawner, TerminalType, WindowGeometry};
-use crate::config::NvimEditSettings;
+use super::{apply_editor_env, editor_command_with_env, SpawnInfo, TerminalSpawner, TerminalType, WindowGeometry};
+use crate::config::{CursorPosition, NvimEditSettings};
+use crate::nvim_edit::EditError;
 
 pub struct GhosttySpawner;
 
@@ -20,13 +21,14 @@ impl TerminalSpawner for GhosttySpawner {
         file_path: &str,
         geometry: Option<WindowGeometry>,
         socket_path: Option<&Path>,
-    ) -> Result<SpawnInfo, String> {
+        cursor: Option<CursorPosition>,
+    ) -> Result<SpawnInfo, EditError> {
         // Generate a unique window title so we can find it
         let unique_title = format!("ovim-edit-{}", std::process::id());
 
         // Get editor path and args from settings
         let editor_path = settings.editor_path();
-        let editor_args = settings.editor_args();
+        let editor_args = super::editor_args(settings, cursor);
         let process_name = settings.editor_process_name();
 
         // Build socket args for nvim RPC if socket_path provided and using nvim
@@ -76,9 +78,15 @@ impl TerminalSpawner for GhosttySpawner {
             ]);
         }
 
-        // Execute editor using -e flag
+        // Execute editor using -e flag. `open` doesn't pass on its environment, so then
+        // the editor's variables go on the command line
         cmd.arg("-e");
-        cmd.arg(&resolved_editor);
+        if use_direct_binary {
+            apply_editor_env(&mut cmd, settings);
+            cmd.arg(&resolved_editor);
+        } else {
+            cmd.args(editor_command_with_env(&resolved_editor, settings));
+        }
         for arg in &socket_args {
             cmd.arg(arg);
         }
@@ -88,7 +96,7 @@ impl TerminalSpawner for GhosttySpawner {
         cmd.arg(file_path);
 
         cmd.spawn()
-            .map_err(|e| format!("Failed to spawn ghostty: {}", e))?;
+            .map_err(|e| EditError::spawn_failed("ghostty", e))?;
 
         // Wait a bit for editor to start, then find its PID by the file it's editing
         let pid = find_editor_pid_for_file(file_path, process_name);
@@ -4,8 +4,10 @@ use std::path::Path;
 use std::process::Command;
 
 use super::process_utils::find_editor_pid_for_file;
-use super::{SpawnInfo, TerminalSpawner, TerminalType, WindowGeometry};
-use crate::config::NvimEditSettings;
+use super::applescript_utils::escape_string;
+use super::{shell_env_prefix, SpawnInfo, TerminalSpawner, TerminalType, WindowGeometry};
+use crate::config::{CursorPosition, NvimEditSettings};
+use crate::nvim_edit::EditError;
 
 pub struct ITermSpawner;
 
@@ -20,10 +22,11 @@ impl TerminalSpawner for ITermSpawner {
         file_path: &str,
         geometry: Option<WindowGeometry>,
         socket_path: Option<&Path>,
-    ) -> Result<SpawnInfo, String> {
+        cursor: Option<CursorPosition>,
+    ) -> Result<SpawnInfo, EditError> {
         // Get editor path and args from settings
         let editor_path = settings.editor_path();
-        let editor_args = settings.editor_args();
+        let editor_args = super::editor_args(settings, cursor);
         let process_name = settings.editor_process_name();
 
         // Build socket args for nvim RPC if socket_path provided and using nvim
@@ -38,13 +41,17 @@ impl TerminalSpawner for ITermSpawner {
         };
 
         // Build the command string for AppleScript (socket args + editor args)
+        // Args are quoted so the shell keeps e.g. "+normal 5|" as one argument
         let mut all_args: Vec<String> = socket_args;
-        all_args.extend(editor_args.iter().map(|s| s.to_string()));
+        all_args.extend(editor_args);
+        let all_args: Vec<String> = all_args.iter().map(|arg| format!("'{}'", arg)).collect();
         let args_str = if all_args.is_empty() {
             String::new()
         } else {
             format!(" {}", all_args.join(" "))
         };
+        // The shell the terminal opens starts the editor, so `editor_env` goes before it
+        let env_prefix = escape_string(&shell_env_prefix(settings));
 
         // Use AppleScript to open iTerm and run editor with position/size
         let script = if let Some(geo) = geometry {
@@ -55,7 +62,7 @@ impl TerminalSpawner for ITermSpawner {
                 set newWindow to (create window with default profile)
                 set bounds of newWindow to {{{}, {}, {}, {}}}
                 tell current session of newWindow
-                    write text "{}{} '{}'; exit"
+                    write text "{}{}{} '{}'; exit"
                 end tell
             end tell
             "#,
@@ -63,6 +70,7 @@ impl TerminalSpawner for ITermSpawner {
                 geo.y,
                 geo.x + geo.width as i32,
                 geo.y + geo.height as i32,
+                env_prefix,
                 editor_path,
                 args_str,
                 file_path
@@ -74,11 +82,11 @@ impl TerminalSpawner for ITermSpawner {
                 activate
                 set newWindow to (create window with default profile)
                 tell current session of newWindow
-                    write text "{}{} '{}'; exit"
+                    write text "{}{}{} '{}'; exit"
                 end tell
             end tell
             "#,
-                editor_path, args_str, file_path
+                env_prefix, editor_path, args_str, file_path
             )
         };
 
@@ -86,7 +94,7 @@ impl TerminalSpawner for ITermSpawner {
             .arg("-e")
             .arg(&script)
             .output()
-            .map_err(|e| format!("Failed to run iTerm AppleScript: {}", e))?;
+            .map_err(|e| EditError::spawn_failed("iTerm", e))?;
 
         // Try to find the editor process ID by the file it's editing
         let pid = find_editor_pid_for_file(file_path, process_name);
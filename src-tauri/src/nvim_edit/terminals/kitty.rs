@@ -4,8 +4,9 @@ use std::path::Path;
 use std::process::Command;
 
 use super::process_utils::{find_editor_pid_for_file, resolve_command_path, resolve_terminal_path};
-use super::{SpawnInfo, TerminalSpawner, TerminalType, WindowGeometry};
-use crate::config::NvimEditSettings;
+use super::{apply_editor_env, SpawnInfo, TerminalSpawner, TerminalType, WindowGeometry};
+use crate::config::{CursorPosition, NvimEditSettings};
+use crate::nvim_edit::EditError;
 
 pub struct KittySpawner;
 
@@ -20,13 +21,14 @@ impl TerminalSpawner for KittySpawner {
         file_path: &str,
         geometry: Option<WindowGeometry>,
         socket_path: Option<&Path>,
-    ) -> Result<SpawnInfo, String> {
+        cursor: Option<CursorPosition>,
+    ) -> Result<SpawnInfo, EditError> {
         // Generate a unique window title
         let unique_title = format!("ovim-edit-{}", std::process::id());
 
         // Get editor path and args from settings
         let editor_path = settings.editor_path();
-        let editor_args = settings.editor_args();
+        let editor_args = super::editor_args(settings, cursor);
         let process_name = settings.editor_process_name();
 
         // Build socket args for nvim RPC if socket_path provided and using nvim
@@ -50,6 +52,7 @@ impl TerminalSpawner for KittySpawner {
         log::info!("Resolved terminal path: {} -> {}", terminal_cmd, resolved_terminal);
 
         let mut cmd = Command::new(&resolved_terminal);
+        apply_editor_env(&mut cmd, settings);
 
         // Use single instance to avoid multiple dock icons, close window when editor exits
         cmd.args(["--single-instance", "--wait-for-single-instance-window-close"]);
@@ -82,7 +85,7 @@ impl TerminalSpawner for KittySpawner {
 
         let child = cmd
             .spawn()
-            .map_err(|e| format!("Failed to spawn kitty: {}", e))?;
+            .map_err(|e| EditError::spawn_failed("kitty", e))?;
 
         // Wait a bit for editor to start, then find its PID by the file it's editing
         let pid = find_editor_pid_for_file(file_path, process_name);
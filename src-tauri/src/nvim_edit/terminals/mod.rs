@@ -19,9 +19,10 @@ pub use kitty::KittySpawner;
 pub use terminal_app::TerminalAppSpawner;
 pub use wezterm::WezTermSpawner;
 
-use crate::config::NvimEditSettings;
+use super::{gui_editor, minimal_config, EditError};
+use crate::config::{CursorPosition, NvimEditSettings};
 use std::path::Path;
-use std::process::Child;
+use std::process::{Child, Command};
 
 /// Window position and size for popup mode
 #[derive(Debug, Clone, Default)]
@@ -41,6 +42,8 @@ pub enum TerminalType {
     WezTerm,
     ITerm,
     Default, // Terminal.app
+    /// No terminal, the editor is a GUI app (see `gui_editor`)
+    GuiEditor,
 }
 
 impl TerminalType {
@@ -75,38 +78,85 @@ pub trait TerminalSpawner {
     ///
     /// If `socket_path` is provided, the editor will be started with RPC enabled
     /// (e.g., nvim --listen <socket_path>) for live buffer sync.
+    /// `cursor` is where the editor opens, if known.
     fn spawn(
         &self,
         settings: &NvimEditSettings,
         file_path: &str,
         geometry: Option<WindowGeometry>,
         socket_path: Option<&Path>,
-    ) -> Result<SpawnInfo, String>;
+        cursor: Option<CursorPosition>,
+    ) -> Result<SpawnInfo, EditError>;
 }
 
 /// Spawn a terminal with the configured editor editing the given file
+/// A GUI editor is started on its own instead, without a terminal
 ///
 /// If `socket_path` is provided, the editor will be started with RPC enabled
-/// for live buffer sync.
+/// for live buffer sync. `cursor` is where the editor opens, if known.
 pub fn spawn_terminal(
     settings: &NvimEditSettings,
     temp_file: &Path,
     geometry: Option<WindowGeometry>,
     socket_path: Option<&Path>,
-) -> Result<SpawnInfo, String> {
-    let terminal_type = TerminalType::from_string(&settings.terminal);
+    cursor: Option<CursorPosition>,
+) -> Result<SpawnInfo, EditError> {
+    let terminal_type = if settings.editor.is_gui() {
+        TerminalType::GuiEditor
+    } else {
+        TerminalType::from_string(&settings.terminal)
+    };
     let file_path = temp_file.to_string_lossy();
 
     match terminal_type {
-        TerminalType::Alacritty => AlacrittySpawner.spawn(settings, &file_path, geometry, socket_path),
-        TerminalType::Ghostty => GhosttySpawner.spawn(settings, &file_path, geometry, socket_path),
-        TerminalType::Kitty => KittySpawner.spawn(settings, &file_path, geometry, socket_path),
-        TerminalType::WezTerm => WezTermSpawner.spawn(settings, &file_path, geometry, socket_path),
-        TerminalType::ITerm => ITermSpawner.spawn(settings, &file_path, geometry, socket_path),
-        TerminalType::Default => TerminalAppSpawner.spawn(settings, &file_path, geometry, socket_path),
+        TerminalType::Alacritty => AlacrittySpawner.spawn(settings, &file_path, geometry, socket_path, cursor),
+        TerminalType::Ghostty => GhosttySpawner.spawn(settings, &file_path, geometry, socket_path, cursor),
+        TerminalType::Kitty => KittySpawner.spawn(settings, &file_path, geometry, socket_path, cursor),
+        TerminalType::WezTerm => WezTermSpawner.spawn(settings, &file_path, geometry, socket_path, cursor),
+        TerminalType::ITerm => ITermSpawner.spawn(settings, &file_path, geometry, socket_path, cursor),
+        TerminalType::Default => TerminalAppSpawner.spawn(settings, &file_path, geometry, socket_path, cursor),
+        TerminalType::GuiEditor => gui_editor::spawn(settings, temp_file, cursor),
     }
 }
 
+/// Arguments the editor is started with before the file: its config, then where to open
+pub fn editor_args(settings: &NvimEditSettings, cursor: Option<CursorPosition>) -> Vec<String> {
+    let mut args = minimal_config::config_args(settings);
+    args.extend(settings.editor_args(cursor));
+    args
+}
+
+/// Set `editor_env` on a command that starts the editor
+pub fn apply_editor_env(cmd: &mut Command, settings: &NvimEditSettings) {
+    cmd.envs(settings.editor_env_vars());
+}
+
+/// The editor as a command line that sets `editor_env`, for terminals that start it from a
+/// running instance or `open`, which don't pass on the environment of the launching command
+pub fn editor_command_with_env(editor: &str, settings: &NvimEditSettings) -> Vec<String> {
+    let vars = settings.editor_env_vars();
+    if vars.is_empty() {
+        return vec![editor.to_string()];
+    }
+    let mut command = vec!["/usr/bin/env".to_string()];
+    command.extend(vars.iter().map(|(name, value)| format!("{}={}", name, value)));
+    command.push(editor.to_string());
+    command
+}
+
+/// `env VAR='value' ` to put before the editor in a shell command line, empty without variables
+pub fn shell_env_prefix(settings: &NvimEditSettings) -> String {
+    let vars = settings.editor_env_vars();
+    if vars.is_empty() {
+        return String::new();
+    }
+    let assignments: Vec<String> = vars
+        .iter()
+        .map(|(name, value)| format!("{}='{}'", name, value.replace('\'', r"'\''")))
+        .collect();
+    format!("env {} ", assignments.join(" "))
+}
+
 /// Wait for the terminal/nvim process to exit
 pub fn wait_for_process(
     terminal_type: &TerminalType,
@@ -116,7 +166,8 @@ pub fn wait_for_process(
         TerminalType::Alacritty
         | TerminalType::Ghostty
         | TerminalType::Kitty
-        | TerminalType::WezTerm => {
+        | TerminalType::WezTerm
+        | TerminalType::GuiEditor => {
             if let Some(pid) = process_id {
                 process_utils::wait_for_pid(pid)
             } else {
@@ -134,3 +185,65 @@ pub fn wait_for_process(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    fn settings_with_env(vars: &[(&str, &str)]) -> NvimEditSettings {
+        NvimEditSettings {
+            editor_env: vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_editor_env_is_set_on_command() {
+        let settings = settings_with_env(&[("NVIM_APPNAME", "nvim-popup"), ("XDG_CONFIG_HOME", "/tmp/cfg")]);
+        let mut cmd = Command::new("kitty");
+        apply_editor_env(&mut cmd, &settings);
+        let envs: Vec<_> = cmd.get_envs().collect();
+        assert_eq!(
+            envs,
+            vec![
+                (OsStr::new("NVIM_APPNAME"), Some(OsStr::new("nvim-popup"))),
+                (OsStr::new("XDG_CONFIG_HOME"), Some(OsStr::new("/tmp/cfg"))),
+            ]
+        );
+
+        let mut cmd = Command::new("kitty");
+        apply_editor_env(&mut cmd, &NvimEditSettings::default());
+        assert_eq!(cmd.get_envs().count(), 0);
+    }
+
+    #[test]
+    fn test_editor_command_with_env() {
+        let settings = settings_with_env(&[("NVIM_APPNAME", "nvim-popup")]);
+        assert_eq!(
+            editor_command_with_env("/opt/homebrew/bin/nvim", &settings),
+            vec!["/usr/bin/env", "NVIM_APPNAME=nvim-popup", "/opt/homebrew/bin/nvim"]
+        );
+        assert_eq!(
+            editor_command_with_env("nvim", &NvimEditSettings::default()),
+            vec!["nvim"]
+        );
+    }
+
+    #[test]
+    fn test_shell_env_prefix() {
+        let settings = settings_with_env(&[("XDG_CONFIG_HOME", "/tmp/my cfg"), ("NVIM_APPNAME", "it's")]);
+        assert_eq!(
+            shell_env_prefix(&settings),
+            r"env NVIM_APPNAME='it'\''s' XDG_CONFIG_HOME='/tmp/my cfg' "
+        );
+        assert_eq!(shell_env_prefix(&NvimEditSettings::default()), "");
+        // Variables without a name are left out
+        assert_eq!(shell_env_prefix(&settings_with_env(&[("", "x")])), "");
+        // The AppleScript terminals write the prefix inside a string
+        assert_eq!(
+            applescript_utils::escape_string(r#"env A='it'\''s' B='say "hi"' "#),
+            r#"env A='it'\\''s' B='say \"hi\"' "#
+        );
+    }
+}
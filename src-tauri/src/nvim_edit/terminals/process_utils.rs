@@ -1,9 +1,22 @@
 //! Process management utilities for terminal spawning
 
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::thread;
 use std::time::Duration;
 
+use serde::Serialize;
+
+/// A process holding one of ovim's edit files open
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EditProcess {
+    pub pid: u32,
+    /// Process name as reported by lsof
+    pub command: String,
+    /// The edit file the process has open
+    pub file: String,
+}
+
 /// Wait for a specific PID to exit
 pub fn wait_for_pid(pid: u32) -> Result<(), String> {
     loop {
@@ -167,3 +180,172 @@ pub fn resolve_terminal_path(terminal_name: &str) -> String {
     // Fall back to general command resolution
     resolve_command_path(terminal_name)
 }
+
+/// Check whether `path` is an edit temp file or nvim socket created by ovim in `edit_dir`
+///
+/// Vim and Neovim read the temp file and close it, so lsof rarely reports it open; in practice
+/// only Neovim's socket matches. Stray Vim, Helix and terminal processes aren't found this way
+pub fn is_edit_file(path: &Path, edit_dir: &Path) -> bool {
+    if path.parent() != Some(edit_dir) {
+        return false;
+    }
+    let name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+    (name.starts_with("edit_") && name.ends_with(".txt"))
+        || (name.starts_with("nvim_") && name.ends_with(".sock"))
+}
+
+/// Parse `lsof -F pcn` output into the processes that have edit files open
+/// Other processes, and ovim itself, are left out so only editors we started are offered
+pub fn parse_edit_processes(lsof_output: &str, edit_dir: &Path) -> Vec<EditProcess> {
+    let own_pid = std::process::id();
+    let mut processes: Vec<EditProcess> = Vec::new();
+    let mut pid = None;
+    let mut command = String::new();
+
+    for line in lsof_output.lines() {
+        let (field, value) = match line.char_indices().nth(1) {
+            Some((i, _)) => line.split_at(i),
+            None => continue,
+        };
+        match field {
+            "p" => {
+                pid = value.parse::<u32>().ok();
+                command.clear();
+            }
+            "c" => command = value.to_string(),
+            "n" => {
+                let Some(pid) = pid else { continue };
+                let known = processes.iter().any(|p| p.pid == pid);
+                if pid != own_pid && !known && is_edit_file(Path::new(value), edit_dir) {
+                    processes.push(EditProcess {
+                        pid,
+                        command: command.clone(),
+                        file: value.to_string(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    processes
+}
+
+/// List the processes that have files in the edit directory open
+/// Mostly Neovim instances holding their socket, see `is_edit_file`
+pub fn list_edit_processes(edit_dir: &Path) -> Result<Vec<EditProcess>, String> {
+    let output = Command::new("lsof")
+        .args(["-F", "pcn", "+d"])
+        .arg(edit_dir)
+        .output()
+        .map_err(|e| format!("Failed to run lsof: {}", e))?;
+
+    // lsof exits with 1 when no files are open, which isn't an error here
+    Ok(parse_edit_processes(
+        &String::from_utf8_lossy(&output.stdout),
+        edit_dir,
+    ))
+}
+
+/// Leave out the processes holding a file of an active session, whose edit is still going on
+pub fn without_active_sessions(mut processes: Vec<EditProcess>, active_files: &[PathBuf]) -> Vec<EditProcess> {
+    processes.retain(|p| !active_files.iter().any(|f| f.as_os_str() == p.file.as_str()));
+    processes
+}
+
+/// Check that `pid` is one of `processes` and not the editor of an active session
+fn check_killable(pid: u32, processes: Vec<EditProcess>, active_files: &[PathBuf]) -> Result<(), String> {
+    if !processes.iter().any(|p| p.pid == pid) {
+        return Err(format!("Process {} is not an ovim edit process", pid));
+    }
+    if !without_active_sessions(processes, active_files).iter().any(|p| p.pid == pid) {
+        return Err(format!("Process {} is editing in an active session", pid));
+    }
+    Ok(())
+}
+
+/// Terminate a process, but only if it has one of ovim's edit files open and no active
+/// session owns it
+pub fn kill_edit_process(pid: u32, edit_dir: &Path, active_files: &[PathBuf]) -> Result<(), String> {
+    check_killable(pid, list_edit_processes(edit_dir)?, active_files)?;
+
+    if unsafe { libc::kill(pid as i32, libc::SIGTERM) } != 0 {
+        return Err(format!(
+            "Failed to kill process {}: {}",
+            pid,
+            std::io::Error::last_os_error()
+        ));
+    }
+    log::info!("Killed stray edit process {}", pid);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_edit_file() {
+        let dir = Path::new("/Users/me/Library/Caches/ovim");
+        assert!(is_edit_file(&dir.join("edit_1234.txt"), dir));
+        assert!(is_edit_file(&dir.join("nvim_1234.sock"), dir));
+        assert!(!is_edit_file(&dir.join("sessions.json"), dir));
+        assert!(!is_edit_file(Path::new("/tmp/edit_1234.txt"), dir));
+        assert!(!is_edit_file(&dir.join("sub").join("edit_1234.txt"), dir));
+    }
+
+    #[test]
+    fn test_active_sessions_are_left_out() {
+        let dir = Path::new("/Users/me/Library/Caches/ovim");
+        let process = |pid, file: &str| EditProcess {
+            pid,
+            command: "nvim".to_string(),
+            file: dir.join(file).to_string_lossy().into_owned(),
+        };
+        let processes = vec![process(100, "nvim_a.sock"), process(200, "nvim_b.sock")];
+        let active_files = [dir.join("edit_a.txt"), dir.join("nvim_a.sock")];
+
+        assert_eq!(
+            without_active_sessions(processes.clone(), &active_files),
+            vec![process(200, "nvim_b.sock")]
+        );
+        // Killing goes by the same ownership
+        assert_eq!(
+            check_killable(100, processes.clone(), &active_files),
+            Err("Process 100 is editing in an active session".to_string())
+        );
+        assert_eq!(check_killable(200, processes.clone(), &active_files), Ok(()));
+        assert_eq!(
+            check_killable(300, processes, &active_files),
+            Err("Process 300 is not an ovim edit process".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_edit_processes_keeps_only_edit_files() {
+        let dir = Path::new("/Users/me/Library/Caches/ovim");
+        let output = "p100\ncnvim\nfcwd\nn/Users/me\nf3\nn/Users/me/Library/Caches/ovim/edit_a.txt\n\
+                      f4\nn/Users/me/Library/Caches/ovim/nvim_a.sock\n\
+                      p200\ncQuick Look\nf5\nn/Users/me/Library/Caches/ovim/sessions.json\n\
+                      p300\ncvim\nf3\nn/Users/me/Library/Caches/ovim/edit_b.txt\n";
+
+        assert_eq!(
+            parse_edit_processes(output, dir),
+            vec![
+                EditProcess {
+                    pid: 100,
+                    command: "nvim".to_string(),
+                    file: "/Users/me/Library/Caches/ovim/edit_a.txt".to_string(),
+                },
+                EditProcess {
+                    pid: 300,
+                    command: "vim".to_string(),
+                    file: "/Users/me/Library/Caches/ovim/edit_b.txt".to_string(),
+                },
+            ]
+        );
+    }
+}
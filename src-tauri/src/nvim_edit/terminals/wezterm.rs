@@ -5,8 +5,9 @@ use std::process::Command;
 
 use super::applescript_utils::set_window_size;
 use super::process_utils::{resolve_command_path, resolve_terminal_path};
-use super::{SpawnInfo, TerminalSpawner, TerminalType, WindowGeometry};
-use crate::config::NvimEditSettings;
+use super::{apply_editor_env, SpawnInfo, TerminalSpawner, TerminalType, WindowGeometry};
+use crate::config::{CursorPosition, NvimEditSettings};
+use crate::nvim_edit::EditError;
 
 pub struct WezTermSpawner;
 
@@ -21,10 +22,11 @@ impl TerminalSpawner for WezTermSpawner {
         file_path: &str,
         geometry: Option<WindowGeometry>,
         socket_path: Option<&Path>,
-    ) -> Result<SpawnInfo, String> {
+        cursor: Option<CursorPosition>,
+    ) -> Result<SpawnInfo, EditError> {
         // Get editor path and args from settings
         let editor_path = settings.editor_path();
-        let editor_args = settings.editor_args();
+        let editor_args = super::editor_args(settings, cursor);
 
         // Build socket args for nvim RPC if socket_path provided and using nvim
         let socket_args: Vec<String> = if let Some(socket) = socket_path {
@@ -47,6 +49,7 @@ impl TerminalSpawner for WezTermSpawner {
         log::info!("Resolved terminal path: {} -> {}", terminal_cmd, resolved_terminal);
 
         let mut cmd = Command::new(&resolved_terminal);
+        apply_editor_env(&mut cmd, settings);
 
         // Use --always-new-process so wezterm blocks until the command exits.
         // WezTerm only supports --position for window placement (no --width/--height)
@@ -73,7 +76,7 @@ impl TerminalSpawner for WezTermSpawner {
 
         let child = cmd
             .spawn()
-            .map_err(|e| format!("Failed to spawn wezterm: {}", e))?;
+            .map_err(|e| EditError::spawn_failed("wezterm", e))?;
 
         // Get the wezterm process PID - with --always-new-process, the wezterm
         // process itself will block until editor exits, so we can track it directly
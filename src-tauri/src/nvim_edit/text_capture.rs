@@ -0,0 +1,109 @@
+//! Choosing between the accessibility text of the focused field and a clipboard copy
+//!
+//! Some apps (Slack, Electron) return only the visible part of a long text area from
+//! `AXValue`, so text that looks cut off is copied via the clipboard instead.
+
+use crate::config::TextCapture;
+
+/// Whether the accessibility text looks like only part of the field's text
+/// `char_count` is the field's full length in UTF-16 units, if the app reports it.
+/// Without it, text in a scroll view that ends in the middle of a word counts as cut off
+pub fn looks_truncated(text: &str, char_count: Option<usize>, scrollable: bool) -> bool {
+    match char_count {
+        Some(count) => count > text.encode_utf16().count(),
+        None => scrollable && ends_mid_word(text),
+    }
+}
+
+fn ends_mid_word(text: &str) -> bool {
+    text.chars().last().is_some_and(char::is_alphanumeric)
+}
+
+/// What the clipboard is set to before copying the field, to tell whether the copy happened
+/// Plain text that's new each time: NULs don't survive pbcopy and turn up in binary contents
+pub fn empty_marker(nonce: u128) -> String {
+    format!("__OVIM_EMPTY_MARKER_{:x}__", nonce)
+}
+
+/// The field's text from the clipboard after select all and copy, empty if the field was
+/// `copied` is None when the clipboard never changed from `marker`, as copying nothing
+/// leaves it alone
+pub fn copied_field_text(copied: Option<String>, marker: &str) -> String {
+    copied
+        .filter(|text| text.trim_matches('\0') != marker)
+        .unwrap_or_default()
+}
+
+/// Whether to open the editor for the field's `text`, with `keep_window_open_on_empty`
+/// A field of only whitespace counts as empty
+pub fn opens_editor(keep_window_open_on_empty: bool, text: &str) -> bool {
+    keep_window_open_on_empty || !text.trim().is_empty()
+}
+
+/// Whether to copy the text via the clipboard rather than use the accessibility `text`
+/// `truncated` is only checked in auto mode, as it queries the focused element
+pub fn prefers_clipboard(capture: TextCapture, text: &str, truncated: impl FnOnce() -> bool) -> bool {
+    match capture {
+        TextCapture::Clipboard => true,
+        TextCapture::Accessibility => text.is_empty(),
+        TextCapture::Auto => text.is_empty() || truncated(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_character_count_decides() {
+        assert!(looks_truncated("only the visible li", Some(400), false));
+        assert!(!looks_truncated("the whole text", Some(14), true));
+        // UTF-16 units, so an emoji counts twice
+        assert!(!looks_truncated("hi 👋", Some(5), false));
+    }
+
+    #[test]
+    fn test_cut_off_in_scroll_view() {
+        assert!(looks_truncated("a long message that sto", None, true));
+        assert!(!looks_truncated("a long message that stops.", None, true));
+        assert!(!looks_truncated("ends with a line break\n", None, true));
+        // Outside a scroll view the field shows all of its text
+        assert!(!looks_truncated("a short fiel", None, false));
+        assert!(!looks_truncated("", None, true));
+    }
+
+    #[test]
+    fn test_empty_field_detection() {
+        let marker = empty_marker(0xbeef);
+        assert_eq!(marker, "__OVIM_EMPTY_MARKER_beef__");
+        assert_ne!(empty_marker(1), empty_marker(2));
+        assert_eq!(copied_field_text(Some("hello".to_string()), &marker), "hello");
+        // The clipboard never changed, or still holds the marker
+        assert_eq!(copied_field_text(None, &marker), "");
+        assert_eq!(copied_field_text(Some(marker.clone()), &marker), "");
+        assert_eq!(copied_field_text(Some(format!("\0{}\0", marker)), &marker), "");
+        // NULs in copied content don't make it look empty
+        assert_eq!(copied_field_text(Some("a\0b".to_string()), &marker), "a\0b");
+        assert_eq!(copied_field_text(Some("\0".to_string()), &marker), "\0");
+    }
+
+    #[test]
+    fn test_empty_field_policy() {
+        assert!(opens_editor(true, ""));
+        assert!(opens_editor(true, "text"));
+        assert!(opens_editor(false, "text"));
+        assert!(!opens_editor(false, ""));
+        assert!(!opens_editor(false, " \n\t"));
+    }
+
+    #[test]
+    fn test_capture_preference() {
+        assert!(prefers_clipboard(TextCapture::Auto, "", || false));
+        assert!(prefers_clipboard(TextCapture::Auto, "text", || true));
+        assert!(!prefers_clipboard(TextCapture::Auto, "text", || false));
+
+        assert!(!prefers_clipboard(TextCapture::Accessibility, "text", || panic!("not checked")));
+        assert!(prefers_clipboard(TextCapture::Accessibility, "", || panic!("not checked")));
+        assert!(prefers_clipboard(TextCapture::Clipboard, "text", || panic!("not checked")));
+    }
+}
@@ -0,0 +1,275 @@
+//! Where the focused field's text is read from: accessibility, a clipboard copy, or the
+//! nvim buffer of a live-synced edit
+//!
+//! A `TextReader` tries the sources that suit the focused app in turn and keeps the first
+//! text it gets, so one command reads the field once however many times it asks.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::TextCapture;
+
+use super::accessibility::{self, TextSelection};
+
+/// A way of reading the focused field's text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextSourceKind {
+    /// `AXValue` and `AXSelectedTextRange`, with the caret
+    Accessibility,
+    /// Select all and copy, leaving the caret at the end
+    Clipboard,
+    /// The buffer of a live-synced nvim edit of the field
+    NvimRpc,
+}
+
+impl TextSourceKind {
+    /// Whether the source tells where the caret is
+    pub fn knows_caret(self) -> bool {
+        self == Self::Accessibility
+    }
+}
+
+/// Something that can read the focused field's text
+pub trait TextSource {
+    fn kind(&self) -> TextSourceKind;
+    /// The field's text and selection, None if this source can't read it
+    fn read(&self) -> Option<TextSelection>;
+}
+
+/// Reads the field through accessibility
+pub struct AccessibilitySource;
+
+impl TextSource for AccessibilitySource {
+    fn kind(&self) -> TextSourceKind {
+        TextSourceKind::Accessibility
+    }
+
+    fn read(&self) -> Option<TextSelection> {
+        accessibility::get_focused_text_selection()
+    }
+}
+
+/// Copies the field's text through the clipboard, which is restored after
+pub struct ClipboardSource {
+    pub delay: Duration,
+    /// Select all and copy, `capture_text_via_clipboard` outside tests
+    copy: fn(Duration) -> Option<String>,
+}
+
+impl ClipboardSource {
+    pub fn new(delay: Duration) -> Self {
+        Self { delay, copy: super::capture_text_via_clipboard }
+    }
+}
+
+impl TextSource for ClipboardSource {
+    fn kind(&self) -> TextSourceKind {
+        TextSourceKind::Clipboard
+    }
+
+    fn read(&self) -> Option<TextSelection> {
+        // An empty field copies nothing, which can't be told from a copy that didn't happen,
+        // so the next source gets to read it. The copy ends with Right, leaving the caret at the end
+        let text = (self.copy)(self.delay).filter(|text| !text.is_empty())?;
+        Some(caret_at_end(text))
+    }
+}
+
+/// Reads the buffer of a live-synced edit, when the edited app is frontmost
+pub struct NvimRpcSource;
+
+/// Text of the buffer a live edit syncs to an app, by the app's pid
+static LIVE_BUFFER: Mutex<Option<(i32, String)>> = Mutex::new(None);
+
+/// Note the text live sync just wrote to the app with this pid
+pub(super) fn set_live_buffer(app_pid: i32, text: String) {
+    *LIVE_BUFFER.lock().unwrap() = Some((app_pid, text));
+}
+
+/// Forget a live edit's buffer once it ends
+pub(super) fn clear_live_buffer() {
+    *LIVE_BUFFER.lock().unwrap() = None;
+}
+
+impl NvimRpcSource {
+    /// Whether a live edit is syncing to the frontmost app
+    fn is_live() -> bool {
+        let Some(pid) = LIVE_BUFFER.lock().unwrap().as_ref().map(|(pid, _)| *pid) else {
+            return false;
+        };
+        accessibility::capture_focus_context().is_some_and(|context| context.app_pid == pid)
+    }
+}
+
+impl TextSource for NvimRpcSource {
+    fn kind(&self) -> TextSourceKind {
+        TextSourceKind::NvimRpc
+    }
+
+    fn read(&self) -> Option<TextSelection> {
+        if !Self::is_live() {
+            return None;
+        }
+        let text = LIVE_BUFFER.lock().unwrap().as_ref()?.1.clone();
+        Some(caret_at_end(text))
+    }
+}
+
+fn caret_at_end(text: String) -> TextSelection {
+    let start = text.chars().count();
+    TextSelection { text, start, length: 0 }
+}
+
+/// Sources to try for the focused field, best first
+/// Only accessibility knows the caret, so it's the one source when `needs_caret`
+pub fn source_order(capture: TextCapture, live_edit: bool, needs_caret: bool) -> Vec<TextSourceKind> {
+    let mut order = Vec::new();
+    if live_edit {
+        order.push(TextSourceKind::NvimRpc);
+    }
+    match capture {
+        TextCapture::Clipboard => order.extend([TextSourceKind::Clipboard, TextSourceKind::Accessibility]),
+        TextCapture::Auto | TextCapture::Accessibility => {
+            order.extend([TextSourceKind::Accessibility, TextSourceKind::Clipboard])
+        }
+    }
+    order.retain(|kind| kind.knows_caret() || !needs_caret);
+    order
+}
+
+/// Reads the focused field's text from the first source that has it, once
+pub struct TextReader {
+    sources: Vec<Box<dyn TextSource>>,
+    read: Option<Option<TextSelection>>,
+}
+
+impl TextReader {
+    pub fn new(sources: Vec<Box<dyn TextSource>>) -> Self {
+        Self { sources, read: None }
+    }
+
+    /// Reader for commands that work from the caret, e.g. motions and text objects
+    pub fn with_caret() -> Self {
+        Self::new(vec![Box::new(AccessibilitySource)])
+    }
+
+    /// Reader for commands on the whole field, trying the sources that suit the focused app
+    pub fn for_field(capture: TextCapture, clipboard_delay: Duration) -> Self {
+        let order = source_order(capture, NvimRpcSource::is_live(), false);
+        let sources = order
+            .into_iter()
+            .map(|kind| -> Box<dyn TextSource> {
+                match kind {
+                    TextSourceKind::Accessibility => Box::new(AccessibilitySource),
+                    TextSourceKind::Clipboard => Box::new(ClipboardSource::new(clipboard_delay)),
+                    TextSourceKind::NvimRpc => Box::new(NvimRpcSource),
+                }
+            })
+            .collect();
+        Self::new(sources)
+    }
+
+    /// The field's text and selection, read on the first call
+    pub fn read(&mut self) -> Option<&TextSelection> {
+        let sources = &self.sources;
+        self.read
+            .get_or_insert_with(|| {
+                sources.iter().find_map(|source| {
+                    let text = source.read()?;
+                    log::debug!("Read {} chars from {:?}", text.text.len(), source.kind());
+                    Some(text)
+                })
+            })
+            .as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct FakeSource {
+        kind: TextSourceKind,
+        text: Option<&'static str>,
+        reads: Rc<Cell<u32>>,
+    }
+
+    impl TextSource for FakeSource {
+        fn kind(&self) -> TextSourceKind {
+            self.kind
+        }
+
+        fn read(&self) -> Option<TextSelection> {
+            self.reads.set(self.reads.get() + 1);
+            self.text.map(|text| caret_at_end(text.to_string()))
+        }
+    }
+
+    fn fake(kind: TextSourceKind, text: Option<&'static str>) -> (Box<dyn TextSource>, Rc<Cell<u32>>) {
+        let reads = Rc::new(Cell::new(0));
+        let source = FakeSource { kind, text, reads: Rc::clone(&reads) };
+        (Box::new(source), reads)
+    }
+
+    #[test]
+    fn test_source_order() {
+        use TextSourceKind::*;
+        assert_eq!(source_order(TextCapture::Auto, false, false), vec![Accessibility, Clipboard]);
+        assert_eq!(source_order(TextCapture::Clipboard, false, false), vec![Clipboard, Accessibility]);
+        assert_eq!(source_order(TextCapture::Auto, true, false), vec![NvimRpc, Accessibility, Clipboard]);
+        // Motions need the caret, which only accessibility knows
+        assert_eq!(source_order(TextCapture::Clipboard, true, true), vec![Accessibility]);
+    }
+
+    #[test]
+    fn test_falls_back_to_next_source() {
+        let (accessibility, _) = fake(TextSourceKind::Accessibility, None);
+        let (clipboard, _) = fake(TextSourceKind::Clipboard, Some("copied"));
+        let mut reader = TextReader::new(vec![accessibility, clipboard]);
+        assert_eq!(reader.read().map(|t| t.text.as_str()), Some("copied"));
+    }
+
+    #[test]
+    fn test_empty_copy_falls_back_to_accessibility() {
+        // As `for_field` orders them when the clipboard is preferred
+        let sources = source_order(TextCapture::Clipboard, false, false)
+            .into_iter()
+            .map(|kind| match kind {
+                TextSourceKind::Clipboard => {
+                    Box::new(ClipboardSource { delay: Duration::ZERO, copy: |_| Some(String::new()) })
+                        as Box<dyn TextSource>
+                }
+                kind => fake(kind, Some("field text")).0,
+            })
+            .collect();
+        let mut reader = TextReader::new(sources);
+        assert_eq!(reader.read().map(|t| t.text.as_str()), Some("field text"));
+
+        let copied = ClipboardSource { delay: Duration::ZERO, copy: |_| Some("copied".to_string()) };
+        let (accessibility, reads) = fake(TextSourceKind::Accessibility, Some("field text"));
+        let mut reader = TextReader::new(vec![Box::new(copied), accessibility]);
+        assert_eq!(reader.read().map(|t| t.text.as_str()), Some("copied"));
+        assert_eq!(reads.get(), 0);
+    }
+
+    #[test]
+    fn test_reads_once() {
+        let (accessibility, first_reads) = fake(TextSourceKind::Accessibility, Some("text"));
+        let (clipboard, second_reads) = fake(TextSourceKind::Clipboard, Some("other"));
+        let mut reader = TextReader::new(vec![accessibility, clipboard]);
+        for _ in 0..3 {
+            assert_eq!(reader.read().map(|t| t.text.as_str()), Some("text"));
+        }
+        assert_eq!(first_reads.get(), 1);
+        assert_eq!(second_reads.get(), 0);
+
+        // No text is remembered too
+        let (empty, reads) = fake(TextSourceKind::Accessibility, None);
+        let mut reader = TextReader::new(vec![empty]);
+        assert!(reader.read().is_none());
+        assert!(reader.read().is_none());
+        assert_eq!(reads.get(), 1);
+    }
+}
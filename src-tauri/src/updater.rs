@@ -1,10 +1,115 @@
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Url};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
 use tauri_plugin_updater::UpdaterExt;
 
 use crate::config::Settings;
 
+/// Minimum time between automatic update checks
+const UPDATE_CHECK_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+/// Result of checking the release feed, without downloading anything
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct UpdateCheck {
+    pub available: bool,
+    pub version: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Check the release feed for a newer version
+/// An empty `feed_url` uses the endpoint from tauri.conf.json
+pub async fn check_for_updates(app: &AppHandle, feed_url: &str) -> Result<UpdateCheck, String> {
+    let mut builder = app.updater_builder();
+    if !feed_url.is_empty() {
+        let url = Url::parse(feed_url).map_err(|e| format!("Invalid update feed URL: {}", e))?;
+        builder = builder.endpoints(vec![url]).map_err(|e| e.to_string())?;
+    }
+    let updater = builder.build().map_err(|e| e.to_string())?;
+
+    let update = updater.check().await.map_err(|e| e.to_string())?;
+    Ok(UpdateCheck {
+        available: update.is_some(),
+        version: update.as_ref().map(|u| u.version.clone()),
+        notes: update.and_then(|u| u.body),
+    })
+}
+
+/// Location of the file holding the last automatic check time
+fn last_check_path() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join("ovim").join("last_update_check"))
+}
+
+/// Last automatic check time in seconds since the epoch
+fn read_last_check(path: &Path) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn write_last_check(path: &Path, now: u64) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(path, now.to_string()).map_err(|e| e.to_string())
+}
+
+/// Whether an automatic check is due, at most once per `UPDATE_CHECK_INTERVAL_SECS`
+/// A last check in the future (clock changed) doesn't block checks
+fn check_due(last_check: Option<u64>, now: u64) -> bool {
+    match last_check {
+        Some(last) if last <= now => now - last >= UPDATE_CHECK_INTERVAL_SECS,
+        _ => true,
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// On startup, check for updates once a day when `auto_check_updates` is on,
+/// and show a dialog with the release notes if one is available
+pub fn start_update_notifier(app: AppHandle, settings: Arc<std::sync::Mutex<Settings>>) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+
+        let (enabled, feed_url) = match settings.lock() {
+            Ok(s) => (s.auto_check_updates, s.update_feed_url.clone()),
+            Err(_) => return,
+        };
+        let Some(path) = last_check_path() else {
+            return;
+        };
+        let now = now_secs();
+        if !enabled || !check_due(read_last_check(&path), now) {
+            return;
+        }
+        if let Err(e) = write_last_check(&path, now) {
+            log::error!("Failed to save update check time: {}", e);
+        }
+
+        match check_for_updates(&app, &feed_url).await {
+            Ok(UpdateCheck { available: true, version: Some(version), notes }) => {
+                log::info!("Update available: v{}", version);
+                let mut message = format!("ovim v{} is available.", version);
+                if let Some(notes) = notes.filter(|n| !n.is_empty()) {
+                    message.push_str("\n\n");
+                    message.push_str(&notes);
+                }
+                app.dialog()
+                    .message(message)
+                    .title("Update available")
+                    .kind(MessageDialogKind::Info)
+                    .show(|_| {});
+            }
+            Ok(_) => log::info!("No update available"),
+            Err(e) => log::error!("Update check error: {}", e),
+        }
+    });
+}
+
 /// Check for updates and install if available
 /// Returns the new version string if an update was installed
 pub async fn check_and_install_update(app: &AppHandle) -> Result<Option<String>, String> {
@@ -84,3 +189,33 @@ pub fn start_update_checker(app: AppHandle, settings: Arc<std::sync::Mutex<Setti
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = UPDATE_CHECK_INTERVAL_SECS;
+
+    #[test]
+    fn test_check_due() {
+        let now = 10 * DAY;
+        assert!(check_due(None, now));
+        assert!(!check_due(Some(now), now));
+        assert!(!check_due(Some(now - DAY + 1), now));
+        assert!(check_due(Some(now - DAY), now));
+        // A check time ahead of the clock doesn't block checks
+        assert!(check_due(Some(now + 60), now));
+    }
+
+    #[test]
+    fn test_last_check_round_trip() {
+        let dir = std::env::temp_dir().join(format!("ovim-update-check-{}", std::process::id()));
+        let path = dir.join("last_update_check");
+        assert_eq!(read_last_check(&path), None);
+        write_last_check(&path, 1234).unwrap();
+        assert_eq!(read_last_check(&path), Some(1234));
+        std::fs::write(&path, "garbage").unwrap();
+        assert_eq!(read_last_check(&path), None);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,7 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Settings, TabInserts, TextCapture, WordMotionMode};
 use crate::keyboard;
+use crate::nvim_edit::text_source::TextReader;
+use crate::nvim_edit::{accessibility, clipboard};
+
+use super::motion::{self, Motion};
+use super::scroll::{self, ScrollTo};
+use super::{custom_command, line_motion, reflow, text_edit, text_object, word_motion};
 
 /// Vim commands that can be executed
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum VimCommand {
     // Basic motions
     MoveLeft,
@@ -14,10 +24,12 @@ pub enum VimCommand {
     WordEnd,
     WordBackward,
     WordEndBackward, // ge
+    WordEndBackwardBig, // gE
 
     // Line motions
     LineStart,
     LineEnd,
+    FirstNonBlank, // smart-home `0`
 
     // Paragraph motions
     ParagraphUp,   // {
@@ -26,6 +38,15 @@ pub enum VimCommand {
     // Document motions
     DocumentStart,
     DocumentEnd,
+    GoToLine,   // {count}G, {count}gg
+    GoToColumn, // {count}|
+
+    // Scrolling the caret into place
+    ScrollCaretCenter, // zz
+    ScrollCaretTop,    // zt
+    ScrollCaretBottom, // zb
+    ScrollLineDown,    // Ctrl+E
+    ScrollLineUp,      // Ctrl+Y
 
     // Page motions
     PageUp,
@@ -45,6 +66,9 @@ pub enum VimCommand {
     // Operations
     DeleteChar,
     DeleteCharBefore, // X
+    DeleteWordBackward, // Ctrl+W in insert mode
+    DeleteToLineStart,  // Ctrl+U in insert mode
+    InsertLiteralTab,   // Ctrl+I in insert mode
     DeleteLine,
     DeleteToLineEnd,  // D
     YankLine,
@@ -55,6 +79,8 @@ pub enum VimCommand {
     // Text objects
     InnerWord, // iw - select word
     AroundWord, // aw - select word + space
+    InnerBacktick,  // i` - inline code or a fenced code block
+    AroundBacktick, // a` - with the backticks or fence lines
 
     // Indent
     IndentLine,  // >>
@@ -68,6 +94,13 @@ pub enum VimCommand {
     Undo,
     Redo,
 
+    // Tabs
+    NextTab,  // gt
+    PrevTab,  // gT
+    GoToTab,  // {count}gt
+
+    // Repeating
+    RepeatMotion, // the last motion again, resolved by the state machine
 }
 
 impl VimCommand {
@@ -82,10 +115,14 @@ impl VimCommand {
 
             // Word motions
             Self::WordForward | Self::WordEnd => keyboard::word_forward(count, select),
-            Self::WordBackward | Self::WordEndBackward => keyboard::word_backward(count, select),
+            Self::WordBackward => keyboard::word_backward(count, select),
+            Self::WordEndBackward => keyboard::word_end_backward(count, select),
+            Self::WordEndBackwardBig => keyboard::word_end_backward_big(count, select),
 
             // Line motions
             Self::LineStart => keyboard::line_start(select),
+            // Without the field's text this can only go to the line start
+            Self::FirstNonBlank => keyboard::line_start(select),
             Self::LineEnd => keyboard::line_end(select),
 
             // Paragraph motions
@@ -95,6 +132,21 @@ impl VimCommand {
             // Document motions
             Self::DocumentStart => keyboard::document_start(select),
             Self::DocumentEnd => keyboard::document_end(select),
+            Self::GoToLine => {
+                keyboard::document_start(select)?;
+                keyboard::cursor_down(count.saturating_sub(1), select)
+            }
+            Self::GoToColumn => {
+                keyboard::line_start(select)?;
+                keyboard::cursor_right(count.saturating_sub(1), select)
+            }
+
+            // Scrolling
+            Self::ScrollCaretCenter => scroll::scroll_caret(ScrollTo::Center),
+            Self::ScrollCaretTop => scroll::scroll_caret(ScrollTo::Top),
+            Self::ScrollCaretBottom => scroll::scroll_caret(ScrollTo::Bottom),
+            Self::ScrollLineDown => scroll::scroll_lines(count as i32),
+            Self::ScrollLineUp => scroll::scroll_lines(-(count as i32)),
 
             // Page motions
             Self::PageUp | Self::HalfPageUp => keyboard::page_up(select),
@@ -126,6 +178,15 @@ impl VimCommand {
                 }
                 Ok(())
             }
+            Self::DeleteWordBackward | Self::DeleteToLineStart | Self::InsertLiteralTab => {
+                let keystrokes = self.insert_keystrokes().unwrap_or_default();
+                for _ in 0..count {
+                    for &(keycode, modifiers) in &keystrokes {
+                        keyboard::inject_key_press(keycode, modifiers)?;
+                    }
+                }
+                Ok(())
+            }
             Self::DeleteLine => {
                 keyboard::line_start(false)?;
                 keyboard::line_end(true)?;
@@ -159,6 +220,12 @@ impl VimCommand {
             // Text objects
             Self::InnerWord => keyboard::select_inner_word(),
             Self::AroundWord => keyboard::select_around_word(),
+            Self::InnerBacktick => {
+                select_text_object(|chars, caret| text_object::backtick_range(chars, caret, false))
+            }
+            Self::AroundBacktick => {
+                select_text_object(|chars, caret| text_object::backtick_range(chars, caret, true))
+            }
 
             // Indent
             Self::IndentLine => {
@@ -180,25 +247,462 @@ impl VimCommand {
             // Undo/Redo
             Self::Undo => keyboard::undo(),
             Self::Redo => keyboard::redo(),
+
+            // Tabs
+            Self::NextTab => {
+                for _ in 0..count {
+                    keyboard::next_tab()?;
+                }
+                Ok(())
+            }
+            Self::PrevTab => {
+                for _ in 0..count {
+                    keyboard::prev_tab()?;
+                }
+                Ok(())
+            }
+            Self::GoToTab => keyboard::go_to_tab(count),
+
+            // The state machine swaps this for the motion it repeats
+            Self::RepeatMotion => Ok(()),
+        }
+    }
+}
+
+/// Settings that change how motions are executed
+#[derive(Debug, Clone, Copy)]
+pub struct MotionOptions {
+    pub word_motion_mode: WordMotionMode,
+    /// Place the caret through the accessibility range for line and document motions
+    pub prefer_accessibility_motions: bool,
+    /// Delete through the accessibility value for `x`, `X`, `dd` and `D`
+    pub prefer_accessibility_edits: bool,
+    /// Skip commands the field's text shows can't do anything, which would only beep
+    pub suppress_invalid_ops: bool,
+    pub tab_inserts: TabInserts,
+    /// Column `gq` and `gw` wrap lines at
+    pub text_width: usize,
+    /// Yanks and deletes stay on the clipboard, rather than only in the unnamed register
+    pub yank_to_system_clipboard: bool,
+    /// How commands on the whole field read its text, as the Edit Popup does
+    pub text_capture: TextCapture,
+    /// Wait for a clipboard copy of the field's text
+    pub clipboard_delay: std::time::Duration,
+    /// Pause between injected key events, see `injection_pacing_ms`
+    pub injection_pacing: std::time::Duration,
+}
+
+impl Default for MotionOptions {
+    fn default() -> Self {
+        Self::from_settings(&Settings::default())
+    }
+}
+
+impl MotionOptions {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            word_motion_mode: settings.word_motion_mode,
+            prefer_accessibility_motions: settings.prefer_accessibility_motions,
+            prefer_accessibility_edits: settings.prefer_accessibility_edits,
+            suppress_invalid_ops: settings.suppress_invalid_ops,
+            tab_inserts: settings.tab_inserts,
+            text_width: settings.text_width,
+            yank_to_system_clipboard: settings.yank_to_system_clipboard,
+            text_capture: settings.nvim_edit.text_capture,
+            clipboard_delay: std::time::Duration::from_millis(settings.nvim_edit.clipboard_delay_ms),
+            injection_pacing: std::time::Duration::from_millis(settings.injection_pacing_ms),
         }
     }
 }
 
-/// Pending operator (d, y, c)
+impl VimCommand {
+    /// Execute the command, computing word motions from the field's text in vim-accurate mode
+    /// Visual selections use native motions, as the selection range doesn't say which end moves
+    pub fn execute_with_mode(&self, count: u32, select: bool, options: MotionOptions) -> Result<(), String> {
+        if let (Self::IndentLine, TabInserts::Spaces(width)) = (self, options.tab_inserts) {
+            for _ in 0..count {
+                keyboard::indent_line_with_spaces(width)?;
+            }
+            return Ok(());
+        }
+        // The checks and motions below share one read of the field
+        let mut text = TextReader::with_caret();
+        if options.suppress_invalid_ops && !select {
+            if let Some((chars, caret)) = focused_chars_and_caret(&mut text) {
+                if !self.can_apply_at(&chars, caret) {
+                    log::debug!("{:?} can't apply at offset {}, skipping", self, caret);
+                    return Ok(());
+                }
+            }
+        }
+        if options.prefer_accessibility_edits && !select {
+            match self.delete_through_accessibility(&mut text, count) {
+                Some(Ok(())) => return Ok(()),
+                Some(Err(e)) => log::debug!("Accessibility edit failed, injecting keys: {}", e),
+                None => {}
+            }
+        }
+        if options.word_motion_mode == WordMotionMode::VimAccurate && self.is_word_motion() && !select {
+            if let Some((chars, caret)) = focused_chars_and_caret(&mut text) {
+                if let Some(target) = word_motion::word_motion_target(*self, &chars, caret, count) {
+                    return move_caret(caret, target, false);
+                }
+            }
+            log::debug!("Vim-accurate word motion unavailable, using native motion");
+        }
+        if options.prefer_accessibility_motions && !select {
+            match self.set_caret_from_text(&mut text, count) {
+                Some(Ok(())) => return Ok(()),
+                Some(Err(e)) => log::debug!("Accessibility motion failed, using native motion: {}", e),
+                None => {}
+            }
+        }
+        // Large relative jumps like `8j` land at once instead of one arrow press per line
+        if count > 1 && !select {
+            match self.jump_lines(&mut text, count) {
+                Some(Ok(())) => return Ok(()),
+                Some(Err(e)) => log::debug!("Line jump failed, using arrow keys: {}", e),
+                None => {}
+            }
+        }
+        if *self == Self::FirstNonBlank && !select {
+            if let Some((chars, caret)) = focused_chars_and_caret(&mut text) {
+                return move_caret(caret, line_motion::first_non_blank(&chars, caret), false);
+            }
+        }
+        self.execute(count, select)
+    }
+
+    /// Place the caret at a line or document motion's target through the accessibility range
+    /// Returns None if this isn't such a motion or the field doesn't expose its text
+    fn set_caret_from_text(&self, text: &mut TextReader, count: u32) -> Option<Result<(), String>> {
+        let (chars, caret) = focused_chars_and_caret(text)?;
+        let target = line_motion::line_motion_target(*self, &chars, caret, count)?;
+        let element = accessibility::capture_focused_element()?;
+        Some(accessibility::set_selected_range(&element, target, 0))
+    }
+
+    /// Delete by replacing the deleted range of the field's text, leaving the caret at its start
+    /// Returns None if this isn't a delete or the field doesn't expose or take its text
+    fn delete_through_accessibility(&self, text: &mut TextReader, count: u32) -> Option<Result<(), String>> {
+        let (chars, caret) = focused_chars_and_caret(text)?;
+        let range = text_edit::delete_range(*self, &chars, caret, count)?;
+        if range.is_empty() {
+            return Some(Ok(()));
+        }
+        let element = accessibility::capture_focused_element()?;
+        // Only the range is replaced, so an action still editing the field isn't undone
+        if !accessibility::is_selected_text_settable(&element) {
+            return None;
+        }
+        if text_edit::cuts(*self) {
+            let deleted: String = chars[range.clone()].iter().collect();
+            if let Err(e) = clipboard::write_clipboard(&deleted) {
+                return Some(Err(e));
+            }
+        }
+        Some(accessibility::replace_range(&element, range.start, range.len(), ""))
+    }
+
+    /// `{count}j`/`{count}k`: place the caret `count` lines away through the accessibility range
+    /// Returns None for other commands or if the field doesn't expose its text
+    fn jump_lines(&self, text: &mut TextReader, count: u32) -> Option<Result<(), String>> {
+        let step: fn(&[char], usize, u32) -> usize = match self {
+            Self::MoveDown => motion::line_down,
+            Self::MoveUp => motion::line_up,
+            _ => return None,
+        };
+        let (chars, caret) = focused_chars_and_caret(text)?;
+        let target = step(&chars, caret, count);
+        let element = accessibility::capture_focused_element()?;
+        Some(accessibility::set_selected_range(&element, target, 0))
+    }
+
+    /// Whether the command can do anything with the caret at `caret` in `chars`
+    /// The injected keys beep when they can't: ForwardDelete at the end of the field,
+    /// Delete at its start and arrows at either end. `x` and `X` also stay on their line
+    fn can_apply_at(&self, chars: &[char], caret: usize) -> bool {
+        match self {
+            Self::DeleteChar | Self::SubstituteChar => chars.get(caret).is_some_and(|&c| c != '\n'),
+            Self::DeleteCharBefore => caret > line_motion::line_start(chars, caret),
+            Self::MoveLeft => caret > 0,
+            Self::MoveRight => caret < chars.len(),
+            _ => true,
+        }
+    }
+
+    /// Whether the command switches to insert mode, like `A` or `cc`
+    pub fn enters_insert(&self) -> bool {
+        matches!(
+            self,
+            Self::InsertAtLineStart
+                | Self::AppendAfterCursor
+                | Self::AppendAtLineEnd
+                | Self::OpenLineBelow
+                | Self::OpenLineAbove
+                | Self::SubstituteChar
+                | Self::SubstituteLine
+                | Self::ChangeLine
+                | Self::ChangeToLineEnd
+        )
+    }
+
+    /// Whether the command only moves the caret, so `RepeatMotion` can repeat it
+    pub fn is_motion(&self) -> bool {
+        matches!(
+            self,
+            Self::MoveLeft
+                | Self::MoveRight
+                | Self::MoveUp
+                | Self::MoveDown
+                | Self::LineStart
+                | Self::LineEnd
+                | Self::FirstNonBlank
+                | Self::ParagraphUp
+                | Self::ParagraphDown
+                | Self::DocumentStart
+                | Self::DocumentEnd
+                | Self::GoToLine
+                | Self::GoToColumn
+                | Self::PageUp
+                | Self::PageDown
+                | Self::HalfPageUp
+                | Self::HalfPageDown
+        ) || self.is_word_motion()
+    }
+
+    fn is_word_motion(&self) -> bool {
+        matches!(
+            self,
+            Self::WordForward
+                | Self::WordEnd
+                | Self::WordBackward
+                | Self::WordEndBackward
+                | Self::WordEndBackwardBig
+        )
+    }
+
+    /// Whether an operator over this motion includes the character the motion lands on
+    pub fn motion_kind(&self) -> MotionKind {
+        match self {
+            Self::WordEnd | Self::WordEndBackward | Self::WordEndBackwardBig | Self::LineEnd => {
+                MotionKind::Inclusive
+            }
+            _ => MotionKind::Exclusive,
+        }
+    }
+
+    /// Characters to select before running the native motion for an operator
+    /// `e` always moves, so from a word's last char it goes on to the next word's end,
+    /// while Option+Right from there would only reach the end of the current word
+    fn selection_lead(&self) -> u32 {
+        match self {
+            Self::WordEnd => 1,
+            _ => 0,
+        }
+    }
+
+    /// Characters to add to the native selection so it covers vim's operator range, when
+    /// the field's text isn't there to measure it (see `word_forward_extension`)
+    /// Option+Right and Cmd+Right stop after the last char of the word or line, which is
+    /// where `e` and `$` end already. For `w` they stop before the blank that follows,
+    /// assumed to be a single space
+    fn selection_extension(&self) -> u32 {
+        match self {
+            Self::WordForward => 1,
+            _ => 0,
+        }
+    }
+
+    /// Keystrokes that perform an insert-mode editing command, or None for other commands
+    pub fn insert_keystrokes(&self) -> Option<Vec<(keyboard::KeyCode, keyboard::Modifiers)>> {
+        use keyboard::{KeyCode, Modifiers};
+        match self {
+            Self::DeleteWordBackward => Some(vec![(
+                KeyCode::Delete,
+                Modifiers { option: true, ..Default::default() },
+            )]),
+            // Select to the line start, then delete the selection
+            Self::DeleteToLineStart => Some(vec![
+                (
+                    KeyCode::Left,
+                    Modifiers { command: true, shift: true, ..Default::default() },
+                ),
+                (KeyCode::Delete, Modifiers::default()),
+            ]),
+            // A bare Tab, which most fields insert as-is outside of completion popups
+            Self::InsertLiteralTab => Some(vec![(KeyCode::Tab, Modifiers::default())]),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a motion includes its target character when used with an operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MotionKind {
+    /// Includes the target (`e`, `$`): `de` deletes through the last char of the word
+    Inclusive,
+    /// Stops before the target (`w`, `b`, `0`): `dw` deletes up to the next word
+    Exclusive,
+}
+
+/// Character range an operator affects, from the cursor to a motion's target offset
+pub fn operator_range(cursor: usize, target: usize, kind: MotionKind) -> std::ops::Range<usize> {
+    let inclusive = (kind == MotionKind::Inclusive) as usize;
+    if target >= cursor {
+        cursor..target + inclusive
+    } else {
+        target..cursor + inclusive
+    }
+}
+
+/// Chars to add to a native `w` selection ending at `native_end`, after the word, so it
+/// reaches the next word: the blanks that follow, stopping at the line's end as `dw` does
+fn word_forward_extension(chars: &[char], native_end: usize) -> usize {
+    chars
+        .get(native_end..)
+        .unwrap_or_default()
+        .iter()
+        .take_while(|&&c| c.is_whitespace() && c != '\n')
+        .count()
+}
+
+/// `word_forward_extension` for the focused field's selection, None without its text
+fn focused_word_forward_extension() -> Option<u32> {
+    let mut text = TextReader::with_caret();
+    let selection = text.read()?;
+    let chars: Vec<char> = selection.text.chars().collect();
+    Some(word_forward_extension(&chars, selection.start + selection.length) as u32)
+}
+
+/// Text of the focused field as chars, with the caret offset
+fn focused_chars_and_caret(text: &mut TextReader) -> Option<(Vec<char>, usize)> {
+    let selection = text.read()?;
+    Some((selection.text.chars().collect(), selection.start))
+}
+
+/// Select the range a text object finds in the focused field's text
+fn select_text_object(
+    range: impl Fn(&[char], usize) -> Option<std::ops::Range<usize>>,
+) -> Result<(), String> {
+    let (chars, caret) = focused_chars_and_caret(&mut TextReader::with_caret()).ok_or("The field's text isn't available")?;
+    let range = range(&chars, caret).ok_or("No text object around the caret")?;
+    let element = accessibility::capture_focused_element();
+    accessibility::select_range_or_arrows(element.as_ref(), caret, range.start, range.len())
+}
+
+/// Move the caret between char offsets with arrow keys
+fn move_caret(from: usize, to: usize, select: bool) -> Result<(), String> {
+    if to > from {
+        keyboard::cursor_right((to - from) as u32, select)
+    } else if to < from {
+        keyboard::cursor_left((from - to) as u32, select)
+    } else {
+        Ok(())
+    }
+}
+
+/// Pending operator (d, y, c, gq, gw)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Operator {
     Delete,
     Yank,
     Change,
+    /// `gq`: rewrap lines at the text width, leaving the caret after them
+    Format,
+    /// `gw`: rewrap lines at the text width, keeping the caret on its char
+    FormatKeepCursor,
+}
+
+/// Text of the lines in `range` rewrapped at `width`, replacing them in the focused field
+/// The caret ends after the new text, or on the char it was on with `keep_cursor`
+fn format_lines(
+    chars: &[char],
+    caret: usize,
+    range: std::ops::Range<usize>,
+    width: usize,
+    keep_cursor: bool,
+) -> Result<(), String> {
+    let original: String = chars[range.clone()].iter().collect();
+    let formatted = reflow::reflow(&original, width);
+    if formatted == original {
+        return Ok(());
+    }
+    let element = accessibility::capture_focused_element();
+    accessibility::select_range_or_arrows(element.as_ref(), caret, range.start, range.len())?;
+    custom_command::paste_text(&formatted)?;
+    if keep_cursor {
+        let end = range.start + formatted.chars().count();
+        let offset = reflow::map_offset(&original, &formatted, caret.saturating_sub(range.start));
+        accessibility::select_range_or_arrows(element.as_ref(), end, range.start + offset, 0)?;
+    }
+    Ok(())
 }
 
 impl Operator {
     /// Execute operator with the given motion
-    pub fn execute_with_motion(&self, motion: VimCommand, count: u32) -> Result<bool, String> {
+    pub fn execute_with_motion(&self, motion: VimCommand, count: u32, options: MotionOptions) -> Result<bool, String> {
+        if self.formats() {
+            let (chars, caret) = focused_chars_and_caret(&mut TextReader::with_caret()).ok_or("The field's text isn't available")?;
+            let target = motion::motion_target(Motion::Command(motion), &chars, caret, count)
+                .ok_or_else(|| format!("Can't reflow over {:?}", motion))?;
+            let range = reflow::line_range(&chars, caret, target);
+            format_lines(&chars, caret, range, options.text_width, *self == Self::FormatKeepCursor)?;
+            return Ok(false);
+        }
+
+        // As in vim, `cw` on a word changes to the end of the word like `ce`,
+        // but from the word's last char it only changes that char
+        let changes_word = *self == Self::Change && motion == VimCommand::WordForward;
+        let motion = if changes_word { VimCommand::WordEnd } else { motion };
+
+        if options.word_motion_mode == WordMotionMode::VimAccurate && motion.is_word_motion() {
+            if let Some((chars, caret)) = focused_chars_and_caret(&mut TextReader::with_caret()) {
+                if let Some(target) = word_motion::operator_word_target(motion, &chars, caret, count) {
+                    let range = operator_range(caret, target, motion.motion_kind());
+                    move_caret(caret, range.start, false)?;
+                    move_caret(range.start, range.end, true)?;
+                    return self.apply_to_selection(options);
+                }
+            }
+            log::debug!("Vim-accurate word motion unavailable, using native motion");
+        }
+
+        // `ge` also covers the char under the cursor, so the selection starts after it
+        if matches!(motion, VimCommand::WordEndBackward | VimCommand::WordEndBackwardBig) {
+            keyboard::cursor_right(1, false)?;
+        }
+
+        // Select the char under the cursor first where the motion must move past it
+        let lead = if changes_word { 0 } else { motion.selection_lead() };
+        if lead > 0 {
+            keyboard::cursor_right(lead, true)?;
+        }
+
         // First, select the text
         motion.execute(count, true)?;
 
-        // Then apply the operator
+        // Adjust the selection to the motion's inclusive/exclusive range
+        let extension = if motion == VimCommand::WordForward {
+            focused_word_forward_extension().unwrap_or_else(|| motion.selection_extension())
+        } else {
+            motion.selection_extension()
+        };
+        if extension > 0 {
+            keyboard::cursor_right(extension, true)?;
+        }
+
+        self.apply_to_selection(options)
+    }
+
+    /// Whether the operator rewraps lines (`gq`, `gw`)
+    pub fn formats(&self) -> bool {
+        matches!(self, Self::Format | Self::FormatKeepCursor)
+    }
+
+    /// Apply the operator to the current selection
+    /// Returns whether to enter insert mode
+    pub fn apply_to_selection(&self, options: MotionOptions) -> Result<bool, String> {
         match self {
             Self::Delete => {
                 keyboard::cut()?;
@@ -214,6 +718,178 @@ impl Operator {
                 keyboard::cut()?;
                 Ok(true) // Enter insert mode
             }
+            Self::Format | Self::FormatKeepCursor => {
+                // Rewrap the whole lines the selection touches
+                let mut text = TextReader::with_caret();
+                let selection = text.read().ok_or("The field's text isn't available")?;
+                let chars: Vec<char> = selection.text.chars().collect();
+                let end = (selection.start + selection.length).saturating_sub(1).max(selection.start);
+                let range = reflow::line_range(&chars, selection.start, end);
+                format_lines(&chars, selection.start, range, options.text_width, *self == Self::FormatKeepCursor)?;
+                Ok(false)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operator_text(text: &str, cursor: usize, target: usize, motion: VimCommand) -> &str {
+        &text[operator_range(cursor, target, motion.motion_kind())]
+    }
+
+    #[test]
+    fn test_can_apply_at_boundaries() {
+        let text: Vec<char> = "ab\n\ncd".chars().collect();
+        assert!(VimCommand::DeleteChar.can_apply_at(&text, 0));
+        assert!(VimCommand::DeleteChar.can_apply_at(&text, 5));
+        // End of the field, and line ends that would join lines
+        assert!(!VimCommand::DeleteChar.can_apply_at(&text, 6));
+        assert!(!VimCommand::DeleteChar.can_apply_at(&text, 2));
+        assert!(!VimCommand::SubstituteChar.can_apply_at(&text, 3)); // empty line
+        assert!(!VimCommand::DeleteChar.can_apply_at(&[], 0));
+
+        assert!(VimCommand::DeleteCharBefore.can_apply_at(&text, 1));
+        assert!(!VimCommand::DeleteCharBefore.can_apply_at(&text, 0));
+        assert!(!VimCommand::DeleteCharBefore.can_apply_at(&text, 4)); // line start
+
+        assert!(!VimCommand::MoveLeft.can_apply_at(&text, 0));
+        assert!(VimCommand::MoveLeft.can_apply_at(&text, 4));
+        assert!(!VimCommand::MoveRight.can_apply_at(&text, 6));
+        assert!(VimCommand::MoveRight.can_apply_at(&text, 5));
+        assert!(VimCommand::DeleteLine.can_apply_at(&text, 6));
+    }
+
+    #[test]
+    fn test_de_is_inclusive_dw_is_exclusive() {
+        let text = "hello world";
+        // `e` lands on the final `o`, `w` on the `w` of "world"
+        assert_eq!(operator_text(text, 0, 4, VimCommand::WordEnd), "hello");
+        assert_eq!(operator_text(text, 0, 6, VimCommand::WordForward), "hello ");
+    }
+
+    #[test]
+    fn test_backward_motions() {
+        let text = "hello world";
+        assert_eq!(operator_text(text, 6, 0, VimCommand::WordBackward), "hello ");
+        assert_eq!(operator_text(text, 6, 0, VimCommand::LineStart), "hello ");
+    }
+
+    /// Where Option+Right lands from `pos`: past any separators, then past the word
+    fn native_word_end(chars: &[char], mut pos: usize) -> usize {
+        while pos < chars.len() && !chars[pos].is_alphanumeric() {
+            pos += 1;
+        }
+        while pos < chars.len() && chars[pos].is_alphanumeric() {
+            pos += 1;
+        }
+        pos
+    }
+
+    /// Text a native `de`/`dw` selects from `caret`, with a simulated Option+Right
+    fn native_operator_text(text: &str, caret: usize, motion: VimCommand) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        let start = caret + motion.selection_lead() as usize;
+        let native_end = native_word_end(&chars, start);
+        let extension = if motion == VimCommand::WordForward {
+            word_forward_extension(&chars, native_end)
+        } else {
+            motion.selection_extension() as usize
+        };
+        let end = native_end + extension;
+        chars[caret..end.min(chars.len())].iter().collect()
+    }
+
+    #[test]
+    fn test_native_de_and_dw_selections() {
+        let text = "hello world";
+        assert_eq!(native_operator_text(text, 0, VimCommand::WordEnd), "hello");
+        assert_eq!(native_operator_text(text, 0, VimCommand::WordForward), "hello ");
+        assert_eq!(native_operator_text(text, 2, VimCommand::WordEnd), "llo");
+        // From a word's last char `e` goes on to the end of the next word
+        assert_eq!(native_operator_text(text, 4, VimCommand::WordEnd), "o world");
+        assert_eq!(native_operator_text(text, 4, VimCommand::WordForward), "o ");
+
+        // The same ranges as the vim-accurate path
+        for text in ["hello world", "hello   world", "hello\nworld", "hello  \nworld"] {
+            let chars: Vec<char> = text.chars().collect();
+            for caret in [0, 2, 4] {
+                for motion in [VimCommand::WordEnd, VimCommand::WordForward] {
+                    let target = word_motion::operator_word_target(motion, &chars, caret, 1).unwrap();
+                    assert_eq!(
+                        native_operator_text(text, caret, motion),
+                        operator_text(text, caret, target, motion),
+                        "{:?} from {} in {:?}",
+                        motion,
+                        caret,
+                        text
+                    );
+                }
+            }
         }
     }
+
+    #[test]
+    fn test_dw_takes_the_blanks_up_to_the_line_end() {
+        // All the spaces up to the next word
+        assert_eq!(native_operator_text("hello   world", 0, VimCommand::WordForward), "hello   ");
+        // The last word of a line leaves the line break, so lines aren't joined
+        assert_eq!(native_operator_text("hello\nworld", 0, VimCommand::WordForward), "hello");
+        assert_eq!(native_operator_text("hello  \nworld", 0, VimCommand::WordForward), "hello  ");
+        assert_eq!(native_operator_text("hello", 0, VimCommand::WordForward), "hello");
+        let text: Vec<char> = "foo.bar".chars().collect();
+        assert_eq!(word_forward_extension(&text, 3), 0);
+        assert_eq!(word_forward_extension(&text, 9), 0);
+    }
+
+    #[test]
+    fn test_selection_extension() {
+        // Without the field's text, `w` assumes a single space after the word
+        assert_eq!(VimCommand::WordForward.selection_extension(), 1);
+        // Native selection already ends after the inclusive target
+        assert_eq!(VimCommand::WordEnd.selection_extension(), 0);
+        assert_eq!(VimCommand::LineEnd.selection_extension(), 0);
+        assert_eq!(VimCommand::MoveRight.selection_extension(), 0);
+        assert_eq!(VimCommand::WordEndBackward.selection_extension(), 0);
+    }
+
+    #[test]
+    fn test_insert_keystrokes() {
+        let keys = |command: VimCommand| -> Vec<(keyboard::KeyCode, bool, bool, bool)> {
+            command
+                .insert_keystrokes()
+                .unwrap()
+                .into_iter()
+                .map(|(k, m)| (k, m.shift, m.option, m.command))
+                .collect()
+        };
+        // Ctrl+W: Option+Delete
+        assert_eq!(
+            keys(VimCommand::DeleteWordBackward),
+            vec![(keyboard::KeyCode::Delete, false, true, false)]
+        );
+        // Ctrl+U: Cmd+Shift+Left, then Delete
+        assert_eq!(
+            keys(VimCommand::DeleteToLineStart),
+            vec![
+                (keyboard::KeyCode::Left, true, false, true),
+                (keyboard::KeyCode::Delete, false, false, false),
+            ]
+        );
+        assert!(VimCommand::WordForward.insert_keystrokes().is_none());
+    }
+
+    #[test]
+    fn test_insert_literal_tab() {
+        let keys = VimCommand::InsertLiteralTab.insert_keystrokes().unwrap();
+        assert_eq!(keys.len(), 1);
+        let (keycode, m) = keys[0];
+        assert_eq!(keycode, keyboard::KeyCode::Tab);
+        assert!(!(m.shift || m.control || m.option || m.command));
+        // Named the way `run_vim_command` receives it
+        let command: VimCommand = serde_json::from_str("\"insert_literal_tab\"").unwrap();
+        assert_eq!(command, VimCommand::InsertLiteralTab);
+    }
 }
@@ -0,0 +1,210 @@
+//! Custom commands from settings: named sequences of editing primitives
+//!
+//! A custom command can be bound in the keymap or run with `run_vim_command` by its name,
+//! wherever a built-in command's name is accepted.
+
+use serde::{Deserialize, Serialize};
+
+use crate::keyboard;
+use crate::nvim_edit::clipboard;
+
+use super::commands::{MotionOptions, VimCommand};
+
+/// Pause between steps, so the app applies each before the next
+const STEP_DELAY: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// A step of a custom command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Primitive {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    WordForward,
+    WordBackward,
+    LineStart,
+    LineEnd,
+    SelectLeft,
+    SelectRight,
+    SelectWordForward,
+    SelectWordBackward,
+    SelectLineStart,
+    SelectLineEnd,
+    Cut,
+    Copy,
+    Paste,
+    DeleteChar,
+    Backspace,
+    Undo,
+    Redo,
+    NewLineBelow,
+    NewLineAbove,
+    /// Insert text at the cursor, through the clipboard
+    TypeText(String),
+    /// Run a built-in command
+    Command(VimCommand),
+}
+
+impl Primitive {
+    pub fn execute(&self, options: MotionOptions) -> Result<(), String> {
+        match self {
+            Primitive::MoveLeft => keyboard::cursor_left(1, false),
+            Primitive::MoveRight => keyboard::cursor_right(1, false),
+            Primitive::MoveUp => keyboard::cursor_up(1, false),
+            Primitive::MoveDown => keyboard::cursor_down(1, false),
+            Primitive::WordForward => keyboard::word_forward(1, false),
+            Primitive::WordBackward => keyboard::word_backward(1, false),
+            Primitive::LineStart => keyboard::line_start(false),
+            Primitive::LineEnd => keyboard::line_end(false),
+            Primitive::SelectLeft => keyboard::cursor_left(1, true),
+            Primitive::SelectRight => keyboard::cursor_right(1, true),
+            Primitive::SelectWordForward => keyboard::word_forward(1, true),
+            Primitive::SelectWordBackward => keyboard::word_backward(1, true),
+            Primitive::SelectLineStart => keyboard::line_start(true),
+            Primitive::SelectLineEnd => keyboard::line_end(true),
+            Primitive::Cut => keyboard::cut(),
+            Primitive::Copy => keyboard::copy(),
+            Primitive::Paste => keyboard::paste(),
+            Primitive::DeleteChar => keyboard::delete_char(),
+            Primitive::Backspace => keyboard::backspace(),
+            Primitive::Undo => keyboard::undo(),
+            Primitive::Redo => keyboard::redo(),
+            Primitive::NewLineBelow => keyboard::new_line_below(),
+            Primitive::NewLineAbove => keyboard::new_line_above(),
+            Primitive::TypeText(text) => paste_text(text),
+            Primitive::Command(command) => command.execute_with_mode(1, false, options),
+        }
+    }
+}
+
+/// A named sequence of primitives, defined in settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomCommand {
+    pub name: String,
+    pub steps: Vec<Primitive>,
+}
+
+/// A command given by name: built-in, or else a custom command
+/// A custom command named like a built-in one is shadowed by it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum NamedCommand {
+    Builtin(VimCommand),
+    Custom(String),
+}
+
+impl From<VimCommand> for NamedCommand {
+    fn from(command: VimCommand) -> Self {
+        NamedCommand::Builtin(command)
+    }
+}
+
+/// Steps of the custom command called `name`, if there is one
+pub fn find<'a>(commands: &'a [CustomCommand], name: &str) -> Option<&'a [Primitive]> {
+    commands
+        .iter()
+        .find(|command| command.name == name)
+        .map(|command| command.steps.as_slice())
+}
+
+/// Run `steps` `count` times with `run`, stopping at the first step that fails
+pub fn run_steps(
+    steps: &[Primitive],
+    count: u32,
+    mut run: impl FnMut(&Primitive) -> Result<(), String>,
+) -> Result<(), String> {
+    for _ in 0..count.max(1) {
+        for step in steps {
+            run(step)?;
+        }
+    }
+    Ok(())
+}
+
+/// Run a custom command's steps in the focused app
+pub fn execute(steps: &[Primitive], count: u32, options: MotionOptions) -> Result<(), String> {
+    run_steps(steps, count, |step| {
+        step.execute(options)?;
+        std::thread::sleep(STEP_DELAY);
+        Ok(())
+    })
+}
+
+/// Paste `text` at the cursor, restoring the clipboard afterwards
+pub fn paste_text(text: &str) -> Result<(), String> {
+    with_clipboard_text(text, keyboard::paste)
+}
+
+/// Run `paste` with `text` on the clipboard, restoring the clipboard afterwards
+pub fn with_clipboard_text(text: &str, paste: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+    let previous = clipboard::read_clipboard();
+    clipboard::write_clipboard(text)?;
+    paste()?;
+    // The app reads the clipboard after the paste key arrives
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    match previous {
+        Some(previous) => clipboard::write_clipboard(&previous),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DELETE_WORD_AND_COMMENT: &str = r#"
+name: delete-word-and-comment
+steps:
+  - select_word_forward
+  - cut
+  - !type_text "// "
+"#;
+
+    #[test]
+    fn test_composed_command() {
+        let command: CustomCommand = serde_yml::from_str(DELETE_WORD_AND_COMMENT).unwrap();
+        assert_eq!(command.name, "delete-word-and-comment");
+
+        let mut ran = Vec::new();
+        run_steps(&command.steps, 2, |step| {
+            ran.push(step.clone());
+            Ok(())
+        })
+        .unwrap();
+        let once = [
+            Primitive::SelectWordForward,
+            Primitive::Cut,
+            Primitive::TypeText("// ".to_string()),
+        ];
+        assert_eq!(ran, [once.clone(), once].concat());
+    }
+
+    #[test]
+    fn test_failing_step_stops_the_command() {
+        let steps = [Primitive::MoveLeft, Primitive::Cut, Primitive::MoveRight];
+        let mut ran = Vec::new();
+        let result = run_steps(&steps, 1, |step| {
+            ran.push(step.clone());
+            match step {
+                Primitive::Cut => Err("cut failed".to_string()),
+                _ => Ok(()),
+            }
+        });
+        assert_eq!(result, Err("cut failed".to_string()));
+        assert_eq!(ran, [Primitive::MoveLeft, Primitive::Cut]);
+    }
+
+    #[test]
+    fn test_named_commands() {
+        let builtin: NamedCommand = serde_json::from_str("\"redo\"").unwrap();
+        assert_eq!(builtin, NamedCommand::Builtin(VimCommand::Redo));
+        let custom: NamedCommand = serde_json::from_str("\"delete-word-and-comment\"").unwrap();
+        assert_eq!(custom, NamedCommand::Custom("delete-word-and-comment".to_string()));
+        assert_eq!(serde_json::to_string(&NamedCommand::from(VimCommand::Undo)).unwrap(), "\"undo\"");
+
+        let commands = vec![serde_yml::from_str::<CustomCommand>(DELETE_WORD_AND_COMMENT).unwrap()];
+        assert_eq!(find(&commands, "delete-word-and-comment").map(<[_]>::len), Some(3));
+        assert!(find(&commands, "missing").is_none());
+    }
+}
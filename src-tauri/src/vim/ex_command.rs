@@ -0,0 +1,212 @@
+//! Commands typed on the command line after `:`, e.g. `:'<,'>sort`
+//!
+//! `:` in normal mode applies to the whole field, and in visual mode to the selection.
+//! Commands read the focused field's text through accessibility, change it in Rust and
+//! paste the result over the lines they apply to. Offsets are char indices.
+
+use std::ops::Range;
+
+use crate::nvim_edit::accessibility;
+use crate::nvim_edit::text_source::TextReader;
+
+use super::commands::MotionOptions;
+use super::custom_command;
+use super::reflow::line_range;
+
+/// Range visual `:` fills in, standing for the selected lines
+pub const SELECTION_RANGE: &str = "'<,'>";
+
+/// Lines a command applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineRange {
+    /// `'<,'>`: the visual selection
+    Selection,
+    /// `%`, or no range: the whole field
+    All,
+}
+
+/// A command the command line understands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExCommand {
+    /// `:sort`: sort the lines, `:sort!` in reverse, `:sort u` dropping duplicates
+    Sort { reverse: bool, unique: bool },
+    /// `:u`: lowercase the text
+    Lowercase,
+    /// `:U`: uppercase the text
+    Uppercase,
+}
+
+/// A parsed command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandLine {
+    pub range: LineRange,
+    pub command: ExCommand,
+}
+
+/// Parse what was typed after `:`
+pub fn parse(line: &str) -> Result<CommandLine, String> {
+    let line = line.trim();
+    let (range, rest) = if let Some(rest) = line.strip_prefix(SELECTION_RANGE) {
+        (LineRange::Selection, rest)
+    } else if let Some(rest) = line.strip_prefix('%') {
+        (LineRange::All, rest)
+    } else {
+        (LineRange::All, line)
+    };
+    let rest = rest.trim();
+    if let Some(args) = rest.strip_prefix("sort").or_else(|| rest.strip_prefix("sor")) {
+        return parse_sort(args).map(|command| CommandLine { range, command });
+    }
+    let command = match rest {
+        "u" => ExCommand::Lowercase,
+        "U" => ExCommand::Uppercase,
+        "" => return Err("No command after the range".to_string()),
+        other => return Err(format!("Not an editor command: {}", other)),
+    };
+    Ok(CommandLine { range, command })
+}
+
+/// `!` and the flags after `:sort`
+fn parse_sort(args: &str) -> Result<ExCommand, String> {
+    let (reverse, flags) = match args.strip_prefix('!') {
+        Some(flags) => (true, flags),
+        None => (false, args),
+    };
+    let mut unique = false;
+    for flag in flags.chars().filter(|c| !c.is_whitespace()) {
+        match flag {
+            'u' => unique = true,
+            other => return Err(format!("Unsupported :sort flag: {}", other)),
+        }
+    }
+    Ok(ExCommand::Sort { reverse, unique })
+}
+
+/// Lines of `text` in sorted order, or reversed, keeping one of each repeated line with `unique`
+pub fn sort_lines(text: &str, reverse: bool, unique: bool) -> String {
+    let mut lines: Vec<&str> = text.split('\n').collect();
+    lines.sort_unstable();
+    if reverse {
+        lines.reverse();
+    }
+    if unique {
+        lines.dedup();
+    }
+    lines.join("\n")
+}
+
+impl ExCommand {
+    /// Whether the command works on whole lines, rather than exactly the selected text
+    fn is_linewise(self) -> bool {
+        matches!(self, Self::Sort { .. })
+    }
+
+    /// The text the command replaces `text` with
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Self::Sort { reverse, unique } => sort_lines(text, reverse, unique),
+            Self::Lowercase => text.to_lowercase(),
+            Self::Uppercase => text.to_uppercase(),
+        }
+    }
+}
+
+impl CommandLine {
+    /// Chars the command applies to, given the field's selection
+    pub fn char_range(&self, chars: &[char], start: usize, length: usize) -> Range<usize> {
+        match self.range {
+            LineRange::All => 0..chars.len(),
+            LineRange::Selection if self.command.is_linewise() => {
+                let end = (start + length).saturating_sub(1).max(start);
+                line_range(chars, start, end)
+            }
+            LineRange::Selection => start..(start + length).min(chars.len()),
+        }
+    }
+
+    /// Run the command on the focused field's text
+    /// The whole field's text may come from a clipboard copy, where accessibility can't read it all
+    pub fn execute(&self, options: MotionOptions) -> Result<(), String> {
+        let mut text = match self.range {
+            LineRange::Selection => TextReader::with_caret(),
+            LineRange::All => TextReader::for_field(options.text_capture, options.clipboard_delay),
+        };
+        let selection = text.read().ok_or("The field's text isn't available")?;
+        let chars: Vec<char> = selection.text.chars().collect();
+        let range = self.char_range(&chars, selection.start, selection.length);
+        let original: String = chars[range.clone()].iter().collect();
+        let replaced = self.command.apply(&original);
+        if replaced == original {
+            return Ok(());
+        }
+        let element = accessibility::capture_focused_element();
+        accessibility::select_range_or_arrows(element.as_ref(), selection.start, range.start, range.len())?;
+        custom_command::paste_text(&replaced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SORT: ExCommand = ExCommand::Sort { reverse: false, unique: false };
+
+    #[test]
+    fn test_parse_selection_range() {
+        let parsed = parse("'<,'>sort").unwrap();
+        assert_eq!(parsed, CommandLine { range: LineRange::Selection, command: SORT });
+        assert_eq!(parse("'<,'>U").unwrap().command, ExCommand::Uppercase);
+        assert_eq!(parse("'<,'> u ").unwrap().range, LineRange::Selection);
+    }
+
+    #[test]
+    fn test_parse_whole_field() {
+        assert_eq!(parse("%sort").unwrap().range, LineRange::All);
+        assert_eq!(parse("sor").unwrap(), CommandLine { range: LineRange::All, command: SORT });
+    }
+
+    #[test]
+    fn test_parse_sort_flags() {
+        let sort = |reverse, unique| ExCommand::Sort { reverse, unique };
+        assert_eq!(parse("sort!").unwrap().command, sort(true, false));
+        assert_eq!(parse("'<,'>sort u").unwrap().command, sort(false, true));
+        assert_eq!(parse("%sort! u").unwrap().command, sort(true, true));
+        assert!(parse("sort n").unwrap_err().contains('n'));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("'<,'>").is_err());
+        assert!(parse("'<,'>frobnicate").unwrap_err().contains("frobnicate"));
+    }
+
+    #[test]
+    fn test_char_range() {
+        let chars: Vec<char> = "one\ntwo\nthree".chars().collect();
+        let sort = parse("'<,'>sort").unwrap();
+        // Sorting covers the whole lines the selection touches
+        assert_eq!(sort.char_range(&chars, 1, 4), 0..7);
+        let upper = parse("'<,'>U").unwrap();
+        assert_eq!(upper.char_range(&chars, 1, 4), 1..5);
+        assert_eq!(parse("%U").unwrap().char_range(&chars, 1, 4), 0..13);
+    }
+
+    #[test]
+    fn test_sort_lines() {
+        let text = "pear\napple\npear\nfig";
+        assert_eq!(sort_lines(text, false, false), "apple\nfig\npear\npear");
+        assert_eq!(sort_lines(text, true, false), "pear\npear\nfig\napple");
+        assert_eq!(sort_lines(text, false, true), "apple\nfig\npear");
+        assert_eq!(sort_lines(text, true, true), "pear\nfig\napple");
+        // Sorting is by code point, so capitals come first as in vim
+        assert_eq!(sort_lines("b\nB\na", false, false), "B\na\nb");
+        assert_eq!(sort_lines("", false, true), "");
+    }
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(SORT.apply("pear\napple\nfig"), "apple\nfig\npear");
+        assert_eq!(ExCommand::Uppercase.apply("Mixed"), "MIXED");
+        assert_eq!(ExCommand::Lowercase.apply("Mixed"), "mixed");
+    }
+}
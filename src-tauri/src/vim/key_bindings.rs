@@ -0,0 +1,184 @@
+//! What a key does in each mode, for tooltips and help
+//!
+//! Each mode's answer comes from running the key through a fresh state machine with the
+//! user's options, so keymap bindings and built-in keys are described as they behave.
+
+use serde::Serialize;
+
+use crate::config::{ModifierSide, VimKeyModifiers};
+use crate::keyboard::keycode::SideModifiers;
+use crate::keyboard::{KeyCode, KeyEvent, Modifiers};
+
+use super::commands::{Operator, VimCommand};
+use super::{ProcessResult, VimAction, VimMode, VimOptions, VimState};
+
+/// What a key does in one mode
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModeBinding {
+    pub mode: VimMode,
+    pub description: String,
+}
+
+/// Readable name of a command, from its settings name (e.g. "word forward")
+fn command_name(command: VimCommand) -> String {
+    serde_json::to_value(command)
+        .ok()
+        .and_then(|name| name.as_str().map(|name| name.replace('_', " ")))
+        .unwrap_or_else(|| format!("{:?}", command))
+}
+
+fn operator_name(operator: Operator) -> &'static str {
+    match operator {
+        Operator::Delete => "delete",
+        Operator::Yank => "yank",
+        Operator::Change => "change",
+        Operator::Format => "format",
+        Operator::FormatKeepCursor => "format keeping cursor",
+    }
+}
+
+fn describe_action(action: &VimAction) -> String {
+    match action {
+        VimAction::Command { command, select: true, .. } => {
+            format!("extend selection: {}", command_name(*command))
+        }
+        VimAction::Command { command, .. } => command_name(*command),
+        VimAction::OperatorMotion { operator, motion, .. } => {
+            format!("{} {}", operator_name(*operator), command_name(*motion))
+        }
+        VimAction::TextObject { operator, text_object, .. } => {
+            format!("{} {}", operator_name(*operator), command_name(*text_object))
+        }
+        VimAction::ReplaceChar { .. } => "replace character".to_string(),
+        VimAction::RepeatLastInsertedText { .. } => "repeat last insert".to_string(),
+        VimAction::PasteRegister { .. } => "paste register".to_string(),
+        VimAction::CyclePaste { .. } => "cycle paste".to_string(),
+        VimAction::PlayMacro { .. } => "play macro".to_string(),
+        VimAction::SwapSelectionEnds { .. } => "swap selection ends".to_string(),
+        VimAction::CustomCommand { .. } => "custom command".to_string(),
+        VimAction::ExCommand(_) => "command line".to_string(),
+        VimAction::Linewise { action, .. } => format!("{} lines", describe_action(action)),
+        VimAction::ToClipboard(action) => format!("{} to clipboard", describe_action(action)),
+        VimAction::Cut => "cut".to_string(),
+        VimAction::Copy => "copy".to_string(),
+    }
+}
+
+/// Modifiers held as `modifiers` asks, on its side of the keyboard
+fn held_modifiers(modifiers: &VimKeyModifiers) -> Modifiers {
+    let side = SideModifiers {
+        shift: modifiers.shift,
+        control: modifiers.control,
+        option: modifiers.option,
+        command: modifiers.command,
+    };
+    let (left, right) = match modifiers.side {
+        ModifierSide::Right => (SideModifiers::default(), side),
+        ModifierSide::Any | ModifierSide::Left => (side, SideModifiers::default()),
+    };
+    Modifiers {
+        shift: modifiers.shift,
+        control: modifiers.control,
+        option: modifiers.option,
+        command: modifiers.command,
+        left,
+        right,
+        ..Default::default()
+    }
+}
+
+/// What the key does in `mode` when pressed with nothing pending, or None if it
+/// passes through or does nothing
+fn binding_in_mode(mode: VimMode, keycode: KeyCode, modifiers: Modifiers, options: &VimOptions) -> Option<String> {
+    let (mut state, _) = VimState::with_mode(mode);
+    state.set_options(options.clone());
+    let result = state.process_key(KeyEvent {
+        code: keycode.as_raw(),
+        modifiers,
+        is_key_down: true,
+    });
+    match result {
+        ProcessResult::PassThrough => None,
+        ProcessResult::Suppress => {
+            let pending = state.get_pending_keys();
+            (!pending.is_empty()).then(|| format!("starts {}", pending))
+        }
+        ProcessResult::SuppressWithAction(action) => Some(describe_action(&action)),
+        ProcessResult::ModeChanged(new_mode, action)
+        | ProcessResult::ModeChangedWithPending(new_mode, action, _) => {
+            let enter = format!("enter {} mode", new_mode);
+            Some(match action {
+                Some(action) => format!("{}, {}", describe_action(&action), enter),
+                None => enter,
+            })
+        }
+    }
+}
+
+/// What a key with modifiers does in each mode, leaving out modes where it passes through
+pub fn bindings_for_key(keycode: KeyCode, modifiers: &VimKeyModifiers, options: &VimOptions) -> Vec<ModeBinding> {
+    let modifiers = held_modifiers(modifiers);
+    [VimMode::Insert, VimMode::Normal, VimMode::Visual]
+        .into_iter()
+        .filter_map(|mode| {
+            let description = binding_in_mode(mode, keycode, modifiers, options)?;
+            Some(ModeBinding { mode, description })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vim::keymap::KeyBinding;
+
+    fn describe(key: &str, modifiers: VimKeyModifiers, options: &VimOptions) -> Vec<(VimMode, String)> {
+        bindings_for_key(KeyCode::from_name(key).unwrap(), &modifiers, options)
+            .into_iter()
+            .map(|binding| (binding.mode, binding.description))
+            .collect()
+    }
+
+    #[test]
+    fn test_motion_across_modes() {
+        assert_eq!(
+            describe("w", VimKeyModifiers::default(), &VimOptions::default()),
+            vec![
+                (VimMode::Normal, "word forward".to_string()),
+                (VimMode::Visual, "extend selection: word forward".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mode_changes_and_prefixes() {
+        let options = VimOptions::default();
+        let bindings = describe("i", VimKeyModifiers::default(), &options);
+        assert!(bindings.contains(&(VimMode::Normal, "enter insert mode".to_string())));
+        let bindings = describe("d", VimKeyModifiers::default(), &options);
+        assert!(bindings.contains(&(VimMode::Normal, "starts d".to_string())));
+
+        // Escape follows the configured model
+        let escape = describe("escape", VimKeyModifiers::default(), &options);
+        assert_eq!(escape[0], (VimMode::Normal, "enter insert mode".to_string()));
+        let options = VimOptions { escape_target_mode: VimMode::Normal, ..Default::default() };
+        let escape = describe("escape", VimKeyModifiers::default(), &options);
+        assert_eq!(escape[0], (VimMode::Insert, "enter normal mode".to_string()));
+    }
+
+    #[test]
+    fn test_keymap_bindings() {
+        let shift = VimKeyModifiers { shift: true, ..Default::default() };
+        let options = VimOptions {
+            keymap: vec![KeyBinding {
+                key: "u".to_string(),
+                modifiers: shift.clone(),
+                command: VimCommand::Redo.into(),
+            }],
+            ..Default::default()
+        };
+        assert!(describe("u", shift, &options).contains(&(VimMode::Normal, "redo".to_string())));
+        assert!(describe("u", VimKeyModifiers::default(), &options)
+            .contains(&(VimMode::Normal, "undo".to_string())));
+    }
+}
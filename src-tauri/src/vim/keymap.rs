@@ -0,0 +1,153 @@
+//! Normal mode key bindings from settings, which take precedence over the built-in keys
+//!
+//! Presets are built-in binding tables for users coming from other editors. Loading one
+//! replaces the keymap in settings, where it can be edited further.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ModifierSide, VimKeyModifiers};
+use crate::keyboard::{KeyCode, Modifiers};
+
+use super::commands::VimCommand;
+use super::custom_command::NamedCommand;
+
+/// A key with modifiers that runs a command in normal mode
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    /// Key name, as in `KeyCode::from_name` (e.g. "u", "home")
+    pub key: String,
+    #[serde(default)]
+    pub modifiers: VimKeyModifiers,
+    /// A built-in command, or the name of a custom command
+    pub command: NamedCommand,
+}
+
+impl KeyBinding {
+    fn matches(&self, keycode: KeyCode, modifiers: &Modifiers) -> bool {
+        KeyCode::from_name(&self.key) == Some(keycode) && self.modifiers.matches(keycode, modifiers)
+    }
+}
+
+/// Command bound to a key, if any
+pub fn lookup<'a>(keymap: &'a [KeyBinding], keycode: KeyCode, modifiers: &Modifiers) -> Option<&'a NamedCommand> {
+    keymap
+        .iter()
+        .find(|binding| binding.matches(keycode, modifiers))
+        .map(|binding| &binding.command)
+}
+
+/// Built-in keymaps that can be loaded from the settings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeymapPreset {
+    /// Vim's keys only
+    #[default]
+    VimDefault,
+    /// Redo on `U` and Home/End on the line, as in Helix
+    HelixLike,
+    /// Cmd+Z/Cmd+Shift+Z undo and redo, and the navigation keys take counts
+    MacosFriendly,
+}
+
+const fn mods(shift: bool, command: bool) -> VimKeyModifiers {
+    VimKeyModifiers {
+        shift,
+        control: false,
+        option: false,
+        command,
+        side: ModifierSide::Any,
+    }
+}
+
+const NONE: VimKeyModifiers = mods(false, false);
+
+const HELIX_LIKE: &[(&str, VimKeyModifiers, VimCommand)] = &[
+    ("u", mods(true, false), VimCommand::Redo),
+    ("home", NONE, VimCommand::LineStart),
+    ("end", NONE, VimCommand::LineEnd),
+];
+
+const MACOS_FRIENDLY: &[(&str, VimKeyModifiers, VimCommand)] = &[
+    ("z", mods(false, true), VimCommand::Undo),
+    ("z", mods(true, true), VimCommand::Redo),
+    ("left", NONE, VimCommand::MoveLeft),
+    ("right", NONE, VimCommand::MoveRight),
+    ("up", NONE, VimCommand::MoveUp),
+    ("down", NONE, VimCommand::MoveDown),
+    ("up", mods(false, true), VimCommand::DocumentStart),
+    ("down", mods(false, true), VimCommand::DocumentEnd),
+    ("home", NONE, VimCommand::LineStart),
+    ("end", NONE, VimCommand::LineEnd),
+    ("page_up", NONE, VimCommand::PageUp),
+    ("page_down", NONE, VimCommand::PageDown),
+];
+
+impl KeymapPreset {
+    /// The preset's bindings, on top of vim's keys
+    pub fn bindings(self) -> Vec<KeyBinding> {
+        let table = match self {
+            Self::VimDefault => &[],
+            Self::HelixLike => HELIX_LIKE,
+            Self::MacosFriendly => MACOS_FRIENDLY,
+        };
+        table
+            .iter()
+            .map(|(key, modifiers, command)| KeyBinding {
+                key: key.to_string(),
+                modifiers: modifiers.clone(),
+                command: (*command).into(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shift() -> Modifiers {
+        Modifiers { shift: true, ..Default::default() }
+    }
+
+    fn cmd() -> Modifiers {
+        Modifiers { command: true, ..Default::default() }
+    }
+
+    #[test]
+    fn test_preset_bindings() {
+        let helix = KeymapPreset::HelixLike.bindings();
+        assert_eq!(lookup(&helix, KeyCode::U, &shift()), Some(&VimCommand::Redo.into()));
+        assert_eq!(lookup(&helix, KeyCode::Home, &Modifiers::default()), Some(&VimCommand::LineStart.into()));
+        // Keys the preset doesn't bind keep vim's meaning
+        assert_eq!(lookup(&helix, KeyCode::U, &Modifiers::default()), None);
+
+        let macos = KeymapPreset::MacosFriendly.bindings();
+        assert_eq!(lookup(&macos, KeyCode::Z, &cmd()), Some(&VimCommand::Undo.into()));
+        let cmd_shift = Modifiers { shift: true, command: true, ..Default::default() };
+        assert_eq!(lookup(&macos, KeyCode::Z, &cmd_shift), Some(&VimCommand::Redo.into()));
+        assert_eq!(lookup(&macos, KeyCode::Down, &cmd()), Some(&VimCommand::DocumentEnd.into()));
+        assert_eq!(lookup(&macos, KeyCode::Down, &Modifiers::default()), Some(&VimCommand::MoveDown.into()));
+
+        assert!(KeymapPreset::VimDefault.bindings().is_empty());
+    }
+
+    #[test]
+    fn test_preset_names() {
+        let preset: KeymapPreset = serde_json::from_str("\"macos-friendly\"").unwrap();
+        assert_eq!(preset, KeymapPreset::MacosFriendly);
+        assert_eq!(serde_json::to_string(&KeymapPreset::HelixLike).unwrap(), "\"helix-like\"");
+    }
+
+    #[test]
+    fn test_bindings_round_trip() {
+        for preset in [KeymapPreset::HelixLike, KeymapPreset::MacosFriendly] {
+            let bindings = preset.bindings();
+            for binding in &bindings {
+                assert!(KeyCode::from_name(&binding.key).is_some(), "unknown key {}", binding.key);
+            }
+            let json = serde_json::to_string(&bindings).unwrap();
+            let parsed: Vec<KeyBinding> = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, bindings);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Caret targets for line and document motions, computed from the field's text
+//!
+//! Used to place the caret directly through the accessibility range instead of
+//! synthesizing arrow keys. Offsets are char indices, as in `word_motion`.
+
+use super::commands::VimCommand;
+
+/// Start of the line containing `pos`
+pub fn line_start(chars: &[char], pos: usize) -> usize {
+    let pos = pos.min(chars.len());
+    chars[..pos]
+        .iter()
+        .rposition(|&c| c == '\n')
+        .map_or(0, |newline| newline + 1)
+}
+
+/// End of the line containing `pos`, just before its newline
+/// This is where Cmd+Right leaves the caret
+pub fn line_end(chars: &[char], pos: usize) -> usize {
+    let pos = pos.min(chars.len());
+    chars[pos..]
+        .iter()
+        .position(|&c| c == '\n')
+        .map_or(chars.len(), |newline| pos + newline)
+}
+
+/// First non-blank char of the line containing `pos`, or its end if it's all blank
+pub fn first_non_blank(chars: &[char], pos: usize) -> usize {
+    let start = line_start(chars, pos);
+    let end = line_end(chars, pos);
+    chars[start..end]
+        .iter()
+        .position(|c| !c.is_whitespace())
+        .map_or(end, |offset| start + offset)
+}
+
+/// Start of the 1-based `line`, or of the last line if there are fewer
+pub fn nth_line_start(chars: &[char], line: u32) -> usize {
+    let mut start = 0;
+    for _ in 1..line.max(1) {
+        match chars[start..].iter().position(|&c| c == '\n') {
+            Some(newline) => start += newline + 1,
+            None => break,
+        }
+    }
+    start
+}
+
+/// Target of a line or document motion repeated `count` times, or None for other commands
+pub fn line_motion_target(command: VimCommand, chars: &[char], pos: usize, count: u32) -> Option<usize> {
+    let target = match command {
+        VimCommand::LineStart => line_start(chars, pos),
+        VimCommand::FirstNonBlank => first_non_blank(chars, pos),
+        // `3$` goes to the end of the second line below
+        VimCommand::LineEnd => (1..count.max(1)).fold(line_end(chars, pos), |end, _| {
+            if end < chars.len() {
+                line_end(chars, end + 1)
+            } else {
+                end
+            }
+        }),
+        VimCommand::DocumentStart => 0,
+        VimCommand::DocumentEnd => chars.len(),
+        VimCommand::GoToLine => nth_line_start(chars, count),
+        VimCommand::GoToColumn => {
+            let start = line_start(chars, pos);
+            (start + count.max(1) as usize - 1).min(line_end(chars, pos))
+        }
+        _ => return None,
+    };
+    Some(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vim::state::test_util::chars;
+
+    #[test]
+    fn test_line_start_and_end() {
+        let text = chars("one\ntwo words\n\nend");
+        assert_eq!(line_start(&text, 0), 0);
+        assert_eq!(line_start(&text, 8), 4);
+        assert_eq!(line_start(&text, 4), 4);
+        assert_eq!(line_end(&text, 5), 13);
+        assert_eq!(line_end(&text, 14), 14); // empty line
+        assert_eq!(line_end(&text, 16), 18); // last line has no newline
+    }
+
+    #[test]
+    fn test_first_non_blank() {
+        let text = chars("  one\n\tx y\n   \nz");
+        assert_eq!(first_non_blank(&text, 4), 2);
+        assert_eq!(first_non_blank(&text, 0), 2);
+        assert_eq!(first_non_blank(&text, 9), 7);
+        assert_eq!(first_non_blank(&text, 11), 14); // blank line: its end
+        assert_eq!(first_non_blank(&text, 15), 15);
+    }
+
+    #[test]
+    fn test_line_end_with_count() {
+        let text = chars("one\ntwo\nthree");
+        assert_eq!(line_motion_target(VimCommand::LineEnd, &text, 1, 1), Some(3));
+        assert_eq!(line_motion_target(VimCommand::LineEnd, &text, 1, 2), Some(7));
+        assert_eq!(line_motion_target(VimCommand::LineEnd, &text, 1, 9), Some(13));
+    }
+
+    #[test]
+    fn test_document_motions() {
+        let text = chars("one\ntwo");
+        assert_eq!(line_motion_target(VimCommand::DocumentStart, &text, 5, 1), Some(0));
+        assert_eq!(line_motion_target(VimCommand::DocumentEnd, &text, 1, 1), Some(7));
+    }
+
+    #[test]
+    fn test_go_to_line() {
+        let text = chars("one\ntwo\nthree");
+        assert_eq!(line_motion_target(VimCommand::GoToLine, &text, 9, 1), Some(0));
+        assert_eq!(line_motion_target(VimCommand::GoToLine, &text, 0, 2), Some(4));
+        assert_eq!(line_motion_target(VimCommand::GoToLine, &text, 0, 3), Some(8));
+        // Past the last line stays on the last line
+        assert_eq!(line_motion_target(VimCommand::GoToLine, &text, 0, 50), Some(8));
+    }
+
+    #[test]
+    fn test_go_to_column() {
+        let text = chars("one\ntwo words");
+        assert_eq!(line_motion_target(VimCommand::GoToColumn, &text, 6, 1), Some(4));
+        assert_eq!(line_motion_target(VimCommand::GoToColumn, &text, 6, 5), Some(8));
+        // Past the line end stops at the end of the line
+        assert_eq!(line_motion_target(VimCommand::GoToColumn, &text, 1, 50), Some(3));
+    }
+
+    #[test]
+    fn test_other_commands() {
+        let text = chars("one");
+        assert_eq!(line_motion_target(VimCommand::WordForward, &text, 0, 1), None);
+    }
+}
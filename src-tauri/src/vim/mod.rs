@@ -1,6 +1,23 @@
 pub mod state;
 pub mod modes;
 pub mod commands;
+pub mod custom_command;
+pub mod ex_command;
+pub mod word_motion;
+pub mod keymap;
+pub mod key_bindings;
+pub mod line_motion;
+pub mod motion;
+pub mod mode_history;
+pub mod pending_help;
+pub mod reflow;
+pub mod registers;
+pub mod scroll;
+pub mod seen_apps;
+pub mod selection;
+pub mod text_edit;
+pub mod text_object;
 
-pub use state::{VimState, ProcessResult, VimAction};
+pub use state::{MacroStep, ModeChange, VimState, ProcessResult, VimAction, VimOptions};
 pub use modes::VimMode;
+pub use commands::MotionOptions;
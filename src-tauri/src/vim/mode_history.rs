@@ -0,0 +1,128 @@
+//! Bounded timeline of mode transitions, e.g. to show time spent in each mode
+//!
+//! Only modes and timestamps are kept, never any typed text.
+
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use super::modes::VimMode;
+
+/// Transitions kept before the oldest are dropped
+pub const MAX_MODE_TRANSITIONS: usize = 500;
+
+/// Entering a mode at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ModeTransition {
+    pub mode: VimMode,
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+}
+
+/// Time spent in each mode, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ModeDurations {
+    pub insert_ms: u64,
+    pub normal_ms: u64,
+    pub visual_ms: u64,
+}
+
+/// Recent mode transitions, oldest first
+#[derive(Debug, Clone)]
+pub struct ModeHistory {
+    transitions: VecDeque<ModeTransition>,
+    capacity: usize,
+}
+
+impl ModeHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            transitions: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record entering `mode`, dropping the oldest transition when full
+    pub fn record(&mut self, mode: VimMode, timestamp_ms: u64) {
+        if self.transitions.len() == self.capacity {
+            self.transitions.pop_front();
+        }
+        self.transitions.push_back(ModeTransition { mode, timestamp_ms });
+    }
+
+    pub fn transitions(&self) -> Vec<ModeTransition> {
+        self.transitions.iter().copied().collect()
+    }
+
+    /// Time spent in each mode over the kept timeline
+    /// Each transition lasts until the next one, and the last until `now_ms`
+    pub fn durations(&self, now_ms: u64) -> ModeDurations {
+        let mut durations = ModeDurations::default();
+        let ends = self
+            .transitions
+            .iter()
+            .skip(1)
+            .map(|t| t.timestamp_ms)
+            .chain(std::iter::once(now_ms));
+
+        for (transition, end) in self.transitions.iter().zip(ends) {
+            let elapsed = end.saturating_sub(transition.timestamp_ms);
+            match transition.mode {
+                VimMode::Insert => durations.insert_ms += elapsed,
+                VimMode::Normal => durations.normal_ms += elapsed,
+                VimMode::Visual => durations.visual_ms += elapsed,
+            }
+        }
+        durations
+    }
+}
+
+/// Current time in milliseconds since the Unix epoch
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oldest_transitions_are_evicted() {
+        let mut history = ModeHistory::new(3);
+        for (i, mode) in [VimMode::Insert, VimMode::Normal, VimMode::Visual, VimMode::Insert]
+            .into_iter()
+            .enumerate()
+        {
+            history.record(mode, i as u64 * 100);
+        }
+
+        let transitions = history.transitions();
+        assert_eq!(transitions.len(), 3);
+        assert_eq!(transitions[0], ModeTransition { mode: VimMode::Normal, timestamp_ms: 100 });
+        assert_eq!(transitions[2], ModeTransition { mode: VimMode::Insert, timestamp_ms: 300 });
+    }
+
+    #[test]
+    fn test_durations() {
+        let mut history = ModeHistory::new(MAX_MODE_TRANSITIONS);
+        assert_eq!(history.durations(1000), ModeDurations::default());
+
+        history.record(VimMode::Insert, 1000);
+        history.record(VimMode::Normal, 4000);
+        history.record(VimMode::Visual, 4500);
+        history.record(VimMode::Normal, 5000);
+
+        assert_eq!(
+            history.durations(6000),
+            ModeDurations {
+                insert_ms: 3000,
+                normal_ms: 1500,
+                visual_ms: 500,
+            }
+        );
+    }
+}
@@ -23,8 +23,36 @@ impl VimMode {
     }
 }
 
+impl std::str::FromStr for VimMode {
+    type Err = String;
+
+    /// Parse a mode name or its initial, in any case (e.g. "normal", "N")
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "insert" | "i" => Ok(Self::Insert),
+            "normal" | "n" => Ok(Self::Normal),
+            "visual" | "v" => Ok(Self::Visual),
+            _ => Err(format!("Unknown mode: {}", name)),
+        }
+    }
+}
+
 impl std::fmt::Display for VimMode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.as_str())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!("insert".parse(), Ok(VimMode::Insert));
+        assert_eq!("Normal".parse(), Ok(VimMode::Normal));
+        assert_eq!("v".parse(), Ok(VimMode::Visual));
+        assert_eq!("replace".parse::<VimMode>(), Err("Unknown mode: replace".to_string()));
+        assert!("".parse::<VimMode>().is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//! Where a motion leaves the cursor, computed from text alone
+//!
+//! Pure offset math shared by `preview_motion` and the text-based motions. Word and line
+//! motions come from `word_motion` and `line_motion`; this adds the character, vertical,
+//! paragraph and find-char motions. Offsets are char indices.
+
+use serde::Deserialize;
+
+use super::commands::VimCommand;
+use super::line_motion::{line_end, line_motion_target, line_start};
+use super::word_motion::word_motion_target;
+
+/// `f`, `F`, `t` and `T`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindKind {
+    /// `f`: onto the next occurrence
+    Find,
+    /// `F`: onto the previous occurrence
+    FindBackward,
+    /// `t`: just before the next occurrence
+    Till,
+    /// `T`: just after the previous occurrence
+    TillBackward,
+}
+
+/// A motion to compute, named as `run_vim_command` names commands, e.g. "word_forward",
+/// or `{ "find": "till", "char": "x" }` for a find-char motion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Motion {
+    Command(VimCommand),
+    FindChar { find: FindKind, char: char },
+}
+
+/// Last char of the line containing `pos`, where normal mode's cursor stops
+fn last_char_of_line(chars: &[char], pos: usize) -> usize {
+    let start = line_start(chars, pos);
+    line_end(chars, pos).saturating_sub(1).max(start)
+}
+
+/// `h`: left within the line
+pub fn char_left(chars: &[char], pos: usize, count: u32) -> usize {
+    pos.saturating_sub(count as usize).max(line_start(chars, pos))
+}
+
+/// `l`: right within the line, stopping on its last char
+pub fn char_right(chars: &[char], pos: usize, count: u32) -> usize {
+    (pos + count as usize).min(last_char_of_line(chars, pos))
+}
+
+/// `j`: same column `count` lines down, clamped to the target line
+pub fn line_down(chars: &[char], pos: usize, count: u32) -> usize {
+    let column = pos - line_start(chars, pos);
+    let mut start = line_start(chars, pos);
+    for _ in 0..count {
+        let end = line_end(chars, start);
+        if end >= chars.len() {
+            break;
+        }
+        start = end + 1;
+    }
+    (start + column).min(last_char_of_line(chars, start))
+}
+
+/// `k`: same column `count` lines up, clamped to the target line
+pub fn line_up(chars: &[char], pos: usize, count: u32) -> usize {
+    let column = pos - line_start(chars, pos);
+    let mut start = line_start(chars, pos);
+    for _ in 0..count {
+        if start == 0 {
+            break;
+        }
+        start = line_start(chars, start - 1);
+    }
+    (start + column).min(last_char_of_line(chars, start))
+}
+
+/// Whether the line starting at `start` is empty
+fn is_blank_line(chars: &[char], start: usize) -> bool {
+    chars.get(start).is_none_or(|&c| c == '\n')
+}
+
+/// `}`: the next empty line, or the end of the text
+pub fn paragraph_down(chars: &[char], pos: usize, count: u32) -> usize {
+    let mut pos = pos;
+    for _ in 0..count {
+        // Skip the empty lines we're on, then find the next one
+        let mut start = line_start(chars, pos);
+        while start < chars.len() && is_blank_line(chars, start) {
+            start = line_end(chars, start) + 1;
+        }
+        loop {
+            let end = line_end(chars, start);
+            if end >= chars.len() {
+                return chars.len();
+            }
+            start = end + 1;
+            if is_blank_line(chars, start) {
+                break;
+            }
+        }
+        pos = start;
+    }
+    pos
+}
+
+/// `{`: the previous empty line, or the start of the text
+pub fn paragraph_up(chars: &[char], pos: usize, count: u32) -> usize {
+    let mut pos = pos;
+    for _ in 0..count {
+        let mut start = line_start(chars, pos);
+        while start > 0 && is_blank_line(chars, start) {
+            start = line_start(chars, start - 1);
+        }
+        loop {
+            if start == 0 {
+                return 0;
+            }
+            start = line_start(chars, start - 1);
+            if is_blank_line(chars, start) {
+                break;
+            }
+        }
+        pos = start;
+    }
+    pos
+}
+
+/// Target of a find-char motion within the current line, or None if `target` doesn't occur
+/// `count` times on it
+pub fn find_char_target(kind: FindKind, chars: &[char], pos: usize, target: char, count: u32) -> Option<usize> {
+    let start = line_start(chars, pos);
+    let end = line_end(chars, pos);
+    let nth = count.max(1) as usize - 1;
+    match kind {
+        FindKind::Find => ((pos + 1).min(end)..end).filter(|&i| chars[i] == target).nth(nth),
+        FindKind::Till => find_char_target(FindKind::Find, chars, pos, target, count).map(|i| i - 1),
+        FindKind::FindBackward => (start..pos).rev().filter(|&i| chars[i] == target).nth(nth),
+        FindKind::TillBackward => {
+            find_char_target(FindKind::FindBackward, chars, pos, target, count).map(|i| i + 1)
+        }
+    }
+}
+
+/// Where `motion` repeated `count` times leaves the cursor, or None if it isn't a motion that
+/// can be computed from text (e.g. page motions) or a find-char target isn't on the line
+pub fn motion_target(motion: Motion, chars: &[char], pos: usize, count: u32) -> Option<usize> {
+    let pos = pos.min(chars.len());
+    let command = match motion {
+        Motion::FindChar { find, char } => return find_char_target(find, chars, pos, char, count),
+        Motion::Command(command) => command,
+    };
+    let count = count.max(1);
+    let target = match command {
+        VimCommand::MoveLeft => char_left(chars, pos, count),
+        VimCommand::MoveRight => char_right(chars, pos, count),
+        VimCommand::MoveUp => line_up(chars, pos, count),
+        VimCommand::MoveDown => line_down(chars, pos, count),
+        VimCommand::ParagraphUp => paragraph_up(chars, pos, count),
+        VimCommand::ParagraphDown => paragraph_down(chars, pos, count),
+        _ => {
+            return word_motion_target(command, chars, pos, count)
+                .or_else(|| line_motion_target(command, chars, pos, count))
+        }
+    };
+    Some(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vim::state::test_util::chars;
+
+    fn target(command: VimCommand, text: &str, pos: usize, count: u32) -> Option<usize> {
+        motion_target(Motion::Command(command), &chars(text), pos, count)
+    }
+
+    fn find(kind: FindKind, text: &str, pos: usize, c: char, count: u32) -> Option<usize> {
+        find_char_target(kind, &chars(text), pos, c, count)
+    }
+
+    #[test]
+    fn test_char_left_and_right() {
+        let text = "one\ntwo";
+        assert_eq!(target(VimCommand::MoveLeft, text, 6, 1), Some(5));
+        assert_eq!(target(VimCommand::MoveLeft, text, 6, 9), Some(4)); // stops at line start
+        assert_eq!(target(VimCommand::MoveRight, text, 0, 1), Some(1));
+        assert_eq!(target(VimCommand::MoveRight, text, 0, 9), Some(2)); // stops on the last char
+        assert_eq!(target(VimCommand::MoveRight, "one\n\nx", 4, 1), Some(4)); // empty line
+    }
+
+    #[test]
+    fn test_line_up_and_down_keep_column() {
+        let text = "first\nab\nthird line";
+        assert_eq!(target(VimCommand::MoveDown, text, 3, 1), Some(7)); // clamped to "ab"
+        assert_eq!(target(VimCommand::MoveDown, text, 3, 2), Some(12));
+        assert_eq!(target(VimCommand::MoveDown, text, 12, 1), Some(12)); // last line
+        assert_eq!(target(VimCommand::MoveUp, text, 13, 1), Some(7));
+        assert_eq!(target(VimCommand::MoveUp, text, 13, 2), Some(4));
+        assert_eq!(target(VimCommand::MoveUp, text, 2, 1), Some(2)); // first line
+    }
+
+    #[test]
+    fn test_vertical_motion_onto_empty_line() {
+        let text = "abc\n\nabc";
+        assert_eq!(target(VimCommand::MoveDown, text, 2, 1), Some(4));
+        assert_eq!(target(VimCommand::MoveDown, text, 2, 2), Some(7));
+    }
+
+    #[test]
+    fn test_paragraph_motions() {
+        let text = "one\ntwo\n\nthree\n\n\nfour";
+        assert_eq!(target(VimCommand::ParagraphDown, text, 0, 1), Some(8));
+        assert_eq!(target(VimCommand::ParagraphDown, text, 8, 1), Some(15));
+        assert_eq!(target(VimCommand::ParagraphDown, text, 0, 2), Some(15));
+        assert_eq!(target(VimCommand::ParagraphDown, text, 18, 1), Some(21)); // end of text
+        assert_eq!(target(VimCommand::ParagraphUp, text, 18, 1), Some(16));
+        assert_eq!(target(VimCommand::ParagraphUp, text, 16, 1), Some(8));
+        assert_eq!(target(VimCommand::ParagraphUp, text, 18, 3), Some(0));
+    }
+
+    #[test]
+    fn test_find_char() {
+        let text = "a,b,c,d\nx,y";
+        assert_eq!(find(FindKind::Find, text, 0, ',', 1), Some(1));
+        assert_eq!(find(FindKind::Find, text, 1, ',', 1), Some(3));
+        assert_eq!(find(FindKind::Find, text, 0, ',', 3), Some(5));
+        assert_eq!(find(FindKind::Find, text, 0, ',', 4), None); // not past the line end
+        assert_eq!(find(FindKind::Find, text, 0, 'q', 1), None);
+        assert_eq!(find(FindKind::FindBackward, text, 6, ',', 1), Some(5));
+        assert_eq!(find(FindKind::FindBackward, text, 6, ',', 2), Some(3));
+        assert_eq!(find(FindKind::FindBackward, text, 9, ',', 1), None); // not before the line start
+    }
+
+    #[test]
+    fn test_till_char() {
+        let text = "a,b,c";
+        assert_eq!(find(FindKind::Till, text, 0, 'c', 1), Some(3));
+        // Right before an occurrence, `t` stays put
+        assert_eq!(find(FindKind::Till, text, 0, ',', 1), Some(0));
+        assert_eq!(find(FindKind::Till, text, 0, ',', 2), Some(2));
+        assert_eq!(find(FindKind::Till, text, 0, 'q', 1), None);
+        assert_eq!(find(FindKind::TillBackward, text, 4, 'a', 1), Some(1));
+        assert_eq!(find(FindKind::TillBackward, text, 4, ',', 1), Some(4));
+        assert_eq!(find(FindKind::TillBackward, text, 4, ',', 2), Some(2));
+        assert_eq!(find(FindKind::TillBackward, text, 0, ',', 1), None);
+    }
+
+    #[test]
+    fn test_word_and_line_motions_are_included() {
+        let text = "one two\nthree";
+        assert_eq!(target(VimCommand::WordForward, text, 0, 1), Some(4));
+        assert_eq!(target(VimCommand::WordEnd, text, 0, 2), Some(6));
+        assert_eq!(target(VimCommand::WordBackward, text, 8, 1), Some(4));
+        assert_eq!(target(VimCommand::LineEnd, text, 0, 1), Some(7));
+        assert_eq!(target(VimCommand::LineStart, text, 10, 1), Some(8));
+        assert_eq!(target(VimCommand::GoToLine, text, 0, 2), Some(8));
+        assert_eq!(target(VimCommand::DocumentEnd, text, 0, 1), Some(13));
+        assert_eq!(target(VimCommand::PageDown, text, 0, 1), None);
+        assert_eq!(target(VimCommand::DeleteLine, text, 0, 1), None);
+    }
+
+    #[test]
+    fn test_motion_names() {
+        let motion: Motion = serde_json::from_str("\"word_end_backward\"").unwrap();
+        assert_eq!(motion, Motion::Command(VimCommand::WordEndBackward));
+        let motion: Motion = serde_json::from_str(r#"{"find": "till_backward", "char": "x"}"#).unwrap();
+        assert_eq!(motion, Motion::FindChar { find: FindKind::TillBackward, char: 'x' });
+    }
+}
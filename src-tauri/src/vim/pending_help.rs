@@ -0,0 +1,166 @@
+//! Which-key style help: the keys that can follow a pending sequence
+//!
+//! Prefixes use the notation of `VimState::get_pending_keys`, e.g. `d`, `2d3`, `dg` or `di`.
+//! Counts don't change what can follow, so they are ignored.
+
+use serde::Serialize;
+
+/// A key that completes or extends a pending sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Continuation {
+    pub key: &'static str,
+    pub description: &'static str,
+}
+
+/// Motions accepted after an operator
+const OPERATOR_MOTIONS: &[(&str, &str)] = &[
+    ("h", "left"),
+    ("j", "down"),
+    ("k", "up"),
+    ("l", "right"),
+    ("w", "to next word"),
+    ("e", "to end of word"),
+    ("b", "to previous word"),
+    ("0", "to line start"),
+    ("^", "to first character"),
+    ("$", "to line end"),
+    ("|", "to column"),
+    ("{", "to previous paragraph"),
+    ("}", "to next paragraph"),
+    ("G", "to end of document"),
+    ("g", "more motions"),
+    ("i", "inner text object"),
+    ("a", "around text object"),
+];
+
+/// Keys after `g` in normal mode
+const G_COMBOS: &[(&str, &str)] = &[
+    ("g", "start of document"),
+    ("e", "end of previous word"),
+    ("E", "end of previous WORD"),
+    ("j", "down"),
+    ("k", "up"),
+    ("0", "line start"),
+    ("$", "line end"),
+    ("q", "rewrap lines"),
+    ("w", "rewrap lines, keeping the cursor"),
+];
+
+/// Keys after `g` following an operator
+const OPERATOR_G_COMBOS: &[(&str, &str)] = &[
+    ("g", "to start of document"),
+    ("e", "to end of previous word"),
+    ("E", "to end of previous WORD"),
+];
+
+/// Text objects after `i` or `a`
+const TEXT_OBJECTS: &[(&str, &str)] = &[("w", "word"), ("`", "inline code or code block")];
+
+/// Keys that can follow `prefix`, or an empty list if nothing is pending
+pub fn continuations(prefix: &str) -> Vec<Continuation> {
+    // `"` waits for a register name, `"3` for the paste
+    if prefix.ends_with('"') {
+        return vec![to_continuation(&("1-9", "recent delete"))];
+    }
+    if prefix.starts_with('"') {
+        return vec![to_continuation(&("p", "paste register"))];
+    }
+    let keys: String = prefix.chars().filter(|c| !c.is_ascii_digit()).collect();
+    match keys.as_str() {
+        "q" => return vec![to_continuation(&("a-z", "record macro"))],
+        "z" => {
+            return [("z", "caret to center"), ("t", "caret to top"), ("b", "caret to bottom")]
+                .iter()
+                .map(to_continuation)
+                .collect()
+        }
+        "@" => {
+            return [("a-z", "play macro"), ("@", "play last macro")]
+                .iter()
+                .map(to_continuation)
+                .collect()
+        }
+        _ => {}
+    }
+
+    let doubled = match keys.as_str() {
+        "d" => Some(("d", "delete line")),
+        "y" => Some(("y", "yank line")),
+        "c" => Some(("c", "change line")),
+        "gq" => Some(("q", "rewrap line")),
+        "gw" => Some(("w", "rewrap line")),
+        _ => None,
+    };
+    if let Some(doubled) = doubled {
+        return std::iter::once(&doubled)
+            .chain(OPERATOR_MOTIONS)
+            .map(to_continuation)
+            .collect();
+    }
+
+    let table: &[(&str, &str)] = match keys.as_str() {
+        "g" => G_COMBOS,
+        "dg" | "yg" | "cg" | "gqg" | "gwg" => OPERATOR_G_COMBOS,
+        "di" | "da" | "yi" | "ya" | "ci" | "ca" | "gqi" | "gqa" | "gwi" | "gwa" => TEXT_OBJECTS,
+        "r" => &[("{char}", "replace character")],
+        ">" => &[(">", "indent line")],
+        "<" => &[("<", "outdent line")],
+        _ => &[],
+    };
+    table.iter().map(to_continuation).collect()
+}
+
+fn to_continuation(&(key, description): &(&'static str, &'static str)) -> Continuation {
+    Continuation { key, description }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(prefix: &str) -> Vec<&'static str> {
+        continuations(prefix).into_iter().map(|c| c.key).collect()
+    }
+
+    #[test]
+    fn test_continuations_after_d() {
+        let keys = keys("d");
+        assert_eq!(keys[0], "d");
+        for key in ["w", "e", "b", "$", "0", "G", "g", "i", "a"] {
+            assert!(keys.contains(&key), "missing {}", key);
+        }
+        assert!(!keys.contains(&"y"));
+        assert_eq!(continuations("d")[0].description, "delete line");
+    }
+
+    #[test]
+    fn test_register_continuations() {
+        assert_eq!(keys("\""), vec!["1-9"]);
+        assert_eq!(keys("\"3"), vec!["p"]);
+        assert_eq!(keys("\"32"), vec!["p"]);
+    }
+
+    #[test]
+    fn test_macro_continuations() {
+        assert_eq!(keys("q"), vec!["a-z"]);
+        assert_eq!(keys("3@"), vec!["a-z", "@"]);
+        assert_eq!(keys("z"), vec!["z", "t", "b"]);
+    }
+
+    #[test]
+    fn test_counts_are_ignored() {
+        assert_eq!(keys("2d3"), keys("d"));
+        assert_eq!(keys("12g"), keys("g"));
+    }
+
+    #[test]
+    fn test_nested_prefixes() {
+        assert_eq!(keys("dg"), vec!["g", "e", "E"]);
+        assert_eq!(keys("ci"), vec!["w", "`"]);
+        assert_eq!(keys("gqi"), vec!["w", "`"]);
+        assert_eq!(keys("2gq")[0], "q");
+        assert!(keys("g").contains(&"e"));
+        assert!(keys("").is_empty());
+        assert!(keys("5").is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+//! Hard-wrapping text at a column, for `gq` and `gw`
+//!
+//! Offsets are char indices, as in `motion`.
+
+use std::ops::Range;
+
+use super::line_motion::{line_end, line_start};
+
+/// Chars of the whole lines from `from` to `to`, in either order, without the last newline
+pub fn line_range(chars: &[char], from: usize, to: usize) -> Range<usize> {
+    line_start(chars, from.min(to))..line_end(chars, from.max(to))
+}
+
+/// Rewrap `text` so no line is longer than `width` chars, unless a single word is
+/// Blank lines separate paragraphs and are kept; each paragraph keeps its first line's indent
+pub fn reflow(text: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut lines: Vec<String> = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    for line in text.split('\n') {
+        if line.trim().is_empty() {
+            wrap_paragraph(&paragraph, width, &mut lines);
+            paragraph.clear();
+            lines.push(String::new());
+        } else {
+            paragraph.push(line);
+        }
+    }
+    wrap_paragraph(&paragraph, width, &mut lines);
+    lines.join("\n")
+}
+
+/// Greedily fill lines of `width` chars with the words of a paragraph's lines
+fn wrap_paragraph(paragraph: &[&str], width: usize, lines: &mut Vec<String>) {
+    let Some(first) = paragraph.first() else {
+        return;
+    };
+    let indent: String = first.chars().take_while(|c| c.is_whitespace()).collect();
+    let mut line = indent.clone();
+    for word in paragraph.iter().flat_map(|line| line.split_whitespace()) {
+        let has_words = line.len() > indent.len();
+        if has_words && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::replace(&mut line, indent.clone()));
+        } else if has_words {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+}
+
+/// Offset in `formatted` of the char at `offset` in `original`, matched by how many
+/// non-blank chars come before it, as reflowing only moves whitespace
+pub fn map_offset(original: &str, formatted: &str, offset: usize) -> usize {
+    let before = original
+        .chars()
+        .take(offset)
+        .filter(|c| !c.is_whitespace())
+        .count();
+    let mut seen = 0;
+    for (i, c) in formatted.chars().enumerate() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if seen == before {
+            return i;
+        }
+        seen += 1;
+    }
+    formatted.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wraps_long_lines() {
+        let text = "the quick brown fox jumps over the lazy dog";
+        assert_eq!(reflow(text, 15), "the quick brown\nfox jumps over\nthe lazy dog");
+        assert_eq!(reflow(text, 80), text);
+    }
+
+    #[test]
+    fn test_joins_short_lines() {
+        assert_eq!(reflow("one\ntwo  three\nfour", 80), "one two three four");
+        assert_eq!(reflow("one\ntwo three\nfour", 9), "one two\nthree\nfour");
+    }
+
+    #[test]
+    fn test_keeps_paragraphs_and_indent() {
+        let text = "  first para\n  goes on\n\nsecond para here";
+        assert_eq!(reflow(text, 12), "  first para\n  goes on\n\nsecond para\nhere");
+        assert_eq!(reflow(text, 80), "  first para goes on\n\nsecond para here");
+    }
+
+    #[test]
+    fn test_long_words_get_their_own_line() {
+        assert_eq!(reflow("a verylongword b", 5), "a\nverylongword\nb");
+        assert_eq!(reflow("word", 0), "word");
+    }
+
+    #[test]
+    fn test_line_range() {
+        let chars: Vec<char> = "one\ntwo\nthree".chars().collect();
+        assert_eq!(line_range(&chars, 5, 1), 0..7);
+        assert_eq!(line_range(&chars, 9, 9), 8..13);
+    }
+
+    #[test]
+    fn test_map_offset() {
+        let original = "one\ntwo  three";
+        let formatted = "one two three";
+        assert_eq!(map_offset(original, formatted, 4), 4); // 't' of two
+        assert_eq!(map_offset(original, formatted, 9), 8); // 't' of three
+        assert_eq!(map_offset(original, formatted, 14), 13); // the end
+    }
+}
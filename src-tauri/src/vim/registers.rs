@@ -0,0 +1,230 @@
+//! Registers for `p`
+//!
+//! Yanked and deleted text lands on the system clipboard, which is the unnamed register,
+//! unless `yank_to_system_clipboard` is off: then it's kept here and the clipboard restored.
+//! `"+` and `"*` always name the clipboard.
+//! The last few deletes are also kept in a ring, pasted with `"1p`..`"9p` or by cycling with
+//! Ctrl+P/Ctrl+N after a paste. Registers are shared because deletes run away from the state
+//! machine.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::keyboard::KeyCode;
+
+/// Number of deletes kept, as vim's `"1`-`"9`
+pub const DELETE_RING_SIZE: usize = 9;
+
+/// A register `p` pastes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    /// The system clipboard
+    Unnamed,
+    /// `"1`-`"9`, newest delete first
+    Numbered(u8),
+    /// `"+` or `"*`: the system clipboard, even when yanks are kept off it
+    Clipboard,
+}
+
+impl Register {
+    /// Register named by the key typed after `"`
+    pub fn from_key(keycode: KeyCode, shift: bool) -> Option<Self> {
+        match (keycode, shift) {
+            (KeyCode::Equal, true) | (KeyCode::Num8, true) => Some(Self::Clipboard),
+            (_, false) => match keycode.to_digit() {
+                Some(n @ 1..=9) => Some(Self::Numbered(n as u8)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Name shown in pending keys, e.g. `"3`
+    pub fn name(&self) -> String {
+        match self {
+            Self::Unnamed => "\"\"".to_string(),
+            Self::Numbered(n) => format!("\"{}", n),
+            Self::Clipboard => "\"+".to_string(),
+        }
+    }
+
+    /// The next older delete (Ctrl+P), if the ring has one
+    pub fn older(self, ring_len: usize) -> Option<Self> {
+        let next = match self {
+            Self::Unnamed | Self::Clipboard => 1,
+            Self::Numbered(n) => n + 1,
+        };
+        (next as usize <= ring_len).then_some(Self::Numbered(next))
+    }
+
+    /// The next newer delete (Ctrl+N), ending at the clipboard
+    pub fn newer(self) -> Option<Self> {
+        match self {
+            Self::Unnamed | Self::Clipboard => None,
+            Self::Numbered(1) => Some(Self::Unnamed),
+            Self::Numbered(n) => Some(Self::Numbered(n - 1)),
+        }
+    }
+}
+
+/// Where a yank or delete leaves its text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YankTarget {
+    /// The system clipboard
+    Clipboard,
+    /// Only the unnamed register; the clipboard gets its previous contents back
+    Internal,
+}
+
+/// Where `p` reads a register from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasteSource {
+    Clipboard,
+    /// The unnamed register kept away from the clipboard
+    Internal,
+    /// `"1`-`"9`
+    Deleted(u8),
+}
+
+/// Where yanks and deletes go
+pub fn yank_target(yank_to_system_clipboard: bool) -> YankTarget {
+    if yank_to_system_clipboard {
+        YankTarget::Clipboard
+    } else {
+        YankTarget::Internal
+    }
+}
+
+/// Where pasting `register` reads from
+pub fn paste_source(register: Register, yank_to_system_clipboard: bool) -> PasteSource {
+    match register {
+        Register::Numbered(n) => PasteSource::Deleted(n),
+        Register::Clipboard => PasteSource::Clipboard,
+        Register::Unnamed => match yank_target(yank_to_system_clipboard) {
+            YankTarget::Clipboard => PasteSource::Clipboard,
+            YankTarget::Internal => PasteSource::Internal,
+        },
+    }
+}
+
+/// The last `DELETE_RING_SIZE` deleted texts
+#[derive(Debug, Default)]
+struct DeleteRing {
+    entries: VecDeque<String>,
+}
+
+impl DeleteRing {
+    /// Add a delete as `"1`, shifting older ones up and dropping the oldest
+    fn push(&mut self, text: String) {
+        self.entries.push_front(text);
+        self.entries.truncate(DELETE_RING_SIZE);
+    }
+
+    /// Text of `"n`, 1 being the newest
+    fn get(&self, n: u8) -> Option<&str> {
+        self.entries.get((n as usize).checked_sub(1)?).map(String::as_str)
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+static DELETE_RING: Mutex<DeleteRing> = Mutex::new(DeleteRing {
+    entries: VecDeque::new(),
+});
+
+/// Yanked or deleted text kept off the clipboard
+static UNNAMED: Mutex<Option<String>> = Mutex::new(None);
+
+/// Keep yanked or deleted text in the unnamed register
+pub fn set_unnamed(text: String) {
+    *UNNAMED.lock().unwrap() = Some(text);
+}
+
+/// Text of the unnamed register, when it's kept off the clipboard
+pub fn unnamed() -> Option<String> {
+    UNNAMED.lock().unwrap().clone()
+}
+
+/// Record deleted text in the ring
+pub fn record_delete(text: String) {
+    if !text.is_empty() {
+        DELETE_RING.lock().unwrap().push(text);
+    }
+}
+
+/// Text of `"n`
+pub fn delete_register(n: u8) -> Option<String> {
+    DELETE_RING.lock().unwrap().get(n).map(str::to_string)
+}
+
+/// Number of deletes in the ring
+pub fn delete_ring_len() -> usize {
+    DELETE_RING.lock().unwrap().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_rotation() {
+        let mut ring = DeleteRing::default();
+        assert_eq!(ring.len(), 0);
+        for i in 1..=DELETE_RING_SIZE + 2 {
+            ring.push(format!("delete {}", i));
+        }
+        assert_eq!(ring.len(), DELETE_RING_SIZE);
+        assert_eq!(ring.get(1), Some("delete 11"));
+        assert_eq!(ring.get(9), Some("delete 3"));
+        assert_eq!(ring.get(0), None);
+        assert_eq!(ring.get(10), None);
+    }
+
+    #[test]
+    fn test_register_from_key() {
+        assert_eq!(Register::from_key(KeyCode::Num1, false), Some(Register::Numbered(1)));
+        assert_eq!(Register::from_key(KeyCode::Num9, false), Some(Register::Numbered(9)));
+        assert_eq!(Register::from_key(KeyCode::Num0, false), None);
+        assert_eq!(Register::from_key(KeyCode::Num1, true), None);
+        assert_eq!(Register::from_key(KeyCode::A, false), None);
+        assert_eq!(Register::Numbered(3).name(), "\"3");
+    }
+
+    #[test]
+    fn test_routing_per_setting() {
+        assert_eq!(yank_target(true), YankTarget::Clipboard);
+        assert_eq!(yank_target(false), YankTarget::Internal);
+
+        assert_eq!(paste_source(Register::Unnamed, true), PasteSource::Clipboard);
+        assert_eq!(paste_source(Register::Unnamed, false), PasteSource::Internal);
+        // The delete ring is the same either way
+        assert_eq!(paste_source(Register::Numbered(2), true), PasteSource::Deleted(2));
+        assert_eq!(paste_source(Register::Numbered(2), false), PasteSource::Deleted(2));
+    }
+
+    #[test]
+    fn test_clipboard_registers() {
+        assert_eq!(Register::from_key(KeyCode::Equal, true), Some(Register::Clipboard));
+        assert_eq!(Register::from_key(KeyCode::Num8, true), Some(Register::Clipboard));
+        assert_eq!(Register::from_key(KeyCode::Equal, false), None);
+        assert_eq!(Register::from_key(KeyCode::Num8, false), Some(Register::Numbered(8)));
+        assert_eq!(Register::Clipboard.name(), "\"+");
+        // `"+p` reads the clipboard whatever the setting
+        assert_eq!(paste_source(Register::Clipboard, true), PasteSource::Clipboard);
+        assert_eq!(paste_source(Register::Clipboard, false), PasteSource::Clipboard);
+    }
+
+    #[test]
+    fn test_cycling() {
+        assert_eq!(Register::Unnamed.older(2), Some(Register::Numbered(1)));
+        assert_eq!(Register::Numbered(1).older(2), Some(Register::Numbered(2)));
+        assert_eq!(Register::Numbered(2).older(2), None);
+        assert_eq!(Register::Unnamed.older(0), None);
+
+        assert_eq!(Register::Numbered(2).newer(), Some(Register::Numbered(1)));
+        assert_eq!(Register::Numbered(1).newer(), Some(Register::Unnamed));
+        assert_eq!(Register::Unnamed.newer(), None);
+    }
+}
@@ -0,0 +1,118 @@
+//! `zz`, `zt` and `zb`: scroll the view so the caret is centered, at the top or at the bottom,
+//! and `Ctrl+E`/`Ctrl+Y`: scroll it by lines without moving the caret
+//!
+//! The caret's and the scroll view's screen frames come from accessibility, and the view is
+//! moved with synthesized scroll-wheel events over it.
+
+use crate::keyboard;
+use crate::nvim_edit::accessibility::{self, ElementFrame};
+
+/// Line height to scroll by when the caret's frame isn't available
+const DEFAULT_LINE_HEIGHT: f64 = 16.0;
+
+/// Where the caret should end up in the view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollTo {
+    Center,
+    Top,
+    Bottom,
+}
+
+/// Pixels to scroll the content up so the caret lands at `to` in the view
+/// Negative values scroll it down
+pub fn scroll_amount(to: ScrollTo, caret: &ElementFrame, view: &ElementFrame) -> f64 {
+    match to {
+        ScrollTo::Center => (caret.y + caret.height / 2.0) - (view.y + view.height / 2.0),
+        ScrollTo::Top => caret.y - view.y,
+        ScrollTo::Bottom => (caret.y + caret.height) - (view.y + view.height),
+    }
+}
+
+/// Pixels to scroll the content up for `lines` lines of `line_height` (the caret's height)
+/// Negative `lines` scroll it down
+pub fn lines_amount(lines: i32, line_height: Option<f64>) -> f64 {
+    let line_height = line_height.filter(|&height| height > 0.0).unwrap_or(DEFAULT_LINE_HEIGHT);
+    f64::from(lines) * line_height
+}
+
+/// Scroll the view around the focused element so the caret lands at `to`
+/// Does nothing if the element isn't in a scroll view
+pub fn scroll_caret(to: ScrollTo) -> Result<(), String> {
+    let Some(view) = accessibility::get_scroll_view_frame() else {
+        log::debug!("No scroll view around the focused element, not scrolling");
+        return Ok(());
+    };
+    let caret = accessibility::get_caret_frame().ok_or("Caret position isn't available")?;
+    let pixels = scroll_amount(to, &caret, &view).round() as i32;
+    if pixels == 0 {
+        return Ok(());
+    }
+    scroll_view(&view, pixels)
+}
+
+/// Scroll the view around the focused element by `lines` lines, leaving the caret where it is
+/// Positive values show the lines below (`Ctrl+E`), negative ones those above (`Ctrl+Y`)
+pub fn scroll_lines(lines: i32) -> Result<(), String> {
+    let Some(view) = accessibility::get_scroll_view_frame() else {
+        log::debug!("No scroll view around the focused element, not scrolling");
+        return Ok(());
+    };
+    let line_height = accessibility::get_caret_frame().map(|caret| caret.height);
+    scroll_view(&view, lines_amount(lines, line_height).round() as i32)
+}
+
+/// Scroll the content of `view` up by `pixels` with a single wheel event over it
+fn scroll_view(view: &ElementFrame, pixels: i32) -> Result<(), String> {
+    // Wheel values scroll the content down, the opposite of `scroll_amount`
+    keyboard::post_scroll_wheel(view.x + view.width / 2.0, view.y + view.height / 2.0, -pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(y: f64, height: f64) -> ElementFrame {
+        ElementFrame {
+            x: 0.0,
+            y,
+            width: 400.0,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_scroll_amount() {
+        let view = frame(100.0, 400.0);
+        // Caret at y 500..520, the view's center is at 300
+        let caret = frame(500.0, 20.0);
+        assert_eq!(scroll_amount(ScrollTo::Center, &caret, &view), 210.0);
+        assert_eq!(scroll_amount(ScrollTo::Top, &caret, &view), 400.0);
+        assert_eq!(scroll_amount(ScrollTo::Bottom, &caret, &view), 20.0);
+    }
+
+    #[test]
+    fn test_scroll_amount_above_the_view() {
+        let view = frame(100.0, 400.0);
+        let caret = frame(40.0, 20.0);
+        assert_eq!(scroll_amount(ScrollTo::Center, &caret, &view), -250.0);
+        assert_eq!(scroll_amount(ScrollTo::Top, &caret, &view), -60.0);
+        assert_eq!(scroll_amount(ScrollTo::Bottom, &caret, &view), -440.0);
+    }
+
+    #[test]
+    fn test_centered_caret_stays() {
+        let view = frame(100.0, 400.0);
+        let caret = frame(290.0, 20.0);
+        assert_eq!(scroll_amount(ScrollTo::Center, &caret, &view), 0.0);
+    }
+
+    #[test]
+    fn test_lines_amount() {
+        assert_eq!(lines_amount(1, Some(20.0)), 20.0);
+        assert_eq!(lines_amount(5, Some(20.0)), 100.0);
+        assert_eq!(lines_amount(-3, Some(20.0)), -60.0);
+        // Without a usable caret height lines are the default height
+        assert_eq!(lines_amount(2, None), 2.0 * DEFAULT_LINE_HEIGHT);
+        assert_eq!(lines_amount(2, Some(0.0)), 2.0 * DEFAULT_LINE_HEIGHT);
+    }
+}
@@ -0,0 +1,38 @@
+//! Apps focused so far this session, so an app's first focus can set the mode it starts in
+
+use std::collections::HashSet;
+
+/// Bundle identifiers of the apps seen this session, and the frontmost one
+#[derive(Debug, Default)]
+pub struct SeenApps {
+    seen: HashSet<String>,
+    current: Option<String>,
+}
+
+impl SeenApps {
+    /// Note the app with this bundle identifier as the frontmost one
+    /// Returns true when it's the app's first focus this session
+    pub fn focus(&mut self, bundle_id: &str) -> bool {
+        if self.current.as_deref() == Some(bundle_id) {
+            return false;
+        }
+        self.current = Some(bundle_id.to_string());
+        self.seen.insert(bundle_id.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_first_focus_counts() {
+        let mut apps = SeenApps::default();
+        assert!(apps.focus("com.apple.Terminal"));
+        assert!(!apps.focus("com.apple.Terminal"));
+        assert!(apps.focus("com.apple.Notes"));
+        // Coming back later isn't a first focus
+        assert!(!apps.focus("com.apple.Terminal"));
+        assert!(!apps.focus("com.apple.Notes"));
+    }
+}
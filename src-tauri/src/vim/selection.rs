@@ -0,0 +1,181 @@
+//! Visual mode `o`: swap the ends of the selection, so motions move the other end
+//!
+//! The range is read from `AXSelectedTextRange`. A range set through accessibility grows
+//! from its start, so a head at the end is set directly; a head at the start is selected
+//! back from the end with Shift+Left. Offsets are char indices.
+//!
+//! Linewise visual operators grow the selection to the whole lines it touches first.
+
+use std::ops::Range;
+
+use crate::keyboard;
+use crate::nvim_edit::accessibility;
+use crate::nvim_edit::text_source::TextReader;
+
+use super::reflow::line_range;
+
+/// Ends of a selection: the anchor stays put and the head moves with motions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionEnds {
+    pub anchor: usize,
+    pub head: usize,
+}
+
+impl SelectionEnds {
+    /// Ends of `length` chars at `start`, with the head at the start or the end
+    pub fn from_range(start: usize, length: usize, head_at_start: bool) -> Self {
+        let end = start + length;
+        if head_at_start {
+            Self { anchor: end, head: start }
+        } else {
+            Self { anchor: start, head: end }
+        }
+    }
+
+    pub fn swapped(self) -> Self {
+        Self {
+            anchor: self.head,
+            head: self.anchor,
+        }
+    }
+
+    /// The selected (start, length)
+    pub fn range(self) -> (usize, usize) {
+        (self.anchor.min(self.head), self.anchor.abs_diff(self.head))
+    }
+
+    pub fn head_at_start(self) -> bool {
+        self.head < self.anchor
+    }
+}
+
+/// Swap the ends of the focused field's selection, whose head is at its start or end
+pub fn swap_selection_ends(head_at_start: bool) -> Result<(), String> {
+    let selection =
+        accessibility::get_focused_text_selection().ok_or("The selection range isn't available")?;
+    if selection.length == 0 {
+        return Ok(());
+    }
+    let ends = SelectionEnds::from_range(selection.start, selection.length, head_at_start).swapped();
+    let (start, length) = ends.range();
+    let element = accessibility::capture_focused_element();
+
+    if !ends.head_at_start() {
+        // Setting the range again moves the anchor to its start
+        let set = element
+            .as_ref()
+            .map(|element| accessibility::set_selected_range(element, start, length));
+        match set {
+            Some(Ok(())) => return Ok(()),
+            Some(Err(e)) => log::debug!("Setting the swapped selection failed, using arrows: {}", e),
+            None => {}
+        }
+        // Left collapses the selection to its start
+        keyboard::cursor_left(1, false)?;
+        return keyboard::cursor_right(length as u32, true);
+    }
+
+    // Place the caret at the new anchor, the end, and select back to the start
+    let placed = element
+        .as_ref()
+        .is_some_and(|element| accessibility::set_selected_range(element, ends.anchor, 0).is_ok());
+    if !placed {
+        // Right collapses the selection to its end
+        keyboard::cursor_right(1, false)?;
+    }
+    keyboard::cursor_left(length as u32, true)
+}
+
+/// The whole lines a selection over several lines touches, with the newline after them,
+/// or before them on the field's last line, when `include_newline`
+/// None when the selection is within one line
+pub fn line_span(chars: &[char], start: usize, length: usize, include_newline: bool) -> Option<Range<usize>> {
+    let end = (start + length).min(chars.len());
+    if !chars[start.min(end)..end.saturating_sub(1).max(start)].contains(&'\n') {
+        return None;
+    }
+    let lines = line_range(chars, start, end.saturating_sub(1));
+    if !include_newline {
+        return Some(lines);
+    }
+    if lines.end < chars.len() {
+        Some(lines.start..lines.end + 1)
+    } else {
+        Some(lines.start.saturating_sub(1)..lines.end)
+    }
+}
+
+/// Grow a selection over several lines to their whole lines, for a linewise operator
+pub fn select_whole_lines(include_newline: bool) -> Result<(), String> {
+    let mut text = TextReader::with_caret();
+    let selection = text.read().ok_or("The selection range isn't available")?;
+    let chars: Vec<char> = selection.text.chars().collect();
+    let Some(lines) = line_span(&chars, selection.start, selection.length, include_newline) else {
+        return Ok(());
+    };
+    let element = accessibility::capture_focused_element();
+    let set = element
+        .as_ref()
+        .is_some_and(|element| accessibility::set_selected_range(element, lines.start, lines.len()).is_ok());
+    if set {
+        return Ok(());
+    }
+    // Left collapses the selection to its start
+    keyboard::cursor_left(1, false)?;
+    accessibility::select_range_or_arrows(None, selection.start, lines.start, lines.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swap_ends() {
+        // "hello" selected from its start, the head after the `o`
+        let ends = SelectionEnds::from_range(3, 5, false);
+        assert_eq!(ends, SelectionEnds { anchor: 3, head: 8 });
+        let swapped = ends.swapped();
+        assert_eq!(swapped, SelectionEnds { anchor: 8, head: 3 });
+        assert!(swapped.head_at_start());
+        // The selected text stays the same
+        assert_eq!(swapped.range(), (3, 5));
+        assert_eq!(swapped.swapped(), ends);
+    }
+
+    #[test]
+    fn test_swap_ends_from_start() {
+        let ends = SelectionEnds::from_range(10, 2, true);
+        assert_eq!(ends, SelectionEnds { anchor: 12, head: 10 });
+        let swapped = ends.swapped();
+        assert!(!swapped.head_at_start());
+        assert_eq!(swapped.range(), (10, 2));
+    }
+
+    #[test]
+    fn test_empty_selection() {
+        let ends = SelectionEnds::from_range(4, 0, false);
+        assert_eq!(ends.swapped(), ends);
+        assert_eq!(ends.range(), (4, 0));
+        assert!(!ends.head_at_start());
+    }
+
+    #[test]
+    fn test_line_span() {
+        let chars: Vec<char> = "one\ntwo\nthree".chars().collect();
+        // "ne\ntw" grows to "one\ntwo" and the newline after it
+        assert_eq!(line_span(&chars, 1, 5, false), Some(0..7));
+        assert_eq!(line_span(&chars, 1, 5, true), Some(0..8));
+        // On the last line the newline before goes instead
+        assert_eq!(line_span(&chars, 5, 5, true), Some(3..13));
+        assert_eq!(line_span(&chars, 5, 5, false), Some(4..13));
+    }
+
+    #[test]
+    fn test_line_span_within_a_line() {
+        let chars: Vec<char> = "one\ntwo".chars().collect();
+        assert_eq!(line_span(&chars, 0, 2, true), None);
+        // A selection ending with its line's newline is still on one line
+        assert_eq!(line_span(&chars, 1, 3, true), None);
+        assert_eq!(line_span(&chars, 2, 0, true), None);
+    }
+}
@@ -1,5 +1,22 @@
-use crate::keyboard::{self, KeyCode};
-use super::super::commands::{Operator, VimCommand};
+use crate::keyboard::{self, KeyCode, Modifiers};
+use crate::nvim_edit::accessibility;
+use super::super::commands::{MotionOptions, Operator, VimCommand};
+use super::super::custom_command::{self, Primitive};
+use super::super::ex_command::CommandLine;
+use super::super::registers::{self, PasteSource, Register};
+use super::super::selection;
+
+/// Pause between the steps of a macro, so the app applies each before the next
+const MACRO_STEP_DELAY: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// One step of a played macro
+#[derive(Debug, Clone)]
+pub enum MacroStep {
+    /// An action a key produced
+    Action(VimAction),
+    /// A key the state machine passed through, typed again
+    Key { keycode: KeyCode, modifiers: Modifiers },
+}
 
 /// Action to execute after suppressing the key event
 #[derive(Debug, Clone)]
@@ -7,49 +24,176 @@ pub enum VimAction {
     /// Execute a vim command
     Command { command: VimCommand, count: u32, select: bool },
     /// Execute an operator with a motion
-    OperatorMotion { operator: Operator, motion: VimCommand, count: u32 },
+    /// Counts typed before the operator and before the motion multiply, as in vim (2d3w = 6)
+    OperatorMotion {
+        operator: Operator,
+        motion: VimCommand,
+        operator_count: u32,
+        motion_count: u32,
+    },
     /// Execute an operator with a text object
     TextObject { operator: Operator, text_object: VimCommand, count: u32 },
     /// Replace character at cursor
     ReplaceChar { keycode: KeyCode, shift: bool, count: u32 },
+    /// `.` after an insert: redo the change that entered insert mode (if any),
+    /// then type the inserted keys, `count` times
+    RepeatLastInsertedText {
+        change: Option<Box<VimAction>>,
+        keys: Vec<(KeyCode, bool)>,
+        count: u32,
+    },
+    /// `"1p`..`"9p`: paste a register `count` times
+    PasteRegister { register: Register, count: u32 },
+    /// Ctrl+P/Ctrl+N after a paste: undo it and paste this register instead
+    CyclePaste { register: Register },
+    /// `@a`: the steps of a macro, in order
+    PlayMacro { steps: Vec<MacroStep> },
+    /// Visual `o`: swap the selection's ends, whose head is at its start or end
+    SwapSelectionEnds { head_at_start: bool },
+    /// A custom command from settings: its steps, `count` times
+    CustomCommand { steps: Vec<Primitive>, count: u32 },
+    /// A command typed after `:`
+    ExCommand(CommandLine),
+    /// Visual `d`/`y`/`c` on the whole lines of a selection over several lines, with the
+    /// newline after them unless it's a change
+    Linewise { action: Box<VimAction>, include_newline: bool },
+    /// `"+` or `"*` before a yank or delete: its text goes to the system clipboard
+    /// even when yanks are kept off it
+    ToClipboard(Box<VimAction>),
     /// Cut (Cmd+X)
     Cut,
     /// Copy (Cmd+C)
     Copy,
 }
 
+/// Paste a register's text, restoring the clipboard afterwards
+fn paste_register(register: Register, options: MotionOptions) -> Result<(), String> {
+    let text = match registers::paste_source(register, options.yank_to_system_clipboard) {
+        PasteSource::Clipboard => return keyboard::paste(),
+        PasteSource::Internal => registers::unnamed().ok_or("Nothing has been yanked yet")?,
+        PasteSource::Deleted(n) => {
+            registers::delete_register(n).ok_or_else(|| format!("Register {} is empty", n))?
+        }
+    };
+    custom_command::paste_text(&text)
+}
+
 impl VimAction {
+    /// Number of times the action applies
+    pub fn count(&self) -> u32 {
+        match self {
+            VimAction::ToClipboard(action) | VimAction::Linewise { action, .. } => action.count(),
+            VimAction::Command { count, .. }
+            | VimAction::TextObject { count, .. }
+            | VimAction::ReplaceChar { count, .. }
+            | VimAction::RepeatLastInsertedText { count, .. }
+            | VimAction::PasteRegister { count, .. }
+            | VimAction::CustomCommand { count, .. } => *count,
+            VimAction::OperatorMotion { operator_count, motion_count, .. } => {
+                super::combined_count(*operator_count, *motion_count)
+            }
+            VimAction::CyclePaste { .. }
+            | VimAction::PlayMacro { .. }
+            | VimAction::SwapSelectionEnds { .. }
+            | VimAction::ExCommand(_)
+            | VimAction::Cut
+            | VimAction::Copy => 1,
+        }
+    }
+
+    /// Whether the action cuts text to the clipboard, so it belongs in the delete ring
+    pub fn cuts_text(&self) -> bool {
+        match self {
+            VimAction::ToClipboard(action) | VimAction::Linewise { action, .. } => action.cuts_text(),
+            VimAction::Command { command, .. } => matches!(
+                command,
+                VimCommand::DeleteLine
+                    | VimCommand::DeleteToLineEnd
+                    | VimCommand::ChangeLine
+                    | VimCommand::ChangeToLineEnd
+                    | VimCommand::SubstituteLine
+            ),
+            VimAction::OperatorMotion { operator, .. } | VimAction::TextObject { operator, .. } => {
+                matches!(operator, Operator::Delete | Operator::Change)
+            }
+            VimAction::Cut => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the action copies text to the clipboard without cutting it
+    pub fn yanks_text(&self) -> bool {
+        match self {
+            VimAction::ToClipboard(action) | VimAction::Linewise { action, .. } => action.yanks_text(),
+            VimAction::Command { command, .. } => *command == VimCommand::YankLine,
+            VimAction::OperatorMotion { operator, .. } | VimAction::TextObject { operator, .. } => {
+                *operator == Operator::Yank
+            }
+            VimAction::Copy => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the action changes the field's text, so it's pointless in a read-only field
+    pub fn modifies_text(&self) -> bool {
+        match self {
+            VimAction::ToClipboard(action) | VimAction::Linewise { action, .. } => action.modifies_text(),
+            VimAction::Command { command, .. } => {
+                self.cuts_text()
+                    || matches!(
+                        command,
+                        VimCommand::DeleteChar
+                            | VimCommand::DeleteCharBefore
+                            | VimCommand::SubstituteChar
+                            | VimCommand::JoinLines
+                            | VimCommand::IndentLine
+                            | VimCommand::OutdentLine
+                            | VimCommand::Paste
+                            | VimCommand::PasteBefore
+                    )
+            }
+            VimAction::ReplaceChar { .. }
+            | VimAction::RepeatLastInsertedText { .. }
+            | VimAction::PasteRegister { .. }
+            | VimAction::CyclePaste { .. }
+            | VimAction::ExCommand(_) => true,
+            VimAction::OperatorMotion { operator, .. } | VimAction::TextObject { operator, .. } => {
+                *operator != Operator::Yank
+            }
+            _ => self.cuts_text(),
+        }
+    }
+
     /// Execute the action
-    pub fn execute(&self) -> Result<bool, String> {
+    /// Text changes are skipped when the focused field is read-only
+    pub fn execute(&self, options: MotionOptions) -> Result<bool, String> {
+        if self.modifies_text() && !accessibility::is_editable() {
+            log::info!("Focused field is read-only, skipping {:?}", self);
+            return Ok(false);
+        }
         match self {
+            // Pasting the unnamed register kept off the clipboard
+            VimAction::Command { command: VimCommand::Paste | VimCommand::PasteBefore, count, .. }
+                if !options.yank_to_system_clipboard =>
+            {
+                for _ in 0..*count {
+                    paste_register(Register::Unnamed, options)?;
+                }
+                Ok(false)
+            }
             VimAction::Command { command, count, select } => {
-                command.execute(*count, *select)?;
+                command.execute_with_mode(*count, *select, options)?;
                 Ok(false)
             }
-            VimAction::OperatorMotion { operator, motion, count } => {
-                operator.execute_with_motion(*motion, *count)
+            VimAction::OperatorMotion { operator, motion, .. } => {
+                operator.execute_with_motion(*motion, self.count(), options)
             }
             VimAction::TextObject { operator, text_object, count } => {
                 // Execute the text object selection
                 for _ in 0..*count {
                     text_object.execute(1, false)?;
                 }
-                // Apply the operator
-                match operator {
-                    Operator::Delete => {
-                        keyboard::cut()?;
-                        Ok(false)
-                    }
-                    Operator::Yank => {
-                        keyboard::copy()?;
-                        keyboard::cursor_left(1, false)?;
-                        Ok(false)
-                    }
-                    Operator::Change => {
-                        keyboard::cut()?;
-                        Ok(true) // Enter insert mode
-                    }
-                }
+                operator.apply_to_selection(options)
             }
             VimAction::ReplaceChar { keycode, shift, count } => {
                 // Delete char(s) and type replacement
@@ -59,6 +203,59 @@ impl VimAction {
                 }
                 Ok(false)
             }
+            VimAction::RepeatLastInsertedText { change, keys, count } => {
+                for _ in 0..*count {
+                    if let Some(change) = change {
+                        change.execute(options)?;
+                    }
+                    for &(keycode, shift) in keys {
+                        keyboard::type_char(keycode, shift)?;
+                    }
+                }
+                Ok(false)
+            }
+            VimAction::PasteRegister { register, count } => {
+                for _ in 0..*count {
+                    paste_register(*register, options)?;
+                }
+                Ok(false)
+            }
+            VimAction::CyclePaste { register } => {
+                keyboard::undo()?;
+                paste_register(*register, options)?;
+                Ok(false)
+            }
+            VimAction::PlayMacro { steps } => {
+                for step in steps {
+                    match step {
+                        MacroStep::Action(action) => {
+                            action.execute(options)?;
+                        }
+                        MacroStep::Key { keycode, modifiers } => {
+                            keyboard::inject_key_press(*keycode, *modifiers)?
+                        }
+                    }
+                    std::thread::sleep(MACRO_STEP_DELAY);
+                }
+                Ok(false)
+            }
+            VimAction::SwapSelectionEnds { head_at_start } => {
+                selection::swap_selection_ends(*head_at_start)?;
+                Ok(false)
+            }
+            VimAction::CustomCommand { steps, count } => {
+                custom_command::execute(steps, *count, options)?;
+                Ok(false)
+            }
+            VimAction::ExCommand(command_line) => {
+                command_line.execute(options)?;
+                Ok(false)
+            }
+            VimAction::ToClipboard(action) => action.execute(options),
+            VimAction::Linewise { action, include_newline } => {
+                selection::select_whole_lines(*include_newline)?;
+                action.execute(options)
+            }
             VimAction::Cut => {
                 keyboard::cut()?;
                 Ok(false)
@@ -70,3 +267,59 @@ impl VimAction {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cuts_text() {
+        let command = |command| VimAction::Command { command, count: 1, select: false };
+        let motion = |operator| VimAction::OperatorMotion {
+            operator,
+            motion: VimCommand::WordForward,
+            operator_count: 1,
+            motion_count: 1,
+        };
+        assert!(command(VimCommand::DeleteLine).cuts_text());
+        assert!(command(VimCommand::ChangeToLineEnd).cuts_text());
+        assert!(!command(VimCommand::YankLine).cuts_text());
+        assert!(!command(VimCommand::Paste).cuts_text());
+        assert!(motion(Operator::Delete).cuts_text());
+        assert!(motion(Operator::Change).cuts_text());
+        assert!(!motion(Operator::Yank).cuts_text());
+        assert!(!motion(Operator::Format).cuts_text());
+        assert!(VimAction::Cut.cuts_text());
+        assert!(!VimAction::Copy.cuts_text());
+    }
+
+    #[test]
+    fn test_yanks_text() {
+        let command = |command| VimAction::Command { command, count: 1, select: false };
+        assert!(command(VimCommand::YankLine).yanks_text());
+        assert!(!command(VimCommand::DeleteLine).yanks_text());
+        assert!(VimAction::Copy.yanks_text());
+        assert!(!VimAction::Cut.yanks_text());
+    }
+
+    #[test]
+    fn test_modifies_text() {
+        let command = |command| VimAction::Command { command, count: 1, select: false };
+        assert!(command(VimCommand::DeleteChar).modifies_text());
+        assert!(command(VimCommand::DeleteLine).modifies_text());
+        assert!(command(VimCommand::Paste).modifies_text());
+        assert!(!command(VimCommand::WordForward).modifies_text());
+        assert!(!command(VimCommand::YankLine).modifies_text());
+        assert!(VimAction::ReplaceChar { keycode: KeyCode::A, shift: false, count: 1 }.modifies_text());
+        let motion = |operator| VimAction::OperatorMotion {
+            operator,
+            motion: VimCommand::MoveDown,
+            operator_count: 1,
+            motion_count: 1,
+        };
+        assert!(motion(Operator::Format).modifies_text());
+        assert!(!motion(Operator::Yank).modifies_text());
+        assert!(VimAction::Cut.modifies_text());
+        assert!(!VimAction::Copy.modifies_text());
+    }
+}
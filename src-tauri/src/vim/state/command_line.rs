@@ -0,0 +1,186 @@
+//! Typing a command line after `:`, shown as the pending keys until Return runs it
+
+use crate::keyboard::{KeyCode, Modifiers};
+use super::super::ex_command;
+use super::super::modes::VimMode;
+use super::action::VimAction;
+use super::{ProcessResult, VimState};
+
+/// Char a key types on the command line, if any
+fn typed_char(keycode: KeyCode, modifiers: &Modifiers) -> Option<char> {
+    if modifiers.control || modifiers.command || modifiers.option {
+        return None;
+    }
+    let c = match keycode {
+        KeyCode::Space => ' ',
+        keycode => keycode.to_char()?,
+    };
+    if !modifiers.shift {
+        return Some(c);
+    }
+    match c {
+        'a'..='z' => Some(c.to_ascii_uppercase()),
+        '1' => Some('!'),
+        '5' => Some('%'),
+        _ => None,
+    }
+}
+
+impl VimState {
+    /// Open the command line, with `prefill` already typed
+    pub(super) fn start_command_line(&mut self, prefill: &str) -> ProcessResult {
+        self.pending_count = None;
+        self.command_line = Some(prefill.to_string());
+        ProcessResult::Suppress
+    }
+
+    /// Type a key on the open command line: Return runs it, Escape or deleting past
+    /// the `:` closes it
+    pub(super) fn handle_command_line_key(&mut self, keycode: KeyCode, modifiers: &Modifiers) -> ProcessResult {
+        let Some(line) = self.command_line.as_mut() else {
+            return ProcessResult::PassThrough;
+        };
+        match keycode {
+            KeyCode::Return => {
+                let line = self.command_line.take().unwrap_or_default();
+                match ex_command::parse(&line) {
+                    Ok(command_line) => {
+                        let action = VimAction::ExCommand(command_line);
+                        if self.mode == VimMode::Visual {
+                            self.set_mode(VimMode::Normal);
+                            return ProcessResult::ModeChanged(VimMode::Normal, Some(action));
+                        }
+                        ProcessResult::SuppressWithAction(action)
+                    }
+                    Err(e) => {
+                        log::warn!(":{}: {}", line, e);
+                        ProcessResult::Suppress
+                    }
+                }
+            }
+            KeyCode::Escape => self.close_command_line(),
+            KeyCode::Delete => {
+                if line.pop().is_none() {
+                    return self.close_command_line();
+                }
+                ProcessResult::Suppress
+            }
+            _ => {
+                if let Some(c) = typed_char(keycode, modifiers) {
+                    line.push(c);
+                }
+                ProcessResult::Suppress
+            }
+        }
+    }
+
+    /// Close the command line without running it, leaving visual mode as vim does
+    fn close_command_line(&mut self) -> ProcessResult {
+        self.command_line = None;
+        if self.mode == VimMode::Visual {
+            self.set_mode(VimMode::Normal);
+            return ProcessResult::ModeChanged(VimMode::Normal, None);
+        }
+        ProcessResult::Suppress
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vim::ex_command::{CommandLine, ExCommand, LineRange};
+    use super::super::test_util::{press, shift};
+
+    fn type_text(state: &mut VimState, text: &str) {
+        for c in text.chars() {
+            let keycode = KeyCode::from_name(&c.to_ascii_lowercase().to_string()).unwrap();
+            press(state, keycode, Modifiers { shift: c.is_ascii_uppercase(), ..Default::default() });
+        }
+    }
+
+    #[test]
+    fn test_visual_colon_runs_on_selection() {
+        let (mut state, _) = VimState::with_mode(VimMode::Visual);
+        press(&mut state, KeyCode::Semicolon, shift());
+        assert_eq!(state.get_pending_keys(), ":'<,'>");
+
+        type_text(&mut state, "sort");
+        assert_eq!(state.get_pending_keys(), ":'<,'>sort");
+        let result = press(&mut state, KeyCode::Return, Modifiers::default());
+        let expected = CommandLine {
+            range: LineRange::Selection,
+            command: ExCommand::Sort { reverse: false, unique: false },
+        };
+        assert!(matches!(
+            result,
+            ProcessResult::ModeChangedWithPending(VimMode::Normal, Some(VimAction::ExCommand(command_line)), pending)
+                if command_line == expected && pending.is_empty()
+        ));
+        assert_eq!(state.get_pending_keys(), "");
+    }
+
+    #[test]
+    fn test_shifted_chars() {
+        let (mut state, _) = VimState::with_mode(VimMode::Visual);
+        press(&mut state, KeyCode::Semicolon, shift());
+        type_text(&mut state, "U");
+        assert_eq!(state.get_pending_keys(), ":'<,'>U");
+    }
+
+    #[test]
+    fn test_normal_colon_runs_on_whole_field() {
+        let (mut state, _) = VimState::with_mode(VimMode::Normal);
+        press(&mut state, KeyCode::Semicolon, shift());
+        assert_eq!(state.get_pending_keys(), ":");
+        type_text(&mut state, "sort");
+        press(&mut state, KeyCode::Num1, shift());
+        press(&mut state, KeyCode::Space, Modifiers::default());
+        type_text(&mut state, "u");
+        assert_eq!(state.get_pending_keys(), ":sort! u");
+
+        let expected = CommandLine {
+            range: LineRange::All,
+            command: ExCommand::Sort { reverse: true, unique: true },
+        };
+        assert!(matches!(
+            press(&mut state, KeyCode::Return, Modifiers::default()),
+            ProcessResult::SuppressWithAction(VimAction::ExCommand(command_line)) if command_line == expected
+        ));
+        assert_eq!(state.mode(), VimMode::Normal);
+
+        // Escape closes the line without leaving normal mode
+        press(&mut state, KeyCode::Semicolon, shift());
+        assert!(matches!(press(&mut state, KeyCode::Escape, Modifiers::default()), ProcessResult::Suppress));
+        assert_eq!(state.get_pending_keys(), "");
+    }
+
+    #[test]
+    fn test_unknown_command_keeps_selection() {
+        let (mut state, _) = VimState::with_mode(VimMode::Visual);
+        press(&mut state, KeyCode::Semicolon, shift());
+        type_text(&mut state, "wq");
+        assert!(matches!(press(&mut state, KeyCode::Return, Modifiers::default()), ProcessResult::Suppress));
+        assert_eq!(state.mode(), VimMode::Visual);
+        assert_eq!(state.get_pending_keys(), "");
+    }
+
+    #[test]
+    fn test_escape_and_backspace_close_the_line() {
+        let (mut state, _) = VimState::with_mode(VimMode::Visual);
+        press(&mut state, KeyCode::Semicolon, shift());
+        assert!(matches!(
+            press(&mut state, KeyCode::Escape, Modifiers::default()),
+            ProcessResult::ModeChangedWithPending(VimMode::Normal, None, pending) if pending.is_empty()
+        ));
+
+        state.set_mode_external(VimMode::Visual);
+        press(&mut state, KeyCode::Semicolon, shift());
+        for _ in 0..5 {
+            press(&mut state, KeyCode::Delete, Modifiers::default());
+        }
+        assert_eq!(state.get_pending_keys(), ":");
+        press(&mut state, KeyCode::Delete, Modifiers::default());
+        assert_eq!(state.mode(), VimMode::Normal);
+        assert_eq!(state.get_pending_keys(), "");
+    }
+}
@@ -0,0 +1,300 @@
+use crate::keyboard::{KeyCode, Modifiers};
+use super::super::commands::VimCommand;
+use super::action::VimAction;
+use super::{ProcessResult, VimState};
+
+/// Readline/emacs-style editing command bound to a key in insert mode, if any
+fn readline_binding(keycode: KeyCode, modifiers: &Modifiers) -> Option<VimCommand> {
+    let control_only = modifiers.control && !modifiers.shift && !modifiers.option && !modifiers.command;
+    if !control_only {
+        return None;
+    }
+    match keycode {
+        KeyCode::W => Some(VimCommand::DeleteWordBackward),
+        KeyCode::U => Some(VimCommand::DeleteToLineStart),
+        KeyCode::A => Some(VimCommand::LineStart),
+        KeyCode::E => Some(VimCommand::LineEnd),
+        KeyCode::F => Some(VimCommand::MoveRight),
+        KeyCode::B => Some(VimCommand::MoveLeft),
+        KeyCode::D => Some(VimCommand::DeleteChar),
+        KeyCode::I => Some(VimCommand::InsertLiteralTab),
+        _ => None,
+    }
+}
+
+/// Keys typed in an insert started from normal mode, for `.`
+#[derive(Debug, Clone, Default)]
+pub(super) struct InsertRecording {
+    /// Change that entered insert mode, e.g. `cw`. None for plain `i`
+    change: Option<VimAction>,
+    /// Typed keys as (key, shift)
+    keys: Vec<(KeyCode, bool)>,
+    /// Whether keys are still being added
+    pub(super) recording: bool,
+}
+
+impl InsertRecording {
+    pub(super) fn start(&mut self, change: Option<VimAction>) {
+        self.change = change;
+        self.keys.clear();
+        self.recording = true;
+    }
+
+    /// Add a key passed through in insert mode
+    /// Only plain typing is kept: a shortcut or cursor movement starts the text over, as in vim
+    fn record(&mut self, keycode: KeyCode, modifiers: &Modifiers) {
+        if !self.recording {
+            return;
+        }
+        if modifiers.control || modifiers.option || modifiers.command {
+            self.keys.clear();
+            return;
+        }
+        match keycode {
+            KeyCode::Delete => {
+                self.keys.pop();
+            }
+            KeyCode::Return | KeyCode::Tab => self.keys.push((keycode, modifiers.shift)),
+            _ if keycode.to_char().is_some() => self.keys.push((keycode, modifiers.shift)),
+            _ => self.keys.clear(),
+        }
+    }
+}
+
+impl VimState {
+    /// Whether the key is a completion key pressed with Ctrl (Ctrl+N/Ctrl+P by default),
+    /// which always goes to the app
+    fn is_completion_key(&self, keycode: KeyCode, modifiers: &Modifiers) -> bool {
+        modifiers.control
+            && !modifiers.option
+            && !modifiers.command
+            && self.options.insert_completion_keys.contains(&keycode)
+    }
+
+    pub(super) fn process_insert_mode(&mut self, keycode: KeyCode, modifiers: &Modifiers) -> ProcessResult {
+        let command = (self.options.insert_readline_bindings && !self.is_completion_key(keycode, modifiers))
+            .then(|| readline_binding(keycode, modifiers))
+            .flatten();
+        match command {
+            Some(command) => {
+                self.last_insert.keys.clear();
+                ProcessResult::SuppressWithAction(VimAction::Command {
+                    command,
+                    count: 1,
+                    select: false,
+                })
+            }
+            None => {
+                self.last_insert.record(keycode, modifiers);
+                ProcessResult::PassThrough
+            }
+        }
+    }
+
+    /// `.`: replay the last insert entered from normal mode
+    pub(super) fn repeat_last_insert(&mut self, count: u32) -> ProcessResult {
+        self.pending_count = None;
+        let last = &self.last_insert;
+        if last.change.is_none() && last.keys.is_empty() {
+            return ProcessResult::Suppress;
+        }
+        ProcessResult::SuppressWithAction(VimAction::RepeatLastInsertedText {
+            change: last.change.clone().map(Box::new),
+            keys: last.keys.clone(),
+            count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard::KeyEvent;
+    use crate::vim::modes::VimMode;
+    use crate::vim::VimOptions;
+    use super::super::test_util::{control, normal_state, press, shift, state_with, type_keys};
+
+    #[test]
+    fn test_readline_binding_keys() {
+        assert_eq!(
+            readline_binding(KeyCode::W, &control()),
+            Some(VimCommand::DeleteWordBackward)
+        );
+        assert_eq!(
+            readline_binding(KeyCode::U, &control()),
+            Some(VimCommand::DeleteToLineStart)
+        );
+        assert_eq!(readline_binding(KeyCode::A, &control()), Some(VimCommand::LineStart));
+        assert_eq!(readline_binding(KeyCode::E, &control()), Some(VimCommand::LineEnd));
+        assert_eq!(readline_binding(KeyCode::F, &control()), Some(VimCommand::MoveRight));
+        assert_eq!(readline_binding(KeyCode::B, &control()), Some(VimCommand::MoveLeft));
+        assert_eq!(readline_binding(KeyCode::D, &control()), Some(VimCommand::DeleteChar));
+        assert_eq!(readline_binding(KeyCode::I, &control()), Some(VimCommand::InsertLiteralTab));
+        assert_eq!(readline_binding(KeyCode::K, &control()), None);
+        // Other modifier combinations are left to the app
+        let cmd_w = Modifiers { command: true, ..Default::default() };
+        assert_eq!(readline_binding(KeyCode::W, &cmd_w), None);
+        let ctrl_shift = Modifiers { shift: true, ..control() };
+        assert_eq!(readline_binding(KeyCode::W, &ctrl_shift), None);
+        assert_eq!(readline_binding(KeyCode::W, &Modifiers::default()), None);
+    }
+
+    #[test]
+    fn test_readline_bindings_in_insert_mode() {
+        let mut state = state_with(
+            VimMode::Insert,
+            VimOptions { insert_readline_bindings: true, ..Default::default() },
+        );
+        assert_eq!(state.mode(), VimMode::Insert);
+
+        for (keycode, expected) in [
+            (KeyCode::W, VimCommand::DeleteWordBackward),
+            (KeyCode::U, VimCommand::DeleteToLineStart),
+            (KeyCode::A, VimCommand::LineStart),
+            (KeyCode::E, VimCommand::LineEnd),
+            (KeyCode::F, VimCommand::MoveRight),
+            (KeyCode::B, VimCommand::MoveLeft),
+            (KeyCode::D, VimCommand::DeleteChar),
+            (KeyCode::I, VimCommand::InsertLiteralTab),
+        ] {
+            match press(&mut state, keycode, control()) {
+                ProcessResult::SuppressWithAction(VimAction::Command { command, count: 1, select: false }) => {
+                    assert_eq!(command, expected)
+                }
+                other => panic!("unexpected result {:?}", other),
+            }
+            assert_eq!(state.mode(), VimMode::Insert);
+        }
+
+        // Plain typing still passes through
+        let result = state.process_key(KeyEvent {
+            code: KeyCode::W.as_raw(),
+            modifiers: Modifiers::default(),
+            is_key_down: true,
+        });
+        assert!(matches!(result, ProcessResult::PassThrough));
+    }
+
+    /// Leave insert mode with the vim key and press `.`
+    fn repeat(state: &mut VimState) -> ProcessResult {
+        state.handle_vim_key();
+        assert_eq!(state.mode(), VimMode::Normal);
+        press(state, KeyCode::Period, Modifiers::default())
+    }
+
+    #[test]
+    fn test_insert_text_is_buffered() {
+        let mut state = normal_state();
+        press(&mut state, KeyCode::I, Modifiers::default());
+        assert!(matches!(
+            type_keys(&mut state, &[KeyCode::H, KeyCode::I, KeyCode::X, KeyCode::Delete]),
+            ProcessResult::PassThrough
+        ));
+        press(&mut state, KeyCode::Num1, shift());
+
+        match repeat(&mut state) {
+            ProcessResult::SuppressWithAction(VimAction::RepeatLastInsertedText { change, keys, count }) => {
+                assert!(change.is_none());
+                assert_eq!(keys, vec![(KeyCode::H, false), (KeyCode::I, false), (KeyCode::Num1, true)]);
+                assert_eq!(count, 1);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_repeat_replays_change_and_text() {
+        let mut state = normal_state();
+        press(&mut state, KeyCode::C, Modifiers::default());
+        press(&mut state, KeyCode::W, Modifiers::default());
+        assert_eq!(state.mode(), VimMode::Insert);
+        assert!(matches!(
+            type_keys(&mut state, &[KeyCode::O, KeyCode::K]),
+            ProcessResult::PassThrough
+        ));
+
+        state.handle_vim_key();
+        press(&mut state, KeyCode::Num3, Modifiers::default());
+        match press(&mut state, KeyCode::Period, Modifiers::default()) {
+            ProcessResult::SuppressWithAction(VimAction::RepeatLastInsertedText { change, keys, count }) => {
+                assert!(matches!(
+                    change.as_deref(),
+                    Some(VimAction::OperatorMotion { motion: VimCommand::WordForward, .. })
+                ));
+                assert_eq!(keys, vec![(KeyCode::O, false), (KeyCode::K, false)]);
+                assert_eq!(count, 3);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(state.get_pending_keys(), "");
+    }
+
+    #[test]
+    fn test_cursor_movement_restarts_insert_text() {
+        let mut state = normal_state();
+        press(&mut state, KeyCode::A, Modifiers::default());
+        assert!(matches!(
+            type_keys(&mut state, &[KeyCode::A, KeyCode::Left, KeyCode::B]),
+            ProcessResult::PassThrough
+        ));
+
+        match repeat(&mut state) {
+            ProcessResult::SuppressWithAction(VimAction::RepeatLastInsertedText { keys, .. }) => {
+                assert_eq!(keys, vec![(KeyCode::B, false)]);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_repeat_without_insert_does_nothing() {
+        let mut state = normal_state();
+        assert!(matches!(press(&mut state, KeyCode::Period, Modifiers::default()), ProcessResult::Suppress));
+
+        // Text typed after toggling insert with the vim key isn't recorded
+        state.handle_vim_key();
+        assert!(matches!(type_keys(&mut state, &[KeyCode::A]), ProcessResult::PassThrough));
+        assert!(matches!(repeat(&mut state), ProcessResult::Suppress));
+    }
+
+    #[test]
+    fn test_completion_keys_pass_through() {
+        let mut state = state_with(
+            VimMode::Insert,
+            VimOptions {
+                insert_readline_bindings: true,
+                insert_completion_keys: vec![KeyCode::N, KeyCode::P, KeyCode::W],
+                ..Default::default()
+            },
+        );
+        for keycode in [KeyCode::N, KeyCode::P, KeyCode::W] {
+            assert!(matches!(press(&mut state, keycode, control()), ProcessResult::PassThrough));
+        }
+        // Readline bindings not in the list still apply
+        assert!(matches!(
+            press(&mut state, KeyCode::U, control()),
+            ProcessResult::SuppressWithAction(VimAction::Command {
+                command: VimCommand::DeleteToLineStart,
+                ..
+            })
+        ));
+        assert_eq!(state.mode(), VimMode::Insert);
+    }
+
+    #[test]
+    fn test_default_completion_keys() {
+        let options = VimOptions::from_settings(&crate::config::Settings::default());
+        assert_eq!(options.insert_completion_keys, vec![KeyCode::N, KeyCode::P]);
+    }
+
+    #[test]
+    fn test_readline_bindings_disabled_pass_through() {
+        let mut state = state_with(
+            VimMode::Insert,
+            VimOptions { insert_readline_bindings: false, ..Default::default() },
+        );
+        for keycode in [KeyCode::W, KeyCode::U, KeyCode::A, KeyCode::E, KeyCode::F, KeyCode::B, KeyCode::D, KeyCode::I] {
+            assert!(matches!(press(&mut state, keycode, control()), ProcessResult::PassThrough));
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Macros: `q{a-z}` records keys until the next `q`, `@{a-z}` plays them and `@@` repeats
+//!
+//! Keys are played back through the state machine, so a macro does what its keys would do
+//! now. The actions they produce run in order, and keys the state machine passes through
+//! (text typed in insert mode) are injected.
+
+use std::collections::HashMap;
+
+use crate::keyboard::{KeyCode, KeyEvent, Modifiers};
+use super::action::{MacroStep, VimAction};
+use super::{ProcessResult, VimState};
+
+/// How deep macros can play other macros, so one that plays itself still ends
+const MAX_MACRO_DEPTH: u32 = 10;
+
+/// What the register after `q` or `@` is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum PendingMacro {
+    Record,
+    Play,
+}
+
+/// Recorded macros and the one being recorded
+#[derive(Debug, Default)]
+pub(super) struct Macros {
+    /// Key downs of each recorded macro
+    registers: HashMap<char, Vec<KeyEvent>>,
+    /// Register being recorded into and its keys so far
+    recording: Option<(char, Vec<KeyEvent>)>,
+    /// Register `@@` plays
+    last_played: Option<char>,
+    /// Macros currently being played
+    depth: u32,
+}
+
+impl Macros {
+    pub(super) fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Add a key to the macro being recorded
+    pub(super) fn record(&mut self, event: KeyEvent) {
+        if let Some((_, keys)) = self.recording.as_mut() {
+            keys.push(event);
+        }
+    }
+}
+
+/// Macro register named by a key: a-z
+fn macro_register(keycode: KeyCode, modifiers: &Modifiers) -> Option<char> {
+    if modifiers.shift || modifiers.control || modifiers.option || modifiers.command {
+        return None;
+    }
+    keycode.to_char().filter(char::is_ascii_lowercase)
+}
+
+impl VimState {
+    /// `q`: stop recording, or wait for the register to record into
+    pub(super) fn handle_q_key(&mut self) -> ProcessResult {
+        match self.macros.recording.take() {
+            Some((register, keys)) => {
+                log::info!("Recorded macro @{} ({} keys)", register, keys.len());
+                self.macros.registers.insert(register, keys);
+            }
+            None => self.pending_macro = Some(PendingMacro::Record),
+        }
+        ProcessResult::Suppress
+    }
+
+    /// Register name after `q` or `@` (`@@` plays the last macro again)
+    pub(super) fn handle_macro_register(
+        &mut self,
+        pending: PendingMacro,
+        keycode: KeyCode,
+        modifiers: &Modifiers,
+    ) -> ProcessResult {
+        let count = self.pending_count.take().unwrap_or(1);
+        let replays_last = keycode == KeyCode::Num2 && modifiers.shift;
+        let register = match pending {
+            PendingMacro::Play if replays_last => self.macros.last_played,
+            _ => macro_register(keycode, modifiers),
+        };
+        let Some(register) = register else {
+            self.reset_pending();
+            return ProcessResult::Suppress;
+        };
+        match pending {
+            PendingMacro::Record => {
+                self.macros.recording = Some((register, Vec::new()));
+                ProcessResult::Suppress
+            }
+            PendingMacro::Play => self.play_macro(register, count),
+        }
+    }
+
+    /// Feed a macro's keys through the state machine `count` times, collecting what they do
+    fn play_macro(&mut self, register: char, count: u32) -> ProcessResult {
+        self.macros.last_played = Some(register);
+        let keys = match self.macros.registers.get(&register) {
+            Some(keys) if self.macros.depth < MAX_MACRO_DEPTH => keys.clone(),
+            _ => return ProcessResult::Suppress,
+        };
+
+        self.macros.depth += 1;
+        let mut steps = Vec::new();
+        for _ in 0..count {
+            for &event in &keys {
+                match self.apply_key(event) {
+                    ProcessResult::SuppressWithAction(action)
+                    | ProcessResult::ModeChanged(_, Some(action))
+                    | ProcessResult::ModeChangedWithPending(_, Some(action), _) => {
+                        steps.push(MacroStep::Action(action))
+                    }
+                    ProcessResult::PassThrough => {
+                        if let Some(keycode) = event.keycode() {
+                            steps.push(MacroStep::Key { keycode, modifiers: event.modifiers });
+                        }
+                    }
+                    ProcessResult::Suppress
+                    | ProcessResult::ModeChanged(_, None)
+                    | ProcessResult::ModeChangedWithPending(_, None, _) => {}
+                }
+            }
+        }
+        self.macros.depth -= 1;
+
+        if steps.is_empty() {
+            ProcessResult::Suppress
+        } else {
+            ProcessResult::SuppressWithAction(VimAction::PlayMacro { steps })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vim::commands::VimCommand;
+    use crate::vim::modes::VimMode;
+    use crate::vim::VimOptions;
+    use super::super::test_util::{press, shift, state_with, type_keys};
+
+    /// Escape ends the recorded insert in normal mode
+    fn macro_options() -> VimOptions {
+        VimOptions {
+            escape_target_mode: VimMode::Normal,
+            ..Default::default()
+        }
+    }
+
+    fn play(state: &mut VimState, keys: &[KeyCode]) -> Vec<MacroStep> {
+        type_keys(state, &keys[..keys.len() - 1]);
+        press(state, KeyCode::Num2, shift());
+        let modifiers = Modifiers { shift: keys.last() == Some(&KeyCode::Num2), ..Default::default() };
+        match press(state, *keys.last().unwrap(), modifiers) {
+            ProcessResult::SuppressWithAction(VimAction::PlayMacro { steps }) => steps,
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    fn commands(steps: &[MacroStep]) -> Vec<VimCommand> {
+        steps
+            .iter()
+            .map(|step| match step {
+                MacroStep::Action(VimAction::Command { command, .. }) => *command,
+                other => panic!("unexpected step {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_record_and_play() {
+        let mut state = state_with(VimMode::Normal, macro_options());
+        press(&mut state, KeyCode::Q, Modifiers::default());
+        assert_eq!(state.get_pending_keys(), "q");
+        press(&mut state, KeyCode::A, Modifiers::default());
+        assert_eq!(state.get_pending_keys(), "");
+        assert!(state.macros.is_recording());
+
+        // Keys still work while recording
+        assert!(matches!(
+            press(&mut state, KeyCode::J, Modifiers::default()),
+            ProcessResult::SuppressWithAction(VimAction::Command { command: VimCommand::MoveDown, .. })
+        ));
+        type_keys(&mut state, &[KeyCode::X, KeyCode::Q]);
+        assert!(!state.macros.is_recording());
+
+        let steps = play(&mut state, &[KeyCode::A]);
+        assert_eq!(commands(&steps), [VimCommand::MoveDown, VimCommand::DeleteChar]);
+        assert_eq!(state.mode(), VimMode::Normal);
+    }
+
+    #[test]
+    fn test_play_with_count_and_repeat_last() {
+        let mut state = state_with(VimMode::Normal, macro_options());
+        type_keys(&mut state, &[KeyCode::Q, KeyCode::B, KeyCode::W, KeyCode::Q]);
+
+        let steps = play(&mut state, &[KeyCode::Num3, KeyCode::B]);
+        assert_eq!(commands(&steps), [VimCommand::WordForward; 3]);
+        // `@@` plays the last macro again
+        let steps = play(&mut state, &[KeyCode::Num2]);
+        assert_eq!(commands(&steps), [VimCommand::WordForward]);
+    }
+
+    #[test]
+    fn test_typed_text_is_injected() {
+        let mut state = state_with(VimMode::Normal, macro_options());
+        type_keys(&mut state, &[KeyCode::Q, KeyCode::A, KeyCode::I, KeyCode::H, KeyCode::I]);
+        type_keys(&mut state, &[KeyCode::Escape, KeyCode::Q]);
+        assert_eq!(state.mode(), VimMode::Normal);
+
+        let steps = play(&mut state, &[KeyCode::A]);
+        let typed: Vec<KeyCode> = steps
+            .iter()
+            .map(|step| match step {
+                MacroStep::Key { keycode, .. } => *keycode,
+                other => panic!("unexpected step {:?}", other),
+            })
+            .collect();
+        assert_eq!(typed, [KeyCode::H, KeyCode::I]);
+        // The macro left insert mode again
+        assert_eq!(state.mode(), VimMode::Normal);
+    }
+
+    #[test]
+    fn test_unknown_register_and_cancel() {
+        let mut state = state_with(VimMode::Normal, macro_options());
+        press(&mut state, KeyCode::Num2, shift());
+        assert_eq!(state.get_pending_keys(), "@");
+        assert!(matches!(press(&mut state, KeyCode::Z, Modifiers::default()), ProcessResult::Suppress));
+        // Only a-z name macros
+        press(&mut state, KeyCode::Q, Modifiers::default());
+        press(&mut state, KeyCode::Num1, Modifiers::default());
+        assert!(!state.macros.is_recording());
+        assert_eq!(state.get_pending_keys(), "");
+    }
+
+    #[test]
+    fn test_macro_playing_itself_ends() {
+        let mut state = state_with(VimMode::Normal, macro_options());
+        // `@a` does nothing while `a` is still being recorded
+        type_keys(&mut state, &[KeyCode::Q, KeyCode::A, KeyCode::J]);
+        press(&mut state, KeyCode::Num2, shift());
+        press(&mut state, KeyCode::A, Modifiers::default());
+        press(&mut state, KeyCode::Q, Modifiers::default());
+
+        let mut steps = play(&mut state, &[KeyCode::A]);
+        let mut depth = 0;
+        loop {
+            depth += 1;
+            match steps.as_slice() {
+                [MacroStep::Action(VimAction::Command { command: VimCommand::MoveDown, .. })] => break,
+                [_, MacroStep::Action(VimAction::PlayMacro { steps: inner })] => steps = inner.clone(),
+                other => panic!("unexpected steps {:?}", other),
+            }
+        }
+        assert_eq!(depth, MAX_MACRO_DEPTH);
+    }
+}
@@ -1,14 +1,36 @@
 mod action;
+mod command_line;
+mod insert_mode;
+mod macros;
 mod normal_mode;
+#[cfg(test)]
+pub(crate) mod test_util;
 mod visual_mode;
 
-pub use action::VimAction;
+pub use action::{MacroStep, VimAction};
+
+use insert_mode::InsertRecording;
+use macros::{Macros, PendingMacro};
+
+use std::time::{Duration, Instant};
 
 use tokio::sync::broadcast;
 
-use crate::keyboard::{KeyCode, KeyEvent};
-use super::commands::Operator;
+use crate::config::{ControlComboModifier, Settings, UnknownMotionKey};
+use crate::keyboard::{KeyCode, KeyEvent, Modifiers};
+use super::commands::{Operator, VimCommand};
+use super::custom_command::CustomCommand;
+use super::keymap::KeyBinding;
 use super::modes::VimMode;
+use super::registers::Register;
+
+/// Largest count that can be typed; further digits leave it there
+const MAX_COUNT: u32 = 9999;
+
+/// The counts before and after an operator multiplied, clamped to `MAX_COUNT` like a typed count
+fn combined_count(operator_count: u32, motion_count: u32) -> u32 {
+    operator_count.saturating_mul(motion_count).min(MAX_COUNT)
+}
 
 /// Result of processing a key event
 #[derive(Debug, Clone)]
@@ -21,6 +43,16 @@ pub enum ProcessResult {
     PassThrough,
     /// Mode changed (emit event), with optional action to execute
     ModeChanged(VimMode, Option<VimAction>),
+    /// Mode changed by a key that also changed the pending keys, e.g. the motion of `cw`
+    /// The pending keys left are emitted with the mode, so both update at once
+    ModeChangedWithPending(VimMode, Option<VimAction>, String),
+}
+
+/// A mode change as it's emitted, with the pending keys left after it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModeChange {
+    pub mode: VimMode,
+    pub pending: String,
 }
 
 /// Text object modifier (i for inner, a for around)
@@ -30,6 +62,20 @@ pub enum TextObjectModifier {
     Around, // a
 }
 
+impl TextObjectModifier {
+    /// The text object a key after `i` or `a` selects, if it's supported
+    fn command(self, keycode: KeyCode, modifiers: &Modifiers) -> Option<VimCommand> {
+        match (keycode, self) {
+            (KeyCode::W, Self::Inner) => Some(VimCommand::InnerWord),
+            (KeyCode::W, Self::Around) => Some(VimCommand::AroundWord),
+            (KeyCode::Grave, _) if modifiers.shift => None,
+            (KeyCode::Grave, Self::Inner) => Some(VimCommand::InnerBacktick),
+            (KeyCode::Grave, Self::Around) => Some(VimCommand::AroundBacktick),
+            _ => None,
+        }
+    }
+}
+
 /// Indent direction
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IndentDirection {
@@ -37,6 +83,65 @@ pub enum IndentDirection {
     Outdent, // <
 }
 
+/// Behavior options taken from settings
+#[derive(Debug, Clone, Default)]
+pub struct VimOptions {
+    /// Treat Ctrl+C like Escape (see `handle_ctrl_c`)
+    pub ctrl_c_as_escape: bool,
+    /// Stay in visual mode and keep the selection after `y`
+    pub keep_selection_after_yank: bool,
+    /// `d`/`y`/`c` take the whole lines of a selection over several lines
+    pub visual_mode_linewise_operators: bool,
+    /// Modifier that triggers Ctrl-combos in normal mode
+    pub control_combo_modifier: ControlComboModifier,
+    /// Mode Escape switches to: insert (ovim's default model) or normal (vim's model)
+    pub escape_target_mode: VimMode,
+    /// Escape cancels pending keys before switching to insert mode
+    pub escape_cancels_pending_first: bool,
+    /// What a key that isn't a motion does after an operator
+    pub unknown_motion_key: UnknownMotionKey,
+    /// How long a pending operator waits for the next key, if not indefinitely
+    pub operator_timeout: Option<Duration>,
+    /// Readline-style editing keys in insert mode (see `insert_mode`)
+    pub insert_readline_bindings: bool,
+    /// Keys that pass through with Ctrl in insert mode, ahead of the readline bindings
+    pub insert_completion_keys: Vec<KeyCode>,
+    /// `gt`/`gT` switch tabs
+    pub enable_tab_motions: bool,
+    /// `0` alternates between the first non-blank and the line start
+    pub smart_home: bool,
+    /// Normal mode bindings that take precedence over vim's keys (see `keymap`)
+    pub keymap: Vec<KeyBinding>,
+    /// Custom commands the keymap can bind by name
+    pub custom_commands: Vec<CustomCommand>,
+}
+
+impl VimOptions {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            ctrl_c_as_escape: settings.ctrl_c_as_escape,
+            keep_selection_after_yank: settings.keep_selection_after_yank,
+            visual_mode_linewise_operators: settings.visual_mode_linewise_operators,
+            control_combo_modifier: settings.control_combo_modifier,
+            escape_target_mode: settings.escape_target_mode,
+            escape_cancels_pending_first: settings.escape_cancels_pending_first,
+            unknown_motion_key: settings.unknown_motion_key,
+            operator_timeout: (settings.operator_timeout_ms > 0)
+                .then(|| Duration::from_millis(settings.operator_timeout_ms)),
+            insert_readline_bindings: settings.insert_readline_bindings,
+            insert_completion_keys: settings
+                .insert_completion_keys
+                .iter()
+                .filter_map(|name| KeyCode::from_name(name))
+                .collect(),
+            enable_tab_motions: settings.enable_tab_motions,
+            smart_home: settings.smart_home,
+            keymap: settings.keymap.clone(),
+            custom_commands: settings.custom_commands.clone(),
+        }
+    }
+}
+
 /// Vim state machine
 pub struct VimState {
     mode: VimMode,
@@ -44,31 +149,89 @@ pub struct VimState {
     pending_count: Option<u32>,
     /// Pending operator (d, y, c)
     pending_operator: Option<Operator>,
+    /// Count typed before the pending operator (e.g., "2" in "2d3w")
+    pending_operator_count: Option<u32>,
     /// Pending g key for gg, gt, gT, ge, etc
     pending_g: bool,
     /// Pending r key for r{char} replace
     pending_r: bool,
+    /// Pending z key for zz, zt, zb
+    pending_z: bool,
     /// Pending text object modifier (i or a after d/y/c)
     pending_text_object: Option<TextObjectModifier>,
     /// Pending indent direction (> or <)
     pending_indent: Option<IndentDirection>,
+    /// `"` typed, waiting for a register name
+    pending_register: bool,
+    /// Register selected with `"` for the next `p`
+    register: Option<Register>,
+    /// Register the last key pasted from, while Ctrl+P/Ctrl+N can cycle it
+    last_paste: Option<Register>,
+    /// The last key was a smart-home `0` that went to the first non-blank
+    at_first_non_blank: bool,
+    /// Visual `o` moved the selection's head to its start
+    visual_head_at_start: bool,
+    /// `q` or `@` typed, waiting for a macro register
+    pending_macro: Option<PendingMacro>,
+    /// Text typed after `:`, while the command line is open
+    command_line: Option<String>,
+    /// Behavior options from settings
+    options: VimOptions,
     /// Channel to emit mode changes
-    mode_tx: broadcast::Sender<VimMode>,
+    mode_tx: broadcast::Sender<ModeChange>,
+    /// A key is being applied, so its mode change is emitted once it's done (see `apply_key`)
+    applying_key: bool,
+    /// Channel to emit pending key changes (see `subscribe_pending`)
+    pending_tx: broadcast::Sender<String>,
+    /// Channel to emit changes of the count being typed (see `subscribe_count`)
+    count_tx: broadcast::Sender<Option<u32>>,
+    /// Last insert entered from normal mode, replayed by `.`
+    last_insert: InsertRecording,
+    /// Recorded macros (see `macros`)
+    macros: Macros,
+    /// Last motion and its count, re-run by `RepeatMotion`
+    last_motion: Option<(VimCommand, u32)>,
+    /// When the last key was pressed, to time out a pending operator
+    last_key_at: Option<Instant>,
 }
 
 impl VimState {
-    pub fn new() -> (Self, broadcast::Receiver<VimMode>) {
+    pub fn new() -> (Self, broadcast::Receiver<ModeChange>) {
+        Self::with_mode(VimMode::Insert)
+    }
+
+    /// Create a state machine that starts in `mode`
+    pub fn with_mode(mode: VimMode) -> (Self, broadcast::Receiver<ModeChange>) {
         let (mode_tx, mode_rx) = broadcast::channel(16);
+        let (pending_tx, _) = broadcast::channel(16);
+        let (count_tx, _) = broadcast::channel(16);
         (
             Self {
-                mode: VimMode::Insert,
+                mode,
                 pending_count: None,
                 pending_operator: None,
+                pending_operator_count: None,
                 pending_g: false,
                 pending_r: false,
+                pending_z: false,
                 pending_text_object: None,
                 pending_indent: None,
+                pending_register: false,
+                register: None,
+                last_paste: None,
+                at_first_non_blank: false,
+                visual_head_at_start: false,
+                pending_macro: None,
+                command_line: None,
+                options: VimOptions::default(),
                 mode_tx,
+                applying_key: false,
+                pending_tx,
+                count_tx,
+                last_insert: InsertRecording::default(),
+                macros: Macros::default(),
+                last_motion: None,
+                last_key_at: None,
             },
             mode_rx,
         )
@@ -82,7 +245,13 @@ impl VimState {
         if self.mode != mode {
             self.mode = mode;
             self.reset_pending();
-            let _ = self.mode_tx.send(mode);
+            // A new selection grows from its start
+            self.visual_head_at_start = false;
+            // Only inserts started by a normal mode command are recorded (see `process_key`)
+            self.last_insert.recording = false;
+            if !self.applying_key {
+                let _ = self.mode_tx.send(ModeChange { mode, pending: self.get_pending_keys() });
+            }
         }
     }
 
@@ -91,6 +260,24 @@ impl VimState {
         self.set_mode(mode);
     }
 
+    /// Set mode externally by name, as `VimMode` parses it
+    /// An unknown name leaves the mode as it is
+    pub fn set_mode_named(&mut self, name: &str) -> Result<VimMode, String> {
+        let mode = name.parse()?;
+        self.set_mode(mode);
+        Ok(mode)
+    }
+
+    /// The last motion and the count it ran with
+    pub fn last_motion(&self) -> Option<(VimCommand, u32)> {
+        self.last_motion
+    }
+
+    /// Update the behavior options (called with the settings at startup and whenever they change)
+    pub fn set_options(&mut self, options: VimOptions) {
+        self.options = options;
+    }
+
     /// Toggle between insert and normal mode (for CLI/IPC)
     pub fn toggle_mode(&mut self) -> VimMode {
         let new_mode = match self.mode {
@@ -104,35 +291,83 @@ impl VimState {
     pub(super) fn reset_pending(&mut self) {
         self.pending_count = None;
         self.pending_operator = None;
+        self.pending_operator_count = None;
         self.pending_g = false;
         self.pending_r = false;
+        self.pending_z = false;
         self.pending_text_object = None;
         self.pending_indent = None;
+        self.pending_register = false;
+        self.register = None;
+        self.pending_macro = None;
+        self.command_line = None;
     }
 
     pub(super) fn get_count(&self) -> u32 {
         self.pending_count.unwrap_or(1)
     }
 
+    /// Append a typed digit to the pending count, clamped to `MAX_COUNT`
+    pub(super) fn push_count_digit(&mut self, digit: u32) {
+        let current = self.pending_count.unwrap_or(0);
+        self.pending_count = Some((current * 10 + digit).min(MAX_COUNT));
+    }
+
+    /// Take the counts typed before and after a pending operator
+    pub(super) fn take_operator_counts(&mut self) -> (u32, u32) {
+        let counts = (self.pending_operator_count.unwrap_or(1), self.get_count());
+        self.pending_operator_count = None;
+        self.pending_count = None;
+        counts
+    }
+
+    /// Take the combined count for a pending operator (2d3d deletes 6 lines)
+    pub(super) fn take_operator_count(&mut self) -> u32 {
+        let (operator_count, motion_count) = self.take_operator_counts();
+        combined_count(operator_count, motion_count)
+    }
+
     /// Get a string representation of pending keys for display
     pub fn get_pending_keys(&self) -> String {
+        if let Some(ref line) = self.command_line {
+            return format!(":{}", line);
+        }
         let mut buf = String::new();
-        if let Some(count) = self.pending_count {
+        if self.pending_register {
+            buf.push('"');
+        }
+        if let Some(register) = self.register {
+            buf.push_str(&register.name());
+        }
+        if let Some(count) = self.pending_operator_count {
             buf.push_str(&count.to_string());
         }
         if let Some(ref op) = self.pending_operator {
-            buf.push(match op {
-                Operator::Delete => 'd',
-                Operator::Yank => 'y',
-                Operator::Change => 'c',
+            buf.push_str(match op {
+                Operator::Delete => "d",
+                Operator::Yank => "y",
+                Operator::Change => "c",
+                Operator::Format => "gq",
+                Operator::FormatKeepCursor => "gw",
             });
         }
+        if let Some(count) = self.pending_count {
+            buf.push_str(&count.to_string());
+        }
+        match self.pending_macro {
+            Some(PendingMacro::Record) => buf.push('q'),
+            Some(PendingMacro::Play) => buf.push('@'),
+            None => {}
+        }
         if self.pending_g {
             buf.push('g');
         }
         if self.pending_r {
             buf.push('r');
         }
+        if self.pending_z {
+            buf.push('z');
+        }
         if let Some(ref modifier) = self.pending_text_object {
             buf.push(match modifier {
                 TextObjectModifier::Inner => 'i',
@@ -148,25 +383,184 @@ impl VimState {
         buf
     }
 
+    /// Receive the pending keys (as in `get_pending_keys`) whenever a key changes them
+    pub fn subscribe_pending(&self) -> broadcast::Receiver<String> {
+        self.pending_tx.subscribe()
+    }
+
+    /// Receive the count being typed whenever a key changes it, and `None` once it's used
+    /// or cancelled
+    pub fn subscribe_count(&self) -> broadcast::Receiver<Option<u32>> {
+        self.count_tx.subscribe()
+    }
+
     /// Process a key event and return what to do with it
     pub fn process_key(&mut self, event: KeyEvent) -> ProcessResult {
+        let recording = self.macros.is_recording();
+        let result = self.apply_key(event);
+        // The `q` that stops recording isn't part of the macro
+        if recording && event.is_key_down {
+            self.macros.record(event);
+        }
+        result
+    }
+
+    /// Process a key, as typed or played from a macro
+    fn apply_key(&mut self, event: KeyEvent) -> ProcessResult {
+        let pending_before = self.get_pending_keys();
+        let count_before = self.pending_count;
+        let mode_before = self.mode;
+        // Keys played from a macro are applied within the `@` key, which emits for them all
+        let nested = std::mem::replace(&mut self.applying_key, true);
+        if event.is_key_down {
+            self.expire_pending_operator(Instant::now());
+        }
+        let result = self.process_key_event(event);
+        let result = self.route_to_clipboard_register(result);
+        self.applying_key = nested;
+
+        // Record what's typed after a normal mode command enters insert, for `.`
+        if let ProcessResult::ModeChanged(VimMode::Insert, ref change) = result {
+            if mode_before == VimMode::Normal && event.keycode() != Some(KeyCode::Escape) {
+                self.last_insert.start(change.clone());
+            }
+        }
+
+        // Remember motions for `RepeatMotion`, which re-runs them as they were typed
+        if let ProcessResult::SuppressWithAction(VimAction::Command { command, count, .. }) = result {
+            if command.is_motion() {
+                self.last_motion = Some((command, count));
+            }
+        }
+
+        let pending = self.get_pending_keys();
+        let pending_changed = pending != pending_before;
+        if pending_changed {
+            let _ = self.pending_tx.send(pending.clone());
+        }
+        if self.pending_count != count_before {
+            let _ = self.count_tx.send(self.pending_count);
+        }
+        if self.mode != mode_before && !nested {
+            let _ = self.mode_tx.send(ModeChange { mode: self.mode, pending: pending.clone() });
+        }
+        match result {
+            ProcessResult::ModeChanged(mode, action) if pending_changed => {
+                ProcessResult::ModeChangedWithPending(mode, action, pending)
+            }
+            result => result,
+        }
+    }
+
+    /// Send the yank or delete a `"+` or `"*` was selected for to the clipboard, once
+    /// it's complete
+    fn route_to_clipboard_register(&mut self, result: ProcessResult) -> ProcessResult {
+        if self.register != Some(Register::Clipboard) || self.pending_operator.is_some() {
+            return result;
+        }
+        let to_clipboard = |action: VimAction| {
+            if action.cuts_text() || action.yanks_text() {
+                VimAction::ToClipboard(Box::new(action))
+            } else {
+                action
+            }
+        };
+        match result {
+            ProcessResult::SuppressWithAction(action) => {
+                self.register = None;
+                ProcessResult::SuppressWithAction(to_clipboard(action))
+            }
+            ProcessResult::ModeChanged(mode, Some(action)) => {
+                self.register = None;
+                ProcessResult::ModeChanged(mode, Some(to_clipboard(action)))
+            }
+            result => result,
+        }
+    }
+
+    /// Drop a pending operator whose next key didn't come within `operator_timeout`
+    fn expire_pending_operator(&mut self, now: Instant) {
+        let last = self.last_key_at.replace(now);
+        if let (Some(timeout), Some(last)) = (self.options.operator_timeout, last) {
+            if self.pending_operator.is_some() && now.duration_since(last) > timeout {
+                self.reset_pending();
+            }
+        }
+    }
+
+    fn process_key_event(&mut self, event: KeyEvent) -> ProcessResult {
         // For key up events in Normal/Visual mode, suppress keys that we would suppress on key down
         if !event.is_key_down {
             return self.process_key_up(&event);
         }
 
-        let keycode = match event.keycode() {
-            Some(k) => k,
+        let (keycode, modifiers) = match event.keycode() {
+            Some(k) => escape_synonym(k, event.modifiers),
             None => return ProcessResult::PassThrough,
         };
 
+        if self.is_ctrl_c_escape(keycode, &modifiers) {
+            return self.handle_ctrl_c();
+        }
+
+        // In vim's model, Escape leaves insert mode
+        if self.mode == VimMode::Insert && self.escape_enters_normal(keycode) {
+            self.set_mode(VimMode::Normal);
+            return ProcessResult::ModeChanged(VimMode::Normal, None);
+        }
+
         match self.mode {
-            VimMode::Insert => ProcessResult::PassThrough,
-            VimMode::Normal => self.process_normal_mode(keycode, &event.modifiers),
-            VimMode::Visual => self.process_visual_mode_with_modifiers(keycode, &event.modifiers),
+            VimMode::Insert => self.process_insert_mode(keycode, &modifiers),
+            VimMode::Normal => self.process_normal_mode(keycode, &modifiers),
+            VimMode::Visual => self.process_visual_mode_with_modifiers(keycode, &modifiers),
         }
     }
 
+    /// Check whether a key down would take us out of insert mode
+    /// Used to let these keys through in ignored apps
+    pub fn would_leave_insert(&self, event: &KeyEvent) -> bool {
+        match event.keycode() {
+            Some(keycode) if self.mode == VimMode::Insert => {
+                let (keycode, modifiers) = escape_synonym(keycode, event.modifiers);
+                self.is_ctrl_c_escape(keycode, &modifiers) || self.escape_enters_normal(keycode)
+            }
+            _ => false,
+        }
+    }
+
+    fn is_ctrl_c_escape(&self, keycode: KeyCode, modifiers: &Modifiers) -> bool {
+        self.options.ctrl_c_as_escape
+            && keycode == KeyCode::C
+            && modifiers.control
+            && !modifiers.command
+    }
+
+    /// Whether Escape goes to normal mode (vim's model) rather than insert mode
+    pub(super) fn escape_enters_normal(&self, keycode: KeyCode) -> bool {
+        keycode == KeyCode::Escape && self.options.escape_target_mode != VimMode::Insert
+    }
+
+    /// Ctrl+C: leave insert or visual mode, or cancel pending keys in normal mode
+    fn handle_ctrl_c(&mut self) -> ProcessResult {
+        match self.mode {
+            VimMode::Insert | VimMode::Visual => {
+                self.set_mode(VimMode::Normal);
+                ProcessResult::ModeChanged(VimMode::Normal, None)
+            }
+            VimMode::Normal => self.cancel_pending(),
+        }
+    }
+
+    /// Cancel pending keys in normal mode
+    /// With nothing pending the key passes through, so apps still receive it
+    pub(super) fn cancel_pending(&mut self) -> ProcessResult {
+        if self.get_pending_keys().is_empty() {
+            return ProcessResult::PassThrough;
+        }
+        self.reset_pending();
+        ProcessResult::Suppress
+    }
+
     fn process_key_up(&self, event: &KeyEvent) -> ProcessResult {
         // In Insert mode, pass through all key up events
         if self.mode == VimMode::Insert {
@@ -189,7 +583,7 @@ impl VimState {
             KeyCode::Num8 | KeyCode::Num9 | KeyCode::G | KeyCode::R |
             KeyCode::D | KeyCode::Y | KeyCode::C | KeyCode::X |
             KeyCode::I | KeyCode::A | KeyCode::O | KeyCode::S |
-            KeyCode::V | KeyCode::P | KeyCode::U |
+            KeyCode::V | KeyCode::P | KeyCode::U | KeyCode::Q | KeyCode::Z |
             KeyCode::LeftBracket | KeyCode::RightBracket |
             KeyCode::Period | KeyCode::Comma
         );
@@ -221,3 +615,338 @@ impl Default for VimState {
         Self::new().0
     }
 }
+
+/// Treat Ctrl+[ as Escape, as terminals and vim do
+/// Other keys are returned unchanged
+fn escape_synonym(keycode: KeyCode, modifiers: Modifiers) -> (KeyCode, Modifiers) {
+    if keycode == KeyCode::LeftBracket
+        && modifiers.control
+        && !modifiers.command
+        && !modifiers.option
+        && !modifiers.shift
+    {
+        (KeyCode::Escape, Modifiers::default())
+    } else {
+        (keycode, modifiers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_util::{control, press, state_with};
+
+    #[test]
+    fn test_ctrl_c_cancels_pending_keys() {
+        let mut state = state_with(
+            VimMode::Normal,
+            VimOptions { ctrl_c_as_escape: true, ..Default::default() },
+        );
+        press(&mut state, KeyCode::Num2, Modifiers::default());
+        press(&mut state, KeyCode::D, Modifiers::default());
+        assert_eq!(state.get_pending_keys(), "2d");
+
+        assert!(matches!(press(&mut state, KeyCode::C, control()), ProcessResult::Suppress));
+        assert_eq!(state.get_pending_keys(), "");
+        assert_eq!(state.mode(), VimMode::Normal);
+    }
+
+    #[test]
+    fn test_ctrl_c_passes_through_without_pending_keys() {
+        let mut state = state_with(
+            VimMode::Normal,
+            VimOptions { ctrl_c_as_escape: true, ..Default::default() },
+        );
+        assert!(matches!(press(&mut state, KeyCode::C, control()), ProcessResult::PassThrough));
+    }
+
+    #[test]
+    fn test_ctrl_c_returns_to_normal_mode() {
+        for mode in [VimMode::Insert, VimMode::Visual] {
+            let mut state = state_with(
+                mode,
+                VimOptions { ctrl_c_as_escape: true, ..Default::default() },
+            );
+            assert!(matches!(
+                press(&mut state, KeyCode::C, control()),
+                ProcessResult::ModeChanged(VimMode::Normal, None)
+            ));
+            assert_eq!(state.mode(), VimMode::Normal);
+        }
+    }
+
+    #[test]
+    fn test_ctrl_c_ignored_when_disabled() {
+        let mut state = VimState::default();
+        assert!(matches!(press(&mut state, KeyCode::C, control()), ProcessResult::PassThrough));
+        assert_eq!(state.mode(), VimMode::Insert);
+    }
+
+    #[test]
+    fn test_escape_in_default_model() {
+        let mut state = state_with(
+            VimMode::Normal,
+            VimOptions { escape_target_mode: VimMode::Insert, ..Default::default() },
+        );
+        assert!(matches!(
+            press(&mut state, KeyCode::Escape, Modifiers::default()),
+            ProcessResult::ModeChanged(VimMode::Insert, None)
+        ));
+        // Escape in insert mode belongs to the app
+        assert!(matches!(
+            press(&mut state, KeyCode::Escape, Modifiers::default()),
+            ProcessResult::PassThrough
+        ));
+        assert_eq!(state.mode(), VimMode::Insert);
+    }
+
+    #[test]
+    fn test_escape_cancels_pending_first() {
+        let mut state = state_with(
+            VimMode::Normal,
+            VimOptions { escape_target_mode: VimMode::Insert, ..Default::default() },
+        );
+        state.set_options(VimOptions {
+            escape_cancels_pending_first: true,
+            ..Default::default()
+        });
+
+        let sequences: [&[KeyCode]; 4] = [
+            &[KeyCode::D],
+            &[KeyCode::Num5],
+            &[KeyCode::G],
+            &[KeyCode::Num2, KeyCode::D, KeyCode::Num3],
+        ];
+        for keys in sequences {
+            for &keycode in keys {
+                press(&mut state, keycode, Modifiers::default());
+            }
+            assert!(matches!(
+                press(&mut state, KeyCode::Escape, Modifiers::default()),
+                ProcessResult::Suppress
+            ));
+            assert_eq!(state.get_pending_keys(), "");
+            assert_eq!(state.mode(), VimMode::Normal);
+        }
+
+        // With nothing pending, Escape switches mode as usual
+        assert!(matches!(
+            press(&mut state, KeyCode::Escape, Modifiers::default()),
+            ProcessResult::ModeChanged(VimMode::Insert, None)
+        ));
+    }
+
+    #[test]
+    fn test_escape_switches_with_pending_when_disabled() {
+        let mut state = state_with(
+            VimMode::Normal,
+            VimOptions { escape_target_mode: VimMode::Insert, ..Default::default() },
+        );
+        press(&mut state, KeyCode::D, Modifiers::default());
+        assert!(matches!(
+            press(&mut state, KeyCode::Escape, Modifiers::default()),
+            ProcessResult::ModeChangedWithPending(VimMode::Insert, None, pending) if pending.is_empty()
+        ));
+        assert_eq!(state.get_pending_keys(), "");
+    }
+
+    #[test]
+    fn test_escape_in_vim_model() {
+        let mut state = state_with(
+            VimMode::Insert,
+            VimOptions { escape_target_mode: VimMode::Normal, ..Default::default() },
+        );
+        assert!(matches!(
+            press(&mut state, KeyCode::Escape, Modifiers::default()),
+            ProcessResult::ModeChanged(VimMode::Normal, None)
+        ));
+
+        // In normal mode, Escape cancels pending keys and otherwise passes through
+        press(&mut state, KeyCode::D, Modifiers::default());
+        assert!(matches!(
+            press(&mut state, KeyCode::Escape, Modifiers::default()),
+            ProcessResult::Suppress
+        ));
+        assert_eq!(state.get_pending_keys(), "");
+        assert!(matches!(
+            press(&mut state, KeyCode::Escape, Modifiers::default()),
+            ProcessResult::PassThrough
+        ));
+        assert_eq!(state.mode(), VimMode::Normal);
+
+        // i still enters insert mode
+        press(&mut state, KeyCode::I, Modifiers::default());
+        assert_eq!(state.mode(), VimMode::Insert);
+    }
+
+    #[test]
+    fn test_escape_from_visual_goes_to_normal_in_both_models() {
+        for target in [VimMode::Insert, VimMode::Normal] {
+            let mut state = state_with(
+                VimMode::Visual,
+                VimOptions { escape_target_mode: target, ..Default::default() },
+            );
+            press(&mut state, KeyCode::Escape, Modifiers::default());
+            assert_eq!(state.mode(), VimMode::Normal);
+        }
+    }
+
+    #[test]
+    fn test_start_mode() {
+        let (state, _rx) = VimState::with_mode(VimMode::Normal);
+        assert_eq!(state.mode(), VimMode::Normal);
+    }
+
+    #[test]
+    fn test_ctrl_bracket_acts_as_escape() {
+        // Insert mode: the app keeps its own Ctrl+[
+        let mut state = state_with(
+            VimMode::Insert,
+            VimOptions { escape_target_mode: VimMode::Insert, ..Default::default() },
+        );
+        assert!(matches!(
+            press(&mut state, KeyCode::LeftBracket, control()),
+            ProcessResult::PassThrough
+        ));
+        assert_eq!(state.mode(), VimMode::Insert);
+
+        // Normal mode: back to insert, as with Escape
+        let mut state = state_with(
+            VimMode::Normal,
+            VimOptions { escape_target_mode: VimMode::Insert, ..Default::default() },
+        );
+        assert!(matches!(
+            press(&mut state, KeyCode::LeftBracket, control()),
+            ProcessResult::ModeChanged(VimMode::Insert, None)
+        ));
+
+        // Visual mode: back to normal
+        let mut state = state_with(
+            VimMode::Visual,
+            VimOptions { escape_target_mode: VimMode::Insert, ..Default::default() },
+        );
+        press(&mut state, KeyCode::LeftBracket, control());
+        assert_eq!(state.mode(), VimMode::Normal);
+
+        // A plain [ is not Escape
+        press(&mut state, KeyCode::LeftBracket, Modifiers::default());
+        assert_eq!(state.mode(), VimMode::Normal);
+    }
+
+    #[test]
+    fn test_ctrl_bracket_in_vim_model() {
+        let mut state = state_with(
+            VimMode::Insert,
+            VimOptions { escape_target_mode: VimMode::Normal, ..Default::default() },
+        );
+        assert!(state.would_leave_insert(&KeyEvent {
+            code: KeyCode::LeftBracket.as_raw(),
+            modifiers: Modifiers { control: true, ..Default::default() },
+            is_key_down: true,
+        }));
+        assert!(matches!(
+            press(&mut state, KeyCode::LeftBracket, control()),
+            ProcessResult::ModeChanged(VimMode::Normal, None)
+        ));
+
+        // Cancels a pending operator
+        press(&mut state, KeyCode::D, Modifiers::default());
+        assert!(matches!(
+            press(&mut state, KeyCode::LeftBracket, control()),
+            ProcessResult::Suppress
+        ));
+        assert_eq!(state.get_pending_keys(), "");
+        assert_eq!(state.mode(), VimMode::Normal);
+    }
+
+    #[test]
+    fn test_mode_change_carries_pending_keys() {
+        let (mut state, mut mode_rx) = VimState::with_mode(VimMode::Normal);
+        let mut pending_rx = state.subscribe_pending();
+
+        press(&mut state, KeyCode::Num2, Modifiers::default());
+        press(&mut state, KeyCode::C, Modifiers::default());
+        assert_eq!(state.get_pending_keys(), "2c");
+        assert!(mode_rx.try_recv().is_err());
+
+        // The motion enters insert and clears the pending keys, in one mode change
+        assert!(matches!(
+            press(&mut state, KeyCode::W, Modifiers::default()),
+            ProcessResult::ModeChangedWithPending(VimMode::Insert, Some(VimAction::OperatorMotion { .. }), pending)
+                if pending.is_empty()
+        ));
+        let change = ModeChange { mode: VimMode::Insert, pending: String::new() };
+        assert_eq!(mode_rx.try_recv(), Ok(change));
+        assert!(mode_rx.try_recv().is_err());
+        // Pending-key listeners still hear of each change
+        let pending: Vec<String> = std::iter::from_fn(|| pending_rx.try_recv().ok()).collect();
+        assert_eq!(pending, ["2", "2c", ""]);
+
+        // A mode change that leaves the pending keys alone is a plain one
+        state.set_mode_external(VimMode::Normal);
+        assert_eq!(mode_rx.try_recv().map(|change| change.mode), Ok(VimMode::Normal));
+        assert!(matches!(
+            press(&mut state, KeyCode::V, Modifiers::default()),
+            ProcessResult::ModeChanged(VimMode::Visual, _)
+        ));
+        assert_eq!(mode_rx.try_recv().map(|change| change.mode), Ok(VimMode::Visual));
+    }
+
+    #[test]
+    fn test_count_changes_are_broadcast() {
+        let (mut state, _) = VimState::with_mode(VimMode::Normal);
+        let mut count_rx = state.subscribe_count();
+
+        press(&mut state, KeyCode::Num5, Modifiers::default());
+        assert_eq!(count_rx.try_recv(), Ok(Some(5)));
+        press(&mut state, KeyCode::Num0, Modifiers::default());
+        assert_eq!(count_rx.try_recv(), Ok(Some(50)));
+
+        // Using the count clears it
+        press(&mut state, KeyCode::J, Modifiers::default());
+        assert_eq!(count_rx.try_recv(), Ok(None));
+        // Keys that don't change it send nothing
+        press(&mut state, KeyCode::J, Modifiers::default());
+        assert!(count_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_set_mode_named() {
+        let (mut state, mut mode_rx) = VimState::with_mode(VimMode::Insert);
+
+        assert_eq!(state.set_mode_named("normal"), Ok(VimMode::Normal));
+        assert_eq!(mode_rx.try_recv().map(|change| change.mode), Ok(VimMode::Normal));
+        assert_eq!(state.set_mode_named("V"), Ok(VimMode::Visual));
+        assert_eq!(mode_rx.try_recv().map(|change| change.mode), Ok(VimMode::Visual));
+
+        assert!(state.set_mode_named("command").is_err());
+        assert_eq!(state.mode(), VimMode::Visual);
+        assert!(mode_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_operator_timeout() {
+        let (mut state, _) = VimState::with_mode(VimMode::Normal);
+        state.set_options(VimOptions {
+            operator_timeout: Some(Duration::from_millis(500)),
+            ..Default::default()
+        });
+        press(&mut state, KeyCode::D, Modifiers::default());
+        assert_eq!(state.get_pending_keys(), "d");
+
+        // A key soon after still completes the operator
+        assert!(matches!(
+            press(&mut state, KeyCode::W, Modifiers::default()),
+            ProcessResult::SuppressWithAction(VimAction::OperatorMotion { .. })
+        ));
+
+        // A key after the timeout runs on its own
+        press(&mut state, KeyCode::D, Modifiers::default());
+        state.last_key_at = Some(Instant::now() - Duration::from_secs(1));
+        assert!(matches!(
+            press(&mut state, KeyCode::W, Modifiers::default()),
+            ProcessResult::SuppressWithAction(VimAction::Command { command: VimCommand::WordForward, .. })
+        ));
+        assert_eq!(state.get_pending_keys(), "");
+    }
+}
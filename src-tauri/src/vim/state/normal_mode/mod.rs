@@ -6,12 +6,16 @@ mod motions;
 mod operators;
 mod text_objects;
 
+use crate::config::ControlComboModifier;
 use crate::keyboard::{KeyCode, Modifiers};
 
 use super::super::commands::VimCommand;
+use super::super::custom_command::{self, NamedCommand};
+use super::super::keymap;
 use super::super::modes::VimMode;
+use super::super::registers::{self, Register};
 use super::action::VimAction;
-use super::{IndentDirection, ProcessResult, VimState};
+use super::{IndentDirection, PendingMacro, ProcessResult, VimState};
 
 impl VimState {
     pub(super) fn process_normal_mode(
@@ -19,27 +23,63 @@ impl VimState {
         keycode: KeyCode,
         modifiers: &Modifiers,
     ) -> ProcessResult {
-        // Escape always goes to insert mode
+        if self.command_line.is_some() {
+            return self.handle_command_line_key(keycode, modifiers);
+        }
+
+        // Escape goes to insert mode, or in vim's model only cancels pending keys
+        if self.escape_enters_normal(keycode) {
+            return self.cancel_pending();
+        }
         if keycode == KeyCode::Escape {
+            if self.options.escape_cancels_pending_first && !self.get_pending_keys().is_empty() {
+                self.reset_pending();
+                return ProcessResult::Suppress;
+            }
             self.set_mode(VimMode::Insert);
             return ProcessResult::ModeChanged(VimMode::Insert, None);
         }
 
+        // Ctrl+P/Ctrl+N only cycle right after a paste
+        let last_paste = self.last_paste.take();
+        // Smart home alternates only while `0` is pressed repeatedly
+        let at_first_non_blank = std::mem::take(&mut self.at_first_non_blank);
+
+        // Register name after `"`
+        if self.pending_register {
+            self.pending_register = false;
+            match Register::from_key(keycode, modifiers.shift) {
+                Some(register) => self.register = Some(register),
+                None => self.reset_pending(),
+            }
+            return ProcessResult::Suppress;
+        }
+
+        // Macro register after `q` or `@`
+        if let Some(pending) = self.pending_macro.take() {
+            return self.handle_macro_register(pending, keycode, modifiers);
+        }
+
         // Handle pending r (replace char)
         if self.pending_r {
             self.pending_r = false;
             return self.handle_replace_char(keycode, modifiers);
         }
 
-        // Handle pending g
-        if self.pending_g {
+        // Handle pending g (after an operator, g is part of the motion)
+        if self.pending_g && self.pending_operator.is_none() {
             self.pending_g = false;
             return self.handle_g_combo(keycode, modifiers);
         }
 
+        if self.pending_z {
+            self.pending_z = false;
+            return self.handle_z_combo(keycode, modifiers);
+        }
+
         // Handle pending text object modifier (i or a after operator)
         if self.pending_text_object.is_some() {
-            return self.handle_text_object(keycode);
+            return self.handle_text_object(keycode, modifiers);
         }
 
         // Handle pending indent
@@ -48,38 +88,66 @@ impl VimState {
             return self.handle_indent_combo(keycode, dir);
         }
 
+        // Handle operators with pending motion (this also accumulates the motion count)
+        if self.pending_operator.is_some() {
+            return self.handle_operator_motion(keycode, modifiers);
+        }
+
         // Handle count accumulation (1-9, then 0-9)
-        if !modifiers.shift {
-            if let Some(digit) = keycode.to_digit() {
-                if digit != 0 || self.pending_count.is_some() {
-                    let current = self.pending_count.unwrap_or(0);
-                    self.pending_count = Some(current * 10 + digit);
-                    return ProcessResult::Suppress;
-                }
-            }
+        if self.accumulate_count(keycode, modifiers) {
+            return ProcessResult::Suppress;
         }
 
-        // Handle operators with pending motion
-        if self.pending_operator.is_some() {
-            return self.handle_operator_motion(keycode, modifiers);
+        match keymap::lookup(&self.options.keymap, keycode, modifiers).cloned() {
+            Some(NamedCommand::Builtin(command)) => return self.run_bound_command(command),
+            Some(NamedCommand::Custom(name)) => return self.run_custom_command(&name),
+            None => {}
         }
 
-        // Check for control key combinations
-        if modifiers.control {
+        // Check for control key combinations (triggered by the configured modifier)
+        if self.control_combo_modifier_held(modifiers) {
+            if let Some(result) = last_paste.and_then(|last| self.cycle_paste(keycode, last)) {
+                return result;
+            }
             return self.handle_control_combo(keycode);
         }
+        // Control combos remapped to another modifier go to the app
+        if modifiers.control {
+            return ProcessResult::PassThrough;
+        }
 
         // Normal mode commands
-        self.handle_normal_command(keycode, modifiers)
+        self.handle_normal_command(keycode, modifiers, at_first_non_blank)
+    }
+
+    /// Add a typed digit to the pending count (1-9, then 0-9)
+    /// Returns false if the key isn't part of a count, e.g. `0` as a motion
+    pub(super) fn accumulate_count(&mut self, keycode: KeyCode, modifiers: &Modifiers) -> bool {
+        if modifiers.shift {
+            return false;
+        }
+        match keycode.to_digit() {
+            Some(digit) if digit != 0 || self.pending_count.is_some() => {
+                self.push_count_digit(digit);
+                true
+            }
+            _ => false,
+        }
     }
 
     fn handle_normal_command(
         &mut self,
         keycode: KeyCode,
         modifiers: &Modifiers,
+        at_first_non_blank: bool,
     ) -> ProcessResult {
-        let count = self.get_count();
-        self.pending_count = None;
+        let typed_count = self.pending_count.take();
+        let count = typed_count.unwrap_or(1);
+        let register = self.register.take();
+        // `"+` and `"*` stay selected for the yank or delete, through its motion
+        if register == Some(Register::Clipboard) && matches!(keycode, KeyCode::D | KeyCode::Y | KeyCode::C) {
+            self.register = register;
+        }
 
         match keycode {
             // Basic motions
@@ -118,11 +186,19 @@ impl VimState {
             }),
 
             // Line motions
-            KeyCode::Num0 => ProcessResult::SuppressWithAction(VimAction::Command {
-                command: VimCommand::LineStart,
-                count: 1,
-                select: false,
-            }),
+            KeyCode::Num0 => {
+                let command = if self.options.smart_home && !at_first_non_blank {
+                    self.at_first_non_blank = true;
+                    VimCommand::FirstNonBlank
+                } else {
+                    VimCommand::LineStart
+                };
+                ProcessResult::SuppressWithAction(VimAction::Command {
+                    command,
+                    count: 1,
+                    select: false,
+                })
+            }
             KeyCode::Num4 if modifiers.shift => ProcessResult::SuppressWithAction(VimAction::Command {
                 command: VimCommand::LineEnd,
                 count: 1,
@@ -151,8 +227,20 @@ impl VimState {
                 select: false,
             }),
 
+            KeyCode::Backslash if modifiers.shift => ProcessResult::SuppressWithAction(VimAction::Command {
+                command: VimCommand::GoToColumn,
+                count,
+                select: false,
+            }),
+
             // g commands
-            KeyCode::G => self.handle_g_key(modifiers),
+            KeyCode::G => self.handle_g_key(modifiers, typed_count),
+
+            // Scrolling: zz, zt, zb
+            KeyCode::Z if !modifiers.shift => {
+                self.pending_z = true;
+                ProcessResult::Suppress
+            }
 
             // Operators
             KeyCode::D => self.handle_delete_operator(count, modifiers),
@@ -169,14 +257,39 @@ impl VimState {
             KeyCode::A => self.handle_append_key(modifiers),
             KeyCode::O => self.handle_open_line_key(modifiers),
 
-            // Visual mode
+            // Visual mode; `{count}v` starts with `count` characters selected
             KeyCode::V => {
                 self.set_mode(VimMode::Visual);
-                ProcessResult::ModeChanged(VimMode::Visual, None)
+                let selection = typed_count.map(|count| VimAction::Command {
+                    command: VimCommand::MoveRight,
+                    count,
+                    select: true,
+                });
+                ProcessResult::ModeChanged(VimMode::Visual, selection)
+            }
+
+            // Registers: "
+            KeyCode::Quote if modifiers.shift => {
+                self.pending_register = true;
+                self.pending_count = typed_count;
+                ProcessResult::Suppress
+            }
+
+            // Macros: q{a-z} records until q, @{a-z} plays
+            KeyCode::Q if !modifiers.shift => self.handle_q_key(),
+            KeyCode::Num2 if modifiers.shift => {
+                self.pending_macro = Some(PendingMacro::Play);
+                self.pending_count = typed_count;
+                ProcessResult::Suppress
             }
 
             // Clipboard
             KeyCode::P => {
+                if let Some(register) = register {
+                    self.last_paste = Some(register);
+                    return ProcessResult::SuppressWithAction(VimAction::PasteRegister { register, count });
+                }
+                self.last_paste = Some(Register::Unnamed);
                 let command = if modifiers.shift {
                     VimCommand::PasteBefore
                 } else {
@@ -202,6 +315,12 @@ impl VimState {
                 ProcessResult::Suppress
             }
 
+            // Repeat the last insert: .
+            KeyCode::Period => self.repeat_last_insert(count),
+
+            // `:` opens the command line on the whole field
+            KeyCode::Semicolon if modifiers.shift => self.start_command_line(""),
+
             // Outdent: < (Shift+,)
             KeyCode::Comma if modifiers.shift => {
                 self.pending_indent = Some(IndentDirection::Outdent);
@@ -228,14 +347,21 @@ impl VimState {
         }
     }
 
-    fn handle_g_key(&mut self, modifiers: &Modifiers) -> ProcessResult {
+    fn handle_g_key(&mut self, modifiers: &Modifiers, typed_count: Option<u32>) -> ProcessResult {
         if modifiers.shift {
+            // `G` goes to the end, `{count}G` to line {count}
+            let (command, count) = match typed_count {
+                Some(line) => (VimCommand::GoToLine, line),
+                None => (VimCommand::DocumentEnd, 1),
+            };
             ProcessResult::SuppressWithAction(VimAction::Command {
-                command: VimCommand::DocumentEnd,
-                count: 1,
+                command,
+                count,
                 select: false,
             })
         } else {
+            // Keep the count for `{count}gg`
+            self.pending_count = typed_count;
             self.pending_g = true;
             ProcessResult::Suppress
         }
@@ -280,6 +406,66 @@ impl VimState {
         }
     }
 
+    /// Check whether the modifier configured for Ctrl-combos is held
+    fn control_combo_modifier_held(&self, modifiers: &Modifiers) -> bool {
+        match self.options.control_combo_modifier {
+            ControlComboModifier::Control => modifiers.control,
+            ControlComboModifier::Command => modifiers.command,
+            ControlComboModifier::Option => modifiers.option,
+        }
+    }
+
+    /// Ctrl+P/Ctrl+N after a paste: replace it with the next older/newer delete
+    /// Stops at the ends of the ring, keeping the current paste
+    fn cycle_paste(&mut self, keycode: KeyCode, last: Register) -> Option<ProcessResult> {
+        let next = match keycode {
+            KeyCode::P => last.older(registers::delete_ring_len()),
+            KeyCode::N => last.newer(),
+            _ => return None,
+        };
+        self.pending_count = None;
+        self.last_paste = Some(next.unwrap_or(last));
+        Some(match next {
+            Some(register) => ProcessResult::SuppressWithAction(VimAction::CyclePaste { register }),
+            None => ProcessResult::Suppress,
+        })
+    }
+
+    /// Run a command bound in the keymap, with the typed count
+    /// `RepeatMotion` re-runs the last motion, with the typed count or else its own
+    fn run_bound_command(&mut self, command: VimCommand) -> ProcessResult {
+        let typed_count = self.pending_count.take();
+        let (command, count) = match command {
+            VimCommand::RepeatMotion => match self.last_motion {
+                Some((motion, count)) => (motion, typed_count.unwrap_or(count)),
+                None => return ProcessResult::Suppress,
+            },
+            command => (command, typed_count.unwrap_or(1)),
+        };
+        let action = VimAction::Command { command, count, select: false };
+        if command.enters_insert() {
+            self.set_mode(VimMode::Insert);
+            ProcessResult::ModeChanged(VimMode::Insert, Some(action))
+        } else {
+            ProcessResult::SuppressWithAction(action)
+        }
+    }
+
+    /// Run a custom command bound in the keymap, with the typed count
+    fn run_custom_command(&mut self, name: &str) -> ProcessResult {
+        let count = self.pending_count.take().unwrap_or(1);
+        match custom_command::find(&self.options.custom_commands, name) {
+            Some(steps) => ProcessResult::SuppressWithAction(VimAction::CustomCommand {
+                steps: steps.to_vec(),
+                count,
+            }),
+            None => {
+                log::warn!("Keymap binds unknown command {}", name);
+                ProcessResult::Suppress
+            }
+        }
+    }
+
     fn handle_control_combo(&mut self, keycode: KeyCode) -> ProcessResult {
         let count = self.get_count();
         self.pending_count = None;
@@ -290,6 +476,8 @@ impl VimState {
             KeyCode::D => VimCommand::HalfPageDown,
             KeyCode::U => VimCommand::HalfPageUp,
             KeyCode::R => VimCommand::Redo,
+            KeyCode::E => VimCommand::ScrollLineDown,
+            KeyCode::Y => VimCommand::ScrollLineUp,
             _ => return ProcessResult::PassThrough,
         };
 
@@ -328,3 +516,491 @@ impl VimState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::commands::Operator;
+    use crate::config::Settings;
+    use crate::vim::keymap::{KeyBinding, KeymapPreset};
+    use super::super::VimOptions;
+    use super::*;
+    use super::super::test_util::{normal_state, press, state_with};
+
+    fn is_page_down(result: &ProcessResult) -> bool {
+        matches!(
+            result,
+            ProcessResult::SuppressWithAction(VimAction::Command {
+                command: VimCommand::PageDown,
+                ..
+            })
+        )
+    }
+
+    #[test]
+    fn test_control_combo_uses_control_by_default() {
+        let mut state = normal_state();
+        let control = Modifiers { control: true, ..Default::default() };
+        assert!(is_page_down(&press(&mut state, KeyCode::F, control)));
+    }
+
+    #[test]
+    fn test_control_combo_uses_configured_modifier() {
+        let mut state = state_with(
+            VimMode::Normal,
+            VimOptions {
+                control_combo_modifier: ControlComboModifier::Command,
+                ..Default::default()
+            },
+        );
+        let command = Modifiers { command: true, ..Default::default() };
+        let control = Modifiers { control: true, ..Default::default() };
+
+        assert!(is_page_down(&press(&mut state, KeyCode::F, command)));
+        assert!(matches!(
+            press(&mut state, KeyCode::F, control),
+            ProcessResult::PassThrough
+        ));
+    }
+
+    #[test]
+    fn test_control_combo_with_option() {
+        let mut state = state_with(
+            VimMode::Normal,
+            VimOptions {
+                control_combo_modifier: ControlComboModifier::Option,
+                ..Default::default()
+            },
+        );
+        let option = Modifiers { option: true, ..Default::default() };
+        assert!(is_page_down(&press(&mut state, KeyCode::F, option)));
+    }
+
+    fn command_of(result: &ProcessResult) -> Option<(VimCommand, u32)> {
+        match result {
+            ProcessResult::SuppressWithAction(VimAction::Command { command, count, .. }) => {
+                Some((*command, *count))
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_smart_home_alternates() {
+        let mut state = normal_state();
+        let none = Modifiers::default();
+        let zero = |state: &mut VimState| command_of(&press(state, KeyCode::Num0, none));
+
+        // Off by default
+        assert_eq!(zero(&mut state), Some((VimCommand::LineStart, 1)));
+        assert_eq!(zero(&mut state), Some((VimCommand::LineStart, 1)));
+
+        state.set_options(VimOptions {
+            smart_home: true,
+            ..Default::default()
+        });
+        assert_eq!(zero(&mut state), Some((VimCommand::FirstNonBlank, 1)));
+        assert_eq!(zero(&mut state), Some((VimCommand::LineStart, 1)));
+        assert_eq!(zero(&mut state), Some((VimCommand::FirstNonBlank, 1)));
+
+        // Another key in between starts over from the first non-blank
+        press(&mut state, KeyCode::J, none);
+        assert_eq!(zero(&mut state), Some((VimCommand::FirstNonBlank, 1)));
+        press(&mut state, KeyCode::L, none);
+        assert_eq!(zero(&mut state), Some((VimCommand::FirstNonBlank, 1)));
+        assert_eq!(zero(&mut state), Some((VimCommand::LineStart, 1)));
+    }
+
+    #[test]
+    fn test_z_scroll_commands() {
+        let mut state = normal_state();
+        let none = Modifiers::default();
+        for (keycode, expected) in [
+            (KeyCode::Z, VimCommand::ScrollCaretCenter),
+            (KeyCode::T, VimCommand::ScrollCaretTop),
+            (KeyCode::B, VimCommand::ScrollCaretBottom),
+        ] {
+            press(&mut state, KeyCode::Z, none);
+            assert_eq!(state.get_pending_keys(), "z");
+            let result = press(&mut state, keycode, none);
+            assert_eq!(command_of(&result), Some((expected, 1)));
+            assert_eq!(state.get_pending_keys(), "");
+        }
+    }
+
+    #[test]
+    fn test_ctrl_e_y_scroll_lines() {
+        let mut state = normal_state();
+        let none = Modifiers::default();
+        let control = Modifiers { control: true, ..Default::default() };
+
+        let result = press(&mut state, KeyCode::E, control);
+        assert_eq!(command_of(&result), Some((VimCommand::ScrollLineDown, 1)));
+        press(&mut state, KeyCode::Num3, none);
+        let result = press(&mut state, KeyCode::Y, control);
+        assert_eq!(command_of(&result), Some((VimCommand::ScrollLineUp, 3)));
+        assert_eq!(state.get_pending_keys(), "");
+    }
+
+    #[test]
+    fn test_keymap_bindings_take_precedence() {
+        let mut state = normal_state();
+        let mut settings = Settings::default();
+        settings.apply_keymap_preset(KeymapPreset::HelixLike);
+        state.set_options(VimOptions::from_settings(&settings));
+        let none = Modifiers::default();
+        let shift = Modifiers { shift: true, ..Default::default() };
+
+        press(&mut state, KeyCode::Num2, none);
+        assert_eq!(command_of(&press(&mut state, KeyCode::U, shift)), Some((VimCommand::Redo, 2)));
+        assert_eq!(command_of(&press(&mut state, KeyCode::U, none)), Some((VimCommand::Undo, 1)));
+
+        // Bound commands that insert switch modes like their vim keys
+        state.set_options(VimOptions {
+            keymap: vec![KeyBinding {
+                key: "home".to_string(),
+                modifiers: Default::default(),
+                command: VimCommand::InsertAtLineStart.into(),
+            }],
+            ..Default::default()
+        });
+        assert!(matches!(
+            press(&mut state, KeyCode::Home, none),
+            ProcessResult::ModeChanged(VimMode::Insert, Some(_))
+        ));
+        assert_eq!(state.mode(), VimMode::Insert);
+    }
+
+    #[test]
+    fn test_keymap_runs_custom_commands() {
+        use crate::vim::custom_command::{CustomCommand, Primitive};
+
+        let mut state = normal_state();
+        let steps = vec![Primitive::SelectWordForward, Primitive::Cut];
+        state.set_options(VimOptions {
+            keymap: vec![
+                KeyBinding {
+                    key: "q".to_string(),
+                    modifiers: Default::default(),
+                    command: NamedCommand::Custom("cut-word".to_string()),
+                },
+                KeyBinding {
+                    key: "z".to_string(),
+                    modifiers: Default::default(),
+                    command: NamedCommand::Custom("missing".to_string()),
+                },
+            ],
+            custom_commands: vec![CustomCommand {
+                name: "cut-word".to_string(),
+                steps: steps.clone(),
+            }],
+            ..Default::default()
+        });
+        let none = Modifiers::default();
+
+        press(&mut state, KeyCode::Num3, none);
+        match press(&mut state, KeyCode::Q, none) {
+            ProcessResult::SuppressWithAction(VimAction::CustomCommand { steps: ran, count }) => {
+                assert_eq!(ran, steps);
+                assert_eq!(count, 3);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(matches!(press(&mut state, KeyCode::Z, none), ProcessResult::Suppress));
+        assert_eq!(state.mode(), VimMode::Normal);
+    }
+
+    #[test]
+    fn test_count_before_visual_selects_chars() {
+        let mut state = normal_state();
+        let none = Modifiers::default();
+
+        press(&mut state, KeyCode::Num3, none);
+        assert!(matches!(
+            press(&mut state, KeyCode::V, none),
+            ProcessResult::ModeChangedWithPending(
+                VimMode::Visual,
+                Some(VimAction::Command { command: VimCommand::MoveRight, count: 3, select: true }),
+                _,
+            )
+        ));
+        assert_eq!(state.mode(), VimMode::Visual);
+
+        // Without a count nothing is selected yet
+        let mut state = normal_state();
+        assert!(matches!(
+            press(&mut state, KeyCode::V, none),
+            ProcessResult::ModeChanged(VimMode::Visual, None)
+        ));
+    }
+
+    #[test]
+    fn test_repeat_motion() {
+        let mut state = normal_state();
+        state.set_options(VimOptions {
+            keymap: vec![KeyBinding {
+                key: "semicolon".to_string(),
+                modifiers: Default::default(),
+                command: VimCommand::RepeatMotion.into(),
+            }],
+            ..Default::default()
+        });
+        let none = Modifiers::default();
+
+        // Nothing to repeat yet
+        assert!(matches!(press(&mut state, KeyCode::Semicolon, none), ProcessResult::Suppress));
+        assert_eq!(state.last_motion(), None);
+
+        press(&mut state, KeyCode::Num3, none);
+        press(&mut state, KeyCode::W, none);
+        assert_eq!(state.last_motion(), Some((VimCommand::WordForward, 3)));
+        assert_eq!(
+            command_of(&press(&mut state, KeyCode::Semicolon, none)),
+            Some((VimCommand::WordForward, 3))
+        );
+
+        // Edits aren't motions, so they don't replace the one to repeat
+        press(&mut state, KeyCode::X, none);
+        press(&mut state, KeyCode::Num2, none);
+        assert_eq!(
+            command_of(&press(&mut state, KeyCode::Semicolon, none)),
+            Some((VimCommand::WordForward, 2))
+        );
+
+        // A new motion replaces it
+        press(&mut state, KeyCode::J, none);
+        assert_eq!(
+            command_of(&press(&mut state, KeyCode::Semicolon, none)),
+            Some((VimCommand::MoveDown, 1))
+        );
+    }
+
+    #[test]
+    fn test_backtick_text_objects() {
+        let mut state = normal_state();
+        let none = Modifiers::default();
+
+        press(&mut state, KeyCode::C, none);
+        press(&mut state, KeyCode::I, none);
+        assert!(matches!(
+            press(&mut state, KeyCode::Grave, none),
+            ProcessResult::ModeChangedWithPending(
+                VimMode::Insert,
+                Some(VimAction::TextObject {
+                    operator: Operator::Change,
+                    text_object: VimCommand::InnerBacktick,
+                    ..
+                }),
+                _,
+            )
+        ));
+
+        state.set_mode_external(VimMode::Normal);
+        press(&mut state, KeyCode::D, none);
+        press(&mut state, KeyCode::A, none);
+        assert!(matches!(
+            press(&mut state, KeyCode::Grave, none),
+            ProcessResult::SuppressWithAction(VimAction::TextObject {
+                operator: Operator::Delete,
+                text_object: VimCommand::AroundBacktick,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_tab_motions() {
+        let mut state = normal_state();
+        state.set_options(VimOptions {
+            enable_tab_motions: true,
+            ..Default::default()
+        });
+        let none = Modifiers::default();
+        let shift = Modifiers { shift: true, ..Default::default() };
+
+        press(&mut state, KeyCode::G, none);
+        let result = press(&mut state, KeyCode::T, none);
+        assert_eq!(command_of(&result), Some((VimCommand::NextTab, 1)));
+
+        press(&mut state, KeyCode::G, none);
+        let result = press(&mut state, KeyCode::T, shift);
+        assert_eq!(command_of(&result), Some((VimCommand::PrevTab, 1)));
+
+        // {count}gt goes to a tab, {count}gT goes back that many tabs
+        press(&mut state, KeyCode::Num3, none);
+        press(&mut state, KeyCode::G, none);
+        let result = press(&mut state, KeyCode::T, none);
+        assert_eq!(command_of(&result), Some((VimCommand::GoToTab, 3)));
+
+        press(&mut state, KeyCode::Num2, none);
+        press(&mut state, KeyCode::G, none);
+        let result = press(&mut state, KeyCode::T, shift);
+        assert_eq!(command_of(&result), Some((VimCommand::PrevTab, 2)));
+        assert_eq!(state.get_pending_keys(), "");
+    }
+
+    #[test]
+    fn test_tab_motions_disabled() {
+        let mut state = normal_state();
+        press(&mut state, KeyCode::G, Modifiers::default());
+        let result = press(&mut state, KeyCode::T, Modifiers::default());
+        assert!(matches!(result, ProcessResult::PassThrough));
+    }
+
+    #[test]
+    fn test_g_with_and_without_count() {
+        let mut state = normal_state();
+        let none = Modifiers::default();
+        let shift = Modifiers { shift: true, ..Default::default() };
+
+        let result = press(&mut state, KeyCode::G, shift);
+        assert_eq!(command_of(&result), Some((VimCommand::DocumentEnd, 1)));
+
+        press(&mut state, KeyCode::Num1, none);
+        let result = press(&mut state, KeyCode::G, shift);
+        assert_eq!(command_of(&result), Some((VimCommand::GoToLine, 1)));
+
+        press(&mut state, KeyCode::G, none);
+        let result = press(&mut state, KeyCode::G, none);
+        assert_eq!(command_of(&result), Some((VimCommand::DocumentStart, 1)));
+
+        press(&mut state, KeyCode::Num1, none);
+        press(&mut state, KeyCode::Num2, none);
+        press(&mut state, KeyCode::G, none);
+        assert_eq!(state.get_pending_keys(), "12g");
+        let result = press(&mut state, KeyCode::G, none);
+        assert_eq!(command_of(&result), Some((VimCommand::GoToLine, 12)));
+    }
+
+    #[test]
+    fn test_numbered_register_paste() {
+        let mut state = normal_state();
+        let none = Modifiers::default();
+        let shift = Modifiers { shift: true, ..Default::default() };
+
+        press(&mut state, KeyCode::Quote, shift);
+        assert_eq!(state.get_pending_keys(), "\"");
+        press(&mut state, KeyCode::Num3, none);
+        assert_eq!(state.get_pending_keys(), "\"3");
+        press(&mut state, KeyCode::Num2, none);
+        assert_eq!(state.get_pending_keys(), "\"32");
+        assert!(matches!(
+            press(&mut state, KeyCode::P, none),
+            ProcessResult::SuppressWithAction(VimAction::PasteRegister {
+                register: Register::Numbered(3),
+                count: 2,
+            })
+        ));
+        assert_eq!(state.get_pending_keys(), "");
+
+        // The register only applies to the next command
+        press(&mut state, KeyCode::Quote, shift);
+        press(&mut state, KeyCode::Num1, none);
+        press(&mut state, KeyCode::J, none);
+        let result = press(&mut state, KeyCode::P, none);
+        assert_eq!(command_of(&result), Some((VimCommand::Paste, 1)));
+
+        // Anything but 1-9 cancels the register
+        press(&mut state, KeyCode::Quote, shift);
+        press(&mut state, KeyCode::A, none);
+        assert_eq!(state.get_pending_keys(), "");
+    }
+
+    #[test]
+    fn test_clipboard_register() {
+        let mut state = normal_state();
+        let none = Modifiers::default();
+        let shift = Modifiers { shift: true, ..Default::default() };
+        let to_clipboard = |result: ProcessResult| match result {
+            ProcessResult::SuppressWithAction(VimAction::ToClipboard(action)) => Some(*action),
+            _ => None,
+        };
+
+        // `"+yy` yanks the line to the clipboard
+        press(&mut state, KeyCode::Quote, shift);
+        press(&mut state, KeyCode::Equal, shift);
+        press(&mut state, KeyCode::Y, none);
+        assert_eq!(state.get_pending_keys(), "\"+y");
+        let yanked = to_clipboard(press(&mut state, KeyCode::Y, none)).unwrap();
+        assert!(yanked.yanks_text());
+        assert_eq!(state.get_pending_keys(), "");
+
+        // `"*dw` through a motion
+        press(&mut state, KeyCode::Quote, shift);
+        press(&mut state, KeyCode::Num8, shift);
+        press(&mut state, KeyCode::D, none);
+        assert!(to_clipboard(press(&mut state, KeyCode::W, none)).unwrap().cuts_text());
+
+        // `"+D`, and the register is gone after
+        press(&mut state, KeyCode::Quote, shift);
+        press(&mut state, KeyCode::Equal, shift);
+        assert!(to_clipboard(press(&mut state, KeyCode::D, shift)).is_some());
+        assert!(to_clipboard(press(&mut state, KeyCode::D, shift)).is_none());
+
+        // `"+p` pastes from the clipboard
+        press(&mut state, KeyCode::Quote, shift);
+        press(&mut state, KeyCode::Equal, shift);
+        assert!(matches!(
+            press(&mut state, KeyCode::P, none),
+            ProcessResult::SuppressWithAction(VimAction::PasteRegister { register: Register::Clipboard, count: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_cycle_paste_through_delete_ring() {
+        for i in 0..registers::DELETE_RING_SIZE {
+            registers::record_delete(format!("delete {}", i));
+        }
+        let mut state = normal_state();
+        let none = Modifiers::default();
+        let ctrl = Modifiers { control: true, ..Default::default() };
+        let cycled = |result: ProcessResult| match result {
+            ProcessResult::SuppressWithAction(VimAction::CyclePaste { register }) => Some(register),
+            _ => None,
+        };
+
+        // Without a paste just before, Ctrl+P/Ctrl+N go to the app
+        assert!(matches!(press(&mut state, KeyCode::P, ctrl), ProcessResult::PassThrough));
+
+        press(&mut state, KeyCode::P, none);
+        assert_eq!(cycled(press(&mut state, KeyCode::P, ctrl)), Some(Register::Numbered(1)));
+        assert_eq!(cycled(press(&mut state, KeyCode::P, ctrl)), Some(Register::Numbered(2)));
+        assert_eq!(cycled(press(&mut state, KeyCode::N, ctrl)), Some(Register::Numbered(1)));
+        assert_eq!(cycled(press(&mut state, KeyCode::N, ctrl)), Some(Register::Unnamed));
+        // At the newest end the paste is kept
+        assert!(matches!(press(&mut state, KeyCode::N, ctrl), ProcessResult::Suppress));
+        assert_eq!(cycled(press(&mut state, KeyCode::P, ctrl)), Some(Register::Numbered(1)));
+
+        // Any other key ends cycling
+        press(&mut state, KeyCode::J, none);
+        assert!(matches!(press(&mut state, KeyCode::P, ctrl), ProcessResult::PassThrough));
+    }
+
+    #[test]
+    fn test_count_is_clamped() {
+        let mut state = normal_state();
+        let none = Modifiers::default();
+        for _ in 0..12 {
+            press(&mut state, KeyCode::Num9, none);
+        }
+        assert_eq!(state.get_pending_keys(), "9999");
+        let result = press(&mut state, KeyCode::J, none);
+        assert_eq!(command_of(&result), Some((VimCommand::MoveDown, 9999)));
+
+        // Digits that would pass the cap clamp to it
+        for keycode in [KeyCode::Num5, KeyCode::Num0, KeyCode::Num0, KeyCode::Num0, KeyCode::Num0] {
+            press(&mut state, keycode, none);
+        }
+        let result = press(&mut state, KeyCode::J, none);
+        assert_eq!(command_of(&result), Some((VimCommand::MoveDown, 9999)));
+    }
+
+    #[test]
+    fn test_bar_goes_to_column() {
+        let mut state = normal_state();
+        let shift = Modifiers { shift: true, ..Default::default() };
+        press(&mut state, KeyCode::Num5, Modifiers::default());
+        press(&mut state, KeyCode::Num0, Modifiers::default());
+        let result = press(&mut state, KeyCode::Backslash, shift);
+        assert_eq!(command_of(&result), Some((VimCommand::GoToColumn, 50)));
+    }
+}
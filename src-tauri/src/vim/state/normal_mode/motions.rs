@@ -1,8 +1,8 @@
-//! Motion handling for normal mode (g combos, replace char)
+//! Motion handling for normal mode (g and z combos, replace char)
 
 use crate::keyboard::{KeyCode, Modifiers};
 
-use super::super::super::commands::VimCommand;
+use super::super::super::commands::{Operator, VimCommand};
 use super::super::action::VimAction;
 use super::super::{ProcessResult, VimState};
 
@@ -12,13 +12,23 @@ impl VimState {
         keycode: KeyCode,
         modifiers: &Modifiers,
     ) -> ProcessResult {
-        let count = self.get_count();
-        self.pending_count = None;
+        let typed_count = self.pending_count.take();
+        let count = typed_count.unwrap_or(1);
 
         match keycode {
+            // `gg` goes to the start, `{count}gg` to line {count}
             KeyCode::G => ProcessResult::SuppressWithAction(VimAction::Command {
-                command: VimCommand::DocumentStart,
-                count: 1,
+                command: if typed_count.is_some() {
+                    VimCommand::GoToLine
+                } else {
+                    VimCommand::DocumentStart
+                },
+                count,
+                select: false,
+            }),
+            KeyCode::E if modifiers.shift => ProcessResult::SuppressWithAction(VimAction::Command {
+                command: VimCommand::WordEndBackwardBig,
+                count,
                 select: false,
             }),
             KeyCode::E => ProcessResult::SuppressWithAction(VimAction::Command {
@@ -26,6 +36,31 @@ impl VimState {
                 count,
                 select: false,
             }),
+            // `gt`/`gT` cycle tabs, `{count}gt` goes to tab {count}
+            KeyCode::T if self.options.enable_tab_motions => {
+                let command = if modifiers.shift {
+                    VimCommand::PrevTab
+                } else if typed_count.is_some() {
+                    VimCommand::GoToTab
+                } else {
+                    VimCommand::NextTab
+                };
+                ProcessResult::SuppressWithAction(VimAction::Command {
+                    command,
+                    count,
+                    select: false,
+                })
+            }
+            // `gq` and `gw` take a motion like `d`, rewrapping the lines it covers
+            KeyCode::Q | KeyCode::W if !modifiers.shift => {
+                self.pending_operator = Some(if keycode == KeyCode::Q {
+                    Operator::Format
+                } else {
+                    Operator::FormatKeepCursor
+                });
+                self.pending_operator_count = (count > 1).then_some(count);
+                ProcessResult::Suppress
+            }
             KeyCode::J => ProcessResult::SuppressWithAction(VimAction::Command {
                 command: VimCommand::MoveDown,
                 count,
@@ -50,6 +85,22 @@ impl VimState {
         }
     }
 
+    /// `zz`, `zt` and `zb` scroll the caret to the center, top or bottom of the view
+    pub(super) fn handle_z_combo(&mut self, keycode: KeyCode, modifiers: &Modifiers) -> ProcessResult {
+        self.pending_count = None;
+        let command = match keycode {
+            KeyCode::Z if !modifiers.shift => VimCommand::ScrollCaretCenter,
+            KeyCode::T if !modifiers.shift => VimCommand::ScrollCaretTop,
+            KeyCode::B if !modifiers.shift => VimCommand::ScrollCaretBottom,
+            _ => return ProcessResult::PassThrough,
+        };
+        ProcessResult::SuppressWithAction(VimAction::Command {
+            command,
+            count: 1,
+            select: false,
+        })
+    }
+
     pub(super) fn handle_replace_char(
         &mut self,
         keycode: KeyCode,
@@ -1,5 +1,6 @@
-//! Operator handling for normal mode (d, y, c)
+//! Operator handling for normal mode (d, y, c, gq, gw)
 
+use crate::config::UnknownMotionKey;
 use crate::keyboard::{KeyCode, Modifiers};
 
 use super::super::super::commands::{Operator, VimCommand};
@@ -28,6 +29,7 @@ impl VimState {
             })
         } else {
             self.pending_operator = Some(Operator::Delete);
+            self.pending_operator_count = (count > 1).then_some(count);
             ProcessResult::Suppress
         }
     }
@@ -52,6 +54,7 @@ impl VimState {
             })
         } else {
             self.pending_operator = Some(Operator::Yank);
+            self.pending_operator_count = (count > 1).then_some(count);
             ProcessResult::Suppress
         }
     }
@@ -84,6 +87,7 @@ impl VimState {
             )
         } else {
             self.pending_operator = Some(Operator::Change);
+            self.pending_operator_count = (count > 1).then_some(count);
             ProcessResult::Suppress
         }
     }
@@ -149,18 +153,29 @@ impl VimState {
         keycode: KeyCode,
         modifiers: &Modifiers,
     ) -> ProcessResult {
-        // Check for doubled operator (dd, yy, cc)
+        // Motions after g (dgg, dge, dgE)
+        if self.pending_g {
+            self.pending_g = false;
+            return self.handle_operator_g_combo(keycode, modifiers);
+        }
+
+        // Check for doubled operator (dd, yy, cc, gqq, gww)
         let doubled = match (&self.pending_operator, keycode) {
             (Some(Operator::Delete), KeyCode::D) if !modifiers.shift => true,
             (Some(Operator::Yank), KeyCode::Y) if !modifiers.shift => true,
             (Some(Operator::Change), KeyCode::C) if !modifiers.shift => true,
-            _ => false,
+            _ => self.doubles_format_operator(keycode, modifiers),
         };
 
         if doubled {
             return self.handle_doubled_operator();
         }
 
+        // Count typed after the operator applies to the motion (d3w)
+        if self.accumulate_count(keycode, modifiers) {
+            return ProcessResult::Suppress;
+        }
+
         // Check for text object modifier (i or a)
         if keycode == KeyCode::I && !modifiers.shift {
             self.pending_text_object = Some(TextObjectModifier::Inner);
@@ -177,26 +192,35 @@ impl VimState {
             return ProcessResult::Suppress;
         }
 
-        // Handle gg motion after g in operator mode
-        if self.pending_g {
-            self.pending_g = false;
-            if keycode == KeyCode::G {
-                return self.handle_operator_gg();
-            }
-        }
-
         self.handle_operator_with_motion(keycode, modifiers)
     }
 
+    /// Whether the key repeats the last key of `gq` or `gw`, which then apply to whole lines
+    fn doubles_format_operator(&self, keycode: KeyCode, modifiers: &Modifiers) -> bool {
+        !modifiers.shift
+            && matches!(
+                (self.pending_operator, keycode),
+                (Some(Operator::Format), KeyCode::Q) | (Some(Operator::FormatKeepCursor), KeyCode::W)
+            )
+    }
+
     fn handle_doubled_operator(&mut self) -> ProcessResult {
         let operator = self.pending_operator.take().unwrap();
-        let count = self.get_count();
-        self.pending_count = None;
+        let count = self.take_operator_count();
 
         let command = match operator {
             Operator::Delete => VimCommand::DeleteLine,
             Operator::Yank => VimCommand::YankLine,
             Operator::Change => VimCommand::ChangeLine,
+            // `{count}gqq` rewraps {count} lines, up to the end of the last one
+            Operator::Format | Operator::FormatKeepCursor => {
+                return ProcessResult::SuppressWithAction(VimAction::OperatorMotion {
+                    operator,
+                    motion: VimCommand::LineEnd,
+                    operator_count: 1,
+                    motion_count: count,
+                })
+            }
         };
 
         if operator == Operator::Change {
@@ -218,30 +242,65 @@ impl VimState {
         }
     }
 
-    fn handle_operator_gg(&mut self) -> ProcessResult {
+    fn handle_operator_g_combo(&mut self, keycode: KeyCode, modifiers: &Modifiers) -> ProcessResult {
+        // `gqgq` and `gwgw`, spelled out in full
+        if self.doubles_format_operator(keycode, modifiers) {
+            return self.handle_doubled_operator();
+        }
+        let motion = match keycode {
+            KeyCode::G if !modifiers.shift => VimCommand::DocumentStart,
+            KeyCode::E if modifiers.shift => VimCommand::WordEndBackwardBig,
+            KeyCode::E => VimCommand::WordEndBackward,
+            _ => {
+                // Unsupported g motion, cancel the operator
+                self.reset_pending();
+                return ProcessResult::Suppress;
+            }
+        };
         let operator = match self.pending_operator.take() {
             Some(op) => op,
             None => return ProcessResult::PassThrough,
         };
-        let count = self.get_count();
-        self.pending_count = None;
+        let (operator_count, motion_count) = self.take_operator_counts();
+        let action = VimAction::OperatorMotion {
+            operator,
+            motion,
+            operator_count,
+            motion_count,
+        };
 
         if operator == Operator::Change {
             self.set_mode(VimMode::Insert);
-            ProcessResult::ModeChanged(
-                VimMode::Insert,
-                Some(VimAction::OperatorMotion {
-                    operator,
-                    motion: VimCommand::DocumentStart,
-                    count,
-                }),
-            )
+            ProcessResult::ModeChanged(VimMode::Insert, Some(action))
         } else {
-            ProcessResult::SuppressWithAction(VimAction::OperatorMotion {
-                operator,
-                motion: VimCommand::DocumentStart,
-                count,
-            })
+            ProcessResult::SuppressWithAction(action)
+        }
+    }
+
+    /// A key after an operator that isn't a motion cancels the operator, as in vim,
+    /// unless settings keep it waiting for a motion
+    fn handle_unknown_motion_key(
+        &mut self,
+        operator: Operator,
+        operator_count: u32,
+        keycode: KeyCode,
+        modifiers: &Modifiers,
+    ) -> ProcessResult {
+        match self.options.unknown_motion_key {
+            UnknownMotionKey::Reprocess => {
+                self.reset_pending();
+                self.process_normal_mode(keycode, modifiers)
+            }
+            UnknownMotionKey::PassThrough => {
+                self.reset_pending();
+                ProcessResult::PassThrough
+            }
+            UnknownMotionKey::Ignore => {
+                // Restore the operator and its count
+                self.pending_operator = Some(operator);
+                self.pending_operator_count = (operator_count > 1).then_some(operator_count);
+                ProcessResult::Suppress
+            }
         }
     }
 
@@ -255,8 +314,7 @@ impl VimState {
             None => return ProcessResult::PassThrough,
         };
 
-        let count = self.get_count();
-        self.pending_count = None;
+        let (operator_count, motion_count) = self.take_operator_counts();
 
         let motion = match keycode {
             KeyCode::H => Some(VimCommand::MoveLeft),
@@ -270,6 +328,7 @@ impl VimState {
             KeyCode::Num4 if modifiers.shift => Some(VimCommand::LineEnd),
             KeyCode::Num6 if modifiers.shift => Some(VimCommand::LineStart),
             KeyCode::Minus if modifiers.shift => Some(VimCommand::LineStart),
+            KeyCode::Backslash if modifiers.shift => Some(VimCommand::GoToColumn),
             KeyCode::LeftBracket if modifiers.shift => Some(VimCommand::ParagraphUp),
             KeyCode::RightBracket if modifiers.shift => Some(VimCommand::ParagraphDown),
             KeyCode::G if modifiers.shift => Some(VimCommand::DocumentEnd),
@@ -277,27 +336,277 @@ impl VimState {
         };
 
         if let Some(motion) = motion {
+            let action = VimAction::OperatorMotion {
+                operator,
+                motion,
+                operator_count,
+                motion_count,
+            };
             if operator == Operator::Change {
                 self.set_mode(VimMode::Insert);
-                ProcessResult::ModeChanged(
-                    VimMode::Insert,
-                    Some(VimAction::OperatorMotion {
-                        operator,
-                        motion,
-                        count,
-                    }),
-                )
+                ProcessResult::ModeChanged(VimMode::Insert, Some(action))
             } else {
-                ProcessResult::SuppressWithAction(VimAction::OperatorMotion {
-                    operator,
-                    motion,
-                    count,
-                })
+                ProcessResult::SuppressWithAction(action)
             }
         } else {
-            // Invalid motion, restore operator
-            self.pending_operator = Some(operator);
-            ProcessResult::Suppress
+            self.handle_unknown_motion_key(operator, operator_count, keycode, modifiers)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::test_util::{normal_state, press, shift, state_with, type_keys};
+    use crate::vim::VimOptions;
+
+    fn operator_motion_count(result: ProcessResult) -> Option<(VimCommand, u32)> {
+        match result {
+            ProcessResult::SuppressWithAction(action) => match &action {
+                VimAction::OperatorMotion { motion, .. } => Some((*motion, action.count())),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_count_after_operator() {
+        let mut state = normal_state();
+        let result = type_keys(&mut state, &[KeyCode::D, KeyCode::Num3, KeyCode::W]);
+        assert_eq!(operator_motion_count(result), Some((VimCommand::WordForward, 3)));
+    }
+
+    #[test]
+    fn test_counts_before_and_after_operator_multiply() {
+        let mut state = normal_state();
+        type_keys(&mut state, &[KeyCode::Num2, KeyCode::D, KeyCode::Num3]);
+        assert_eq!(state.get_pending_keys(), "2d3");
+
+        let result = type_keys(&mut state, &[KeyCode::W]);
+        assert_eq!(operator_motion_count(result), Some((VimCommand::WordForward, 6)));
+        assert_eq!(state.get_pending_keys(), "");
+    }
+
+    #[test]
+    fn test_operator_and_motion_counts_are_kept_separately() {
+        let mut state = normal_state();
+        let result = type_keys(&mut state, &[KeyCode::Num2, KeyCode::D, KeyCode::Num3, KeyCode::W]);
+        assert!(matches!(
+            result,
+            ProcessResult::SuppressWithAction(VimAction::OperatorMotion {
+                operator: Operator::Delete,
+                operator_count: 2,
+                motion_count: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_operator_counts_are_clamped() {
+        let mut state = normal_state();
+        let digits = [KeyCode::Num8; 10];
+        type_keys(&mut state, &digits);
+        type_keys(&mut state, &[KeyCode::D]);
+        type_keys(&mut state, &digits);
+        let result = type_keys(&mut state, &[KeyCode::W]);
+        assert!(matches!(
+            result,
+            ProcessResult::SuppressWithAction(VimAction::OperatorMotion {
+                operator_count: 9999,
+                motion_count: 9999,
+                ..
+            })
+        ));
+        // Multiplied, they're clamped the same way
+        assert_eq!(operator_motion_count(result), Some((VimCommand::WordForward, 9999)));
+
+        type_keys(&mut state, &digits);
+        type_keys(&mut state, &[KeyCode::D]);
+        type_keys(&mut state, &digits);
+        let result = type_keys(&mut state, &[KeyCode::D]);
+        assert!(matches!(
+            result,
+            ProcessResult::SuppressWithAction(VimAction::Command {
+                command: VimCommand::DeleteLine,
+                count: 9999,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_count_before_operator() {
+        let mut state = normal_state();
+        let result = type_keys(&mut state, &[KeyCode::Num2, KeyCode::D, KeyCode::J]);
+        assert_eq!(operator_motion_count(result), Some((VimCommand::MoveDown, 2)));
+    }
+
+    #[test]
+    fn test_count_with_doubled_operator() {
+        let mut state = normal_state();
+        let result = type_keys(&mut state, &[KeyCode::Num3, KeyCode::D, KeyCode::D]);
+        assert!(matches!(
+            result,
+            ProcessResult::SuppressWithAction(VimAction::Command {
+                command: VimCommand::DeleteLine,
+                count: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_zero_after_operator_is_a_motion() {
+        let mut state = normal_state();
+        let result = type_keys(&mut state, &[KeyCode::D, KeyCode::Num0]);
+        assert_eq!(operator_motion_count(result), Some((VimCommand::LineStart, 1)));
+
+        // A count before the operator doesn't make `0` a digit
+        let result = type_keys(&mut state, &[KeyCode::Num2, KeyCode::D, KeyCode::Num0]);
+        assert_eq!(operator_motion_count(result), Some((VimCommand::LineStart, 2)));
+    }
+
+    #[test]
+    fn test_zero_continues_a_motion_count() {
+        let mut state = normal_state();
+        type_keys(&mut state, &[KeyCode::D, KeyCode::Num1, KeyCode::Num0]);
+        assert_eq!(state.get_pending_keys(), "d10");
+        let result = press(&mut state, KeyCode::Backslash, shift());
+        assert_eq!(operator_motion_count(result), Some((VimCommand::GoToColumn, 10)));
+    }
+
+    #[test]
+    fn test_operator_with_g_motions() {
+        let mut state = normal_state();
+        let result = type_keys(&mut state, &[KeyCode::D, KeyCode::G, KeyCode::E]);
+        assert_eq!(operator_motion_count(result), Some((VimCommand::WordEndBackward, 1)));
+
+        let result = type_keys(&mut state, &[KeyCode::Num2, KeyCode::D, KeyCode::G, KeyCode::G]);
+        assert_eq!(operator_motion_count(result), Some((VimCommand::DocumentStart, 2)));
+
+        type_keys(&mut state, &[KeyCode::D, KeyCode::G]);
+        assert_eq!(state.get_pending_keys(), "dg");
+        let result = press(&mut state, KeyCode::E, shift());
+        assert_eq!(operator_motion_count(result), Some((VimCommand::WordEndBackwardBig, 1)));
+        assert_eq!(state.mode(), VimMode::Normal);
+        assert_eq!(state.get_pending_keys(), "");
+    }
+
+    #[test]
+    fn test_change_with_g_motion_enters_insert() {
+        let mut state = normal_state();
+        type_keys(&mut state, &[KeyCode::C, KeyCode::G]);
+        let result = press(&mut state, KeyCode::E, shift());
+        assert!(matches!(
+            result,
+            ProcessResult::ModeChangedWithPending(
+                VimMode::Insert,
+                Some(VimAction::OperatorMotion {
+                    operator: Operator::Change,
+                    motion: VimCommand::WordEndBackwardBig,
+                    ..
+                }),
+                _,
+            )
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_g_motion_cancels_operator() {
+        let mut state = normal_state();
+        let result = type_keys(&mut state, &[KeyCode::D, KeyCode::G, KeyCode::X]);
+        assert!(matches!(result, ProcessResult::Suppress));
+        assert_eq!(state.get_pending_keys(), "");
+    }
+
+    #[test]
+    fn test_g_motions_without_operator() {
+        let mut state = normal_state();
+        type_keys(&mut state, &[KeyCode::G]);
+        let result = press(&mut state, KeyCode::E, shift());
+        assert!(matches!(
+            result,
+            ProcessResult::SuppressWithAction(VimAction::Command {
+                command: VimCommand::WordEndBackwardBig,
+                ..
+            })
+        ));
+        let result = type_keys(&mut state, &[KeyCode::G, KeyCode::E]);
+        assert!(matches!(
+            result,
+            ProcessResult::SuppressWithAction(VimAction::Command {
+                command: VimCommand::WordEndBackward,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_format_operators() {
+        let mut state = normal_state();
+        type_keys(&mut state, &[KeyCode::G, KeyCode::Q]);
+        assert_eq!(state.get_pending_keys(), "gq");
+        let result = type_keys(&mut state, &[KeyCode::J]);
+        assert!(matches!(
+            result,
+            ProcessResult::SuppressWithAction(VimAction::OperatorMotion {
+                operator: Operator::Format,
+                motion: VimCommand::MoveDown,
+                ..
+            })
+        ));
+
+        // Doubled, `gww` and `gwgw` rewrap the cursor's line
+        for keys in [&[KeyCode::G, KeyCode::W, KeyCode::W][..], &[KeyCode::G, KeyCode::W, KeyCode::G, KeyCode::W]] {
+            let result = type_keys(&mut state, keys);
+            assert!(matches!(
+                result,
+                ProcessResult::SuppressWithAction(VimAction::OperatorMotion {
+                    operator: Operator::FormatKeepCursor,
+                    motion: VimCommand::LineEnd,
+                    motion_count: 1,
+                    ..
+                })
+            ));
         }
+
+        // `3gqq` rewraps three lines
+        let result = type_keys(&mut state, &[KeyCode::Num3, KeyCode::G, KeyCode::Q, KeyCode::Q]);
+        assert_eq!(operator_motion_count(result), Some((VimCommand::LineEnd, 3)));
+        assert_eq!(state.mode(), VimMode::Normal);
+    }
+
+    #[test]
+    fn test_unknown_key_cancels_operator() {
+        // By default the key runs as it would without the operator: `dq` waits for a macro register
+        let mut state = normal_state();
+        assert!(matches!(type_keys(&mut state, &[KeyCode::D, KeyCode::Q]), ProcessResult::Suppress));
+        assert_eq!(state.get_pending_keys(), "q");
+        let mut state = normal_state();
+        assert!(matches!(type_keys(&mut state, &[KeyCode::D, KeyCode::Space]), ProcessResult::PassThrough));
+        assert_eq!(state.get_pending_keys(), "");
+
+        let mut state = state_with(
+            VimMode::Normal,
+            VimOptions { unknown_motion_key: UnknownMotionKey::PassThrough, ..Default::default() },
+        );
+        assert!(matches!(type_keys(&mut state, &[KeyCode::Num2, KeyCode::D, KeyCode::Q]), ProcessResult::PassThrough));
+        assert_eq!(state.get_pending_keys(), "");
+        assert!(matches!(type_keys(&mut state, &[KeyCode::D, KeyCode::Space]), ProcessResult::PassThrough));
+        assert_eq!(state.get_pending_keys(), "");
+    }
+
+    #[test]
+    fn test_unknown_key_can_keep_operator() {
+        let mut state = state_with(
+            VimMode::Normal,
+            VimOptions { unknown_motion_key: UnknownMotionKey::Ignore, ..Default::default() },
+        );
+        assert!(matches!(type_keys(&mut state, &[KeyCode::Num2, KeyCode::D, KeyCode::Q]), ProcessResult::Suppress));
+        assert_eq!(state.get_pending_keys(), "2d");
+        let result = type_keys(&mut state, &[KeyCode::Space, KeyCode::W]);
+        assert_eq!(operator_motion_count(result), Some((VimCommand::WordForward, 2)));
     }
 }
@@ -1,14 +1,14 @@
 //! Text object handling for normal mode (iw, aw, etc.)
 
-use crate::keyboard::KeyCode;
+use crate::keyboard::{KeyCode, Modifiers};
 
-use super::super::super::commands::{Operator, VimCommand};
+use super::super::super::commands::Operator;
 use super::super::super::modes::VimMode;
 use super::super::action::VimAction;
-use super::super::{ProcessResult, TextObjectModifier, VimState};
+use super::super::{ProcessResult, VimState};
 
 impl VimState {
-    pub(super) fn handle_text_object(&mut self, keycode: KeyCode) -> ProcessResult {
+    pub(super) fn handle_text_object(&mut self, keycode: KeyCode, modifiers: &Modifiers) -> ProcessResult {
         let modifier = match self.pending_text_object.take() {
             Some(m) => m,
             None => return ProcessResult::PassThrough,
@@ -19,16 +19,9 @@ impl VimState {
             None => return ProcessResult::PassThrough,
         };
 
-        let count = self.get_count();
-        self.pending_count = None;
-
-        // Only 'w' text object is supported for now
-        if keycode == KeyCode::W {
-            let text_object = match modifier {
-                TextObjectModifier::Inner => VimCommand::InnerWord,
-                TextObjectModifier::Around => VimCommand::AroundWord,
-            };
+        let count = self.take_operator_count();
 
+        if let Some(text_object) = modifier.command(keycode, modifiers) {
             if operator == Operator::Change {
                 self.set_mode(VimMode::Insert);
                 ProcessResult::ModeChanged(
@@ -0,0 +1,51 @@
+//! Helpers shared by the vim tests
+
+use super::{ProcessResult, VimOptions, VimState};
+use crate::keyboard::{KeyCode, KeyEvent, Modifiers};
+use crate::vim::modes::VimMode;
+
+/// A state machine in `mode` with `options`
+pub(crate) fn state_with(mode: VimMode, options: VimOptions) -> VimState {
+    let mut state = VimState::default();
+    state.set_options(options);
+    state.set_mode_external(mode);
+    state
+}
+
+/// A state machine in normal mode, with default options
+pub(crate) fn normal_state() -> VimState {
+    state_with(VimMode::Normal, VimOptions::default())
+}
+
+/// Press `keycode` with `modifiers` held
+pub(crate) fn press(state: &mut VimState, keycode: KeyCode, modifiers: Modifiers) -> ProcessResult {
+    state.process_key(KeyEvent {
+        code: keycode.as_raw(),
+        modifiers,
+        is_key_down: true,
+    })
+}
+
+/// Press each of `keys` without modifiers, returning what the last one did
+pub(crate) fn type_keys(state: &mut VimState, keys: &[KeyCode]) -> ProcessResult {
+    let mut result = ProcessResult::PassThrough;
+    for &keycode in keys {
+        result = press(state, keycode, Modifiers::default());
+    }
+    result
+}
+
+/// Only Shift held
+pub(crate) fn shift() -> Modifiers {
+    Modifiers { shift: true, ..Default::default() }
+}
+
+/// Only Control held
+pub(crate) fn control() -> Modifiers {
+    Modifiers { control: true, ..Default::default() }
+}
+
+/// `text` as chars, as the motion functions take it
+pub(crate) fn chars(text: &str) -> Vec<char> {
+    text.chars().collect()
+}
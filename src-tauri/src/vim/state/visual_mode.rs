@@ -1,5 +1,6 @@
 use crate::keyboard::{KeyCode, Modifiers};
 use super::super::commands::VimCommand;
+use super::super::ex_command::SELECTION_RANGE;
 use super::super::modes::VimMode;
 use super::action::VimAction;
 use super::{ProcessResult, TextObjectModifier};
@@ -7,6 +8,10 @@ use super::VimState;
 
 impl VimState {
     pub(super) fn process_visual_mode_with_modifiers(&mut self, keycode: KeyCode, modifiers: &Modifiers) -> ProcessResult {
+        if self.command_line.is_some() {
+            return self.handle_command_line_key(keycode, modifiers);
+        }
+
         // Escape exits visual mode
         if keycode == KeyCode::Escape {
             self.set_mode(VimMode::Normal);
@@ -22,12 +27,12 @@ impl VimState {
         // Handle pending g
         if self.pending_g {
             self.pending_g = false;
-            return self.handle_visual_g_combo(keycode);
+            return self.handle_visual_g_combo(keycode, modifiers);
         }
 
         // Handle pending text object modifier
         if let Some(modifier) = self.pending_text_object.take() {
-            return self.handle_visual_text_object(keycode, modifier);
+            return self.handle_visual_text_object(keycode, modifiers, modifier);
         }
 
         // Handle count accumulation (1-9, then 0-9)
@@ -36,8 +41,7 @@ impl VimState {
         if !modifiers.shift {
             if let Some(digit) = keycode.to_digit() {
                 if digit != 0 || self.pending_count.is_some() {
-                    let current = self.pending_count.unwrap_or(0);
-                    self.pending_count = Some(current * 10 + digit);
+                    self.push_count_digit(digit);
                     return ProcessResult::Suppress;
                 }
             }
@@ -118,25 +122,49 @@ impl VimState {
                 ProcessResult::Suppress
             }
 
+            // Swap the selection's ends
+            KeyCode::O => {
+                let head_at_start = self.visual_head_at_start;
+                self.visual_head_at_start = !head_at_start;
+                ProcessResult::SuppressWithAction(VimAction::SwapSelectionEnds { head_at_start })
+            }
+
+            // `:` opens the command line on the selected lines
+            KeyCode::Semicolon if modifiers.shift => self.start_command_line(SELECTION_RANGE),
+
             // Operations on selection
             KeyCode::D | KeyCode::X => {
                 self.set_mode(VimMode::Normal);
-                ProcessResult::ModeChanged(VimMode::Normal, Some(VimAction::Cut))
+                ProcessResult::ModeChanged(VimMode::Normal, Some(self.selection_operator(VimAction::Cut, true)))
             }
             KeyCode::Y => {
+                let copy = self.selection_operator(VimAction::Copy, true);
+                // Copying leaves the selection in place, so staying in visual keeps it
+                if self.options.keep_selection_after_yank {
+                    return ProcessResult::SuppressWithAction(copy);
+                }
                 self.set_mode(VimMode::Normal);
-                ProcessResult::ModeChanged(VimMode::Normal, Some(VimAction::Copy))
+                ProcessResult::ModeChanged(VimMode::Normal, Some(copy))
             }
             KeyCode::C => {
                 self.set_mode(VimMode::Insert);
-                ProcessResult::ModeChanged(VimMode::Insert, Some(VimAction::Cut))
+                // A linewise change leaves an empty line to type on
+                ProcessResult::ModeChanged(VimMode::Insert, Some(self.selection_operator(VimAction::Cut, false)))
             }
 
             _ => ProcessResult::PassThrough,
         }
     }
 
-    fn handle_visual_g_combo(&mut self, keycode: KeyCode) -> ProcessResult {
+    /// `action` on the selection, or on its whole lines with `visual_mode_linewise_operators`
+    fn selection_operator(&self, action: VimAction, include_newline: bool) -> VimAction {
+        if !self.options.visual_mode_linewise_operators {
+            return action;
+        }
+        VimAction::Linewise { action: Box::new(action), include_newline }
+    }
+
+    fn handle_visual_g_combo(&mut self, keycode: KeyCode, modifiers: &Modifiers) -> ProcessResult {
         let count = self.get_count();
         self.pending_count = None;
 
@@ -147,6 +175,12 @@ impl VimState {
                     command: VimCommand::DocumentStart, count: 1, select: true
                 })
             }
+            KeyCode::E if modifiers.shift => {
+                // gE = end of previous WORD with selection
+                ProcessResult::SuppressWithAction(VimAction::Command {
+                    command: VimCommand::WordEndBackwardBig, count, select: true
+                })
+            }
             KeyCode::E => {
                 // ge = end of previous word with selection
                 ProcessResult::SuppressWithAction(VimAction::Command {
@@ -157,19 +191,118 @@ impl VimState {
         }
     }
 
-    fn handle_visual_text_object(&self, keycode: KeyCode, modifier: TextObjectModifier) -> ProcessResult {
+    fn handle_visual_text_object(
+        &self,
+        keycode: KeyCode,
+        modifiers: &Modifiers,
+        modifier: TextObjectModifier,
+    ) -> ProcessResult {
         // In visual mode, text objects extend the selection
-        if keycode == KeyCode::W {
-            let text_object = match modifier {
-                TextObjectModifier::Inner => VimCommand::InnerWord,
-                TextObjectModifier::Around => VimCommand::AroundWord,
-            };
+        match modifier.command(keycode, modifiers) {
             // Execute the text object to extend selection
-            ProcessResult::SuppressWithAction(VimAction::Command {
+            Some(text_object) => ProcessResult::SuppressWithAction(VimAction::Command {
                 command: text_object, count: 1, select: false
-            })
-        } else {
-            ProcessResult::PassThrough
+            }),
+            None => ProcessResult::PassThrough,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::VimOptions;
+    use super::*;
+    use super::super::test_util::{press, state_with};
+    use crate::keyboard::KeyEvent;
+
+    #[test]
+    fn test_yank_returns_to_normal_by_default() {
+        let mut state = state_with(VimMode::Visual, VimOptions::default());
+        assert!(matches!(
+            press(&mut state, KeyCode::Y, Modifiers::default()),
+            ProcessResult::ModeChanged(VimMode::Normal, Some(VimAction::Copy))
+        ));
+        assert_eq!(state.mode(), VimMode::Normal);
+    }
+
+    #[test]
+    fn test_count_is_clamped() {
+        let mut state = state_with(VimMode::Visual, VimOptions::default());
+        for _ in 0..12 {
+            state.process_key(KeyEvent {
+                code: KeyCode::Num7.as_raw(),
+                modifiers: Modifiers::default(),
+                is_key_down: true,
+            });
         }
+        let result = state.process_key(KeyEvent {
+            code: KeyCode::J.as_raw(),
+            modifiers: Modifiers::default(),
+            is_key_down: true,
+        });
+        assert!(matches!(
+            result,
+            ProcessResult::SuppressWithAction(VimAction::Command { count: 9999, select: true, .. })
+        ));
+    }
+
+    #[test]
+    fn test_linewise_operators() {
+        let mut state = state_with(VimMode::Visual, VimOptions {
+            visual_mode_linewise_operators: true,
+            ..Default::default()
+        });
+        assert!(matches!(
+            press(&mut state, KeyCode::Y, Modifiers::default()),
+            ProcessResult::ModeChanged(VimMode::Normal, Some(VimAction::Linewise { action, include_newline: true }))
+                if matches!(*action, VimAction::Copy)
+        ));
+
+        state.set_mode_external(VimMode::Visual);
+        let result = state.process_key(KeyEvent {
+            code: KeyCode::C.as_raw(),
+            modifiers: Modifiers::default(),
+            is_key_down: true,
+        });
+        assert!(matches!(
+            result,
+            ProcessResult::ModeChanged(VimMode::Insert, Some(VimAction::Linewise { action, include_newline: false }))
+                if matches!(*action, VimAction::Cut)
+        ));
+    }
+
+    #[test]
+    fn test_yank_keeps_visual_mode_when_enabled() {
+        let mut state = state_with(VimMode::Visual, VimOptions {
+            keep_selection_after_yank: true,
+            ..Default::default()
+        });
+        assert!(matches!(
+            press(&mut state, KeyCode::Y, Modifiers::default()),
+            ProcessResult::SuppressWithAction(VimAction::Copy)
+        ));
+        assert_eq!(state.mode(), VimMode::Visual);
+    }
+
+    #[test]
+    fn test_o_alternates_selection_ends() {
+        let mut state = state_with(VimMode::Visual, VimOptions::default());
+        let press_o = |state: &mut VimState| match state.process_key(KeyEvent {
+            code: KeyCode::O.as_raw(),
+            modifiers: Modifiers::default(),
+            is_key_down: true,
+        }) {
+            ProcessResult::SuppressWithAction(VimAction::SwapSelectionEnds { head_at_start }) => head_at_start,
+            other => panic!("unexpected result {:?}", other),
+        };
+        assert!(!press_o(&mut state));
+        assert!(press_o(&mut state));
+        assert!(!press_o(&mut state));
+
+        // A new selection starts with the head at its end
+        press_o(&mut state);
+        state.set_mode_external(VimMode::Normal);
+        state.set_mode_external(VimMode::Visual);
+        assert!(!press_o(&mut state));
     }
 }
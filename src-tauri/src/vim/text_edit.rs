@@ -0,0 +1,86 @@
+//! Deletes computed from the field's text, for replacing the range through accessibility
+//! instead of injecting ForwardDelete or Cmd+X
+//!
+//! Ranges match what the injected keys delete, so both ways of applying a command agree.
+//! Offsets are char indices, as in `word_motion`.
+
+use std::ops::Range;
+
+use super::commands::VimCommand;
+use super::line_motion::{line_end, line_start};
+
+/// Chars `command` deletes with the caret at `caret`, repeated `count` times
+/// None for commands that aren't deletes
+pub fn delete_range(command: VimCommand, chars: &[char], caret: usize, count: u32) -> Option<Range<usize>> {
+    let caret = caret.min(chars.len());
+    let count = count.max(1) as usize;
+    match command {
+        // `x` and `X` stay on the caret's line
+        VimCommand::DeleteChar => Some(caret..(caret + count).min(line_end(chars, caret))),
+        VimCommand::DeleteCharBefore => Some(caret.saturating_sub(count).max(line_start(chars, caret))..caret),
+        // The line's text, leaving it empty, as selecting it and cutting does
+        VimCommand::DeleteLine => Some(line_start(chars, caret)..line_end(chars, caret)),
+        VimCommand::DeleteToLineEnd => Some(caret..line_end(chars, caret)),
+        _ => None,
+    }
+}
+
+/// Whether the command's deleted text goes to the clipboard, as with Cmd+X
+pub fn cuts(command: VimCommand) -> bool {
+    matches!(command, VimCommand::DeleteLine | VimCommand::DeleteToLineEnd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vim::state::test_util::chars;
+
+    /// `chars` without the chars in `range`
+    fn remove_range(chars: &[char], range: Range<usize>) -> String {
+        chars[..range.start].iter().chain(&chars[range.end..]).collect()
+    }
+
+    /// Text left after `command` with the caret at `caret`
+    fn after(command: VimCommand, text: &str, caret: usize, count: u32) -> Option<String> {
+        let text = chars(text);
+        let range = delete_range(command, &text, caret, count)?;
+        Some(remove_range(&text, range))
+    }
+
+    #[test]
+    fn test_remove_range() {
+        let text = chars("hello world");
+        assert_eq!(remove_range(&text, 5..11), "hello");
+        assert_eq!(remove_range(&text, 0..6), "world");
+        assert_eq!(remove_range(&text, 3..3), "hello world");
+        assert_eq!(remove_range(&chars("añb"), 1..2), "ab");
+    }
+
+    #[test]
+    fn test_delete_char_stays_on_line() {
+        let text = "abc\ndef";
+        assert_eq!(after(VimCommand::DeleteChar, text, 1, 1).as_deref(), Some("ac\ndef"));
+        assert_eq!(after(VimCommand::DeleteChar, text, 1, 5).as_deref(), Some("a\ndef"));
+        // Nothing to delete at the line's end
+        assert_eq!(delete_range(VimCommand::DeleteChar, &chars(text), 3, 1), Some(3..3));
+        assert_eq!(after(VimCommand::DeleteCharBefore, text, 6, 1).as_deref(), Some("abc\ndf"));
+        assert_eq!(after(VimCommand::DeleteCharBefore, text, 6, 9).as_deref(), Some("abc\nf"));
+        assert_eq!(delete_range(VimCommand::DeleteCharBefore, &chars(text), 4, 1), Some(4..4));
+    }
+
+    #[test]
+    fn test_delete_line_and_to_line_end() {
+        let text = "one\ntwo three\nfour";
+        assert_eq!(after(VimCommand::DeleteLine, text, 6, 1).as_deref(), Some("one\n\nfour"));
+        assert_eq!(after(VimCommand::DeleteToLineEnd, text, 7, 1).as_deref(), Some("one\ntwo\nfour"));
+        assert_eq!(after(VimCommand::DeleteLine, "", 0, 1).as_deref(), Some(""));
+        assert!(cuts(VimCommand::DeleteLine));
+        assert!(!cuts(VimCommand::DeleteChar));
+    }
+
+    #[test]
+    fn test_other_commands_arent_deletes() {
+        assert_eq!(delete_range(VimCommand::MoveLeft, &chars("abc"), 1, 1), None);
+        assert_eq!(delete_range(VimCommand::YankLine, &chars("abc"), 1, 1), None);
+    }
+}
@@ -0,0 +1,406 @@
+//! Ranges of text objects computed from the field's text: words, quotes, brackets,
+//! paragraphs and markdown code fences
+//!
+//! `` i` ``/`` a` `` select inline code between backticks on the caret's line, or the
+//! ```` ``` ```` fenced block around the caret when there is none. Offsets are char
+//! indices, as in `word_motion`.
+
+use std::ops::Range;
+
+use super::line_motion::{line_end, line_start};
+use super::word_motion::{big_char_class, char_class, CharClass};
+
+/// What a text object selects, after its `i` or `a`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextObjectKind {
+    /// `w`
+    Word,
+    /// `W`
+    BigWord,
+    /// `"` or `'`
+    Quote(char),
+    /// `` ` ``, inline code or a fenced block
+    Backtick,
+    /// `(`, `[`, `{` or `<`, or the closing one, `b` or `B`
+    Bracket { open: char, close: char },
+    /// `p`
+    Paragraph,
+}
+
+/// A text object as typed, e.g. `iw` or `a(`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextObjectSpec {
+    pub kind: TextObjectKind,
+    /// `a` rather than `i`
+    pub around: bool,
+}
+
+impl TextObjectSpec {
+    /// Parse a text object as typed in vim, e.g. `iw`, `i"` or `ap`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut chars = spec.chars();
+        let around = match chars.next() {
+            Some('i') => false,
+            Some('a') => true,
+            _ => return Err(format!("Text object must start with i or a: {}", spec)),
+        };
+        let kind = match (chars.next(), chars.next()) {
+            (Some('w'), None) => TextObjectKind::Word,
+            (Some('W'), None) => TextObjectKind::BigWord,
+            (Some(quote @ ('"' | '\'')), None) => TextObjectKind::Quote(quote),
+            (Some('`'), None) => TextObjectKind::Backtick,
+            (Some('(' | ')' | 'b'), None) => TextObjectKind::Bracket { open: '(', close: ')' },
+            (Some('[' | ']'), None) => TextObjectKind::Bracket { open: '[', close: ']' },
+            (Some('{' | '}' | 'B'), None) => TextObjectKind::Bracket { open: '{', close: '}' },
+            (Some('<' | '>'), None) => TextObjectKind::Bracket { open: '<', close: '>' },
+            (Some('p'), None) => TextObjectKind::Paragraph,
+            _ => return Err(format!("Unknown text object: {}", spec)),
+        };
+        Ok(Self { kind, around })
+    }
+
+    /// Chars the text object selects with the caret at `caret`, if there's one around it
+    pub fn range(self, chars: &[char], caret: usize) -> Option<Range<usize>> {
+        match self.kind {
+            TextObjectKind::Word => word_range(chars, caret, char_class, self.around),
+            TextObjectKind::BigWord => word_range(chars, caret, big_char_class, self.around),
+            TextObjectKind::Quote(quote) => quote_range(chars, caret, quote, self.around),
+            TextObjectKind::Backtick => backtick_range(chars, caret, self.around),
+            TextObjectKind::Bracket { open, close } => bracket_range(chars, caret, open, close, self.around),
+            TextObjectKind::Paragraph => paragraph_range(chars, caret, self.around),
+        }
+    }
+}
+
+/// Chars around `pos` on its line that are in the same class as it
+fn class_run(chars: &[char], pos: usize, class_of: fn(char) -> CharClass) -> Range<usize> {
+    let class = class_of(chars[pos]);
+    let line = line_start(chars, pos)..line_end(chars, pos);
+    let start = (line.start..pos).rev().take_while(|&i| class_of(chars[i]) == class).last().unwrap_or(pos);
+    let end = (pos..line.end).take_while(|&i| class_of(chars[i]) == class).last().map_or(pos, |i| i + 1);
+    start..end
+}
+
+/// Range of `iw`/`aw` (or `iW`/`aW` with `big_char_class`), within the caret's line
+/// `around` adds the white space after the word, or before it when there's none after.
+/// On white space, `iw` is the white space and `aw` adds the word after it
+fn word_range(
+    chars: &[char],
+    caret: usize,
+    class_of: fn(char) -> CharClass,
+    around: bool,
+) -> Option<Range<usize>> {
+    if caret >= line_end(chars, caret) {
+        return None;
+    }
+    let word = class_run(chars, caret, class_of);
+    if !around {
+        return Some(word);
+    }
+    let line = line_start(chars, caret)..line_end(chars, caret);
+    let is_blank = |i: usize| class_of(chars[i]) == CharClass::Whitespace;
+    if word.end < line.end && (is_blank(caret) || is_blank(word.end)) {
+        return Some(word.start..class_run(chars, word.end, class_of).end);
+    }
+    if word.start > line.start && !is_blank(caret) && is_blank(word.start - 1) {
+        return Some(class_run(chars, word.start - 1, class_of).start..word.end);
+    }
+    Some(word)
+}
+
+/// Range of `i(`/`a(` and the other brackets: the innermost pair around the caret,
+/// or the pair of the bracket it's on. Pairs can span lines
+pub fn bracket_range(chars: &[char], caret: usize, open: char, close: char, around: bool) -> Option<Range<usize>> {
+    let caret = caret.min(chars.len().checked_sub(1)?);
+    let mut depth = 0;
+    let mut open_at = None;
+    for i in (0..=caret).rev() {
+        if chars[i] == close && i != caret {
+            depth += 1;
+        } else if chars[i] == open {
+            if depth == 0 {
+                open_at = Some(i);
+                break;
+            }
+            depth -= 1;
+        }
+    }
+    let open_at = open_at?;
+    let mut depth = 0;
+    let close_at = (open_at + 1..chars.len()).find(|&i| {
+        if chars[i] == open {
+            depth += 1;
+        } else if chars[i] == close {
+            if depth == 0 {
+                return true;
+            }
+            depth -= 1;
+        }
+        false
+    })?;
+    Some(if around { open_at..close_at + 1 } else { open_at + 1..close_at })
+}
+
+/// Range of `ip`/`ap`: the run of non-blank lines around the caret, or of blank lines when
+/// it's on one, with the last newline. `around` adds the blank lines after, or before when
+/// there are none after
+pub fn paragraph_range(chars: &[char], caret: usize, around: bool) -> Option<Range<usize>> {
+    if chars.is_empty() {
+        return None;
+    }
+    let is_blank = |start: usize| chars[start..line_end(chars, start)].iter().all(|c| c.is_whitespace());
+    let next_line = |start: usize| (line_end(chars, start) + 1).min(chars.len());
+    // Lines from `start` on that are blank or not as `blank` says, up to the text's end
+    let extend_down = |mut end: usize, blank: bool| {
+        while end < chars.len() && is_blank(end) == blank {
+            end = next_line(end);
+        }
+        end
+    };
+    let extend_up = |mut start: usize, blank: bool| {
+        while start > 0 && is_blank(line_start(chars, start - 1)) == blank {
+            start = line_start(chars, start - 1);
+        }
+        start
+    };
+
+    let first = line_start(chars, caret);
+    let blank = is_blank(first);
+    let start = extend_up(first, blank);
+    let end = extend_down(next_line(first), blank);
+    if !around {
+        return Some(start..end);
+    }
+    let after = extend_down(end, !blank);
+    if after > end || blank {
+        return Some(start..after);
+    }
+    Some(extend_up(start, true)..end)
+}
+
+/// Range of the `quote`-delimited string on the caret's line, as vim's `i"`/`a"`
+/// Quotes pair up from the line start. The caret picks the pair it's in or on, or else the
+/// first one after it. `around` adds the quotes and the white space after them
+pub fn quote_range(chars: &[char], caret: usize, quote: char, around: bool) -> Option<Range<usize>> {
+    let start = line_start(chars, caret);
+    let end = line_end(chars, caret);
+    let quotes: Vec<usize> = (start..end).filter(|&i| chars[i] == quote).collect();
+    let (open, close) = quotes
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .find(|&(_, close)| caret <= close)?;
+
+    if !around {
+        return Some(open + 1..close);
+    }
+    let trailing = chars[close + 1..end]
+        .iter()
+        .take_while(|c| **c == ' ' || **c == '\t')
+        .count();
+    Some(open..close + 1 + trailing)
+}
+
+/// Whether the line starting at `start` opens or closes a fenced code block
+fn is_fence_line(chars: &[char], start: usize) -> bool {
+    let end = line_end(chars, start);
+    let line: String = chars[start..end].iter().collect();
+    line.trim_start().starts_with("```")
+}
+
+/// Range of the fenced code block around the caret
+/// Inner is the lines between the fences, without the last newline; `around` is
+/// the whole block with the fence lines and the newline after it
+pub fn code_fence_range(chars: &[char], caret: usize, around: bool) -> Option<Range<usize>> {
+    let caret_line = line_start(chars, caret);
+    let mut open = None;
+    let mut start = 0;
+    loop {
+        if is_fence_line(chars, start) {
+            match open.take() {
+                None => open = Some(start),
+                Some(open) if open <= caret_line && caret_line <= start => {
+                    let close_end = line_end(chars, start);
+                    if around {
+                        return Some(open..(close_end + 1).min(chars.len()));
+                    }
+                    let inner_start = line_end(chars, open) + 1;
+                    let inner_end = start.saturating_sub(1).max(inner_start);
+                    return Some(inner_start..inner_end);
+                }
+                Some(_) => {}
+            }
+        }
+        let end = line_end(chars, start);
+        if end >= chars.len() || (start > caret_line && open.is_none()) {
+            return None;
+        }
+        start = end + 1;
+    }
+}
+
+/// Range of `` i` ``/`` a` ``: a fenced block when the caret is on a fence line or outside
+/// inline code, otherwise the backtick pair on the caret's line
+pub fn backtick_range(chars: &[char], caret: usize, around: bool) -> Option<Range<usize>> {
+    if !is_fence_line(chars, line_start(chars, caret)) {
+        if let Some(range) = quote_range(chars, caret, '`', around) {
+            return Some(range);
+        }
+    }
+    code_fence_range(chars, caret, around)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vim::state::test_util::chars;
+
+    fn text_of(chars: &[char], range: Option<Range<usize>>) -> Option<String> {
+        range.map(|range| chars[range].iter().collect())
+    }
+
+    #[test]
+    fn test_quote_range() {
+        let text = chars("run `cargo test` then `ls`  now");
+        // Inside, on the opening quote, and before the first pair
+        assert_eq!(text_of(&text, quote_range(&text, 7, '`', false)).as_deref(), Some("cargo test"));
+        assert_eq!(text_of(&text, quote_range(&text, 4, '`', false)).as_deref(), Some("cargo test"));
+        assert_eq!(text_of(&text, quote_range(&text, 0, '`', false)).as_deref(), Some("cargo test"));
+        // Between pairs the next pair is used
+        assert_eq!(text_of(&text, quote_range(&text, 18, '`', false)).as_deref(), Some("ls"));
+        assert_eq!(text_of(&text, quote_range(&text, 23, '`', true)).as_deref(), Some("`ls`  "));
+        assert_eq!(quote_range(&text, 29, '`', false), None);
+        // Empty quotes
+        let text = chars("a `` b");
+        assert_eq!(quote_range(&text, 2, '`', false), Some(3..3));
+    }
+
+    #[test]
+    fn test_quotes_stay_on_their_line() {
+        let text = chars("one `a\nb` two");
+        assert_eq!(quote_range(&text, 5, '`', false), None);
+        assert_eq!(quote_range(&text, 8, '`', false), None);
+    }
+
+    #[test]
+    fn test_code_fence_range() {
+        let text = chars("Try this:\n```rust\nfn main() {}\nlet x = 1;\n```\nThanks");
+        let caret = 20; // in `fn main`
+        assert_eq!(
+            text_of(&text, code_fence_range(&text, caret, false)).as_deref(),
+            Some("fn main() {}\nlet x = 1;")
+        );
+        assert_eq!(
+            text_of(&text, code_fence_range(&text, caret, true)).as_deref(),
+            Some("```rust\nfn main() {}\nlet x = 1;\n```\n")
+        );
+        // On the fence lines themselves
+        assert_eq!(code_fence_range(&text, 12, false), code_fence_range(&text, caret, false));
+        assert_eq!(code_fence_range(&text, 43, false), code_fence_range(&text, caret, false));
+        // Outside the block
+        assert_eq!(code_fence_range(&text, 2, false), None);
+        assert_eq!(code_fence_range(&text, 48, false), None);
+    }
+
+    #[test]
+    fn test_code_fence_pairs_and_unclosed() {
+        let text = chars("```\na\n```\nout\n```\nb\n```");
+        let second = 18; // `b`
+        assert_eq!(text_of(&text, code_fence_range(&text, second, false)).as_deref(), Some("b"));
+        // Between two blocks isn't inside either
+        assert_eq!(code_fence_range(&text, 11, false), None);
+        // Indented fences and a block without a closing fence
+        let text = chars("  ```\n  x\n  ```");
+        assert_eq!(text_of(&text, code_fence_range(&text, 7, false)).as_deref(), Some("  x"));
+        let text = chars("```\nnever closed");
+        assert_eq!(code_fence_range(&text, 6, false), None);
+        // An empty block has an empty inside after the opening fence
+        let text = chars("```\n```");
+        assert_eq!(code_fence_range(&text, 0, false), Some(4..4));
+        assert_eq!(code_fence_range(&text, 0, true), Some(0..7));
+    }
+
+    #[test]
+    fn test_backtick_range_prefers_inline_code() {
+        let text = chars("```\nuse `x` here\nplain\n```");
+        assert_eq!(text_of(&text, backtick_range(&text, 9, false)).as_deref(), Some("x"));
+        // No inline code on the line, so the fenced block
+        assert_eq!(
+            text_of(&text, backtick_range(&text, 18, false)).as_deref(),
+            Some("use `x` here\nplain")
+        );
+        // The fence line's backticks aren't inline code
+        assert_eq!(backtick_range(&text, 1, false), backtick_range(&text, 18, false));
+    }
+
+    fn select(text: &str, caret: usize, spec: &str) -> Option<String> {
+        let text = chars(text);
+        text_of(&text, TextObjectSpec::parse(spec).unwrap().range(&text, caret))
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        let spec = TextObjectSpec::parse("a)").unwrap();
+        assert!(spec.around);
+        assert_eq!(spec.kind, TextObjectKind::Bracket { open: '(', close: ')' });
+        assert_eq!(TextObjectSpec::parse("iB").unwrap().kind, TextObjectKind::Bracket { open: '{', close: '}' });
+        assert_eq!(TextObjectSpec::parse("i'").unwrap().kind, TextObjectKind::Quote('\''));
+        assert!(TextObjectSpec::parse("w").is_err());
+        assert!(TextObjectSpec::parse("ix").is_err());
+        assert!(TextObjectSpec::parse("iww").is_err());
+    }
+
+    #[test]
+    fn test_word_object() {
+        let text = "foo bar.baz  qux";
+        assert_eq!(select(text, 5, "iw").as_deref(), Some("bar"));
+        // No white space after `bar`, so `aw` takes the space before it
+        assert_eq!(select(text, 5, "aw").as_deref(), Some(" bar"));
+        assert_eq!(select(text, 1, "aw").as_deref(), Some("foo "));
+        assert_eq!(select(text, 5, "iW").as_deref(), Some("bar.baz"));
+        assert_eq!(select(text, 5, "aW").as_deref(), Some("bar.baz  "));
+        // The last word takes the white space before it
+        assert_eq!(select(text, 14, "aw").as_deref(), Some("  qux"));
+        // On white space, `iw` is the white space and `aw` adds the next word
+        assert_eq!(select(text, 11, "iw").as_deref(), Some("  "));
+        assert_eq!(select(text, 11, "aw").as_deref(), Some("  qux"));
+        // Words stop at the line's end
+        assert_eq!(select("one\ntwo", 1, "aw").as_deref(), Some("one"));
+        assert_eq!(select("one\n", 3, "iw"), None);
+    }
+
+    #[test]
+    fn test_quote_object() {
+        let text = r#"say "hi there" now"#;
+        assert_eq!(select(text, 7, "i\"").as_deref(), Some("hi there"));
+        assert_eq!(select(text, 7, "a\"").as_deref(), Some("\"hi there\" "));
+        assert_eq!(select("no quotes", 3, "i\""), None);
+    }
+
+    #[test]
+    fn test_bracket_object() {
+        let text = "f(a, (b, c), d)";
+        assert_eq!(select(text, 7, "i(").as_deref(), Some("b, c"));
+        assert_eq!(select(text, 7, "a)").as_deref(), Some("(b, c)"));
+        // Outside the inner pair, and on the outer bracket itself
+        assert_eq!(select(text, 2, "ib").as_deref(), Some("a, (b, c), d"));
+        assert_eq!(select(text, 14, "i(").as_deref(), Some("a, (b, c), d"));
+        assert_eq!(select(text, 1, "a(").as_deref(), Some("(a, (b, c), d)"));
+        assert_eq!(select("{\n  x\n}", 4, "i{").as_deref(), Some("\n  x\n"));
+        assert_eq!(select(text, 0, "i("), None);
+        assert_eq!(select("(open", 2, "i("), None);
+    }
+
+    #[test]
+    fn test_paragraph_object() {
+        let text = "one\ntwo\n\nthree\n\n\nfour";
+        assert_eq!(select(text, 5, "ip").as_deref(), Some("one\ntwo\n"));
+        assert_eq!(select(text, 5, "ap").as_deref(), Some("one\ntwo\n\n"));
+        // The last paragraph takes the blank lines before it
+        assert_eq!(select(text, 20, "ip").as_deref(), Some("four"));
+        assert_eq!(select(text, 20, "ap").as_deref(), Some("\n\nfour"));
+        // On blank lines, `ip` is the blank lines and `ap` adds the paragraph after
+        assert_eq!(select(text, 16, "ip").as_deref(), Some("\n\n"));
+        assert_eq!(select(text, 16, "ap").as_deref(), Some("\n\nfour"));
+        assert_eq!(select("", 0, "ip"), None);
+    }
+}
@@ -0,0 +1,234 @@
+//! Vim-accurate word boundaries, computed from the field's text
+//!
+//! Offsets are char indices. A cursor "on" a char sits just before it, so the
+//! caret offset and vim's cursor position are the same number.
+
+use super::commands::VimCommand;
+
+/// Character classes vim uses to split words
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum CharClass {
+    Whitespace,
+    Punctuation,
+    Word,
+}
+
+pub(super) fn char_class(c: char) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Whitespace
+    } else if c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+/// Classes for WORDs, which are only split by whitespace
+pub(super) fn big_char_class(c: char) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Whitespace
+    } else {
+        CharClass::Word
+    }
+}
+
+/// `w`: start of the next word, or an empty line. Returns the text length past the last word
+pub fn next_word_start(chars: &[char], pos: usize) -> usize {
+    let len = chars.len();
+    if pos >= len {
+        return len;
+    }
+
+    let mut i = pos;
+    let class = char_class(chars[i]);
+    if class != CharClass::Whitespace {
+        while i < len && char_class(chars[i]) == class {
+            i += 1;
+        }
+    }
+
+    while i < len && char_class(chars[i]) == CharClass::Whitespace {
+        // An empty line counts as a word
+        if chars[i] == '\n' && i > pos && chars[i - 1] == '\n' {
+            break;
+        }
+        i += 1;
+    }
+    i
+}
+
+/// `e`: last char of the current or next word, always moving at least one char
+pub fn word_end(chars: &[char], pos: usize) -> usize {
+    let len = chars.len();
+    if len == 0 {
+        return 0;
+    }
+
+    let mut i = pos + 1;
+    while i < len && char_class(chars[i]) == CharClass::Whitespace {
+        i += 1;
+    }
+    if i >= len {
+        return len - 1;
+    }
+
+    let class = char_class(chars[i]);
+    while i + 1 < len && char_class(chars[i + 1]) == class {
+        i += 1;
+    }
+    i
+}
+
+/// `b`: start of the current or previous word
+pub fn prev_word_start(chars: &[char], pos: usize) -> usize {
+    let mut i = pos.min(chars.len());
+    if i == 0 {
+        return 0;
+    }
+
+    i -= 1;
+    while i > 0 && char_class(chars[i]) == CharClass::Whitespace {
+        i -= 1;
+    }
+
+    let class = char_class(chars[i]);
+    while i > 0 && char_class(chars[i - 1]) == class {
+        i -= 1;
+    }
+    i
+}
+
+/// `ge`: last char of the previous word
+pub fn prev_word_end(chars: &[char], pos: usize) -> usize {
+    prev_end(chars, pos, char_class)
+}
+
+/// `gE`: last char of the previous WORD
+pub fn prev_big_word_end(chars: &[char], pos: usize) -> usize {
+    prev_end(chars, pos, big_char_class)
+}
+
+fn prev_end(chars: &[char], pos: usize, class_of: fn(char) -> CharClass) -> usize {
+    let mut i = pos.min(chars.len());
+
+    // Leave the word the cursor is on
+    if i < chars.len() && class_of(chars[i]) != CharClass::Whitespace {
+        let class = class_of(chars[i]);
+        while i > 0 && class_of(chars[i - 1]) == class {
+            i -= 1;
+        }
+    }
+    if i == 0 {
+        return 0;
+    }
+
+    i -= 1;
+    while i > 0 && class_of(chars[i]) == CharClass::Whitespace {
+        i -= 1;
+    }
+    i
+}
+
+/// Target of a word motion repeated `count` times, or None for other commands
+pub fn word_motion_target(command: VimCommand, chars: &[char], pos: usize, count: u32) -> Option<usize> {
+    let step: fn(&[char], usize) -> usize = match command {
+        VimCommand::WordForward => next_word_start,
+        VimCommand::WordEnd => word_end,
+        VimCommand::WordBackward => prev_word_start,
+        VimCommand::WordEndBackward => prev_word_end,
+        VimCommand::WordEndBackwardBig => prev_big_word_end,
+        _ => return None,
+    };
+    Some((0..count.max(1)).fold(pos, |p, _| step(chars, p)))
+}
+
+/// Target of a word motion used with an operator
+/// As in vim, `dw` on the last word of a line stops at the line end instead of joining lines
+pub fn operator_word_target(command: VimCommand, chars: &[char], pos: usize, count: u32) -> Option<usize> {
+    let target = word_motion_target(command, chars, pos, count)?;
+    if command == VimCommand::WordForward && target > pos {
+        if let Some(newline) = chars[pos..target].iter().position(|&c| c == '\n') {
+            if newline > 0 {
+                return Some(pos + newline);
+            }
+        }
+    }
+    Some(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vim::state::test_util::chars;
+
+    #[test]
+    fn test_next_word_start() {
+        let text = chars("foo.bar  baz");
+        assert_eq!(next_word_start(&text, 0), 3); // foo -> .
+        assert_eq!(next_word_start(&text, 3), 4); // . -> bar
+        assert_eq!(next_word_start(&text, 4), 9); // bar -> baz
+        assert_eq!(next_word_start(&text, 9), 12); // past the last word
+    }
+
+    #[test]
+    fn test_next_word_start_stops_at_empty_line() {
+        let text = chars("foo\n\nbar");
+        assert_eq!(next_word_start(&text, 0), 4);
+        assert_eq!(next_word_start(&text, 4), 5);
+    }
+
+    #[test]
+    fn test_word_end() {
+        let text = chars("foo.bar  baz");
+        assert_eq!(word_end(&text, 0), 2);
+        assert_eq!(word_end(&text, 2), 3); // at end of word, moves to the next
+        assert_eq!(word_end(&text, 3), 6);
+        assert_eq!(word_end(&text, 6), 11);
+        assert_eq!(word_end(&text, 11), 11);
+    }
+
+    #[test]
+    fn test_prev_word_start() {
+        let text = chars("foo.bar  baz");
+        assert_eq!(prev_word_start(&text, 11), 9);
+        assert_eq!(prev_word_start(&text, 9), 4);
+        assert_eq!(prev_word_start(&text, 4), 3);
+        assert_eq!(prev_word_start(&text, 3), 0);
+        assert_eq!(prev_word_start(&text, 0), 0);
+    }
+
+    #[test]
+    fn test_prev_word_end() {
+        let text = chars("foo.bar  baz");
+        assert_eq!(prev_word_end(&text, 10), 6);
+        assert_eq!(prev_word_end(&text, 4), 3);
+        assert_eq!(prev_word_end(&text, 3), 2);
+        assert_eq!(prev_word_end(&text, 1), 0);
+    }
+
+    #[test]
+    fn test_prev_big_word_end() {
+        let text = chars("foo.bar  baz");
+        assert_eq!(prev_big_word_end(&text, 10), 6);
+        // Punctuation doesn't split WORDs
+        assert_eq!(prev_big_word_end(&text, 6), 0);
+        assert_eq!(prev_word_end(&text, 6), 3);
+    }
+
+    #[test]
+    fn test_word_motion_target_with_count() {
+        let text = chars("one two three four");
+        assert_eq!(word_motion_target(VimCommand::WordForward, &text, 0, 2), Some(8));
+        assert_eq!(word_motion_target(VimCommand::WordEnd, &text, 0, 3), Some(12));
+        assert_eq!(word_motion_target(VimCommand::WordBackward, &text, 14, 2), Some(4));
+        assert_eq!(word_motion_target(VimCommand::LineEnd, &text, 0, 1), None);
+    }
+
+    #[test]
+    fn test_operator_word_target_stops_at_line_end() {
+        let text = chars("foo bar\nbaz");
+        assert_eq!(word_motion_target(VimCommand::WordForward, &text, 4, 1), Some(8));
+        assert_eq!(operator_word_target(VimCommand::WordForward, &text, 4, 1), Some(7));
+        assert_eq!(operator_word_target(VimCommand::WordForward, &text, 0, 1), Some(4));
+    }
+}
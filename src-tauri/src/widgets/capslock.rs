@@ -1,3 +1,6 @@
+use std::ffi::c_void;
+use std::os::raw::c_char;
+
 use core_graphics::event::CGEventFlags;
 
 #[link(name = "CoreGraphics", kind = "framework")]
@@ -5,7 +8,25 @@ extern "C" {
     fn CGEventSourceFlagsState(stateID: i32) -> u64;
 }
 
+#[link(name = "IOKit", kind = "framework")]
+extern "C" {
+    fn IOServiceMatching(name: *const c_char) -> *mut c_void;
+    fn IOServiceGetMatchingService(main_port: u32, matching: *mut c_void) -> u32;
+    fn IOServiceOpen(service: u32, owning_task: u32, connect_type: u32, connect: *mut u32) -> i32;
+    fn IOServiceClose(connect: u32) -> i32;
+    fn IOObjectRelease(object: u32) -> i32;
+    fn IOHIDSetModifierLockState(handle: u32, selector: i32, state: bool) -> i32;
+}
+
+extern "C" {
+    /// The task port, which `mach_task_self()` returns
+    static mach_task_self_: u32;
+}
+
 const COMBINED_SESSION_STATE: i32 = 0;
+const IO_MAIN_PORT_DEFAULT: u32 = 0;
+const IOHID_PARAM_CONNECT_TYPE: u32 = 1;
+const IOHID_CAPS_LOCK_STATE: i32 = 1;
 
 /// Check if Caps Lock is currently on
 pub fn is_caps_lock_on() -> bool {
@@ -15,3 +36,34 @@ pub fn is_caps_lock_on() -> bool {
         (flags & CGEventFlags::CGEventFlagAlphaShift.bits()) != 0
     }
 }
+
+/// Set the Caps Lock state (and its LED) through the HID system
+pub fn set_caps_lock_state(on: bool) -> Result<(), String> {
+    unsafe {
+        let matching = IOServiceMatching(c"IOHIDSystem".as_ptr());
+        // Consumes `matching`
+        let service = IOServiceGetMatchingService(IO_MAIN_PORT_DEFAULT, matching);
+        if service == 0 {
+            return Err("IOHIDSystem service not found".to_string());
+        }
+
+        let mut connect = 0u32;
+        let result = IOServiceOpen(
+            service,
+            mach_task_self_,
+            IOHID_PARAM_CONNECT_TYPE,
+            &mut connect,
+        );
+        IOObjectRelease(service);
+        if result != 0 {
+            return Err(format!("Failed to open IOHIDSystem: {}", result));
+        }
+
+        let result = IOHIDSetModifierLockState(connect, IOHID_CAPS_LOCK_STATE, on);
+        IOServiceClose(connect);
+        if result != 0 {
+            return Err(format!("Failed to set Caps Lock state: {}", result));
+        }
+    }
+    Ok(())
+}
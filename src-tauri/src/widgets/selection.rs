@@ -2,10 +2,20 @@ use core_foundation::base::{CFRelease, CFTypeRef, TCFType};
 use core_foundation::string::CFString;
 use serde::Serialize;
 
-#[derive(Debug, Clone, Serialize, Default)]
+use crate::nvim_edit::accessibility;
+
+#[derive(Debug, Clone, Serialize, Default, PartialEq)]
 pub struct SelectionInfo {
     pub char_count: usize,
     pub line_count: usize,
+    /// Char offset of the selection start, None if the field doesn't expose its range
+    pub offset: Option<usize>,
+    /// Selection length in chars
+    pub length: Option<usize>,
+    /// 1-based line of the selection start
+    pub line: Option<usize>,
+    /// 1-based column of the selection start, in chars
+    pub column: Option<usize>,
 }
 
 #[link(name = "ApplicationServices", kind = "framework")]
@@ -19,20 +29,52 @@ extern "C" {
 }
 
 /// Get selection info from the focused application using Accessibility APIs
+/// Fields without `AXSelectedTextRange` only report the selected text's size
 pub fn get_selection_info() -> SelectionInfo {
-    match get_selected_text() {
-        Some(text) if !text.is_empty() => {
-            let char_count = text.chars().count();
-            let line_count = text.lines().count().max(1);
-            SelectionInfo {
-                char_count,
-                line_count,
-            }
-        }
-        _ => SelectionInfo::default(),
+    match accessibility::get_focused_text_selection() {
+        Some(selection) => selection_info(&selection.text, selection.start, selection.length),
+        None => get_selected_text()
+            .map(|text| count_selection(&text))
+            .unwrap_or_default(),
+    }
+}
+
+/// Build selection info for the `length` chars at `offset` in `text`
+fn selection_info(text: &str, offset: usize, length: usize) -> SelectionInfo {
+    let selected: String = text.chars().skip(offset).take(length).collect();
+    let (line, column) = line_and_column(text, offset);
+    SelectionInfo {
+        offset: Some(offset),
+        length: Some(length),
+        line: Some(line),
+        column: Some(column),
+        ..count_selection(&selected)
+    }
+}
+
+/// Char and line counts of the selected text
+fn count_selection(selected: &str) -> SelectionInfo {
+    if selected.is_empty() {
+        return SelectionInfo::default();
+    }
+    SelectionInfo {
+        char_count: selected.chars().count(),
+        line_count: selected.lines().count().max(1),
+        ..Default::default()
     }
 }
 
+/// 1-based line and column of a char offset in `text`
+pub fn line_and_column(text: &str, offset: usize) -> (usize, usize) {
+    let before: Vec<char> = text.chars().take(offset).collect();
+    let line_start = before
+        .iter()
+        .rposition(|&c| c == '\n')
+        .map_or(0, |newline| newline + 1);
+    let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+    (line, before.len() - line_start + 1)
+}
+
 /// Get the selected text from the currently focused application
 fn get_selected_text() -> Option<String> {
     unsafe {
@@ -92,3 +134,35 @@ fn get_selected_text() -> Option<String> {
         Some(cf_string.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_and_column() {
+        let text = "first\nsecond line\n\nlast";
+        assert_eq!(line_and_column(text, 0), (1, 1));
+        assert_eq!(line_and_column(text, 3), (1, 4));
+        assert_eq!(line_and_column(text, 5), (1, 6)); // on the newline
+        assert_eq!(line_and_column(text, 6), (2, 1));
+        assert_eq!(line_and_column(text, 13), (2, 8));
+        assert_eq!(line_and_column(text, 18), (3, 1)); // empty line
+        assert_eq!(line_and_column(text, 23), (4, 5)); // end of text
+        assert_eq!(line_and_column("a😀b", 2), (1, 3));
+    }
+
+    #[test]
+    fn test_selection_info() {
+        let info = selection_info("one\ntwo three", 4, 5);
+        assert_eq!(info.offset, Some(4));
+        assert_eq!(info.length, Some(5));
+        assert_eq!((info.line, info.column), (Some(2), Some(1)));
+        assert_eq!((info.char_count, info.line_count), (5, 1));
+
+        // A caret without a selection still reports its position
+        let info = selection_info("one\ntwo", 2, 0);
+        assert_eq!((info.char_count, info.line_count), (0, 0));
+        assert_eq!((info.line, info.column), (Some(1), Some(3)));
+    }
+}
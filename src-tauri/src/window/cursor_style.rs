@@ -0,0 +1,41 @@
+//! Caret style hints for the focused app
+//!
+//! macOS has no general API to change another app's caret, so this is best effort:
+//! the style is broadcast as a `cursor-style` event for anything that can apply it
+//! (e.g. a terminal integration listening over IPC), and is a no-op otherwise.
+
+use tauri::{AppHandle, Emitter};
+
+use crate::config::CursorStyle;
+use crate::vim::VimMode;
+
+/// Caret style to request for a mode
+/// Insert mode always uses the normal bar caret
+pub fn cursor_style_for_mode(mode: VimMode, configured: CursorStyle) -> CursorStyle {
+    match mode {
+        VimMode::Insert => CursorStyle::Bar,
+        VimMode::Normal | VimMode::Visual => configured,
+    }
+}
+
+/// Request a caret style from the focused app
+pub fn set_cursor_style(app: &AppHandle, style: CursorStyle) {
+    log::debug!("Requesting {:?} cursor style", style);
+    let _ = app.emit("cursor-style", style);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_style_for_mode() {
+        assert_eq!(cursor_style_for_mode(VimMode::Insert, CursorStyle::Block), CursorStyle::Bar);
+        assert_eq!(cursor_style_for_mode(VimMode::Normal, CursorStyle::Block), CursorStyle::Block);
+        assert_eq!(
+            cursor_style_for_mode(VimMode::Visual, CursorStyle::Underline),
+            CursorStyle::Underline
+        );
+        assert_eq!(cursor_style_for_mode(VimMode::Normal, CursorStyle::Bar), CursorStyle::Bar);
+    }
+}
@@ -1,4 +1,22 @@
-use tauri::WebviewWindow;
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::config::Settings;
+use crate::vim::VimMode;
+
+/// Show or hide the indicator window for `mode`, as the settings ask
+pub fn update_indicator_visibility(app: &AppHandle, settings: &Settings, mode: VimMode) {
+    let Some(window) = app.get_webview_window("indicator") else {
+        return;
+    };
+    let result = if settings.indicator_shown(mode) {
+        window.show()
+    } else {
+        window.hide()
+    };
+    if let Err(e) = result {
+        log::warn!("Failed to update indicator visibility: {}", e);
+    }
+}
 
 /// Set up the indicator window with special properties
 #[allow(unused_variables)]
@@ -1,3 +1,5 @@
+mod cursor_style;
 mod indicator;
 
-pub use indicator::{set_indicator_ignores_mouse, setup_indicator_window};
+pub use cursor_style::{cursor_style_for_mode, set_cursor_style};
+pub use indicator::{set_indicator_ignores_mouse, setup_indicator_window, update_indicator_visibility};
@@ -1,16 +1,21 @@
+import type { ReactNode } from "react";
+
 interface Props {
   items: string[];
   onAdd: () => void;
   onRemove: (item: string) => void;
+  /** Extra controls shown next to each app */
+  renderControls?: (item: string) => ReactNode;
 }
 
-export function AppList({ items, onAdd, onRemove }: Props) {
+export function AppList({ items, onAdd, onRemove, renderControls }: Props) {
   return (
     <div className="app-list">
       <ul className="app-list-items">
         {items.map((item) => (
           <li key={item} className="app-list-item">
             <span className="app-bundle-id">{item}</span>
+            {renderControls?.(item)}
             <button
               className="remove-button"
               onClick={() => onRemove(item)}
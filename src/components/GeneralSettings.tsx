@@ -1,6 +1,6 @@
-import { useState, useEffect } from "react"
+import { useState, useEffect, useRef } from "react"
 import { invoke } from "@tauri-apps/api/core"
-import type { Settings } from "./SettingsApp"
+import type { KeymapPreset, Settings, UnknownMotionKey } from "./SettingsApp"
 
 interface Props {
   settings: Settings
@@ -12,9 +12,19 @@ interface PermissionStatus {
   capture_running: boolean
 }
 
+interface InjectionBenchmark {
+  events: number
+  average_us: number
+  min_us: number
+  max_us: number
+  total_us: number
+}
+
 export function GeneralSettings({ settings, onUpdate }: Props) {
   const [permissionStatus, setPermissionStatus] = useState<PermissionStatus | null>(null)
   const [version, setVersion] = useState<string>("")
+  const [benchmark, setBenchmark] = useState<string>("")
+  const benchmarkField = useRef<HTMLTextAreaElement>(null)
 
   useEffect(() => {
     invoke<string>("get_version")
@@ -47,6 +57,19 @@ export function GeneralSettings({ settings, onUpdate }: Props) {
     setPermissionStatus(status)
   }
 
+  const handleBenchmark = async () => {
+    benchmarkField.current?.focus()
+    try {
+      const result = await invoke<InjectionBenchmark>("benchmark_injection")
+      setBenchmark(
+        `${result.events} keys: ${result.average_us.toFixed(0)} µs average, ` +
+          `${result.min_us}-${result.max_us} µs`
+      )
+    } catch (e) {
+      setBenchmark(String(e))
+    }
+  }
+
   const permissionsOk = permissionStatus?.accessibility && permissionStatus?.capture_running
 
   return (
@@ -111,6 +134,497 @@ export function GeneralSettings({ settings, onUpdate }: Props) {
         </label>
       </div>
 
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.auto_check_updates}
+            onChange={(e) => onUpdate({ auto_check_updates: e.target.checked })}
+          />
+          Notify about new versions
+        </label>
+        <span className="hint">
+          Checks once a day at startup and shows the release notes, without downloading
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="word-motion-mode">Word motions (w, e, b)</label>
+        <select
+          id="word-motion-mode"
+          value={settings.word_motion_mode}
+          onChange={(e) =>
+            onUpdate({ word_motion_mode: e.target.value as Settings["word_motion_mode"] })
+          }
+        >
+          <option value="native">macOS native (Option+Arrow)</option>
+          <option value="vim_accurate">Vim-accurate</option>
+        </select>
+        <span className="hint">
+          Vim-accurate reads the text field to stop exactly where vim would. Falls back to native
+          in apps without accessibility support.
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.prefer_accessibility_motions}
+            onChange={(e) => onUpdate({ prefer_accessibility_motions: e.target.checked })}
+          />
+          Jump directly for line and document motions (0, $, gg, G, 50|)
+        </label>
+        <span className="hint">Sets the caret through accessibility instead of arrow keys</span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.prefer_accessibility_edits}
+            onChange={(e) => onUpdate({ prefer_accessibility_edits: e.target.checked })}
+          />
+          Delete through accessibility (x, X, dd, D)
+        </label>
+        <span className="hint">
+          Changes the field's text directly instead of sending delete keys, which some apps beep at
+          or drop
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.suppress_invalid_ops}
+            onChange={(e) => onUpdate({ suppress_invalid_ops: e.target.checked })}
+          />
+          Skip edits that can't apply (x at end of field, X at line start)
+        </label>
+        <span className="hint">Avoids the system beep by checking the text first</span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="keyboard-layout">Keyboard layout</label>
+        <select
+          id="keyboard-layout"
+          value={typeof settings.keyboard_layout === "string" ? settings.keyboard_layout : "custom"}
+          onChange={(e) =>
+            onUpdate({ keyboard_layout: e.target.value as "qwerty" | "dvorak" | "colemak" })
+          }
+        >
+          <option value="qwerty">QWERTY</option>
+          <option value="dvorak">Dvorak</option>
+          <option value="colemak">Colemak</option>
+          {typeof settings.keyboard_layout !== "string" && (
+            <option value="custom" disabled>
+              Custom (from settings file)
+            </option>
+          )}
+        </select>
+        <span className="hint">Makes h/j/k/l and other vim keys follow your layout's labels</span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="normal-mode-key-repeat">Key repeat in normal mode</label>
+        <select
+          id="normal-mode-key-repeat"
+          value={
+            typeof settings.normal_mode_key_repeat === "string"
+              ? settings.normal_mode_key_repeat
+              : "custom"
+          }
+          onChange={(e) =>
+            onUpdate({
+              normal_mode_key_repeat:
+                e.target.value === "custom"
+                  ? { custom: 100 }
+                  : (e.target.value as "os_default" | "disabled"),
+            })
+          }
+        >
+          <option value="os_default">System default</option>
+          <option value="disabled">Disabled (one motion per press)</option>
+          <option value="custom">Custom interval</option>
+        </select>
+        {typeof settings.normal_mode_key_repeat !== "string" && (
+          <input
+            type="number"
+            value={settings.normal_mode_key_repeat.custom}
+            onChange={(e) =>
+              onUpdate({ normal_mode_key_repeat: { custom: parseInt(e.target.value) || 0 } })
+            }
+            min={0}
+            step={10}
+          />
+        )}
+        <span className="hint">Custom lets a held key repeat at most once per this many ms</span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="keymap-preset">Keymap preset</label>
+        <select
+          id="keymap-preset"
+          value={settings.keymap_preset}
+          onChange={(e) =>
+            invoke<Settings>("apply_keymap_preset", { preset: e.target.value as KeymapPreset })
+              .then((s) => onUpdate({ keymap: s.keymap, keymap_preset: s.keymap_preset }))
+              .catch(console.error)
+          }
+        >
+          <option value="vim-default">Vim</option>
+          <option value="helix-like">Helix-like</option>
+          <option value="macos-friendly">macOS-friendly</option>
+        </select>
+        <span className="hint">
+          Extra normal mode keys: Helix-like adds U for redo and Home/End, macOS-friendly adds
+          Cmd+Z/Cmd+Shift+Z and arrow keys that take counts
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="tab-inserts">Indent with</label>
+        <select
+          id="tab-inserts"
+          value={typeof settings.tab_inserts === "string" ? settings.tab_inserts : "spaces"}
+          onChange={(e) =>
+            onUpdate({ tab_inserts: e.target.value === "spaces" ? { spaces: 4 } : "tab" })
+          }
+        >
+          <option value="tab">Tab key</option>
+          <option value="spaces">Spaces</option>
+        </select>
+        {typeof settings.tab_inserts !== "string" && (
+          <input
+            type="number"
+            value={settings.tab_inserts.spaces}
+            onChange={(e) =>
+              onUpdate({ tab_inserts: { spaces: parseInt(e.target.value) || 1 } })
+            }
+            min={1}
+            max={16}
+          />
+        )}
+        <span className="hint">
+          What &gt;&gt; inserts at the line start. &lt;&lt; always sends Shift+Tab
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="text-width">Text width</label>
+        <input
+          id="text-width"
+          type="number"
+          value={settings.text_width}
+          onChange={(e) => onUpdate({ text_width: parseInt(e.target.value) || 80 })}
+          min={10}
+          max={500}
+        />
+        <span className="hint">Column gq and gw wrap lines at</span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="injection-pacing">Key pacing (ms)</label>
+        <input
+          id="injection-pacing"
+          type="number"
+          value={settings.injection_pacing_ms}
+          onChange={(e) => onUpdate({ injection_pacing_ms: parseInt(e.target.value) || 0 })}
+          min={0}
+          max={100}
+        />
+        <span className="hint">
+          Pause between the keys of a command like dd. Raise it for apps that apply commands
+          halfway, or set it for one app in App Overrides
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.enable_tab_motions}
+            onChange={(e) => onUpdate({ enable_tab_motions: e.target.checked })}
+          />
+          Tab motions (gt/gT)
+        </label>
+        <span className="hint">
+          Switch tabs with Cmd+Shift+]/[, and 3gt goes to tab 3 with Cmd+3. Works in browsers and
+          most tabbed apps
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.smart_home}
+            onChange={(e) => onUpdate({ smart_home: e.target.checked })}
+          />
+          Smart home for 0
+        </label>
+        <span className="hint">
+          0 goes to the first non-blank character, and pressing it again goes to the line start
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.mouse_disables_normal_mode}
+            onChange={(e) => onUpdate({ mouse_disables_normal_mode: e.target.checked })}
+          />
+          Mouse click switches to insert mode
+        </label>
+        <span className="hint">
+          Clicking usually means you're about to type, so a click leaves normal and visual mode
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="cursor-style">Cursor in normal/visual mode</label>
+        <select
+          id="cursor-style"
+          value={settings.visual_mode_cursor_style}
+          onChange={(e) =>
+            onUpdate({
+              visual_mode_cursor_style: e.target.value as Settings["visual_mode_cursor_style"],
+            })
+          }
+        >
+          <option value="bar">Bar</option>
+          <option value="block">Block</option>
+          <option value="underline">Underline</option>
+        </select>
+        <span className="hint">Only applied by apps that support cursor style hints</span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.ctrl_c_as_escape}
+            onChange={(e) => onUpdate({ ctrl_c_as_escape: e.target.checked })}
+          />
+          Use Ctrl+C as Escape
+        </label>
+        <span className="hint">
+          Enters normal mode from insert and visual mode, and cancels pending keys in normal mode
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.keep_selection_after_yank}
+            onChange={(e) => onUpdate({ keep_selection_after_yank: e.target.checked })}
+          />
+          Keep selection after yank in visual mode
+        </label>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.visual_mode_linewise_operators}
+            onChange={(e) => onUpdate({ visual_mode_linewise_operators: e.target.checked })}
+          />
+          Linewise d/y/c on selections over several lines
+        </label>
+        <span className="hint">
+          Takes the whole lines the selection touches, like V in vim
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.yank_to_system_clipboard}
+            onChange={(e) => onUpdate({ yank_to_system_clipboard: e.target.checked })}
+          />
+          Yank and delete to the system clipboard
+        </label>
+        <span className="hint">
+          When off, y and d keep text in ovim's own register for p, and the clipboard is left as it was
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="control-combo-modifier">Modifier for Ctrl-combos (Ctrl+F, Ctrl+D, ...)</label>
+        <select
+          id="control-combo-modifier"
+          value={settings.control_combo_modifier}
+          onChange={(e) =>
+            onUpdate({
+              control_combo_modifier: e.target.value as Settings["control_combo_modifier"],
+            })
+          }
+        >
+          <option value="control">Control</option>
+          <option value="command">Command</option>
+          <option value="option">Option</option>
+        </select>
+        <span className="hint">Control presses go to the app when another modifier is chosen</span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="default-mode">Start in mode</label>
+        <select
+          id="default-mode"
+          value={settings.default_mode}
+          onChange={(e) => onUpdate({ default_mode: e.target.value as Settings["default_mode"] })}
+        >
+          <option value="insert">Insert</option>
+          <option value="normal">Normal</option>
+        </select>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="escape-target-mode">Escape switches to</label>
+        <select
+          id="escape-target-mode"
+          value={settings.escape_target_mode}
+          onChange={(e) =>
+            onUpdate({ escape_target_mode: e.target.value as Settings["escape_target_mode"] })
+          }
+        >
+          <option value="insert">Insert mode (toggle)</option>
+          <option value="normal">Normal mode (like vim)</option>
+        </select>
+        <span className="hint">In vim's model, Escape in normal mode only cancels pending keys</span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.escape_cancels_pending_first}
+            onChange={(e) => onUpdate({ escape_cancels_pending_first: e.target.checked })}
+          />
+          Escape cancels pending keys first
+        </label>
+        <span className="hint">After d, g or a count, Escape cancels it instead of switching to insert mode</span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="unknown-motion-key">Key that isn't a motion after d/y/c</label>
+        <select
+          id="unknown-motion-key"
+          value={settings.unknown_motion_key}
+          onChange={(e) => onUpdate({ unknown_motion_key: e.target.value as UnknownMotionKey })}
+        >
+          <option value="reprocess">Cancel and run the key</option>
+          <option value="pass_through">Cancel and send the key to the app</option>
+          <option value="ignore">Ignore the key and keep waiting</option>
+        </select>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="operator-timeout">Operator timeout (ms)</label>
+        <input
+          id="operator-timeout"
+          type="number"
+          value={settings.operator_timeout_ms}
+          onChange={(e) => onUpdate({ operator_timeout_ms: parseInt(e.target.value) || 0 })}
+          min={0}
+          max={10000}
+          step={100}
+        />
+        <span className="hint">Cancel a pending d, y or c after this long without a key. 0 waits indefinitely</span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.insert_readline_bindings}
+            onChange={(e) => onUpdate({ insert_readline_bindings: e.target.checked })}
+          />
+          Readline/emacs editing keys in insert mode
+        </label>
+        <span className="hint">
+          Ctrl+W/U delete the previous word or to the line start, Ctrl+A/E move to the line start or
+          end, Ctrl+F/B move by a character, Ctrl+D deletes forward and Ctrl+I types a literal Tab
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="insert-completion-keys">Completion keys</label>
+        <input
+          id="insert-completion-keys"
+          type="text"
+          defaultValue={settings.insert_completion_keys.join(", ")}
+          onBlur={(e) =>
+            onUpdate({
+              insert_completion_keys: e.target.value
+                .split(",")
+                .map((key) => key.trim().toLowerCase())
+                .filter((key) => key.length > 0),
+            })
+          }
+          placeholder="n, p"
+        />
+        <span className="hint">
+          Keys that always reach the app with Ctrl in insert mode, e.g. Ctrl+N/P for completion
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.tracking_only_mode}
+            onChange={(e) => onUpdate({ tracking_only_mode: e.target.checked })}
+          />
+          Tracking only (safe mode)
+        </label>
+        <span className="hint">
+          Tracks the mode and updates the indicator, but never injects keys. For apps or remote
+          desktops that reject synthetic input
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={settings.debug_events}
+            onChange={(e) => onUpdate({ debug_events: e.target.checked })}
+          />
+          Debug key events
+        </label>
+        <span className="hint">
+          Emit a key-decision event for each key press, telling whether it was suppressed, passed
+          through or ran an action
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="benchmark-field">Injection benchmark</label>
+        <textarea id="benchmark-field" ref={benchmarkField} rows={2} defaultValue={"x".repeat(120)} />
+        <button onClick={handleBenchmark}>Run</button>
+        <span className="hint">
+          {benchmark || "Times 100 right arrows typed into the field above, to check how fast keys are injected"}
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="startup-capture-delay">Startup capture delay (ms)</label>
+        <input
+          type="number"
+          id="startup-capture-delay"
+          value={settings.startup_capture_delay_ms}
+          onChange={(e) => onUpdate({ startup_capture_delay_ms: parseInt(e.target.value) || 0 })}
+          min={0}
+        />
+        <span className="hint">Capture also waits for accessibility permission at launch</span>
+      </div>
+
     </div>
   )
 }
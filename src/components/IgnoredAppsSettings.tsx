@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Settings } from "./SettingsApp";
+import type { AppOverride, Settings } from "./SettingsApp";
 import { AppList } from "./AppList";
 
 interface Props {
@@ -25,6 +25,63 @@ export function IgnoredAppsSettings({ settings, onUpdate }: Props) {
     });
   };
 
+  const handleAddOverride = async () => {
+    try {
+      const bundleId = await invoke<string | null>("pick_app");
+      if (bundleId && !(bundleId in settings.app_overrides)) {
+        onUpdate({
+          app_overrides: {
+            ...settings.app_overrides,
+            [bundleId]: { vim_enabled: true, nvim_edit_enabled: false, injection_pacing_ms: null },
+          },
+        });
+      }
+    } catch (e) {
+      console.error("Failed to pick app:", e);
+    }
+  };
+
+  const handleRemoveOverride = (bundleId: string) => {
+    onUpdate({
+      app_overrides: Object.fromEntries(
+        Object.entries(settings.app_overrides).filter(([id]) => id !== bundleId)
+      ),
+    });
+  };
+
+  const updateOverride = (bundleId: string, changes: Partial<AppOverride>) => {
+    onUpdate({
+      app_overrides: {
+        ...settings.app_overrides,
+        [bundleId]: { ...settings.app_overrides[bundleId], ...changes },
+      },
+    });
+  };
+
+  const handleAddStartupMode = async () => {
+    try {
+      const bundleId = await invoke<string | null>("pick_app");
+      if (bundleId && !(bundleId in settings.startup_mode_per_app)) {
+        onUpdate({
+          startup_mode_per_app: {
+            ...settings.startup_mode_per_app,
+            [bundleId]: settings.default_mode,
+          },
+        });
+      }
+    } catch (e) {
+      console.error("Failed to pick app:", e);
+    }
+  };
+
+  const handleRemoveStartupMode = (bundleId: string) => {
+    onUpdate({
+      startup_mode_per_app: Object.fromEntries(
+        Object.entries(settings.startup_mode_per_app).filter(([id]) => id !== bundleId)
+      ),
+    });
+  };
+
   return (
     <div className="settings-section">
       <h2>Ignored Apps</h2>
@@ -37,6 +94,82 @@ export function IgnoredAppsSettings({ settings, onUpdate }: Props) {
         onAdd={handleAddApp}
         onRemove={handleRemoveApp}
       />
+
+      <h2>App Overrides</h2>
+      <p className="section-description">
+        Turn vim mode or the Edit Popup shortcut off in single applications, or pause
+        longer between injected keys where commands apply halfway.
+      </p>
+
+      <AppList
+        items={Object.keys(settings.app_overrides)}
+        onAdd={handleAddOverride}
+        onRemove={handleRemoveOverride}
+        renderControls={(bundleId) => (
+          <>
+            <label className="checkbox-label">
+              <input
+                type="checkbox"
+                checked={settings.app_overrides[bundleId].vim_enabled}
+                onChange={(e) => updateOverride(bundleId, { vim_enabled: e.target.checked })}
+              />
+              Vim
+            </label>
+            <label className="checkbox-label">
+              <input
+                type="checkbox"
+                checked={settings.app_overrides[bundleId].nvim_edit_enabled}
+                onChange={(e) =>
+                  updateOverride(bundleId, { nvim_edit_enabled: e.target.checked })
+                }
+              />
+              Edit Popup
+            </label>
+            <input
+              type="number"
+              className="pacing-input"
+              placeholder={`${settings.injection_pacing_ms} ms`}
+              title="Pause between injected keys in this app, in ms"
+              value={settings.app_overrides[bundleId].injection_pacing_ms ?? ""}
+              onChange={(e) =>
+                updateOverride(bundleId, {
+                  injection_pacing_ms: e.target.value === "" ? null : parseInt(e.target.value) || 0,
+                })
+              }
+              min={0}
+              max={100}
+            />
+          </>
+        )}
+      />
+
+      <h2>Startup Modes</h2>
+      <p className="section-description">
+        Mode an application starts in the first time it's focused. Other applications
+        start in the default mode.
+      </p>
+
+      <AppList
+        items={Object.keys(settings.startup_mode_per_app)}
+        onAdd={handleAddStartupMode}
+        onRemove={handleRemoveStartupMode}
+        renderControls={(bundleId) => (
+          <select
+            value={settings.startup_mode_per_app[bundleId]}
+            onChange={(e) =>
+              onUpdate({
+                startup_mode_per_app: {
+                  ...settings.startup_mode_per_app,
+                  [bundleId]: e.target.value as Settings["default_mode"],
+                },
+              })
+            }
+          >
+            <option value="insert">Insert</option>
+            <option value="normal">Normal</option>
+          </select>
+        )}
+      />
     </div>
   );
 }
@@ -1,5 +1,5 @@
 import { useState, useEffect } from "react";
-import type { Settings, RgbColor, ModeColors } from "./SettingsApp";
+import type { Settings, RgbColor, ModeColors, ModeLabels } from "./SettingsApp";
 import {
   formatKeyWithModifiers,
   hasAnyModifier,
@@ -13,6 +13,16 @@ const PRESET_KEYS = [
   { value: "escape", label: "Escape" },
   { value: "right_control", label: "Right Control" },
   { value: "right_option", label: "Right Option" },
+  { value: "right_command", label: "Right Command" },
+];
+
+const CHORD_OPTIONS = [
+  { value: "", label: "None" },
+  { value: "control,right_control", label: "Both Control keys" },
+  { value: "shift,right_shift", label: "Both Shift keys" },
+  { value: "command,right_command", label: "Both Command keys" },
+  { value: "control,command", label: "Control + Command" },
+  { value: "function", label: "Fn" },
 ];
 
 interface Props {
@@ -111,6 +121,12 @@ export function IndicatorSettings({ settings, onUpdate }: Props) {
     onUpdate({ mode_colors: newColors });
   };
 
+  const updateModeLabel = (mode: keyof ModeLabels, label: string) => {
+    onUpdate({
+      indicator_text_template: { ...settings.indicator_text_template, [mode]: label },
+    });
+  };
+
   return (
     <div className="settings-section">
       <h2>Indicator</h2>
@@ -130,17 +146,41 @@ export function IndicatorSettings({ settings, onUpdate }: Props) {
           </p>
         </div>
 
+        <div className="form-group checkbox-group">
+          <label>Show floating indicator in</label>
+          {(["insert", "normal", "visual"] as const).map((mode) => (
+            <label key={mode} className="checkbox-label">
+              <input
+                type="checkbox"
+                checked={settings.indicator_per_mode_visibility[mode]}
+                onChange={(e) =>
+                  onUpdate({
+                    indicator_per_mode_visibility: {
+                      ...settings.indicator_per_mode_visibility,
+                      [mode]: e.target.checked,
+                    },
+                  })
+                }
+              />
+              <span>{mode.charAt(0).toUpperCase() + mode.slice(1)} mode</span>
+            </label>
+          ))}
+          <p className="setting-description">
+            Display the mode indicator as a floating window on screen in these modes.
+          </p>
+        </div>
+
         <div className="form-group checkbox-group">
           <label className="checkbox-label">
             <input
               type="checkbox"
-              checked={settings.indicator_visible}
-              onChange={(e) => onUpdate({ indicator_visible: e.target.checked })}
+              checked={settings.show_mode_in_menu_bar}
+              onChange={(e) => onUpdate({ show_mode_in_menu_bar: e.target.checked })}
             />
-            <span>Show floating indicator</span>
+            <span>Show mode in menu bar</span>
           </label>
           <p className="setting-description">
-            Display the mode indicator as a floating window on screen.
+            Display the current mode (N/I/V) in the menu bar icon.
           </p>
         </div>
 
@@ -148,13 +188,13 @@ export function IndicatorSettings({ settings, onUpdate }: Props) {
           <label className="checkbox-label">
             <input
               type="checkbox"
-              checked={settings.show_mode_in_menu_bar}
-              onChange={(e) => onUpdate({ show_mode_in_menu_bar: e.target.checked })}
+              checked={settings.indicator_show_count}
+              onChange={(e) => onUpdate({ indicator_show_count: e.target.checked })}
             />
-            <span>Show mode in menu bar</span>
+            <span>Show count while typing</span>
           </label>
           <p className="setting-description">
-            Display the current mode (N/I/V) in the menu bar icon.
+            Show a count like the 50 of 50j in the indicator until the command runs.
           </p>
         </div>
 
@@ -198,6 +238,73 @@ export function IndicatorSettings({ settings, onUpdate }: Props) {
           </div>
         </div>
 
+        {hasAnyModifier(settings.vim_key_modifiers) && (
+          <div className="form-group">
+            <label htmlFor="vim-key-side">Modifiers held on</label>
+            <select
+              id="vim-key-side"
+              value={settings.vim_key_modifiers.side ?? "any"}
+              onChange={(e) =>
+                onUpdate({
+                  vim_key_modifiers: {
+                    ...settings.vim_key_modifiers,
+                    side: e.target.value as "any" | "left" | "right",
+                  },
+                })
+              }
+            >
+              <option value="any">Either side</option>
+              <option value="left">Left side only</option>
+              <option value="right">Right side only</option>
+            </select>
+          </div>
+        )}
+
+        <div className="form-group">
+          <label htmlFor="vim-key-chord">Modifier chord</label>
+          <select
+            id="vim-key-chord"
+            value={settings.vim_key_chord.join(",")}
+            onChange={(e) =>
+              onUpdate({
+                vim_key_chord: e.target.value ? e.target.value.split(",") : [],
+              })
+            }
+          >
+            {CHORD_OPTIONS.map((opt) => (
+              <option key={opt.value} value={opt.value}>
+                {opt.label}
+              </option>
+            ))}
+            {settings.vim_key_chord.length > 0 &&
+              !CHORD_OPTIONS.some((opt) => opt.value === settings.vim_key_chord.join(",")) && (
+                <option value={settings.vim_key_chord.join(",")} disabled>
+                  {settings.vim_key_chord.join(" + ")}
+                </option>
+              )}
+          </select>
+          <p className="setting-description">
+            Also toggles vim mode when these keys are pressed together and released without
+            another key.
+          </p>
+        </div>
+
+        {settings.vim_key === "caps_lock" && (
+          <div className="form-group">
+            <label className="checkbox-label">
+              <input
+                type="checkbox"
+                checked={settings.preserve_caps_lock_state}
+                onChange={(e) => onUpdate({ preserve_caps_lock_state: e.target.checked })}
+              />
+              <span>Keep Caps Lock off when toggling vim mode</span>
+            </label>
+            <p className="setting-description">
+              Stops the Caps Lock LED from flipping and typing from being capitalized.
+            </p>
+          </div>
+        )}
+
         <div className="slider-group">
           <label>
             Alpha: {Math.round(settings.indicator_opacity * 100)}%
@@ -336,6 +443,22 @@ export function IndicatorSettings({ settings, onUpdate }: Props) {
         </div>
       </div>
 
+      <div className="color-settings">
+        <h3>Mode Labels</h3>
+        <div className="color-pickers">
+          {(["insert", "normal", "visual"] as const).map((mode) => (
+            <div className="color-picker-group" key={mode}>
+              <label>{mode.charAt(0).toUpperCase() + mode.slice(1)} Mode</label>
+              <input
+                type="text"
+                value={settings.indicator_text_template[mode]}
+                onChange={(e) => updateModeLabel(mode, e.target.value)}
+              />
+            </div>
+          ))}
+        </div>
+      </div>
+
       <div className="font-settings">
         <h3>Font</h3>
         <div className="form-group">
@@ -1,7 +1,7 @@
 import { useState, useEffect, useCallback } from "react"
 import { open } from "@tauri-apps/plugin-dialog"
 import { invoke } from "@tauri-apps/api/core"
-import type { Settings, NvimEditSettings as NvimEditSettingsType } from "./SettingsApp"
+import type { Settings, NvimEditSettings as NvimEditSettingsType, TextCapture } from "./SettingsApp"
 import {
   formatKeyWithModifiers,
   recordKey,
@@ -9,6 +9,24 @@ import {
   getKeyDisplayName,
 } from "./keyRecording"
 
+const BATCH_MODIFIER_OPTIONS = [
+  { value: "", label: "Off" },
+  { value: "option", label: "Option" },
+  { value: "shift", label: "Shift" },
+  { value: "control", label: "Control" },
+  { value: "command", label: "Command" },
+]
+
+const DOUBLE_TAP_OPTIONS = [
+  { value: "", label: "None" },
+  { value: "command", label: "Left Command" },
+  { value: "right_command", label: "Right Command" },
+  { value: "option", label: "Left Option" },
+  { value: "right_option", label: "Right Option" },
+  { value: "control", label: "Left Control" },
+  { value: "shift", label: "Left Shift" },
+]
+
 interface PathValidation {
   terminal_valid: boolean
   terminal_resolved_path: string
@@ -18,6 +36,24 @@ interface PathValidation {
   editor_error: string | null
 }
 
+interface DetectedTerminal {
+  terminal: string
+  resolved_path: string
+}
+
+interface DetectedEditor {
+  editor: string
+  executable: string
+  available: boolean
+  resolved_path: string
+}
+
+interface EditProcess {
+  pid: number
+  command: string
+  file: string
+}
+
 interface Props {
   settings: Settings
   onUpdate: (updates: Partial<Settings>) => void
@@ -25,6 +61,7 @@ interface Props {
 
 const TERMINAL_OPTIONS = [
   { value: "alacritty", label: "Alacritty" },
+  { value: "ghostty", label: "Ghostty" },
   { value: "kitty", label: "Kitty" },
   { value: "wezterm", label: "WezTerm" },
   { value: "iterm", label: "iTerm2" },
@@ -33,6 +70,7 @@ const TERMINAL_OPTIONS = [
 
 const DEFAULT_TERMINAL_PATHS: Record<string, string> = {
   alacritty: "/Applications/Alacritty.app/Contents/MacOS/alacritty",
+  ghostty: "/Applications/Ghostty.app/Contents/MacOS/ghostty",
   kitty: "/Applications/kitty.app/Contents/MacOS/kitty",
   wezterm: "/Applications/WezTerm.app/Contents/MacOS/wezterm",
   iterm: "",
@@ -43,6 +81,7 @@ const EDITOR_OPTIONS = [
   { value: "neovim", label: "Neovim" },
   { value: "vim", label: "Vim" },
   { value: "helix", label: "Helix" },
+  { value: "vscode", label: "VS Code / Cursor" },
   { value: "custom", label: "Custom" },
 ]
 
@@ -50,17 +89,42 @@ const DEFAULT_EDITOR_PATHS: Record<string, string> = {
   neovim: "nvim",
   vim: "vim",
   helix: "hx",
+  vscode: "code",
   custom: "",
 }
 
+/** Editor environment as one VAR=value per line */
+function formatEditorEnv(env: Record<string, string>): string {
+  return Object.entries(env)
+    .map(([name, value]) => `${name}=${value}`)
+    .join("\n")
+}
+
+function parseEditorEnv(text: string): Record<string, string> {
+  const env: Record<string, string> = {}
+  for (const line of text.split("\n")) {
+    const separator = line.indexOf("=")
+    const name = line.slice(0, separator).trim()
+    if (separator > 0 && name) {
+      env[name] = line.slice(separator + 1)
+    }
+  }
+  return env
+}
+
 export function NvimEditSettings({ settings, onUpdate }: Props) {
   const [isRecording, setIsRecording] = useState(false)
   const [displayName, setDisplayName] = useState<string | null>(null)
   const [validation, setValidation] = useState<PathValidation | null>(null)
   const [isValidating, setIsValidating] = useState(false)
   const [showErrorDialog, setShowErrorDialog] = useState<"terminal" | "editor" | null>(null)
+  const [strayProcesses, setStrayProcesses] = useState<EditProcess[] | null>(null)
+  const [detectedTerminals, setDetectedTerminals] = useState<DetectedTerminal[] | null>(null)
+  const [detectedEditors, setDetectedEditors] = useState<DetectedEditor[] | null>(null)
 
   const nvimEdit = settings.nvim_edit
+  // Edited as text and saved on blur, so half-typed lines aren't dropped
+  const [editorEnvText, setEditorEnvText] = useState(() => formatEditorEnv(nvimEdit.editor_env))
 
   // Validate paths when settings change
   const validatePaths = useCallback(async () => {
@@ -97,6 +161,15 @@ export function NvimEditSettings({ settings, onUpdate }: Props) {
     validatePaths()
   }, [validatePaths])
 
+  useEffect(() => {
+    invoke<DetectedTerminal[]>("detect_terminal")
+      .then(setDetectedTerminals)
+      .catch((e) => console.error("Failed to detect terminals:", e))
+    invoke<DetectedEditor[]>("detect_editors")
+      .then(setDetectedEditors)
+      .catch((e) => console.error("Failed to detect editors:", e))
+  }, [])
+
   useEffect(() => {
     getKeyDisplayName(nvimEdit.shortcut_key)
       .then((name) => {
@@ -109,6 +182,23 @@ export function NvimEditSettings({ settings, onUpdate }: Props) {
       .catch(() => setDisplayName(null))
   }, [nvimEdit.shortcut_key, nvimEdit.shortcut_modifiers])
 
+  const findStrayProcesses = async () => {
+    try {
+      setStrayProcesses(await invoke<EditProcess[]>("list_edit_processes"))
+    } catch (e) {
+      console.error("Failed to list edit processes:", e)
+    }
+  }
+
+  const killStrayProcess = async (pid: number) => {
+    try {
+      await invoke("kill_edit_process", { pid })
+    } catch (e) {
+      console.error("Failed to kill edit process:", e)
+    }
+    await findStrayProcesses()
+  }
+
   const updateNvimEdit = (updates: Partial<NvimEditSettingsType>) => {
     onUpdate({
       nvim_edit: { ...nvimEdit, ...updates },
@@ -237,6 +327,45 @@ export function NvimEditSettings({ settings, onUpdate }: Props) {
         </div>
       </div>
 
+      <div className="form-group">
+        <label htmlFor="batch-modifier">Batch edit modifier</label>
+        <select
+          id="batch-modifier"
+          value={nvimEdit.batch_modifier}
+          onChange={(e) => updateNvimEdit({ batch_modifier: e.target.value })}
+          disabled={!nvimEdit.enabled}
+        >
+          {BATCH_MODIFIER_OPTIONS.map((opt) => (
+            <option key={opt.value} value={opt.value}>
+              {opt.label}
+            </option>
+          ))}
+        </select>
+        <span className="hint">
+          Add it to the shortcut to edit every field of the window at once. Ignored if the
+          shortcut already uses it
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="double-tap">Double-tap to open</label>
+        <select
+          id="double-tap"
+          value={nvimEdit.trigger_on_double_tap}
+          onChange={(e) => updateNvimEdit({ trigger_on_double_tap: e.target.value })}
+          disabled={!nvimEdit.enabled}
+        >
+          {DOUBLE_TAP_OPTIONS.map((opt) => (
+            <option key={opt.value} value={opt.value}>
+              {opt.label}
+            </option>
+          ))}
+        </select>
+        <span className="hint">
+          Also opens the Edit Popup when this key is tapped twice quickly on its own
+        </span>
+      </div>
+
       <div className="form-row editor-row">
         <div className="form-group">
           <label htmlFor="editor">
@@ -264,9 +393,18 @@ export function NvimEditSettings({ settings, onUpdate }: Props) {
             {EDITOR_OPTIONS.map((opt) => (
               <option key={opt.value} value={opt.value}>
                 {opt.label}
+                {opt.value !== "custom" &&
+                  detectedEditors &&
+                  !detectedEditors.some((d) => d.editor === opt.value && d.available) &&
+                  " (not installed)"}
               </option>
             ))}
           </select>
+          {nvimEdit.editor === "vscode" && (
+            <span className="hint">
+              Opens in its own window, no terminal. Close the tab to finish editing
+            </span>
+          )}
         </div>
 
         <div className="form-group editor-path-group">
@@ -277,6 +415,11 @@ export function NvimEditSettings({ settings, onUpdate }: Props) {
               id="nvim-path"
               value={nvimEdit.nvim_path}
               onChange={(e) => updateNvimEdit({ nvim_path: e.target.value })}
+              list={
+                nvimEdit.editor === "custom" || nvimEdit.editor === "vscode"
+                  ? "detected-editors"
+                  : undefined
+              }
               placeholder={
                 validation?.editor_resolved_path || DEFAULT_EDITOR_PATHS[nvimEdit.editor] || ""
               }
@@ -304,6 +447,11 @@ export function NvimEditSettings({ settings, onUpdate }: Props) {
               ...
             </button>
           </div>
+          <datalist id="detected-editors">
+            {detectedEditors
+              ?.filter((d) => d.editor === nvimEdit.editor && d.available)
+              .map((d) => <option key={d.resolved_path} value={d.resolved_path} />)}
+          </datalist>
           {validation &&
             validation.editor_valid &&
             nvimEdit.enabled &&
@@ -342,9 +490,22 @@ export function NvimEditSettings({ settings, onUpdate }: Props) {
             {TERMINAL_OPTIONS.map((opt) => (
               <option key={opt.value} value={opt.value}>
                 {opt.label}
+                {detectedTerminals &&
+                  !detectedTerminals.some((d) => d.terminal === opt.value) &&
+                  " (not installed)"}
               </option>
             ))}
           </select>
+          {validation && !validation.terminal_valid && nvimEdit.enabled && detectedTerminals?.[0] && (
+            <button
+              type="button"
+              onClick={() => handleTerminalChange(detectedTerminals[0].terminal)}
+            >
+              Use{" "}
+              {TERMINAL_OPTIONS.find((t) => t.value === detectedTerminals[0].terminal)?.label ??
+                detectedTerminals[0].terminal}
+            </button>
+          )}
         </div>
 
         <div className="form-group terminal-path-group">
@@ -480,6 +641,152 @@ export function NvimEditSettings({ settings, onUpdate }: Props) {
           Sync changes to the original text field as you type in the editor. Only works with Neovim.
         </span>
       </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={nvimEdit.open_at_cursor_line}
+            onChange={(e) => updateNvimEdit({ open_at_cursor_line: e.target.checked })}
+            disabled={!nvimEdit.enabled}
+          />
+          Open at the cursor line
+        </label>
+        <span className="hint">
+          Start the editor where the caret was instead of at the end of the text. Neovim and Vim only
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={nvimEdit.use_minimal_config}
+            onChange={(e) => updateNvimEdit({ use_minimal_config: e.target.checked })}
+            disabled={!nvimEdit.enabled}
+          />
+          Use a minimal config
+        </label>
+        <input
+          type="text"
+          value={nvimEdit.minimal_config_path}
+          onChange={(e) => updateNvimEdit({ minimal_config_path: e.target.value })}
+          placeholder="Bundled config"
+          disabled={!nvimEdit.enabled || !nvimEdit.use_minimal_config}
+        />
+        <span className="hint">
+          Start the editor with a small config that skips plugins and saves as you type, which
+          opens faster. The bundled one is for Neovim and Vim; set a path to use your own
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="text-capture">Read text from</label>
+        <select
+          id="text-capture"
+          value={nvimEdit.text_capture}
+          onChange={(e) => updateNvimEdit({ text_capture: e.target.value as TextCapture })}
+          disabled={!nvimEdit.enabled}
+        >
+          <option value="auto">Accessibility, clipboard if it looks cut off</option>
+          <option value="accessibility">Accessibility only</option>
+          <option value="clipboard">Clipboard (select all and copy)</option>
+        </select>
+        <span className="hint">
+          Some apps (Slack, Electron) only expose the visible part of long text via accessibility
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label className="checkbox-label">
+          <input
+            type="checkbox"
+            checked={nvimEdit.keep_window_open_on_empty}
+            onChange={(e) => updateNvimEdit({ keep_window_open_on_empty: e.target.checked })}
+            disabled={!nvimEdit.enabled}
+          />
+          Open the editor for empty fields
+        </label>
+        <span className="hint">
+          When off, the shortcut does nothing in a field without text
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="shell-command">Pipe edited text through</label>
+        <input
+          type="text"
+          id="shell-command"
+          value={nvimEdit.shell_command ?? ""}
+          onChange={(e) => updateNvimEdit({ shell_command: e.target.value || null })}
+          placeholder="e.g. fmt or prettier --stdin-filepath x.md"
+          disabled={!nvimEdit.enabled}
+        />
+        <span className="hint">
+          Shell command run on the edited text before it's written back. The text is used as is if
+          the command fails. Disables live sync
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="editor-env">Editor environment</label>
+        <textarea
+          id="editor-env"
+          rows={2}
+          value={editorEnvText}
+          onChange={(e) => setEditorEnvText(e.target.value)}
+          onBlur={() => updateNvimEdit({ editor_env: parseEditorEnv(editorEnvText) })}
+          placeholder="NVIM_APPNAME=nvim-popup"
+          disabled={!nvimEdit.enabled}
+        />
+        <span className="hint">
+          One VAR=value per line, set for the editor. E.g. NVIM_APPNAME starts Neovim with a
+          separate config for popup editing
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label htmlFor="post-edit-keystrokes">Keystrokes after editing</label>
+        <input
+          type="text"
+          id="post-edit-keystrokes"
+          value={nvimEdit.post_edit_keystrokes["*"] ?? ""}
+          onChange={(e) =>
+            updateNvimEdit({
+              post_edit_keystrokes: { ...nvimEdit.post_edit_keystrokes, "*": e.target.value },
+            })
+          }
+          placeholder="e.g. <CR> or <Tab>"
+          disabled={!nvimEdit.enabled}
+        />
+        <span className="hint">
+          Sent after the edited text is restored. Per-app sequences can be set by bundle id in the
+          settings file
+        </span>
+      </div>
+
+      <div className="form-group">
+        <label>Stray editor processes</label>
+        <button type="button" className="permission-btn secondary" onClick={findStrayProcesses}>
+          Find
+        </button>
+        {strayProcesses?.length === 0 && <span className="hint">No stray editor processes</span>}
+        {strayProcesses?.map((process) => (
+          <div key={process.pid} className="hint">
+            {process.command} (pid {process.pid}){" "}
+            <button
+              type="button"
+              className="permission-btn"
+              onClick={() => killStrayProcess(process.pid)}
+            >
+              Kill
+            </button>
+          </div>
+        ))}
+        <span className="hint">
+          Editors left open by edit sessions that failed to finish
+        </span>
+      </div>
     </div>
   )
 }
@@ -12,6 +12,7 @@ export interface VimKeyModifiers {
   control: boolean;
   option: boolean;
   command: boolean;
+  side?: "any" | "left" | "right";
 }
 
 export interface NvimEditSettings {
@@ -26,6 +27,56 @@ export interface NvimEditSettings {
   popup_width: number;
   popup_height: number;
   live_sync_enabled: boolean;
+  clipboard_delay_ms: number;
+  nvim_server_addr: string | null;
+  confirm_before_apply: boolean;
+  post_edit_keystrokes: Record<string, string>;
+  text_capture: TextCapture;
+  open_at_cursor_line: boolean;
+  shell_command: string | null;
+  editor_env: Record<string, string>;
+  use_minimal_config: boolean;
+  minimal_config_path: string;
+  trigger_on_double_tap: string;
+  keep_window_open_on_empty: boolean;
+  batch_modifier: string;
+}
+
+export type TextCapture = "auto" | "accessibility" | "clipboard";
+
+export type KeyboardLayout =
+  | "qwerty"
+  | "dvorak"
+  | "colemak"
+  | { custom: Record<string, string> };
+
+export interface AppOverride {
+  vim_enabled: boolean;
+  nvim_edit_enabled: boolean;
+  injection_pacing_ms: number | null;
+}
+
+export type KeyRepeat = "os_default" | "disabled" | { custom: number };
+
+export type TabInserts = "tab" | { spaces: number };
+
+export type UnknownMotionKey = "reprocess" | "pass_through" | "ignore";
+
+export type KeymapPreset = "vim-default" | "helix-like" | "macos-friendly";
+
+export interface KeyBinding {
+  key: string;
+  modifiers: VimKeyModifiers;
+  // A built-in command, or the name of a custom command
+  command: string;
+}
+
+// A step of a custom command, e.g. "cut" or { type_text: "// " }
+export type Primitive = string | { type_text: string } | { command: string };
+
+export interface CustomCommand {
+  name: string;
+  steps: Primitive[];
 }
 
 export interface RgbColor {
@@ -34,6 +85,18 @@ export interface RgbColor {
   b: number;
 }
 
+export interface ModeLabels {
+  insert: string;
+  normal: string;
+  visual: string;
+}
+
+export interface IndicatorVisibility {
+  insert: boolean;
+  normal: boolean;
+  visual: boolean;
+}
+
 export interface ModeColors {
   insert: RgbColor;
   normal: RgbColor;
@@ -44,16 +107,22 @@ export interface Settings {
   enabled: boolean;
   vim_key: string;
   vim_key_modifiers: VimKeyModifiers;
+  // Modifier key names that toggle vim mode when pressed together on their own
+  vim_key_chord: string[];
   indicator_position: number;
   indicator_opacity: number;
   indicator_size: number;
   indicator_offset_x: number;
   indicator_offset_y: number;
-  indicator_visible: boolean;
+  indicator_per_mode_visibility: IndicatorVisibility;
   show_mode_in_menu_bar: boolean;
   mode_colors: ModeColors;
+  indicator_text_template: ModeLabels;
   indicator_font: string;
+  indicator_show_count: boolean;
   ignored_apps: string[];
+  app_overrides: Record<string, AppOverride>;
+  startup_mode_per_app: Record<string, "insert" | "normal">;
   launch_at_login: boolean;
   show_in_menu_bar: boolean;
   top_widget: string;
@@ -61,6 +130,40 @@ export interface Settings {
   electron_apps: string[];
   nvim_edit: NvimEditSettings;
   auto_update_enabled: boolean;
+  auto_check_updates: boolean;
+  update_feed_url: string;
+  word_motion_mode: "native" | "vim_accurate";
+  prefer_accessibility_motions: boolean;
+  prefer_accessibility_edits: boolean;
+  suppress_invalid_ops: boolean;
+  keyboard_layout: KeyboardLayout;
+  visual_mode_cursor_style: "bar" | "block" | "underline";
+  ctrl_c_as_escape: boolean;
+  keep_selection_after_yank: boolean;
+  visual_mode_linewise_operators: boolean;
+  yank_to_system_clipboard: boolean;
+  control_combo_modifier: "control" | "command" | "option";
+  default_mode: "insert" | "normal";
+  escape_target_mode: "insert" | "normal";
+  escape_cancels_pending_first: boolean;
+  unknown_motion_key: UnknownMotionKey;
+  operator_timeout_ms: number;
+  startup_capture_delay_ms: number;
+  preserve_caps_lock_state: boolean;
+  insert_readline_bindings: boolean;
+  insert_completion_keys: string[];
+  normal_mode_key_repeat: KeyRepeat;
+  enable_tab_motions: boolean;
+  smart_home: boolean;
+  mouse_disables_normal_mode: boolean;
+  keymap: KeyBinding[];
+  custom_commands: CustomCommand[];
+  keymap_preset: KeymapPreset;
+  tab_inserts: TabInserts;
+  text_width: number;
+  injection_pacing_ms: number;
+  tracking_only_mode: boolean;
+  debug_events: boolean;
 }
 
 type TabId = "general" | "indicator" | "widgets" | "ignored" | "nvim";
@@ -4,7 +4,7 @@ import { invoke } from "@tauri-apps/api/core"
 import { openUrl } from "@tauri-apps/plugin-opener"
 import { Widget } from "./widgets"
 import { applyWindowSettings } from "./windowPosition"
-import type { VimMode, Settings, ModeColors } from "./types"
+import type { VimMode, Settings, ModeColors, ModeChangeEvent, PendingCountEvent } from "./types"
 
 interface PendingUpdate {
   version: string
@@ -18,6 +18,8 @@ const defaultColors: ModeColors = {
 
 export function Indicator() {
   const [mode, setMode] = useState<VimMode>("insert")
+  const [label, setLabel] = useState<string | null>(null)
+  const [pendingCount, setPendingCount] = useState<number | null>(null)
   const [settings, setSettings] = useState<Settings | null>(null)
   const [isHoverable, setIsHoverable] = useState(false)
   const [isHovered, setIsHovered] = useState(false)
@@ -33,6 +35,10 @@ export function Indicator() {
 
     const unlistenSettings = listen<Settings>("settings-changed", async (event) => {
       setSettings(event.payload)
+      // The label from the last mode change may be outdated, use the new template
+      setLabel(null)
+      // Counts aren't sent while the option is off, so a shown one may be outdated
+      setPendingCount(null)
       await applyWindowSettings(event.payload)
     })
 
@@ -46,8 +52,19 @@ export function Indicator() {
       .then((m) => setMode(m as VimMode))
       .catch((e) => console.error("Failed to get initial mode:", e))
 
-    const unlisten = listen<string>("mode-change", (event) => {
-      setMode(event.payload as VimMode)
+    const unlisten = listen<ModeChangeEvent>("mode-change", (event) => {
+      setMode(event.payload.mode)
+      setLabel(event.payload.label)
+    })
+
+    return () => {
+      unlisten.then((fn) => fn())
+    }
+  }, [])
+
+  useEffect(() => {
+    const unlisten = listen<PendingCountEvent>("pending-count", (event) => {
+      setPendingCount(event.payload.count)
     })
 
     return () => {
@@ -133,7 +150,9 @@ export function Indicator() {
     }, 500)
   }
 
-  const modeChar = mode === "insert" ? "i" : mode === "normal" ? "n" : "v"
+  const modeChar =
+    label ?? settings?.indicator_text_template?.[mode] ?? (mode === "insert" ? "i" : mode === "normal" ? "n" : "v")
+  const shownCount = settings?.indicator_show_count ? pendingCount : null
   const opacity = settings?.indicator_opacity ?? 0.9
   const colors = settings?.mode_colors ?? defaultColors
   const color = colors[mode]
@@ -234,7 +253,7 @@ export function Indicator() {
             transform: "translateY(1px)",
           }}
         >
-          {modeChar}
+          {shownCount ?? modeChar}
         </span>
       </div>
       {hasBottom && <Widget type={bottomWidget} fontFamily={fontFamily} />}
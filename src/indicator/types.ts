@@ -17,6 +17,25 @@ export interface RgbColor {
   b: number
 }
 
+export interface ModeLabels {
+  insert: string
+  normal: string
+  visual: string
+}
+
+/** Payload of the mode-change event */
+export interface ModeChangeEvent {
+  mode: VimMode
+  label: string
+  /** Pending keys left after the change */
+  pending: string
+}
+
+/** Payload of the pending-count event */
+export interface PendingCountEvent {
+  count: number | null
+}
+
 export interface ModeColors {
   insert: RgbColor
   normal: RgbColor
@@ -30,10 +49,11 @@ export interface Settings {
   indicator_size: number
   indicator_offset_x: number
   indicator_offset_y: number
-  indicator_visible: boolean
   show_mode_in_menu_bar: boolean
   mode_colors: ModeColors
+  indicator_text_template: ModeLabels
   indicator_font: string
+  indicator_show_count: boolean
   top_widget: WidgetType
   bottom_widget: WidgetType
 }
@@ -41,9 +61,40 @@ export interface Settings {
 export interface SelectionInfo {
   char_count: number
   line_count: number
+  offset: number | null
+  length: number | null
+  line: number | null
+  column: number | null
 }
 
 export interface BatteryInfo {
   percentage: number
   is_charging: boolean
 }
+
+/** A key that can follow the pending keys */
+export interface Continuation {
+  key: string
+  description: string
+}
+
+/** Payload of the pending-help event; an empty prefix hides the help */
+export interface PendingHelpEvent {
+  prefix: string
+  keys: Continuation[]
+}
+
+export interface ModeTransition {
+  mode: VimMode
+  timestamp_ms: number
+}
+
+/** Result of get_mode_history */
+export interface ModeHistoryReport {
+  transitions: ModeTransition[]
+  durations: {
+    insert_ms: number
+    normal_ms: number
+    visual_ms: number
+  }
+}
@@ -1,12 +1,31 @@
+import { useEffect, useState } from "react"
+import { listen } from "@tauri-apps/api/event"
 import { usePollingData } from "../usePollingData"
+import type { ModeChangeEvent } from "../types"
 
 export function KeystrokeBufferWidget({ fontFamily }: { fontFamily: string }) {
-  const pendingKeys = usePollingData<string>({
+  const polledKeys = usePollingData<string>({
     command: "get_pending_keys",
     interval: 100,
     initialValue: "",
     eventName: "pending-keys-changed",
   })
+  const [pendingKeys, setPendingKeys] = useState(polledKeys)
+
+  useEffect(() => {
+    setPendingKeys(polledKeys)
+  }, [polledKeys])
+
+  // Mode changes carry the pending keys, so the keys clear with the mode rather than a poll later
+  useEffect(() => {
+    const unlisten = listen<ModeChangeEvent>("mode-change", (event) => {
+      setPendingKeys(event.payload.pending)
+    })
+
+    return () => {
+      unlisten.then((fn) => fn())
+    }
+  }, [])
 
   if (!pendingKeys) {
     return null
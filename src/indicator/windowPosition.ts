@@ -8,15 +8,10 @@ import type { Settings } from "./types"
 
 const BASE_SIZE = 40
 
+// Showing and hiding the window per mode is done by the backend
 export async function applyWindowSettings(settings: Settings): Promise<void> {
   const window = getCurrentWindow()
 
-  if (!settings.enabled || !settings.indicator_visible) {
-    await window.hide()
-    return
-  }
-  await window.show()
-
   const baseSize = Math.round(BASE_SIZE * settings.indicator_size)
 
   // Calculate height based on active widgets